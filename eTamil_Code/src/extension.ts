@@ -114,6 +114,7 @@ export function activate(context: vscode.ExtensionContext): void {
   registerLanguageFeatures(context);
   registerDiagnostics(context);
   registerCommands(context);
+  registerDebugger(context);
 }
 
 export function deactivate(): void {
@@ -269,6 +270,19 @@ function registerCommands(context: vscode.ExtensionContext): void {
   );
 }
 
+/**
+ * Breakpoints and stepping, by the compiler itself: `etamil --dap` speaks the
+ * Debug Adapter Protocol over stdin and stdout, so there is no adapter code
+ * here to keep in step with the VM.
+ */
+function registerDebugger(context: vscode.ExtensionContext): void {
+  context.subscriptions.push(
+    vscode.debug.registerDebugAdapterDescriptorFactory('etamil', {
+      createDebugAdapterDescriptor: () => new vscode.DebugAdapterExecutable(compilerPath(), ['--dap']),
+    })
+  );
+}
+
 /**
  * Run the active file in a terminal.
  *
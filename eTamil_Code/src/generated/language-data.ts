@@ -99,6 +99,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Asset",
     "forms": [
       "சொத்து",
+      "coqqu",
       "soqqu"
     ],
     "group": "Core Financial & Accounting",
@@ -320,6 +321,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Valuation",
     "forms": [
       "மதிப்பீடு",
+      "maqippItu",
       "matippIDu"
     ],
     "group": "Core Financial & Accounting",
@@ -476,6 +478,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Amount",
     "forms": [
       "தொகை",
+      "qokY",
       "toqai"
     ],
     "group": "Core Financial & Accounting",
@@ -502,6 +505,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Transaction",
     "forms": [
       "பரிவர்த்தனை",
+      "parivarqqaZY",
       "parivarttaZai"
     ],
     "group": "Core Financial & Accounting",
@@ -528,6 +532,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Amortization",
     "forms": [
       "கடன்தீர்ப்பு",
+      "kataZqIrppu",
       "kataZ_qIrppu"
     ],
     "group": "Core Financial & Accounting",
@@ -541,6 +546,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Appreciation",
     "forms": [
       "மதிப்புயர்வு",
+      "maqippuyarvu",
       "matippuyarvu"
     ],
     "group": "Core Financial & Accounting",
@@ -554,6 +560,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Capital",
     "forms": [
       "மூலதனம்",
+      "mUlaqaZam",
       "mUlataZam"
     ],
     "group": "Core Financial & Accounting",
@@ -593,6 +600,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "IncomeStatement",
     "forms": [
       "வருமான_அறிக்கை",
+      "varumAZa_aRikkY",
       "varumAZ_aRikkY"
     ],
     "group": "Core Financial & Accounting",
@@ -619,6 +627,7 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "IncomeTax",
     "forms": [
       "வருமான_வரி",
+      "varumAZa_vari",
       "varumAZ_vari"
     ],
     "group": "Core Financial & Accounting",
@@ -1282,6 +1291,19 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "snippetTamil": "{kw} ${1:பெயர்} = \"${2:2026-04-01}\";",
     "snippetLatin": "{kw} ${1:name} = \"${2:2026-04-01}\";"
   },
+  {
+    "token": "MoneyType",
+    "forms": [
+      "ரொக்கம்",
+      "rokkam"
+    ],
+    "group": "Variables & Data Types",
+    "scope": "storage.type.etamil",
+    "reserved": true,
+    "noSyntax": false,
+    "snippetTamil": null,
+    "snippetLatin": null
+  },
   {
     "token": "True",
     "forms": [
@@ -1920,8 +1942,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Collection",
     "forms": [
       "தொகுப்பு",
-      "toguippu",
-      "_collection"
+      "qokuppu",
+      "_collection",
+      "toguippu"
     ],
     "group": "Database Operations",
     "scope": "keyword.other.database.etamil",
@@ -1976,8 +1999,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "PrimaryKey",
     "forms": [
       "தனிக_விசை",
-      "taZik_vicY",
-      "_primaryKey"
+      "qaZika_vicY",
+      "_primaryKey",
+      "taZik_vicY"
     ],
     "group": "Database Operations",
     "scope": "keyword.other.database.etamil",
@@ -2256,8 +2280,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Endpoint",
     "forms": [
       "இறுதி_புள்ளி",
-      "iraqi_pulli",
-      "_endpoint"
+      "iRuqi_puLLi",
+      "_endpoint",
+      "iraqi_pulli"
     ],
     "group": "REST API & HTTP",
     "scope": "keyword.other.http.etamil",
@@ -2270,8 +2295,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Route",
     "forms": [
       "வழி",
-      "vazhi",
-      "_route"
+      "vazi",
+      "_route",
+      "vazhi"
     ],
     "group": "REST API & HTTP",
     "scope": "keyword.control.route.etamil",
@@ -2294,6 +2320,48 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "snippetTamil": "{kw} ${1:3600} {\n\t${2:அச்சு \"ஒவ்வொரு மணிநேரமும்\";}\n}",
     "snippetLatin": "{kw} ${1:3600} {\n\t${2:accu \"every hour\";}\n}"
   },
+  {
+    "token": "Before",
+    "forms": [
+      "முன்",
+      "muZ",
+      "_before"
+    ],
+    "group": "REST API & HTTP",
+    "scope": "keyword.control.before.etamil",
+    "reserved": true,
+    "noSyntax": false,
+    "snippetTamil": "{kw} \"${1:/api}\" {\n\t${2:அச்சு request_method & \" \" & request_path;}\n}",
+    "snippetLatin": "{kw} \"${1:/api}\" {\n\t${2:accu request_method & \" \" & request_path;}\n}"
+  },
+  {
+    "token": "After",
+    "forms": [
+      "பின்",
+      "piZ",
+      "_after"
+    ],
+    "group": "REST API & HTTP",
+    "scope": "keyword.control.after.etamil",
+    "reserved": true,
+    "noSyntax": false,
+    "snippetTamil": "{kw} \"${1:/api}\" {\n\t${2:பதில்_தலைப்பு(\"X-Served-By\", \"etamil\");}\n}",
+    "snippetLatin": "{kw} \"${1:/api}\" {\n\t${2:paqil_qalYppu(\"X-Served-By\", \"etamil\");}\n}"
+  },
+  {
+    "token": "Mount",
+    "forms": [
+      "கோப்புறை",
+      "kOppuRY",
+      "_mount"
+    ],
+    "group": "REST API & HTTP",
+    "scope": "keyword.control.mount.etamil",
+    "reserved": true,
+    "noSyntax": false,
+    "snippetTamil": "{kw} \"${1:/assets}\", \"${2:dist}\";",
+    "snippetLatin": "{kw} \"${1:/assets}\", \"${2:dist}\";"
+  },
   {
     "token": "Request",
     "forms": [
@@ -2312,8 +2380,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Response",
     "forms": [
       "பதில்",
-      "paDil",
-      "_response"
+      "paqil",
+      "_response",
+      "paDil"
     ],
     "group": "REST API & HTTP",
     "scope": "keyword.other.response.etamil",
@@ -2326,8 +2395,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Header",
     "forms": [
       "தலைப்பு",
-      "talYppu",
-      "_header"
+      "qalYppu",
+      "_header",
+      "talYppu"
     ],
     "group": "REST API & HTTP",
     "scope": "keyword.other.http.etamil",
@@ -2340,8 +2410,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "Body",
     "forms": [
       "உடல்",
-      "uqal",
-      "_body"
+      "utal",
+      "_body",
+      "uqal"
     ],
     "group": "REST API & HTTP",
     "scope": "keyword.other.http.etamil",
@@ -2382,8 +2453,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "PathParam",
     "forms": [
       "பாதை_அளவுரு",
-      "pAtY_aLavuru",
-      "_pathParam"
+      "pAqY_aLavuru",
+      "_pathParam",
+      "pAtY_aLavuru"
     ],
     "group": "REST API & HTTP",
     "scope": "keyword.other.http.etamil",
@@ -2550,8 +2622,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "HttpHead",
     "forms": [
       "தலை",
-      "talY",
-      "_head"
+      "qalY",
+      "_head",
+      "talY"
     ],
     "group": "REST API & HTTP",
     "scope": "support.constant.http-method.etamil",
@@ -2648,8 +2721,9 @@ export const KEYWORDS: readonly KeywordEntry[] = [
     "token": "StartServer",
     "forms": [
       "வழங்கி_தொடங்கு",
-      "vazawki_toqotawku",
-      "_startServer"
+      "vazawki_qotawku",
+      "_startServer",
+      "vazawki_toqotawku"
     ],
     "group": "REST API & HTTP",
     "scope": "keyword.other.http.etamil",
@@ -2773,6 +2847,48 @@ export const KEYWORDS: readonly KeywordEntry[] = [
 ];
 
 export const FUNCTIONS: readonly FunctionEntry[] = [
+  {
+    "name": "மாற்றியமை",
+    "forms": [
+      "மாற்றியமை",
+      "mARRiyamY",
+      "_map"
+    ],
+    "params": null,
+    "arity": 2,
+    "doc": "மாற்றியமை(அணி, செயல்) — a new array: the function applied to each element",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "வடிகட்டு",
+    "forms": [
+      "வடிகட்டு",
+      "vatikattu",
+      "_filter"
+    ],
+    "params": null,
+    "arity": 2,
+    "doc": "வடிகட்டு(அணி, செயல்) — the elements the function answers true for, in order",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "சுருக்கு",
+    "forms": [
+      "சுருக்கு",
+      "curukku",
+      "_reduce"
+    ],
+    "params": null,
+    "arity": 3,
+    "doc": "சுருக்கு(அணி, தொடக்கம், செயல்) — fold: the function called with the running value and each element in turn",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
   {
     "name": "நீளம்",
     "forms": [
@@ -2997,6 +3113,62 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "module": null,
     "line": null
   },
+  {
+    "name": "பணமாக்கு",
+    "forms": [
+      "பணமாக்கு",
+      "paNamAkku",
+      "_money"
+    ],
+    "params": null,
+    "arity": 2,
+    "doc": "பணமாக்கு(தொகை, \"USD\") — an amount in a currency that has no",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "நாணயம்",
+    "forms": [
+      "நாணயம்",
+      "nANayam",
+      "_currency"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "நாணயம்(v) — the currency's code, \"INR\"; இன்மை for a plain number",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "நாணய_குறி",
+    "forms": [
+      "நாணய_குறி",
+      "nANaya_kuRi",
+      "_currencySymbol"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "நாணய_குறி(v) — the symbol it is shown with, \"₹\"; the code when",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "தொகை",
+    "forms": [
+      "தொகை",
+      "qokY",
+      "_amount"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "தொகை(v) — the amount as a plain number, for arithmetic that",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
   {
     "name": "சொல்லாக்கு",
     "forms": [
@@ -3165,6 +3337,202 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "module": null,
     "line": null
   },
+  {
+    "name": "பதில்_தொடங்கு",
+    "forms": [
+      "பதில்_தொடங்கு",
+      "paqil_qotawku",
+      "_respondStart"
+    ],
+    "params": null,
+    "arity": 2,
+    "doc": "பதில்_தொடங்கு(நிலை, தலைப்புகள்) — send the status and headers now",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "பதில்_பகுதி",
+    "forms": [
+      "பதில்_பகுதி",
+      "paqil_pakuqi",
+      "_respondPart"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "பதில்_பகுதி(உரை) — send the next part of a streamed response",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "நிகழ்வுகள்_தொடங்கு",
+    "forms": [
+      "நிகழ்வுகள்_தொடங்கு",
+      "nikazvukaL_qotawku",
+      "_eventsStart"
+    ],
+    "params": null,
+    "arity": 0,
+    "doc": "நிகழ்வுகள்_தொடங்கு() — answer with an events stream",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "வலைசாக்கெட்_தொடங்கு",
+    "forms": [
+      "வலைசாக்கெட்_தொடங்கு",
+      "valYcAkket_qotawku",
+      "_socketStart"
+    ],
+    "params": null,
+    "arity": 0,
+    "doc": "வலைசாக்கெட்_தொடங்கு() — accept the client's WebSocket",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "செய்தி_அனுப்பு",
+    "forms": [
+      "செய்தி_அனுப்பு",
+      "ceyqi_aZuppu",
+      "_messageSend"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "செய்தி_அனுப்பு(உரை) — push one message to the client",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "செய்தி_பெறு",
+    "forms": [
+      "செய்தி_பெறு",
+      "ceyqi_peRu",
+      "_messageReceive"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "செய்தி_பெறு(நொடிகள்) — the client's next WebSocket message",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "இணைப்பு_மூடு",
+    "forms": [
+      "இணைப்பு_மூடு",
+      "iNYppu_mUtu",
+      "_connectionClose"
+    ],
+    "params": null,
+    "arity": 0,
+    "doc": "இணைப்பு_மூடு() — end the events stream or close the WebSocket",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "காத்திரு",
+    "forms": [
+      "காத்திரு",
+      "kAqqiru",
+      "_sleep"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "காத்திரு(நொடிகள்) — wait, then carry on",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "பதில்_தலைப்பு",
+    "forms": [
+      "பதில்_தலைப்பு",
+      "paqil_qalYppu",
+      "_responseHeader"
+    ],
+    "params": null,
+    "arity": 2,
+    "doc": "பதில்_தலைப்பு(பெயர், மதிப்பு) — add one header to the response",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "குக்கீ_அமை",
+    "forms": [
+      "குக்கீ_அமை",
+      "kukkI_amY",
+      "_setCookie"
+    ],
+    "params": null,
+    "arity": 3,
+    "doc": "குக்கீ_அமை(பெயர், மதிப்பு, விருப்பங்கள்) — set a cookie",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "குக்கீ_நீக்கு",
+    "forms": [
+      "குக்கீ_நீக்கு",
+      "kukkI_nIkku",
+      "_deleteCookie"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "குக்கீ_நீக்கு(பெயர்) — tell the browser to forget one set at /",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "அமர்வு_பெறு",
+    "forms": [
+      "அமர்வு_பெறு",
+      "amarvu_peRu",
+      "_sessionGet"
+    ],
+    "params": null,
+    "arity": 1,
+    "doc": "அமர்வு_பெறு(பெயர்) — a value from the request's session",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "அமர்வு_அமை",
+    "forms": [
+      "அமர்வு_அமை",
+      "amarvu_amY",
+      "_sessionSet"
+    ],
+    "params": null,
+    "arity": 2,
+    "doc": "அமர்வு_அமை(பெயர், மதிப்பு) — write one, starting a session if",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "அமர்வு_முடி",
+    "forms": [
+      "அமர்வு_முடி",
+      "amarvu_muti",
+      "_sessionEnd"
+    ],
+    "params": null,
+    "arity": 0,
+    "doc": "அமர்வு_முடி() — end the session and everything in it: signing out",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
   {
     "name": "பதிவேற்றம்_சேமி",
     "forms": [
@@ -3221,6 +3589,34 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "module": null,
     "line": null
   },
+  {
+    "name": "எதிர்பார்",
+    "forms": [
+      "எதிர்பார்",
+      "eqirpAr",
+      "_expect"
+    ],
+    "params": null,
+    "arity": 2,
+    "doc": "எதிர்பார்(நிபந்தனை, விவரம்) — stop here unless the condition holds",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
+  {
+    "name": "எதிர்பார்_சமம்",
+    "forms": [
+      "எதிர்பார்_சமம்",
+      "eqirpAr_camam",
+      "_expectEqual"
+    ],
+    "params": null,
+    "arity": 3,
+    "doc": "எதிர்பார்_சமம்(கிடைத்தது, எதிர்பார்த்தது, விவரம்) — the two must be equal",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
   {
     "name": "வளைவு_சாவிகள்",
     "forms": [
@@ -3599,106 +3995,31 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "module": null,
     "line": null
   },
+  {
+    "name": "வலை_காப்பு_அனுப்பு",
+    "forms": [
+      "வலை_காப்பு_அனுப்பு",
+      "valY_kAppu_aZuppu",
+      "_httpRequestGuarded"
+    ],
+    "params": null,
+    "arity": 5,
+    "doc": "வலை_காப்பு_அனுப்பு(முறை, உரலி, உடலுரை, தலைப்புகள், கொள்கை) — retried, through a breaker",
+    "kind": "builtin",
+    "module": null,
+    "line": null
+  },
   {
     "name": "உள்ளதா",
     "forms": [
       "உள்ளதா"
     ],
     "params": [
-      "பட்டியல்",
+      "பாடம்",
       "தேடல்"
     ],
     "arity": 2,
-    "doc": "உள்ளதா(அணி, மதிப்பு) — membership",
-    "kind": "stdlib",
-    "module": "nUlakam/aNi.qmz",
-    "line": 7
-  },
-  {
-    "name": "இடம்_காண்",
-    "forms": [
-      "இடம்_காண்"
-    ],
-    "params": [
-      "பட்டியல்",
-      "தேடல்"
-    ],
-    "arity": 2,
-    "doc": "இடம்_காண்(அணி, மதிப்பு) — first position, or -1",
-    "kind": "stdlib",
-    "module": "nUlakam/aNi.qmz",
-    "line": 15
-  },
-  {
-    "name": "தலைகீழ்",
-    "forms": [
-      "தலைகீழ்"
-    ],
-    "params": [
-      "பட்டியல்"
-    ],
-    "arity": 1,
-    "doc": "தலைகீழ்(அணி) — reversed copy",
-    "kind": "stdlib",
-    "module": "nUlakam/aNi.qmz",
-    "line": 26
-  },
-  {
-    "name": "வெட்டு",
-    "forms": [
-      "வெட்டு"
-    ],
-    "params": [
-      "பட்டியல்",
-      "தொடக்கம்",
-      "அளவு"
-    ],
-    "arity": 3,
-    "doc": "வெட்டு(அணி, தொடக்கம், அளவு) — slice",
-    "kind": "stdlib",
-    "module": "nUlakam/aNi.qmz",
-    "line": 37
-  },
-  {
-    "name": "புலம்_எடு",
-    "forms": [
-      "புலம்_எடு"
-    ],
-    "params": [
-      "வரிசைகள்",
-      "புலம்"
-    ],
-    "arity": 2,
-    "doc": "புலம்_எடு(வரிசைகள், புலம்) — pluck one field from every record.",
-    "kind": "stdlib",
-    "module": "nUlakam/aNi.qmz",
-    "line": 51
-  },
-  {
-    "name": "காலியா",
-    "forms": [
-      "காலியா"
-    ],
-    "params": [
-      "பட்டியல்"
-    ],
-    "arity": 1,
-    "doc": "காலியா(அணி)",
-    "kind": "stdlib",
-    "module": "nUlakam/aNi.qmz",
-    "line": 60
-  },
-  {
-    "name": "உள்ளதா",
-    "forms": [
-      "உள்ளதா"
-    ],
-    "params": [
-      "பாடம்",
-      "தேடல்"
-    ],
-    "arity": 2,
-    "doc": "Containment through பிரி, so it costs one pass rather than one scan per",
+    "doc": "Containment through பிரி, so it costs one pass rather than one scan per",
     "kind": "stdlib",
     "module": "nUlakam/AvaNam.qmz",
     "line": 41
@@ -3844,6 +4165,188 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "module": "nUlakam/AvaNam.qmz",
     "line": 184
   },
+  {
+    "name": "உள்ளதா",
+    "forms": [
+      "உள்ளதா"
+    ],
+    "params": [
+      "பட்டியல்",
+      "தேடல்"
+    ],
+    "arity": 2,
+    "doc": "உள்ளதா(அணி, மதிப்பு) — membership",
+    "kind": "stdlib",
+    "module": "nUlakam/aNi.qmz",
+    "line": 8
+  },
+  {
+    "name": "இடம்_காண்",
+    "forms": [
+      "இடம்_காண்"
+    ],
+    "params": [
+      "பட்டியல்",
+      "தேடல்"
+    ],
+    "arity": 2,
+    "doc": "இடம்_காண்(அணி, மதிப்பு) — first position, or -1",
+    "kind": "stdlib",
+    "module": "nUlakam/aNi.qmz",
+    "line": 16
+  },
+  {
+    "name": "தலைகீழ்",
+    "forms": [
+      "தலைகீழ்"
+    ],
+    "params": [
+      "பட்டியல்"
+    ],
+    "arity": 1,
+    "doc": "தலைகீழ்(அணி) — reversed copy",
+    "kind": "stdlib",
+    "module": "nUlakam/aNi.qmz",
+    "line": 27
+  },
+  {
+    "name": "வெட்டு",
+    "forms": [
+      "வெட்டு"
+    ],
+    "params": [
+      "பட்டியல்",
+      "தொடக்கம்",
+      "அளவு"
+    ],
+    "arity": 3,
+    "doc": "வெட்டு(அணி, தொடக்கம், அளவு) — slice",
+    "kind": "stdlib",
+    "module": "nUlakam/aNi.qmz",
+    "line": 38
+  },
+  {
+    "name": "புலம்_எடு",
+    "forms": [
+      "புலம்_எடு"
+    ],
+    "params": [
+      "வரிசைகள்",
+      "புலம்"
+    ],
+    "arity": 2,
+    "doc": "புலம்_எடு(வரிசைகள், புலம்) — pluck one field from every record.",
+    "kind": "stdlib",
+    "module": "nUlakam/aNi.qmz",
+    "line": 52
+  },
+  {
+    "name": "காலியா",
+    "forms": [
+      "காலியா"
+    ],
+    "params": [
+      "பட்டியல்"
+    ],
+    "arity": 1,
+    "doc": "காலியா(அணி)",
+    "kind": "stdlib",
+    "module": "nUlakam/aNi.qmz",
+    "line": 57
+  },
+  {
+    "name": "சோதனை_தொடக்கம்",
+    "forms": [
+      "சோதனை_தொடக்கம்"
+    ],
+    "params": [
+      "பெயர்"
+    ],
+    "arity": 1,
+    "doc": "சோதனை_தொடக்கம்(பெயர்) — an empty run",
+    "kind": "stdlib",
+    "module": "nUlakam/cOqaZY.qmz",
+    "line": 22
+  },
+  {
+    "name": "உறுதிசெய்",
+    "forms": [
+      "உறுதிசெய்"
+    ],
+    "params": [
+      "ஓட்டம்",
+      "நிபந்தனை",
+      "விவரம்"
+    ],
+    "arity": 3,
+    "doc": "உறுதிசெய்(ஓட்டம், நிபந்தனை, விவரம்) — the condition must hold",
+    "kind": "stdlib",
+    "module": "nUlakam/cOqaZY.qmz",
+    "line": 28
+  },
+  {
+    "name": "சமம்",
+    "forms": [
+      "சமம்"
+    ],
+    "params": [
+      "ஓட்டம்",
+      "கிடைத்தது",
+      "எதிர்பார்த்தது",
+      "விவரம்"
+    ],
+    "arity": 4,
+    "doc": "சமம்(ஓட்டம், கிடைத்தது, எதிர்பார்த்தது, விவரம்)",
+    "kind": "stdlib",
+    "module": "nUlakam/cOqaZY.qmz",
+    "line": 45
+  },
+  {
+    "name": "வேறுபடு",
+    "forms": [
+      "வேறுபடு"
+    ],
+    "params": [
+      "ஓட்டம்",
+      "கிடைத்தது",
+      "தவிர்க்கப்பட்டது",
+      "விவரம்"
+    ],
+    "arity": 4,
+    "doc": "வேறுபடு(ஓட்டம், கிடைத்தது, தவிர்க்கப்பட்டது, விவரம்) — these must differ",
+    "kind": "stdlib",
+    "module": "nUlakam/cOqaZY.qmz",
+    "line": 61
+  },
+  {
+    "name": "சேர்_ஓட்டம்",
+    "forms": [
+      "சேர்_ஓட்டம்"
+    ],
+    "params": [
+      "முதல்",
+      "இரண்டாவது"
+    ],
+    "arity": 2,
+    "doc": "சேர்_ஓட்டம்(முதல், இரண்டாவது) — two runs as one, for a suite of suites",
+    "kind": "stdlib",
+    "module": "nUlakam/cOqaZY.qmz",
+    "line": 76
+  },
+  {
+    "name": "சோதனை_முடிவு",
+    "forms": [
+      "சோதனை_முடிவு"
+    ],
+    "params": [
+      "ஓட்டம்"
+    ],
+    "arity": 1,
+    "doc": "சோதனை_முடிவு(ஓட்டம்) — the summary, and the exit status",
+    "kind": "stdlib",
+    "module": "nUlakam/cOqaZY.qmz",
+    "line": 86
+  },
   {
     "name": "நுழைவு",
     "forms": [
@@ -4107,111 +4610,18 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
   {
     "name": "இடமிருந்து_நிரப்பு",
     "forms": [
-      "இடமிருந்து_நிரப்பு"
-    ],
-    "params": [
-      "சரம்",
-      "அகலம்",
-      "நிரப்பி"
-    ],
-    "arity": 3,
-    "doc": "இடமிருந்து_நிரப்பு(சரம், அகலம், நிரப்பி) — left pad",
-    "kind": "stdlib",
-    "module": "nUlakam/col.qmz",
-    "line": 111
-  },
-  {
-    "name": "சோதனை_தொடக்கம்",
-    "forms": [
-      "சோதனை_தொடக்கம்"
-    ],
-    "params": [
-      "பெயர்"
-    ],
-    "arity": 1,
-    "doc": "சோதனை_தொடக்கம்(பெயர்) — an empty run",
-    "kind": "stdlib",
-    "module": "nUlakam/cOqaZY.qmz",
-    "line": 22
-  },
-  {
-    "name": "உறுதிசெய்",
-    "forms": [
-      "உறுதிசெய்"
-    ],
-    "params": [
-      "ஓட்டம்",
-      "நிபந்தனை",
-      "விவரம்"
-    ],
-    "arity": 3,
-    "doc": "உறுதிசெய்(ஓட்டம், நிபந்தனை, விவரம்) — the condition must hold",
-    "kind": "stdlib",
-    "module": "nUlakam/cOqaZY.qmz",
-    "line": 28
-  },
-  {
-    "name": "சமம்",
-    "forms": [
-      "சமம்"
-    ],
-    "params": [
-      "ஓட்டம்",
-      "கிடைத்தது",
-      "எதிர்பார்த்தது",
-      "விவரம்"
-    ],
-    "arity": 4,
-    "doc": "சமம்(ஓட்டம், கிடைத்தது, எதிர்பார்த்தது, விவரம்)",
-    "kind": "stdlib",
-    "module": "nUlakam/cOqaZY.qmz",
-    "line": 45
-  },
-  {
-    "name": "வேறுபடு",
-    "forms": [
-      "வேறுபடு"
-    ],
-    "params": [
-      "ஓட்டம்",
-      "கிடைத்தது",
-      "தவிர்க்கப்பட்டது",
-      "விவரம்"
-    ],
-    "arity": 4,
-    "doc": "வேறுபடு(ஓட்டம், கிடைத்தது, தவிர்க்கப்பட்டது, விவரம்) — these must differ",
-    "kind": "stdlib",
-    "module": "nUlakam/cOqaZY.qmz",
-    "line": 61
-  },
-  {
-    "name": "சேர்_ஓட்டம்",
-    "forms": [
-      "சேர்_ஓட்டம்"
-    ],
-    "params": [
-      "முதல்",
-      "இரண்டாவது"
-    ],
-    "arity": 2,
-    "doc": "சேர்_ஓட்டம்(முதல், இரண்டாவது) — two runs as one, for a suite of suites",
-    "kind": "stdlib",
-    "module": "nUlakam/cOqaZY.qmz",
-    "line": 76
-  },
-  {
-    "name": "சோதனை_முடிவு",
-    "forms": [
-      "சோதனை_முடிவு"
+      "இடமிருந்து_நிரப்பு"
     ],
     "params": [
-      "ஓட்டம்"
+      "சரம்",
+      "அகலம்",
+      "நிரப்பி"
     ],
-    "arity": 1,
-    "doc": "சோதனை_முடிவு(ஓட்டம்) — the summary, and the exit status",
+    "arity": 3,
+    "doc": "இடமிருந்து_நிரப்பு(சரம், அகலம், நிரப்பி) — left pad",
     "kind": "stdlib",
-    "module": "nUlakam/cOqaZY.qmz",
-    "line": 86
+    "module": "nUlakam/col.qmz",
+    "line": 111
   },
   {
     "name": "மதிப்பிடத்தக்க_மதிப்பு",
@@ -4430,7 +4840,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "ஜே_எழுத்து(உரை, இடம்) — the character at a position, or \"\" past the end.",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 88
+    "line": 94
   },
   {
     "name": "ஜே_வெண்மை",
@@ -4445,7 +4855,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "ஜே_வெண்மை(உரை, இடம்) — skip whitespace, returning the next position",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 102
+    "line": 108
   },
   {
     "name": "ஜே_விடுவி",
@@ -4459,7 +4869,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "ஜே_விடுவி(எழுத்து) — the character an escape names",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 118
+    "line": 124
   },
   {
     "name": "ஜே_சரம்",
@@ -4474,7 +4884,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 131
+    "line": 137
   },
   {
     "name": "ஜே_எண்",
@@ -4489,7 +4899,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 163
+    "line": 169
   },
   {
     "name": "ஜே_அணி",
@@ -4504,7 +4914,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 188
+    "line": 194
   },
   {
     "name": "ஜே_சோடி",
@@ -4519,7 +4929,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "ஜே_சோடி — one \"சாவி\": மதிப்பு pair, kept separate so the object loop below",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 225
+    "line": 231
   },
   {
     "name": "ஜே_பொருள்",
@@ -4534,7 +4944,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 250
+    "line": 256
   },
   {
     "name": "ஜே_மதிப்பு",
@@ -4549,7 +4959,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 286
+    "line": 292
   },
   {
     "name": "ஜேசான்_படி",
@@ -4563,7 +4973,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "ஜேசான்_படி(உரை) — the whole text as one value, or an explanation",
     "kind": "stdlib",
     "module": "nUlakam/jEcAZ.qmz",
-    "line": 311
+    "line": 317
   },
   {
     "name": "ரூபாயும்_பைசாவும்",
@@ -4581,137 +4991,384 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "line": 38
   },
   {
-    "name": "ரூபாயாக",
+    "name": "ரூபாயாக",
+    "forms": [
+      "ரூபாயாக"
+    ],
+    "params": [
+      "ரூபாய்"
+    ],
+    "arity": 1,
+    "doc": "ரூபாயாக(ரூபாய்) — whole rupees",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 43
+  },
+  {
+    "name": "காசு_உரை",
+    "forms": [
+      "காசு_உரை"
+    ],
+    "params": [
+      "மொத்த_பைசா"
+    ],
+    "arity": 1,
+    "doc": "காசு_உரை(மொத்த_பைசா) — \"2.05\", built from the two halves rather than by",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 51
+  },
+  {
+    "name": "காசு_கூட்டு",
+    "forms": [
+      "காசு_கூட்டு"
+    ],
+    "params": [
+      "அ",
+      "ஆ"
+    ],
+    "arity": 2,
+    "doc": "Addition, subtraction and multiplication by a count: exact on any backend,",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 72
+  },
+  {
+    "name": "காசு_கழி",
+    "forms": [
+      "காசு_கழி"
+    ],
+    "params": [
+      "அ",
+      "ஆ"
+    ],
+    "arity": 2,
+    "doc": "",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 73
+  },
+  {
+    "name": "காசு_மடங்கு",
+    "forms": [
+      "காசு_மடங்கு"
+    ],
+    "params": [
+      "மொத்த_பைசா",
+      "எண்ணிக்கை"
+    ],
+    "arity": 2,
+    "doc": "காசு_மடங்கு(மொத்த_பைசா, எண்ணிக்கை) — a price times a quantity",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 76
+  },
+  {
+    "name": "காசு_கூட்டல்",
+    "forms": [
+      "காசு_கூட்டல்"
+    ],
+    "params": [
+      "பட்டியல்"
+    ],
+    "arity": 1,
+    "doc": "காசு_கூட்டல்(பட்டியல்) — a column of money",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 81
+  },
+  {
+    "name": "விழுக்காடு_காசு",
+    "forms": [
+      "விழுக்காடு_காசு"
+    ],
+    "params": [
+      "மொத்த_பைசா",
+      "மேல்",
+      "கீழ்"
+    ],
+    "arity": 3,
+    "doc": "விழுக்காடு_காசு(மொத்த_பைசா, மேல், கீழ்) — a rate as a fraction",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 98
+  },
+  {
+    "name": "சமமாகப்_பிரி",
+    "forms": [
+      "சமமாகப்_பிரி"
+    ],
+    "params": [
+      "மொத்த_பைசா",
+      "எத்தனை"
+    ],
+    "arity": 2,
+    "doc": "சமமாகப்_பிரி(மொத்த_பைசா, எத்தனை)",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 117
+  },
+  {
+    "name": "விகிதத்தில்_பிரி",
+    "forms": [
+      "விகிதத்தில்_பிரி"
+    ],
+    "params": [
+      "மொத்த_பைசா",
+      "எடைகள்"
+    ],
+    "arity": 2,
+    "doc": "விகிதத்தில்_பிரி(மொத்த_பைசா, எடைகள்)",
+    "kind": "stdlib",
+    "module": "nUlakam/kAcu.qmz",
+    "line": 143
+  },
+  {
+    "name": "முனைமம்",
+    "forms": [
+      "முனைமம்"
+    ],
+    "params": [
+      "காப்பீட்டுத்_தொகை",
+      "விகிதம்"
+    ],
+    "arity": 2,
+    "doc": "முனைமம்(காப்பீட்டுத்_தொகை, விகிதம்) — a premium at a percentage rate",
+    "kind": "stdlib",
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 25
+  },
+  {
+    "name": "ஆயிரத்திற்கு_முனைமம்",
+    "forms": [
+      "ஆயிரத்திற்கு_முனைமம்"
+    ],
+    "params": [
+      "காப்பீட்டுத்_தொகை",
+      "ஆயிரத்திற்கு"
+    ],
+    "arity": 2,
+    "doc": "ஆயிரத்திற்கு_முனைமம்(காப்பீட்டுத்_தொகை, ஆயிரத்திற்கு)",
+    "kind": "stdlib",
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 34
+  },
+  {
+    "name": "ஏற்ற_இறக்கத்துடன்",
+    "forms": [
+      "ஏற்ற_இறக்கத்துடன்"
+    ],
+    "params": [
+      "அடிப்படை_முனைமம்",
+      "ஏற்ற_விகிதம்",
+      "தள்ளுபடி_விகிதம்"
+    ],
+    "arity": 3,
+    "doc": "ஏற்ற_இறக்கத்துடன்(அடிப்படை_முனைமம், ஏற்ற_விகிதம், தள்ளுபடி_விகிதம்)",
+    "kind": "stdlib",
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 43
+  },
+  {
+    "name": "குறுகிய_காலம்",
+    "forms": [
+      "குறுகிய_காலம்"
+    ],
+    "params": [
+      "ஆண்டு_முனைமம்",
+      "நாட்கள்",
+      "ஆண்டு_நாட்கள்"
+    ],
+    "arity": 3,
+    "doc": "குறுகிய_காலம்(ஆண்டு_முனைமம், நாட்கள், ஆண்டு_நாட்கள்)",
+    "kind": "stdlib",
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 52
+  },
+  {
+    "name": "கழிவுக்குப்_பின்",
+    "forms": [
+      "கழிவுக்குப்_பின்"
+    ],
+    "params": [
+      "இழப்புத்_தொகை",
+      "கழிவு"
+    ],
+    "arity": 2,
+    "doc": "கழிவுக்குப்_பின்(இழப்புத்_தொகை, கழிவு) — the excess the insured bears",
+    "kind": "stdlib",
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 65
+  },
+  {
+    "name": "சராசரி_விதி",
+    "forms": [
+      "சராசரி_விதி"
+    ],
+    "params": [
+      "இழப்புத்_தொகை",
+      "காப்பீட்டுத்_தொகை",
+      "உண்மை_மதிப்பு"
+    ],
+    "arity": 3,
+    "doc": "சராசரி_விதி(இழப்புத்_தொகை, காப்பீட்டுத்_தொகை, உண்மை_மதிப்பு)",
+    "kind": "stdlib",
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 81
+  },
+  {
+    "name": "கோரல்_தீர்வு",
+    "forms": [
+      "கோரல்_தீர்வு"
+    ],
+    "params": [
+      "விவரங்கள்"
+    ],
+    "arity": 1,
+    "doc": "கோரல்_தீர்வு(விவரங்கள்) — what is actually payable",
+    "kind": "stdlib",
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 104
+  },
+  {
+    "name": "கோரல்_இல்லா_சலுகை",
     "forms": [
-      "ரூபாயாக"
+      "கோரல்_இல்லா_சலுகை"
     ],
     "params": [
-      "ரூபாய்"
+      "ஆண்டு_முனைமம்",
+      "தொடர்_ஆண்டுகள்",
+      "படிகள்"
     ],
-    "arity": 1,
-    "doc": "ரூபாயாக(ரூபாய்) — whole rupees",
+    "arity": 3,
+    "doc": "கோரல்_இல்லா_சலுகை(ஆண்டு_முனைமம், தொடர்_ஆண்டுகள், படிகள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 43
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 147
   },
   {
-    "name": "காசு_உரை",
+    "name": "நிலுவைக்_கோரல்கள்",
     "forms": [
-      "காசு_உரை"
+      "நிலுவைக்_கோரல்கள்"
     ],
     "params": [
-      "மொத்த_பைசா"
+      "கோரல்கள்"
     ],
     "arity": 1,
-    "doc": "காசு_உரை(மொத்த_பைசா) — \"2.05\", built from the two halves rather than by",
+    "doc": "நிலுவைக்_கோரல்கள்(கோரல்கள்) — what is reported and not yet paid",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 51
+    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
+    "line": 167
   },
   {
-    "name": "காசு_கூட்டு",
+    "name": "மொத்தச்_சம்பளம்",
     "forms": [
-      "காசு_கூட்டு"
+      "மொத்தச்_சம்பளம்"
     ],
     "params": [
-      "அ",
-      "ஆ"
+      "கூறுகள்"
     ],
-    "arity": 2,
-    "doc": "Addition, subtraction and multiplication by a count: exact on any backend,",
+    "arity": 1,
+    "doc": "மொத்தச்_சம்பளம்(கூறுகள்) — the components added up",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 72
+    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
+    "line": 27
   },
   {
-    "name": "காசு_கழி",
+    "name": "நாட்களுக்கு_ஏற்ப",
     "forms": [
-      "காசு_கழி"
+      "நாட்களுக்கு_ஏற்ப"
     ],
     "params": [
-      "அ",
-      "ஆ"
+      "தொகை_மதிப்பு",
+      "ஊதிய_நாட்கள்",
+      "மாத_நாட்கள்"
     ],
-    "arity": 2,
-    "doc": "",
+    "arity": 3,
+    "doc": "நாட்களுக்கு_ஏற்ப(தொகை_மதிப்பு, ஊதிய_நாட்கள், மாத_நாட்கள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 73
+    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
+    "line": 40
   },
   {
-    "name": "காசு_மடங்கு",
+    "name": "வரம்புடன்_பங்களிப்பு",
     "forms": [
-      "காசு_மடங்கு"
+      "வரம்புடன்_பங்களிப்பு"
     ],
     "params": [
-      "மொத்த_பைசா",
-      "எண்ணிக்கை"
+      "பங்களிப்பு_ஊதியம்",
+      "விகிதம்",
+      "உச்ச_வரம்பு"
     ],
-    "arity": 2,
-    "doc": "காசு_மடங்கு(மொத்த_பைசா, எண்ணிக்கை) — a price times a quantity",
+    "arity": 3,
+    "doc": "வரம்புடன்_பங்களிப்பு(பங்களிப்பு_ஊதியம், விகிதம், உச்ச_வரம்பு)",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 76
+    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
+    "line": 54
   },
   {
-    "name": "காசு_கூட்டல்",
+    "name": "தகுதிக்குள்_பங்களிப்பு",
     "forms": [
-      "காசு_கூட்டல்"
+      "தகுதிக்குள்_பங்களிப்பு"
     ],
     "params": [
-      "பட்டியல்"
+      "மொத்தத்_தொகை",
+      "விகிதம்",
+      "தகுதி_எல்லை"
     ],
-    "arity": 1,
-    "doc": "காசு_கூட்டல்(பட்டியல்) — a column of money",
+    "arity": 3,
+    "doc": "தகுதிக்குள்_பங்களிப்பு(மொத்தம், விகிதம், தகுதி_எல்லை)",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 81
+    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
+    "line": 68
   },
   {
-    "name": "விழுக்காடு_காசு",
+    "name": "படிநிலை_வரி",
     "forms": [
-      "விழுக்காடு_காசு"
+      "படிநிலை_வரி"
     ],
     "params": [
-      "மொத்த_பைசா",
-      "மேல்",
-      "கீழ்"
+      "வரிக்குரிய_தொகை",
+      "படிகள்"
     ],
-    "arity": 3,
-    "doc": "விழுக்காடு_காசு(மொத்த_பைசா, மேல், கீழ்) — a rate as a fraction",
+    "arity": 2,
+    "doc": "படிநிலை_வரி(வரிக்குரிய_தொகை, படிகள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 98
+    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
+    "line": 92
   },
   {
-    "name": "சமமாகப்_பிரி",
+    "name": "பணிக்கொடை",
     "forms": [
-      "சமமாகப்_பிரி"
+      "பணிக்கொடை"
     ],
     "params": [
-      "மொத்த_பைசா",
-      "எத்தனை"
+      "கடைசிச்_சம்பளம்",
+      "பணி_ஆண்டுகள்",
+      "மேல்_எண்",
+      "கீழ்_எண்",
+      "உச்ச_வரம்பு"
     ],
-    "arity": 2,
-    "doc": "சமமாகப்_பிரி(மொத்த_பைசா, எத்தனை)",
+    "arity": 5,
+    "doc": "பணிக்கொடை(கடைசிச்_சம்பளம், பணி_ஆண்டுகள், மேல்_எண், கீழ்_எண், உச்ச_வரம்பு)",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 117
+    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
+    "line": 141
   },
   {
-    "name": "விகிதத்தில்_பிரி",
+    "name": "சம்பளச்_சீட்டு",
     "forms": [
-      "விகிதத்தில்_பிரி"
+      "சம்பளச்_சீட்டு"
     ],
     "params": [
-      "மொத்த_பைசா",
-      "எடைகள்"
+      "கூறுகள்",
+      "பிடித்தங்கள்"
     ],
     "arity": 2,
-    "doc": "விகிதத்தில்_பிரி(மொத்த_பைசா, எடைகள்)",
+    "doc": "சம்பளச்_சீட்டு(கூறுகள், பிடித்தங்கள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kAcu.qmz",
-    "line": 143
+    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
+    "line": 156
   },
   {
     "name": "இருப்பாய்வு",
@@ -5688,215 +6345,105 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "arity": 3,
     "doc": "நேர்கோட்டு_விகிதம்(கொள்முதல்_மதிப்பு, எஞ்சிய_மதிப்பு, பயன்_ஆண்டுகள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
-    "line": 38
-  },
-  {
-    "name": "குறையும்_ஆண்டு",
-    "forms": [
-      "குறையும்_ஆண்டு"
-    ],
-    "params": [
-      "திறப்பு_மதிப்பு",
-      "ஆண்டு_விகிதம்",
-      "எஞ்சிய_மதிப்பு"
-    ],
-    "arity": 3,
-    "doc": "குறையும்_ஆண்டு(திறப்பு_மதிப்பு, ஆண்டு_விகிதம், எஞ்சிய_மதிப்பு)",
-    "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
-    "line": 51
-  },
-  {
-    "name": "பகுதி_ஆண்டு",
-    "forms": [
-      "பகுதி_ஆண்டு"
-    ],
-    "params": [
-      "ஆண்டுத்_தொகை",
-      "வைத்திருந்த_நாட்கள்",
-      "ஆண்டு_நாட்கள்"
-    ],
-    "arity": 3,
-    "doc": "பகுதி_ஆண்டு(ஆண்டுத்_தொகை, வைத்திருந்த_நாட்கள், ஆண்டு_நாட்கள்)",
-    "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
-    "line": 67
-  },
-  {
-    "name": "நேர்கோட்டு_அட்டவணை",
-    "forms": [
-      "நேர்கோட்டு_அட்டவணை"
-    ],
-    "params": [
-      "கொள்முதல்_மதிப்பு",
-      "எஞ்சிய_மதிப்பு",
-      "பயன்_ஆண்டுகள்"
-    ],
-    "arity": 3,
-    "doc": "நேர்கோட்டு_அட்டவணை(கொள்முதல்_மதிப்பு, எஞ்சிய_மதிப்பு, பயன்_ஆண்டுகள்)",
-    "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
-    "line": 83
-  },
-  {
-    "name": "குறையும்_அட்டவணை",
-    "forms": [
-      "குறையும்_அட்டவணை"
-    ],
-    "params": [
-      "கொள்முதல்_மதிப்பு",
-      "ஆண்டு_விகிதம்",
-      "எஞ்சிய_மதிப்பு",
-      "எத்தனை_ஆண்டுகள்"
-    ],
-    "arity": 4,
-    "doc": "குறையும்_அட்டவணை(கொள்முதல்_மதிப்பு, ஆண்டு_விகிதம், எஞ்சிய_மதிப்பு, எத்தனை_ஆண்டுகள்)",
-    "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
-    "line": 109
-  },
-  {
-    "name": "மொத்தத்_தேய்வு",
-    "forms": [
-      "மொத்தத்_தேய்வு"
-    ],
-    "params": [
-      "அட்டவணை"
-    ],
-    "arity": 1,
-    "doc": "மொத்தத்_தேய்வு(அட்டவணை) — everything written off across the schedule",
-    "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
-    "line": 128
-  },
-  {
-    "name": "தொகுதி_தேய்வு",
-    "forms": [
-      "தொகுதி_தேய்வு"
-    ],
-    "params": [
-      "திறப்பு_மதிப்பு",
-      "சேர்த்தவை",
-      "விற்றவை",
-      "ஆண்டு_விகிதம்",
-      "குறைந்த_நாட்கள்_சேர்ப்பு"
-    ],
-    "arity": 5,
-    "doc": "தொகுதி_தேய்வு(திறப்பு_மதிப்பு, சேர்த்தவை, விற்றவை, ஆண்டு_விகிதம்,",
-    "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
-    "line": 152
-  },
-  {
-    "name": "மொத்தச்_சம்பளம்",
-    "forms": [
-      "மொத்தச்_சம்பளம்"
-    ],
-    "params": [
-      "கூறுகள்"
-    ],
-    "arity": 1,
-    "doc": "மொத்தச்_சம்பளம்(கூறுகள்) — the components added up",
-    "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
-    "line": 27
+    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
+    "line": 38
   },
   {
-    "name": "நாட்களுக்கு_ஏற்ப",
+    "name": "குறையும்_ஆண்டு",
     "forms": [
-      "நாட்களுக்கு_ஏற்ப"
+      "குறையும்_ஆண்டு"
     ],
     "params": [
-      "தொகை_மதிப்பு",
-      "ஊதிய_நாட்கள்",
-      "மாத_நாட்கள்"
+      "திறப்பு_மதிப்பு",
+      "ஆண்டு_விகிதம்",
+      "எஞ்சிய_மதிப்பு"
     ],
     "arity": 3,
-    "doc": "நாட்களுக்கு_ஏற்ப(தொகை_மதிப்பு, ஊதிய_நாட்கள், மாத_நாட்கள்)",
+    "doc": "குறையும்_ஆண்டு(திறப்பு_மதிப்பு, ஆண்டு_விகிதம், எஞ்சிய_மதிப்பு)",
     "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
-    "line": 40
+    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
+    "line": 51
   },
   {
-    "name": "வரம்புடன்_பங்களிப்பு",
+    "name": "பகுதி_ஆண்டு",
     "forms": [
-      "வரம்புடன்_பங்களிப்பு"
+      "பகுதி_ஆண்டு"
     ],
     "params": [
-      "பங்களிப்பு_ஊதியம்",
-      "விகிதம்",
-      "உச்ச_வரம்பு"
+      "ஆண்டுத்_தொகை",
+      "வைத்திருந்த_நாட்கள்",
+      "ஆண்டு_நாட்கள்"
     ],
     "arity": 3,
-    "doc": "வரம்புடன்_பங்களிப்பு(பங்களிப்பு_ஊதியம், விகிதம், உச்ச_வரம்பு)",
+    "doc": "பகுதி_ஆண்டு(ஆண்டுத்_தொகை, வைத்திருந்த_நாட்கள், ஆண்டு_நாட்கள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
-    "line": 54
+    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
+    "line": 67
   },
   {
-    "name": "தகுதிக்குள்_பங்களிப்பு",
+    "name": "நேர்கோட்டு_அட்டவணை",
     "forms": [
-      "தகுதிக்குள்_பங்களிப்பு"
+      "நேர்கோட்டு_அட்டவணை"
     ],
     "params": [
-      "மொத்தத்_தொகை",
-      "விகிதம்",
-      "தகுதி_எல்லை"
+      "கொள்முதல்_மதிப்பு",
+      "எஞ்சிய_மதிப்பு",
+      "பயன்_ஆண்டுகள்"
     ],
     "arity": 3,
-    "doc": "தகுதிக்குள்_பங்களிப்பு(மொத்தம், விகிதம், தகுதி_எல்லை)",
+    "doc": "நேர்கோட்டு_அட்டவணை(கொள்முதல்_மதிப்பு, எஞ்சிய_மதிப்பு, பயன்_ஆண்டுகள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
-    "line": 68
+    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
+    "line": 83
   },
   {
-    "name": "படிநிலை_வரி",
+    "name": "குறையும்_அட்டவணை",
     "forms": [
-      "படிநிலை_வரி"
+      "குறையும்_அட்டவணை"
     ],
     "params": [
-      "வரிக்குரிய_தொகை",
-      "படிகள்"
+      "கொள்முதல்_மதிப்பு",
+      "ஆண்டு_விகிதம்",
+      "எஞ்சிய_மதிப்பு",
+      "எத்தனை_ஆண்டுகள்"
     ],
-    "arity": 2,
-    "doc": "படிநிலை_வரி(வரிக்குரிய_தொகை, படிகள்)",
+    "arity": 4,
+    "doc": "குறையும்_அட்டவணை(கொள்முதல்_மதிப்பு, ஆண்டு_விகிதம், எஞ்சிய_மதிப்பு, எத்தனை_ஆண்டுகள்)",
     "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
-    "line": 92
+    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
+    "line": 109
   },
   {
-    "name": "பணிக்கொடை",
+    "name": "மொத்தத்_தேய்வு",
     "forms": [
-      "பணிக்கொடை"
+      "மொத்தத்_தேய்வு"
     ],
     "params": [
-      "கடைசிச்_சம்பளம்",
-      "பணி_ஆண்டுகள்",
-      "மேல்_எண்",
-      "கீழ்_எண்",
-      "உச்ச_வரம்பு"
+      "அட்டவணை"
     ],
-    "arity": 5,
-    "doc": "பணிக்கொடை(கடைசிச்_சம்பளம், பணி_ஆண்டுகள், மேல்_எண், கீழ்_எண், உச்ச_வரம்பு)",
+    "arity": 1,
+    "doc": "மொத்தத்_தேய்வு(அட்டவணை) — everything written off across the schedule",
     "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
-    "line": 141
+    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
+    "line": 128
   },
   {
-    "name": "சம்பளச்_சீட்டு",
+    "name": "தொகுதி_தேய்வு",
     "forms": [
-      "சம்பளச்_சீட்டு"
+      "தொகுதி_தேய்வு"
     ],
     "params": [
-      "கூறுகள்",
-      "பிடித்தங்கள்"
+      "திறப்பு_மதிப்பு",
+      "சேர்த்தவை",
+      "விற்றவை",
+      "ஆண்டு_விகிதம்",
+      "குறைந்த_நாட்கள்_சேர்ப்பு"
     ],
-    "arity": 2,
-    "doc": "சம்பளச்_சீட்டு(கூறுகள், பிடித்தங்கள்)",
+    "arity": 5,
+    "doc": "தொகுதி_தேய்வு(திறப்பு_மதிப்பு, சேர்த்தவை, விற்றவை, ஆண்டு_விகிதம்,",
     "kind": "stdlib",
-    "module": "nUlakam/kaNakkiyal/Uqiyam.qmz",
-    "line": 156
+    "module": "nUlakam/kaNakkiyal/qEymAZam.qmz",
+    "line": 152
   },
   {
     "name": "வரி_விகிதம்_ஆக்கு",
@@ -6425,143 +6972,6 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "module": "nUlakam/kaNiqam.qmz",
     "line": 78
   },
-  {
-    "name": "முனைமம்",
-    "forms": [
-      "முனைமம்"
-    ],
-    "params": [
-      "காப்பீட்டுத்_தொகை",
-      "விகிதம்"
-    ],
-    "arity": 2,
-    "doc": "முனைமம்(காப்பீட்டுத்_தொகை, விகிதம்) — a premium at a percentage rate",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 25
-  },
-  {
-    "name": "ஆயிரத்திற்கு_முனைமம்",
-    "forms": [
-      "ஆயிரத்திற்கு_முனைமம்"
-    ],
-    "params": [
-      "காப்பீட்டுத்_தொகை",
-      "ஆயிரத்திற்கு"
-    ],
-    "arity": 2,
-    "doc": "ஆயிரத்திற்கு_முனைமம்(காப்பீட்டுத்_தொகை, ஆயிரத்திற்கு)",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 34
-  },
-  {
-    "name": "ஏற்ற_இறக்கத்துடன்",
-    "forms": [
-      "ஏற்ற_இறக்கத்துடன்"
-    ],
-    "params": [
-      "அடிப்படை_முனைமம்",
-      "ஏற்ற_விகிதம்",
-      "தள்ளுபடி_விகிதம்"
-    ],
-    "arity": 3,
-    "doc": "ஏற்ற_இறக்கத்துடன்(அடிப்படை_முனைமம், ஏற்ற_விகிதம், தள்ளுபடி_விகிதம்)",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 43
-  },
-  {
-    "name": "குறுகிய_காலம்",
-    "forms": [
-      "குறுகிய_காலம்"
-    ],
-    "params": [
-      "ஆண்டு_முனைமம்",
-      "நாட்கள்",
-      "ஆண்டு_நாட்கள்"
-    ],
-    "arity": 3,
-    "doc": "குறுகிய_காலம்(ஆண்டு_முனைமம், நாட்கள், ஆண்டு_நாட்கள்)",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 52
-  },
-  {
-    "name": "கழிவுக்குப்_பின்",
-    "forms": [
-      "கழிவுக்குப்_பின்"
-    ],
-    "params": [
-      "இழப்புத்_தொகை",
-      "கழிவு"
-    ],
-    "arity": 2,
-    "doc": "கழிவுக்குப்_பின்(இழப்புத்_தொகை, கழிவு) — the excess the insured bears",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 65
-  },
-  {
-    "name": "சராசரி_விதி",
-    "forms": [
-      "சராசரி_விதி"
-    ],
-    "params": [
-      "இழப்புத்_தொகை",
-      "காப்பீட்டுத்_தொகை",
-      "உண்மை_மதிப்பு"
-    ],
-    "arity": 3,
-    "doc": "சராசரி_விதி(இழப்புத்_தொகை, காப்பீட்டுத்_தொகை, உண்மை_மதிப்பு)",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 81
-  },
-  {
-    "name": "கோரல்_தீர்வு",
-    "forms": [
-      "கோரல்_தீர்வு"
-    ],
-    "params": [
-      "விவரங்கள்"
-    ],
-    "arity": 1,
-    "doc": "கோரல்_தீர்வு(விவரங்கள்) — what is actually payable",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 104
-  },
-  {
-    "name": "கோரல்_இல்லா_சலுகை",
-    "forms": [
-      "கோரல்_இல்லா_சலுகை"
-    ],
-    "params": [
-      "ஆண்டு_முனைமம்",
-      "தொடர்_ஆண்டுகள்",
-      "படிகள்"
-    ],
-    "arity": 3,
-    "doc": "கோரல்_இல்லா_சலுகை(ஆண்டு_முனைமம், தொடர்_ஆண்டுகள், படிகள்)",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 147
-  },
-  {
-    "name": "நிலுவைக்_கோரல்கள்",
-    "forms": [
-      "நிலுவைக்_கோரல்கள்"
-    ],
-    "params": [
-      "கோரல்கள்"
-    ],
-    "arity": 1,
-    "doc": "நிலுவைக்_கோரல்கள்(கோரல்கள்) — what is reported and not yet paid",
-    "kind": "stdlib",
-    "module": "nUlakam/kAppIttu/kAppIttu.qmz",
-    "line": 167
-  },
   {
     "name": "அறுபத்துநான்கு_எழுத்துகள்",
     "forms": [
@@ -6662,13 +7072,13 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
       "காசு_வடிவம்"
     ],
     "params": [
-      "தொகை"
+      "அளவு"
     ],
     "arity": 1,
-    "doc": "காசு_வடிவம்(தொகை) — group and show exactly two decimal places",
+    "doc": "காசு_வடிவம்(அளவு) — group and show exactly two decimal places. An amount",
     "kind": "stdlib",
     "module": "nUlakam/paNam.qmz",
-    "line": 33
+    "line": 34
   },
   {
     "name": "ரூபாய்",
@@ -6676,13 +7086,13 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
       "ரூபாய்"
     ],
     "params": [
-      "தொகை"
+      "அளவு"
     ],
     "arity": 1,
-    "doc": "ரூபாய்(தொகை) — the same, with the rupee sign",
+    "doc": "ரூபாய்(அளவு) — the same, with the currency's sign: ₹ for a plain number,",
     "kind": "stdlib",
     "module": "nUlakam/paNam.qmz",
-    "line": 52
+    "line": 55
   },
   {
     "name": "காசாக",
@@ -6696,7 +7106,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "காசாக(தொகை) — round to the paisa, which is what a ledger stores",
     "kind": "stdlib",
     "module": "nUlakam/paNam.qmz",
-    "line": 57
+    "line": 62
   },
   {
     "name": "லட்சம்",
@@ -6710,7 +7120,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "லட்சம்(தொகை) / கோடி(தொகை) — express in lakhs or crores",
     "kind": "stdlib",
     "module": "nUlakam/paNam.qmz",
-    "line": 62
+    "line": 67
   },
   {
     "name": "கோடி",
@@ -6724,7 +7134,7 @@ export const FUNCTIONS: readonly FunctionEntry[] = [
     "doc": "",
     "kind": "stdlib",
     "module": "nUlakam/paNam.qmz",
-    "line": 66
+    "line": 71
   },
   {
     "name": "புலம்_உள்ளதா",
@@ -0,0 +1,475 @@
+//! What an editor asks of the front end: the errors in a file, the names it
+//! declares, and which of those can be seen from a given position.
+//!
+//! Two editors ask. The browser editor on etamil.in reaches this through
+//! `wasm.rs`, and `etamil --lsp` through `lsp.rs`; both answer from here, so a
+//! diagnostic is the same diagnostic in either, and the same one `etamil`
+//! prints on the command line -- including the bilingual message text, which
+//! comes straight from each error type's `Display`.
+//!
+//! Portable: `lexer` -> `parser` -> `check` and nothing below them, so it
+//! builds into the browser unchanged.
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::check;
+use crate::lexer::{self, Spanned, Token};
+use crate::parser::{DeclaredType, Parser, Position, Stmt};
+
+/// One diagnostic, positioned the way the compiler positions errors: 1-based
+/// line, 1-based column, both counting characters rather than bytes so Tamil
+/// text reports sensible columns.
+///
+/// `length` is in characters too. The editor needs a range to underline, and
+/// the length of the offending text is the closest thing the error types carry
+/// to an end position.
+#[derive(Serialize)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    /// Always "error" today. Present so warnings can be added without the
+    /// JavaScript side having to change shape.
+    pub severity: &'static str,
+    /// Which pass rejected the input: "lex", "parse" or "type".
+    pub stage: &'static str,
+    /// The compiler's own bilingual message.
+    pub message: String,
+}
+
+/// A name the file declares.
+#[derive(Serialize)]
+pub struct Symbol {
+    pub name: String,
+    /// "function", "parameter" or "variable".
+    pub kind: &'static str,
+    /// Shown beside the name: a signature for functions, the declared type for
+    /// variables that have one.
+    pub detail: String,
+    /// Where the name is first written, which is where go-to-definition lands.
+    /// 1-based, in characters.
+    pub line: usize,
+    pub column: usize,
+    /// Name of the function this was declared inside, or `None` for a top-level
+    /// name. Used to decide visibility in `symbols_at` and never sent to the
+    /// browser editor -- it asks "what can I see from here", not "who owns
+    /// this".
+    #[serde(skip)]
+    pub owner: Option<String>,
+}
+
+/// One function body's extent, taken from the token stream.
+///
+/// The AST is the better source for what a name *is*, but it carries no span
+/// for a `FunctionDef`, so it cannot say where a body begins and ends. The
+/// token stream can: every token knows its line and column. Matching braces
+/// over the tokens gives the ranges without touching parser.rs.
+struct Scope {
+    name: String,
+    /// Position of the `{` that opens the body.
+    start: (usize, usize),
+    /// Position of the matching `}`, or the end of input when there is none
+    /// yet -- which is the normal state while a function is being typed.
+    end: (usize, usize),
+}
+
+/// Tuple ordering compares line first, then column, which is exactly the
+/// document order these positions need.
+fn within(position: (usize, usize), scope: &Scope) -> bool {
+    position >= scope.start && position <= scope.end
+}
+
+/// Function body ranges, by matching braces from each `செயல்` token.
+///
+/// An unterminated body runs to the end of input rather than being discarded:
+/// while you are still typing a function, the cursor is inside it, and that is
+/// precisely when completions are wanted.
+fn function_ranges(tokens: &[Spanned]) -> Vec<Scope> {
+    let mut scopes = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        if !matches!(tokens[index].token, Token::Function) {
+            index += 1;
+            continue;
+        }
+
+        // The name follows `செயல்`. Taking `.text` rather than matching on
+        // Token::Identifier keeps a keyword used as a function name -- which
+        // this language allows -- under the spelling the author wrote.
+        let name = tokens.get(index + 1).map(|t| t.text.clone()).unwrap_or_default();
+
+        let Some(open) = (index + 1..tokens.len())
+            .find(|&i| matches!(tokens[i].token, Token::LBrace))
+        else {
+            break;
+        };
+
+        let mut depth = 0usize;
+        let mut close = None;
+        for (i, spanned) in tokens.iter().enumerate().skip(open) {
+            match spanned.token {
+                Token::LBrace => depth += 1,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        scopes.push(Scope {
+            name,
+            start: (tokens[open].line, tokens[open].column),
+            end: close
+                .map(|i| (tokens[i].line, tokens[i].column))
+                .unwrap_or((usize::MAX, usize::MAX)),
+        });
+
+        index = open + 1;
+    }
+
+    scopes
+}
+
+/// Character count, not byte count -- the compiler's columns are in characters.
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// Every error in one source file.
+///
+/// The passes are run in order and the first one to fail wins: a file that does
+/// not lex cannot be parsed, and reporting invented parse errors on top of a
+/// real lexical one buries the error the author needs to see.
+pub fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    let tokens = match lexer::tokenize(source) {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(|e| Diagnostic {
+                    line: e.line,
+                    column: e.column,
+                    length: char_len(&e.text).max(1),
+                    severity: "error",
+                    stage: "lex",
+                    message: e.to_string(),
+                })
+                .collect();
+        }
+    };
+
+    let statements = match Parser::new(tokens.iter()).parse() {
+        Ok(statements) => statements,
+        Err(e) => {
+            return vec![Diagnostic {
+                line: e.line,
+                column: e.column,
+                // `found` is empty at end of input, where there is nothing to
+                // underline; one column keeps the marker visible.
+                length: char_len(&e.found).max(1),
+                severity: "error",
+                stage: "parse",
+                message: e.to_string(),
+            }];
+        }
+    };
+
+    match check::check(&statements) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .iter()
+            .map(|e| Diagnostic {
+                line: e.line,
+                column: e.column,
+                length: char_len(&e.name).max(1),
+                severity: "error",
+                stage: "type",
+                message: e.to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Every name one source file declares.
+///
+/// Falls back to the identifiers in the token stream when the file does not
+/// parse -- which is most of the time, because the moment you want a completion
+/// is the moment you have typed half a statement. A completion list that
+/// vanishes on the first syntax error is a completion list nobody can use.
+pub fn symbols(source: &str) -> Vec<Symbol> {
+    analyse(source).0
+}
+
+/// The names visible from one position.
+///
+/// The same as `symbols`, minus the names that are out of scope: a parameter
+/// or local of some other function is not something you can refer to from
+/// here, and offering it is worse than offering nothing -- it invites code
+/// that will not compile.
+///
+/// `line` and `column` are 1-based and count characters, matching the way the
+/// compiler positions everything else.
+pub fn symbols_at(source: &str, line: usize, column: usize) -> Vec<Symbol> {
+    let (found, scopes) = analyse(source);
+    let position = (line, column);
+
+    // Every function body containing the cursor. More than one when a function
+    // is nested, and none at top level.
+    let enclosing: HashSet<&str> = scopes
+        .iter()
+        .filter(|scope| within(position, scope))
+        .map(|scope| scope.name.as_str())
+        .collect();
+
+    found
+        .into_iter()
+        .filter(|symbol| match &symbol.owner {
+            None => true,
+            Some(function) => enclosing.contains(function.as_str()),
+        })
+        .collect()
+}
+
+/// The files one source file imports, as written, in order.
+///
+/// Read from the tokens rather than the AST so that a file with a syntax error
+/// further down still knows what it imports -- going to a definition should
+/// not stop working because the line being typed is unfinished.
+pub fn imports(source: &str) -> Vec<String> {
+    let Ok(tokens) = lexer::tokenize(source) else {
+        return Vec::new();
+    };
+    tokens
+        .windows(2)
+        .filter_map(|pair| match (&pair[0].token, &pair[1].token) {
+            (Token::Import, Token::String(path)) => Some(path.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn analyse(source: &str) -> (Vec<Symbol>, Vec<Scope>) {
+    let Ok(tokens) = lexer::tokenize(source) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let scopes = function_ranges(&tokens);
+
+    match Parser::new(tokens.iter()).parse() {
+        Ok(statements) => {
+            let mut walk = Walk {
+                tokens: &tokens,
+                out: Vec::new(),
+                seen: HashSet::new(),
+            };
+            walk.block(&statements, None);
+            (walk.out, scopes)
+        }
+        Err(_) => {
+            let found = identifiers_from_tokens(&tokens, &scopes);
+            (found, scopes)
+        }
+    }
+}
+
+/// Every distinct identifier in the token stream, in first-appearance order,
+/// attributed to the function body it sits in.
+///
+/// This is the path taken whenever the file does not parse -- which, while
+/// someone is typing, is most of the time. So it is worth scoping properly
+/// rather than returning everything: the fallback is the common case, not the
+/// exceptional one.
+///
+/// Keyword spellings never reach here: the lexer resolves those to their own
+/// token variants, so `Token::Identifier` is exactly the set of author-chosen
+/// names. Editors offer keywords from their own tables.
+///
+/// One imprecision: a name appearing both globally and inside a function is
+/// recorded once, under whichever came first. Fixing that needs the parse this
+/// path exists because we do not have.
+fn identifiers_from_tokens(tokens: &[Spanned], scopes: &[Scope]) -> Vec<Symbol> {
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+
+    for spanned in tokens {
+        let Token::Identifier(name) = &spanned.token else {
+            continue;
+        };
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+
+        // Innermost enclosing body: the one that starts latest.
+        let position = (spanned.line, spanned.column);
+        let owner = scopes
+            .iter()
+            .filter(|scope| within(position, scope))
+            .max_by_key(|scope| scope.start)
+            .map(|scope| scope.name.clone());
+
+        out.push(Symbol {
+            name: name.clone(),
+            kind: "variable",
+            detail: String::new(),
+            line: spanned.line,
+            column: spanned.column,
+            owner,
+        });
+    }
+
+    out
+}
+
+/// A `செயல்`'s parameters and return type as they would be written:
+/// `(எண் வருமானம், ஆ) எண்`.
+pub fn signature(params: &[String], declared: &[Option<DeclaredType>], returns: Option<DeclaredType>) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .zip(declared.iter().chain(std::iter::repeat(&None)))
+        .map(|(param, declared)| match declared {
+            Some(declared) => format!("{} {}", declared.keyword(), param),
+            None => param.clone(),
+        })
+        .collect();
+    match returns {
+        Some(returns) => format!("({}) {}", params.join(", "), returns.keyword()),
+        None => format!("({})", params.join(", ")),
+    }
+}
+
+/// Collects declared names, descending into every block that can hold them.
+///
+/// `owner` is the function whose body we are inside, or `None` at top level.
+/// A function's *name* belongs to the scope that encloses it; its parameters
+/// and everything declared in its body belong to the function. `symbols_at`
+/// uses that to decide what a given cursor position can see.
+///
+/// `if`, `else` and loop bodies do not open a scope of their own here. eTamil
+/// has no block-scoped binding form -- assignment is a bare `name = value` --
+/// so a name first written inside an `if` is visible after it, and attributing
+/// it to the enclosing function is the accurate answer rather than a shortcut.
+struct Walk<'a> {
+    tokens: &'a [Spanned],
+    out: Vec<Symbol>,
+    seen: HashSet<String>,
+}
+
+impl Walk<'_> {
+    fn block(&mut self, statements: &[Stmt], owner: Option<&str>) {
+        for statement in statements {
+            match statement {
+                Stmt::Assign { name, declared, at, .. } => {
+                    let detail = declared.as_ref().map(|d| d.name().to_string()).unwrap_or_default();
+                    self.push(owner, name, "variable", detail, at);
+                }
+                Stmt::FunctionDef { name, params, declared, returns, body, at } => {
+                    self.push(owner, name, "function", signature(params, declared, *returns), at);
+                    for (param, declared) in params.iter().zip(declared) {
+                        let detail = declared.map(|d| d.name().to_string()).unwrap_or_default();
+                        self.push(Some(name), param, "parameter", detail, at);
+                    }
+                    self.block(body, Some(name));
+                }
+                Stmt::ForEach { var, body, at, .. } => {
+                    self.push(owner, var, "variable", String::new(), at);
+                    self.block(body, owner);
+                }
+                Stmt::If { then_branch, else_branch, .. } => {
+                    self.block(then_branch, owner);
+                    if let Some(alternative) = else_branch {
+                        self.block(alternative, owner);
+                    }
+                }
+                Stmt::Loop { body, .. } => self.block(body, owner),
+                Stmt::SetIndex { name, at, .. } | Stmt::SetField { name, at, .. } => {
+                    self.push(owner, name, "variable", String::new(), at);
+                }
+                Stmt::FileRead { variable, at, .. } | Stmt::ReadCSV { variable, at, .. } => {
+                    self.push(owner, variable, "variable", String::new(), at);
+                }
+                Stmt::DBQuery { result_var, at, .. } => {
+                    self.push(owner, result_var, "variable", String::new(), at);
+                }
+                // Statements that declare nothing. Listed as a catch-all rather
+                // than exhaustively so a new Stmt variant does not break the
+                // wasm build -- it only means that variant declares no names yet.
+                _ => {}
+            }
+        }
+    }
+
+    /// Record one name, first occurrence winning.
+    fn push(
+        &mut self,
+        owner: Option<&str>,
+        name: &str,
+        kind: &'static str,
+        detail: String,
+        at: &Position,
+    ) {
+        if name.is_empty() || !self.seen.insert(name.to_string()) {
+            return;
+        }
+        let (line, column) = self.written(name, at);
+        self.out.push(Symbol {
+            name: name.to_string(),
+            kind,
+            detail,
+            line,
+            column,
+            owner: owner.map(str::to_string),
+        });
+    }
+
+    /// Where `name` is actually written, at or after the statement that
+    /// declares it. A statement's position is where it starts, which for
+    /// `செயல் வரி(...)` is the keyword rather than the name.
+    fn written(&self, name: &str, at: &Position) -> (usize, usize) {
+        let start = self
+            .tokens
+            .partition_point(|t| (t.line, t.column) < (at.line, at.column));
+        self.tokens[start..]
+            .iter()
+            .find(|t| t.text == name)
+            .map(|t| (t.line, t.column))
+            .unwrap_or((at.line, at.column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_function_is_found_where_its_name_is_written() {
+        let found = symbols("அ = 1;\nசெயல் வரி(எண் வருமானம்) எண் {\n  திரும்பு வருமானம்;\n}\n");
+        let function = found.iter().find(|s| s.name == "வரி").unwrap();
+
+        assert_eq!((function.line, function.column), (2, 7));
+        assert_eq!(function.detail, "(எண் வருமானம்) எண்");
+    }
+
+    #[test]
+    fn a_local_is_visible_only_inside_its_function() {
+        let source = "செயல் f(ப) {\n  இடைநிலை = ப;\n}\nவெளியே = 1;\n";
+
+        let inside: Vec<String> = symbols_at(source, 2, 5).into_iter().map(|s| s.name).collect();
+        let outside: Vec<String> = symbols_at(source, 4, 1).into_iter().map(|s| s.name).collect();
+
+        assert!(inside.contains(&"இடைநிலை".to_string()));
+        assert!(!outside.contains(&"இடைநிலை".to_string()));
+    }
+
+    #[test]
+    fn imports_are_read_even_when_the_file_does_not_parse() {
+        let source = "இறக்கு \"paNam.qmz\";\nஅ = ;\n";
+
+        assert_eq!(imports(source), vec!["paNam.qmz".to_string()]);
+    }
+}
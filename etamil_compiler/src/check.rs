@@ -9,14 +9,19 @@
 //! wrong, and stays silent everywhere else:
 //!
 //! - a value assigned to a declaration whose type it cannot be;
-//! - a later assignment to a variable that was declared with a type.
+//! - a later assignment to a variable that was declared with a type;
+//! - an argument passed to a parameter declared with a type;
+//! - a call to a function the program defines, with the wrong number of
+//!   arguments;
+//! - a value returned from a function declared with a return type.
 //!
 //! It does **not** invent constraints the language does not have. Arithmetic
 //! on text is legal on purpose, because `உள்ளிடு` yields text and the VM
 //! converts it when it is used as a number; flagging that would break the
-//! language's own headline example. Calls infer as unconstrained, because
-//! functions have no declared signatures yet. Silence is not approval here —
-//! it is the absence of a claim.
+//! language's own headline example. A call infers as its function's declared
+//! return type, and as unconstrained when there is none — builtins and
+//! undeclared functions make no claim. Silence is not approval here — it is
+//! the absence of a claim.
 
 use std::collections::HashMap;
 
@@ -28,28 +33,56 @@ pub struct TypeError {
     pub line: usize,
     pub column: usize,
     pub name: String,
-    pub declared: DeclaredType,
-    /// What the value turned out to be, named the way a keyword would be.
-    pub found: &'static str,
+    pub mismatch: Mismatch,
+}
+
+/// What the program said, against what it did.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// A value that cannot be the type it was declared.
+    Type {
+        declared: DeclaredType,
+        /// What the value turned out to be, named the way a keyword would be.
+        found: &'static str,
+    },
+    /// A call with more or fewer arguments than the function has parameters.
+    Arguments { expected: usize, given: usize },
 }
 
 impl std::fmt::Display for TypeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "வரி {}, நெடுவரிசை {}: '{}' {} என அறிவிக்கப்பட்டது, ஆனால் {} வழங்கப்பட்டது  \
-             (line {}, column {}: '{}' is declared {}, but was given {})",
-            self.line,
-            self.column,
-            self.name,
-            self.declared.name(),
-            self.found,
-            self.line,
-            self.column,
-            self.name,
-            self.declared.name(),
-            self.found
-        )
+        match &self.mismatch {
+            Mismatch::Type { declared, found } => write!(
+                f,
+                "வரி {}, நெடுவரிசை {}: '{}' {} என அறிவிக்கப்பட்டது, ஆனால் {} வழங்கப்பட்டது  \
+                 (line {}, column {}: '{}' is declared {}, but was given {})",
+                self.line,
+                self.column,
+                self.name,
+                declared.name(),
+                found,
+                self.line,
+                self.column,
+                self.name,
+                declared.name(),
+                found
+            ),
+            Mismatch::Arguments { expected, given } => write!(
+                f,
+                "வரி {}, நெடுவரிசை {}: செயல் '{}' {} அளவுருக்களை எதிர்பார்க்கிறது, {} வழங்கப்பட்டது  \
+                 (line {}, column {}: function '{}' expects {} argument(s), got {})",
+                self.line,
+                self.column,
+                self.name,
+                expected,
+                given,
+                self.line,
+                self.column,
+                self.name,
+                expected,
+                given
+            ),
+        }
     }
 }
 
@@ -65,10 +98,24 @@ enum Inferred {
     Boolean,
     Array,
     Record,
+    Money,
     Unknown,
 }
 
 impl Inferred {
+    /// What a value of a declared type is known to be.
+    fn from_declared(declared: Option<DeclaredType>) -> Inferred {
+        match declared {
+            Some(DeclaredType::Number) => Inferred::Number,
+            Some(DeclaredType::Text) | Some(DeclaredType::Date) => Inferred::Text,
+            Some(DeclaredType::Boolean) => Inferred::Boolean,
+            Some(DeclaredType::Array) => Inferred::Array,
+            Some(DeclaredType::Record) => Inferred::Record,
+            Some(DeclaredType::Money) => Inferred::Money,
+            Some(DeclaredType::Any) | None => Inferred::Unknown,
+        }
+    }
+
     fn name(self) -> &'static str {
         match self {
             Inferred::Number => "ஒரு எண் (a number)",
@@ -76,6 +123,7 @@ impl Inferred {
             Inferred::Boolean => "ஒரு ஈர்மம் (a boolean)",
             Inferred::Array => "ஒரு அணி (an array)",
             Inferred::Record => "ஒரு பொருள் (a record)",
+            Inferred::Money => "ஒரு தொகை (an amount of money)",
             Inferred::Unknown => "something else",
         }
     }
@@ -88,14 +136,18 @@ impl Inferred {
             DeclaredType::Boolean => matches!(self, Inferred::Boolean | Inferred::Unknown),
             DeclaredType::Array => matches!(self, Inferred::Array | Inferred::Unknown),
             DeclaredType::Record => matches!(self, Inferred::Record | Inferred::Unknown),
+            // A plain number is not money: `ரொக்கம் இருப்பு = 1500;` has not
+            // said which currency it is in. (A literal zero is let through
+            // before this is asked; see `fits`.)
+            DeclaredType::Money => matches!(self, Inferred::Money | Inferred::Unknown),
             // Text accepts a number as well: every value in the language
             // renders as text, `&` concatenates whatever it is given, and
             // `உள்ளிடு` hands back text that is routinely compared with
             // numbers. Refusing `சொல் குறி = 1234;` would be a rule the rest
-            // of the language does not follow.
+            // of the language does not follow. An amount renders as `₹1500.50`.
             DeclaredType::Text => matches!(
                 self,
-                Inferred::Text | Inferred::Number | Inferred::Unknown
+                Inferred::Text | Inferred::Number | Inferred::Money | Inferred::Unknown
             ),
             // A date is ISO-8601 text — that is the representation the whole
             // language uses, because ISO text sorts chronologically.
@@ -114,8 +166,13 @@ impl Inferred {
 pub fn check(statements: &[Stmt]) -> Result<(), Vec<TypeError>> {
     let mut checker = Checker {
         declared: HashMap::new(),
+        signatures: HashMap::new(),
+        returning: None,
         errors: Vec::new(),
     };
+    // Functions may be called before they are defined, so every signature is
+    // known before any call is checked.
+    checker.collect_signatures(statements);
     checker.check_block(statements);
 
     if checker.errors.is_empty() {
@@ -128,10 +185,52 @@ pub fn check(statements: &[Stmt]) -> Result<(), Vec<TypeError>> {
 struct Checker {
     /// Types the program has committed to, by name.
     declared: HashMap<String, DeclaredType>,
+    /// Every function the program defines. A later definition replaces an
+    /// earlier one, as it does in the VM.
+    signatures: HashMap<String, Signature>,
+    /// The function whose body is being checked, and the type it promised to
+    /// return, if it promised one.
+    returning: Option<(String, DeclaredType)>,
     errors: Vec<TypeError>,
 }
 
+/// What a `செயல்` declared about itself.
+struct Signature {
+    params: Vec<(String, Option<DeclaredType>)>,
+    returns: Option<DeclaredType>,
+}
+
 impl Checker {
+    fn collect_signatures(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            match statement {
+                Stmt::FunctionDef { name, params, declared, returns, body, .. } => {
+                    let params = params.iter().cloned().zip(declared.iter().copied()).collect();
+                    self.signatures.insert(
+                        name.clone(),
+                        Signature {
+                            params,
+                            returns: *returns,
+                        },
+                    );
+                    self.collect_signatures(body);
+                }
+                Stmt::If { then_branch, else_branch, .. } => {
+                    self.collect_signatures(then_branch);
+                    if let Some(branch) = else_branch {
+                        self.collect_signatures(branch);
+                    }
+                }
+                Stmt::Loop { body, .. }
+                | Stmt::ForEach { body, .. }
+                | Stmt::Schedule { body, .. }
+                | Stmt::Middleware { body, .. }
+                | Stmt::DefineRoute { handler: body, .. } => self.collect_signatures(body),
+                _ => {}
+            }
+        }
+    }
+
     fn check_block(&mut self, statements: &[Stmt]) {
         for statement in statements {
             self.check_stmt(statement);
@@ -139,20 +238,42 @@ impl Checker {
     }
 
     fn check_stmt(&mut self, statement: &Stmt) {
+        // Calls first, wherever the statement holds an expression: before a
+        // declaration it might change, and before any block it opens.
+        for value in expressions(statement) {
+            self.check_calls(value);
+        }
         match statement {
             Stmt::Assign { name, value, declared, at } => {
-                self.check_assign(name, value, *declared, *at);
+                self.check_assign(name, value, *declared, at);
             }
 
-            // A function body is checked, but its parameters are not declared,
-            // so a name inside can shadow an outer declaration without
-            // inheriting its type. Checking the body against the outer scope
-            // would report errors about variables that are not the same
-            // variable.
-            Stmt::FunctionDef { body, .. } => {
-                let outer = std::mem::take(&mut self.declared);
-                self.check_block(body);
-                self.declared = outer;
+            // A function body is checked against its own parameters, not the
+            // outer scope: a name inside can shadow an outer declaration
+            // without inheriting its type, and checking it against the outer
+            // one would report errors about variables that are not the same
+            // variable. A parameter written with a type is a declaration like
+            // any other.
+            Stmt::FunctionDef { name, params, declared, returns, body, .. } => {
+                self.check_function(name, params, declared, *returns, body);
+            }
+
+            Stmt::Return { value, at } => {
+                // `திரும்பு;` returns இன்மை, which stands anywhere.
+                if let (Some((function, returns)), Some(value)) = (&self.returning, value) {
+                    let found = self.infer(value);
+                    if !self.fits(value, found, *returns) {
+                        self.errors.push(TypeError {
+                            line: at.line,
+                            column: at.column,
+                            name: function.clone(),
+                            mismatch: Mismatch::Type {
+                                declared: *returns,
+                                found: found.name(),
+                            },
+                        });
+                    }
+                }
             }
 
             Stmt::If { then_branch, else_branch, .. } => {
@@ -170,6 +291,7 @@ impl Checker {
             }
             Stmt::DefineRoute { handler, .. } => self.check_block(handler),
             Stmt::Schedule { body, .. } => self.check_block(body),
+            Stmt::Middleware { body, .. } => self.check_block(body),
 
             // These bind a name to a value whose type the host decides — rows
             // from a query, text from a file — so any earlier declaration no
@@ -190,12 +312,35 @@ impl Checker {
         }
     }
 
+    fn check_function(
+        &mut self,
+        name: &str,
+        params: &[String],
+        declared: &[Option<DeclaredType>],
+        returns: Option<DeclaredType>,
+        body: &[Stmt],
+    ) {
+        let inner = params
+            .iter()
+            .zip(declared)
+            .filter_map(|(param, declared)| declared.map(|d| (param.clone(), d)))
+            .collect();
+        let outer = std::mem::replace(&mut self.declared, inner);
+        let outer_returning = std::mem::replace(
+            &mut self.returning,
+            returns.map(|returns| (name.to_string(), returns)),
+        );
+        self.check_block(body);
+        self.declared = outer;
+        self.returning = outer_returning;
+    }
+
     fn check_assign(
         &mut self,
         name: &str,
         value: &Expr,
         declared: Option<DeclaredType>,
-        at: Position,
+        at: &Position,
     ) {
         // A declaration on this statement wins; failing that, one the program
         // made earlier still applies.
@@ -203,13 +348,15 @@ impl Checker {
 
         if let Some(expected) = expected {
             let found = self.infer(value);
-            if !found.satisfies(expected) {
+            if !self.fits(value, found, expected) {
                 self.errors.push(TypeError {
                     line: at.line,
                     column: at.column,
                     name: name.to_string(),
-                    declared: expected,
-                    found: found.name(),
+                    mismatch: Mismatch::Type {
+                        declared: expected,
+                        found: found.name(),
+                    },
                 });
             }
         }
@@ -221,17 +368,115 @@ impl Checker {
         }
     }
 
+    /// Hold every call inside `expr` to its function's parameter types.
+    fn check_calls(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Call { name, args, at } => {
+                for arg in args {
+                    self.check_calls(arg);
+                }
+                // A builtin makes no claim; the VM holds it to its own count.
+                let Some(signature) = self.signatures.get(name) else {
+                    return;
+                };
+                if signature.params.len() != args.len() {
+                    self.errors.push(TypeError {
+                        line: at.line,
+                        column: at.column,
+                        name: name.clone(),
+                        mismatch: Mismatch::Arguments {
+                            expected: signature.params.len(),
+                            given: args.len(),
+                        },
+                    });
+                    return;
+                }
+                let mut errors = Vec::new();
+                for ((param, declared), arg) in signature.params.iter().zip(args) {
+                    let Some(declared) = declared else {
+                        continue;
+                    };
+                    let found = self.infer(arg);
+                    if !self.fits(arg, found, *declared) {
+                        errors.push(TypeError {
+                            line: at.line,
+                            column: at.column,
+                            name: format!("{}({})", name, param),
+                            mismatch: Mismatch::Type {
+                                declared: *declared,
+                                found: found.name(),
+                            },
+                        });
+                    }
+                }
+                self.errors.extend(errors);
+            }
+            Expr::BinaryOp { left, right, .. }
+            | Expr::Comparison { left, right, .. }
+            | Expr::Concat { left, right }
+            | Expr::Logical { left, right, .. } => {
+                self.check_calls(left);
+                self.check_calls(right);
+            }
+            Expr::Index { base, index } => {
+                self.check_calls(base);
+                self.check_calls(index);
+            }
+            Expr::Field { base, .. } | Expr::Not(base) | Expr::Try(base) => self.check_calls(base),
+            Expr::ArrayLiteral(items) => {
+                for item in items {
+                    self.check_calls(item);
+                }
+            }
+            Expr::RecordLiteral(fields) => {
+                for (_, value) in fields {
+                    self.check_calls(value);
+                }
+            }
+            // Its body is checked like a named function's. It sees the
+            // variables around it, but holds them to nothing: a captured
+            // name is a copy, and the copy is the closure's own.
+            Expr::Function { params, declared, returns, body, .. } => {
+                self.check_function("செயல்", params, declared, *returns, body);
+            }
+            Expr::Number(_)
+            | Expr::Money { .. }
+            | Expr::String(_)
+            | Expr::Boolean(_)
+            | Expr::Null
+            | Expr::Variable(_) => {}
+        }
+    }
+
+    /// Does `value`, inferred as `found`, keep the promise `declared` made?
+    fn fits(&self, value: &Expr, found: Inferred, declared: DeclaredType) -> bool {
+        // Zero is an amount in every currency. It is also what a declaration
+        // without a value starts at, so `ரொக்கம் மொத்தம்;` has to pass.
+        let zero = matches!(value, Expr::Number(n) if n.is_zero());
+        found.satisfies(declared) || (zero && declared == DeclaredType::Money)
+    }
+
     fn infer(&self, expr: &Expr) -> Inferred {
         match expr {
             Expr::Number(_) => Inferred::Number,
+            Expr::Money { .. } => Inferred::Money,
             Expr::String(_) => Inferred::Text,
             Expr::Boolean(_) => Inferred::Boolean,
             Expr::ArrayLiteral(_) => Inferred::Array,
             Expr::RecordLiteral(_) => Inferred::Record,
 
-            // Arithmetic yields a number whatever went in, because the VM
-            // converts its operands.
-            Expr::BinaryOp { .. } => Inferred::Number,
+            // Arithmetic on an amount is an amount — except an amount divided
+            // by an amount, which is a ratio. Otherwise it yields a number,
+            // because the VM converts its operands; unless an operand is not
+            // known, in which case it may have been an amount.
+            Expr::BinaryOp { op, left, right } => {
+                match (self.infer(left), self.infer(right)) {
+                    (Inferred::Money, Inferred::Money) if op == "/" => Inferred::Number,
+                    (Inferred::Money, _) | (_, Inferred::Money) => Inferred::Money,
+                    (Inferred::Unknown, _) | (_, Inferred::Unknown) => Inferred::Unknown,
+                    _ => Inferred::Number,
+                }
+            }
             Expr::Comparison { .. } | Expr::Logical { .. } | Expr::Not(_) => Inferred::Boolean,
             Expr::Concat { .. } => Inferred::Text,
 
@@ -239,21 +484,68 @@ impl Checker {
             // claim rather than being its own type.
             Expr::Null => Inferred::Unknown,
 
-            Expr::Variable(name) => match self.declared.get(name) {
-                Some(DeclaredType::Number) => Inferred::Number,
-                Some(DeclaredType::Text) | Some(DeclaredType::Date) => Inferred::Text,
-                Some(DeclaredType::Boolean) => Inferred::Boolean,
-                Some(DeclaredType::Array) => Inferred::Array,
-                Some(DeclaredType::Record) => Inferred::Record,
-                Some(DeclaredType::Any) | None => Inferred::Unknown,
-            },
-
-            // Functions have no declared signatures, and indexing a collection
-            // says nothing about what is inside it.
-            Expr::Call { .. }
-            | Expr::Index { .. }
-            | Expr::Field { .. }
-            | Expr::Try(_) => Inferred::Unknown,
+            Expr::Variable(name) => Inferred::from_declared(self.declared.get(name).copied()),
+
+            // A function that declared what it returns is taken at its word;
+            // its own `திரும்பு` statements are held to the same promise.
+            Expr::Call { name, .. } => Inferred::from_declared(
+                self.signatures.get(name).and_then(|signature| signature.returns),
+            ),
+
+            // Indexing a collection says nothing about what is inside it.
+            Expr::Index { .. } | Expr::Field { .. } | Expr::Try(_) => Inferred::Unknown,
+            // No type keyword names a function, so no declaration can be
+            // contradicted by one.
+            Expr::Function { .. } => Inferred::Unknown,
+        }
+    }
+}
+
+/// Every expression a statement holds itself, not counting those in the
+/// blocks it opens. Each kind is named, so a new one cannot be left out.
+fn expressions(statement: &Stmt) -> Vec<&Expr> {
+    match statement {
+        Stmt::Assign { value, .. }
+        | Stmt::SetField { value, .. }
+        | Stmt::Expression { value, .. }
+        | Stmt::Print { value, .. }
+        | Stmt::Input { value, .. } => vec![value],
+        Stmt::Return { value, .. } => value.iter().collect(),
+        Stmt::SetIndex { index, value, .. } => vec![index, value],
+        Stmt::If { condition, .. } | Stmt::Loop { condition, .. } => vec![condition],
+        Stmt::ForEach { collection, .. } => vec![collection],
+        Stmt::FileOpen { filename, .. }
+        | Stmt::FileClose { filename, .. }
+        | Stmt::FileRead { filename, .. }
+        | Stmt::ReadCSV { filename, .. } => vec![filename],
+        Stmt::FileWrite { filename, data, .. } | Stmt::WriteCSV { filename, data, .. } => {
+            vec![filename, data]
+        }
+        Stmt::DBConnect { connection_string, .. } => vec![connection_string],
+        Stmt::DBQuery { query, params, .. } => vec![query, params],
+        Stmt::DBExecute { command, params, .. } => vec![command, params],
+        Stmt::DBInsert { data, .. } => vec![data],
+        Stmt::DBUpdate { data, condition, .. } => std::iter::once(data).chain(condition).collect(),
+        Stmt::DBDelete { condition, .. } => vec![condition],
+        Stmt::CreateTable { schema, .. } => vec![schema],
+        Stmt::Select { where_clause, .. } => where_clause.iter().collect(),
+        // The spec is never run, so a call in it is never made.
+        Stmt::DefineRoute { path, .. } => vec![path],
+        Stmt::StartServer { host, port } => vec![host, port],
+        Stmt::Schedule { seconds, .. } => vec![seconds],
+        Stmt::Middleware { prefix, .. } => vec![prefix],
+        Stmt::Mount { prefix, dir, max_age } => [prefix, dir].into_iter().chain(max_age).collect(),
+        Stmt::SendResponse { status_code, body, headers, .. } => {
+            [status_code, body].into_iter().chain(headers).collect()
         }
+        Stmt::SendJSON { data, status_code } => std::iter::once(data).chain(status_code).collect(),
+        Stmt::GetRequestParam { param_name, .. } => vec![param_name],
+        Stmt::GetHeader { header_name, .. } => vec![header_name],
+        Stmt::SetHeader { header_name, value } => vec![header_name, value],
+        Stmt::FunctionDef { .. }
+        | Stmt::Import(_)
+        | Stmt::DBDisconnect { .. }
+        | Stmt::StopServer
+        | Stmt::GetRequestBody { .. } => Vec::new(),
     }
 }
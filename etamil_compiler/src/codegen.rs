@@ -225,12 +225,12 @@ impl Compiler {
     fn stmt_label(statement: &Stmt) -> &'static str {
         match statement {
             Stmt::FunctionDef { .. } => "செயல் (function definition)",
-            Stmt::Return(_) => "திரும்பு (return)",
+            Stmt::Return { .. } => "திரும்பு (return)",
             Stmt::ForEach { .. } => "ஒவ்வொரு (for-each)",
             Stmt::SetIndex { .. } => "a[i] = v (index assignment)",
             Stmt::SetField { .. } => "r.f = v (field assignment)",
             Stmt::Import(_) => "இறக்கு (import)",
-            Stmt::Expression(_) => "an expression statement",
+            Stmt::Expression { .. } => "an expression statement",
             // Files
             Stmt::FileOpen { .. } => "கோப்பு_திற (open a file)",
             Stmt::FileClose { .. } => "கோப்பு_மூடு (close a file)",
@@ -270,7 +270,9 @@ impl Compiler {
             Expr::Index { .. } => "an index",
             Expr::Field { .. } => "a field access",
             Expr::Try(_) => "the ? operator",
+            Expr::Function { .. } => "செயல் as a value (a function expression)",
             Expr::String(_) => "உரை (a text value)",
+            Expr::Money { .. } => "ரொக்கம் (an amount of money)",
             Expr::Logical { .. } => "a logical operator",
             Expr::Not(_) => "இல்லை (not)",
             Expr::Boolean(_) => "a boolean literal",
@@ -318,7 +320,7 @@ impl Compiler {
             }
 
             for statement in &statements {
-                if let Stmt::FunctionDef { name, params, body } = statement {
+                if let Stmt::FunctionDef { name, params, body, .. } = statement {
                     self.compile_function(name, params, body);
                 }
             }
@@ -367,7 +369,7 @@ impl Compiler {
                     LLVMBuildStore(self.builder, val, var_ptr);
                 }
                 Stmt::FunctionDef { .. } => {}
-                Stmt::Return(value) => {
+                Stmt::Return { value, .. } => {
                     if self.in_function {
                         let val = match value.as_ref() {
                             Some(expr) => self.compile_expr(expr),
@@ -379,7 +381,7 @@ impl Compiler {
                         self.unsupported.push("திரும்பு (return)".to_string());
                     }
                 }
-                Stmt::SetIndex { name, index, value } => {
+                Stmt::SetIndex { name, index, value, .. } => {
                     if let Some(array) = self.arrays.get(&name).copied() {
                         let index = self.compile_array_index(&index);
                         let value = self.compile_expr(&value);
@@ -397,7 +399,7 @@ impl Compiler {
                         self.unsupported.push("array assignment".to_string());
                     }
                 }
-                Stmt::SetField { name, field, value } => {
+                Stmt::SetField { name, field, value, .. } => {
                     let pointer = self.records.get(&name).and_then(|record| record.get(&field).copied());
                     if let Some(pointer) = pointer {
                         let value = self.compile_expr(&value);
@@ -406,7 +408,7 @@ impl Compiler {
                         self.unsupported.push(format!("record field {}", field));
                     }
                 }
-                Stmt::Print(expr) => {
+                Stmt::Print { value: expr, .. } => {
                     // Emit a printf call; strings and numbers are handled differently
                     let (printf, printf_type) = self.get_printf();
                     assert!(!printf.is_null(), "printf declaration missing");
@@ -452,7 +454,7 @@ impl Compiler {
                         }
                     }
                 }
-                Stmt::Input(expr) => {
+                Stmt::Input { value: expr, .. } => {
                     // Input statement: print prompt and read value from stdin
                     let (scanf, scanf_type) = self.get_scanf();
 
@@ -510,7 +512,7 @@ impl Compiler {
                         }
                     }
                 }
-                Stmt::If { condition, then_branch, else_branch } => {
+                Stmt::If { condition, then_branch, else_branch, .. } => {
                     let cond_val = self.compile_comparison(&condition);
                     
                     let then_bb = LLVMAppendBasicBlockInContext(
@@ -575,7 +577,7 @@ impl Compiler {
                         LLVMBuildUnreachable(self.builder);
                     }
                 }
-                Stmt::Loop { condition, body } => {
+                Stmt::Loop { condition, body, .. } => {
                     let loop_cond_bb = LLVMAppendBasicBlockInContext(
                         self.context,
                         self.function,
@@ -618,7 +620,7 @@ impl Compiler {
                     LLVMPositionBuilderAtEnd(self.builder, after_loop_bb);
                     self.terminated = false;
                 }
-                Stmt::ForEach { var, collection, body } => {
+                Stmt::ForEach { var, collection, body, .. } => {
                     let array = match &collection {
                         Expr::Variable(name) => self.arrays.get(name).copied(),
                         Expr::ArrayLiteral(items) => Some(self.compile_array_literal(items)),
@@ -1000,7 +1002,7 @@ impl Compiler {
                     );
                     self.number_const(0)
                 }
-                Expr::Call { name, args } => {
+                Expr::Call { name, args, .. } => {
                     // A function the author wrote wins over a builtin of the
                     // same name, which is the order the VM resolves in too.
                     let function = self.functions.get(name).copied();
@@ -111,6 +111,11 @@ const DIVISION: &str = "வகுத்தல் — தரை() அல்லத
                         but only under தரை() or மேல் — on its own its result \
                         has a fractional part and i64 has nowhere to put it)";
 
+const MONEY: &str = "நாணயத்துடன் ஒரு தொகை  \
+                     (an amount with a currency: an i64 has nowhere to keep \
+                     the currency, and arithmetic without it would add rupees \
+                     to dollars — write whole paise instead)";
+
 const TOO_LARGE: &str = "i64 இல் அடங்காத எண்  \
                          (a whole number too large for the i64 this backend \
                          computes in — the VM's decimal type holds more)";
@@ -159,14 +164,14 @@ impl Walk {
     fn stmt(&mut self, statement: &Stmt) {
         match statement {
             Stmt::Assign { value, .. } => self.expr(value),
-            Stmt::Return(Some(value)) => self.expr(value),
-            Stmt::Return(None) => {}
+            Stmt::Return { value: Some(value), .. } => self.expr(value),
+            Stmt::Return { value: None, .. } => {}
             Stmt::SetIndex { index, value, .. } => {
                 self.expr(index);
                 self.expr(value);
             }
             Stmt::SetField { value, .. } => self.expr(value),
-            Stmt::Expression(value) | Stmt::Print(value) | Stmt::Input(value) => self.expr(value),
+            Stmt::Expression { value, .. } | Stmt::Print { value, .. } | Stmt::Input { value, .. } => self.expr(value),
             Stmt::FunctionDef { body, .. } => {
                 for inner in body {
                     self.stmt(inner);
@@ -176,6 +181,7 @@ impl Walk {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 self.expr(condition);
                 for inner in then_branch {
@@ -187,7 +193,7 @@ impl Walk {
                     }
                 }
             }
-            Stmt::Loop { condition, body } => {
+            Stmt::Loop { condition, body, .. } => {
                 self.expr(condition);
                 for inner in body {
                     self.stmt(inner);
@@ -221,6 +227,7 @@ impl Walk {
                     self.note(TOO_LARGE);
                 }
             }
+            Expr::Money { .. } => self.note(MONEY),
             Expr::BinaryOp { op, left, right } => {
                 // Addition, subtraction and multiplication of whole numbers
                 // are exact in an i64 up to 2^63. Division is exact too — but
@@ -248,7 +255,7 @@ impl Walk {
                 self.expr(right);
             }
             Expr::Not(inner) | Expr::Try(inner) => self.expr(inner),
-            Expr::Call { name, args } => {
+            Expr::Call { name, args, .. } => {
                 // The exception that makes money work. `தரை(அ / ஆ)` and
                 // `மேல்(அ / ஆ)` are compiled as integer division with a
                 // sign correction, which is exactly floor and exactly ceiling
@@ -291,6 +298,11 @@ impl Walk {
                 self.expr(index);
             }
             Expr::Field { base, .. } => self.expr(base),
+            Expr::Function { body, .. } => {
+                for inner in body {
+                    self.stmt(inner);
+                }
+            }
             Expr::String(_) | Expr::Boolean(_) | Expr::Null | Expr::Variable(_) => {}
         }
     }
@@ -330,6 +342,16 @@ mod tests {
         assert!(why[0].contains("i64"), "it should say why: {}", why[0]);
     }
 
+    #[test]
+    fn a_whole_amount_of_money_is_still_refused() {
+        // ₹1500 fits in an i64; its currency does not, and without it the
+        // compiled program would add rupees to dollars.
+        let why = refusals_for("அ = ₹1500 + $2;");
+
+        assert_eq!(why.len(), 1, "one reason, not one per literal: {:?}", why);
+        assert!(why[0].contains("currency"), "it should say why: {}", why[0]);
+    }
+
     #[test]
     fn a_percentage_is_a_decimal() {
         // 18% is 0.18, so every tax program is caught by this — which is the
@@ -0,0 +1,383 @@
+//! `etamil --dap`: the debugger, spoken as the Debug Adapter Protocol, so an
+//! editor can drive it — breakpoints in the gutter, the call stack and the
+//! variables in their own panes.
+//!
+//! The editor starts the process and talks over stdin and stdout, framed the
+//! way the language server's messages are (`lsp::read_message`). Stdout is
+//! therefore not the program's: what it prints is captured by `vm::host` and
+//! sent on as `output` events, and `உள்ளிடு` reports that there is no input
+//! rather than reading the protocol.
+//!
+//! One thread, and the program runs on it between requests. That is enough
+//! for everything but `pause`, which would have to interrupt a run in
+//! progress; a program that never reaches a breakpoint is stopped from the
+//! editor with `disconnect`, which ends the process.
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value as Json, json};
+
+use crate::debugger::same_file;
+use crate::lsp::{read_message, write_message};
+use crate::module;
+use crate::vm::debug::{self, Debugger, Resume, Stop};
+use crate::vm::{BytecodeCompiler, host};
+
+/// The only thread there is.
+const THREAD: i64 = 1;
+
+/// Serve one debugging session, until the editor disconnects or closes the
+/// stream.
+pub fn serve(mut input: impl BufRead, output: impl Write) -> io::Result<()> {
+    host::capture_output();
+    let mut adapter = Adapter {
+        output,
+        seq: 0,
+        debugger: None,
+        stop_on_entry: false,
+    };
+
+    while let Some(request) = read_message(&mut input)? {
+        let command = request["command"].as_str().unwrap_or_default().to_string();
+        let arguments = &request["arguments"];
+
+        let answer: Result<Json, String> = match command.as_str() {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsEvaluateForHovers": true,
+            })),
+            "launch" => adapter.launch(arguments),
+            "setBreakpoints" => adapter.set_breakpoints(arguments),
+            "setExceptionBreakpoints" => Ok(json!({})),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD, "name": "நிரல்" }] })),
+            "stackTrace" => adapter.stack_trace(),
+            "scopes" => adapter.scopes(arguments),
+            "variables" => adapter.variables(arguments),
+            "evaluate" => adapter.evaluate(arguments),
+            // Answered first, then acted on: the editor expects the response
+            // to a resume before the event saying where it stopped.
+            "configurationDone" | "continue" | "next" | "stepIn" | "stepOut" => Ok(json!({})),
+            "disconnect" | "terminate" => {
+                adapter.respond(&request, Ok(json!({})))?;
+                return Ok(());
+            }
+            other => Err(format!("unsupported: {}", other)),
+        };
+        adapter.respond(&request, answer)?;
+
+        match command.as_str() {
+            "launch" if adapter.debugger.is_some() => adapter.event("initialized", json!({}))?,
+            "configurationDone" => {
+                if adapter.stop_on_entry {
+                    adapter.resume(Resume::StepInto, "entry")?;
+                } else {
+                    adapter.resume(Resume::Continue, "breakpoint")?;
+                }
+            }
+            "continue" => adapter.resume(Resume::Continue, "breakpoint")?,
+            "next" => adapter.resume(Resume::StepOver, "step")?,
+            "stepIn" => adapter.resume(Resume::StepInto, "step")?,
+            "stepOut" => adapter.resume(Resume::StepOut, "step")?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+struct Adapter<W> {
+    output: W,
+    /// Numbers every message this side sends, as the protocol requires.
+    seq: i64,
+    /// The program, once `launch` has loaded it.
+    debugger: Option<Debugger>,
+    stop_on_entry: bool,
+}
+
+impl<W: Write> Adapter<W> {
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.output, &message)
+    }
+
+    fn respond(&mut self, request: &Json, answer: Result<Json, String>) -> io::Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": answer.is_ok(),
+        });
+        match answer {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response)
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn debugger(&self) -> Result<&Debugger, String> {
+        self.debugger
+            .as_ref()
+            .ok_or_else(|| "நிரல் இன்னும் ஏற்றப்படவில்லை  (no program has been launched)".to_string())
+    }
+
+    /// `{"program": "/path/to/file.qmz", "stopOnEntry": true}`
+    fn launch(&mut self, arguments: &Json) -> Result<Json, String> {
+        let program = arguments["program"].as_str().ok_or(
+            "நிரல் கோப்பு தேவை  (launch needs \"program\", the .qmz file to debug)",
+        )?;
+        let ast = module::load_file(Path::new(program))?;
+        if let Err(errors) = crate::check::check(&ast) {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            return Err(errors.join("\n"));
+        }
+        self.debugger = Some(Debugger::new(BytecodeCompiler::compile_statements(ast)));
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        Ok(json!({}))
+    }
+
+    /// Replace every breakpoint in one file. Each comes back on the line it
+    /// landed on, which is the next line with a statement if it was set on
+    /// one without.
+    fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, String> {
+        let path = PathBuf::from(arguments["source"]["path"].as_str().unwrap_or_default());
+        let debugger = self
+            .debugger
+            .as_mut()
+            .ok_or("நிரல் இன்னும் ஏற்றப்படவில்லை  (no program has been launched)")?;
+        debugger.clear_breakpoints(|at| same_file(at, &path));
+
+        let requested = arguments["breakpoints"].as_array().cloned().unwrap_or_default();
+        let breakpoints: Vec<Json> = requested
+            .iter()
+            .map(|breakpoint| {
+                let line = breakpoint["line"].as_u64().unwrap_or(0) as usize;
+                match debugger.set_breakpoint(line, |at| same_file(at, &path)) {
+                    Some(landed) => json!({ "verified": true, "line": landed }),
+                    None => json!({
+                        "verified": false,
+                        "line": line,
+                        "message": "இந்த வரியிலோ அதன் பின்னோ கூற்று இல்லை  (no statement on or after this line)",
+                    }),
+                }
+            })
+            .collect();
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    /// Frame ids are the index `Debugger::calls` gives, innermost first.
+    fn stack_trace(&self) -> Result<Json, String> {
+        let calls = self.debugger()?.calls();
+        let frames: Vec<Json> = calls
+            .iter()
+            .enumerate()
+            .map(|(id, call)| {
+                let mut frame = json!({
+                    "id": id,
+                    "name": call.function,
+                    "line": call.at.as_ref().map_or(0, |at| at.line),
+                    "column": call.at.as_ref().map_or(0, |at| at.column),
+                });
+                if let Some(file) = call.at.as_ref().and_then(|at| at.file.as_deref()) {
+                    frame["source"] = source(file);
+                }
+                frame
+            })
+            .collect();
+        Ok(json!({ "stackFrames": frames, "totalFrames": calls.len() }))
+    }
+
+    /// A call's own names, and inside a function the globals as well. A
+    /// scope's reference is the call's frame id plus one, since 0 means
+    /// "nothing to expand".
+    fn scopes(&self, arguments: &Json) -> Result<Json, String> {
+        let top = self.debugger()?.calls().len() - 1;
+        let frame = arguments["frameId"].as_u64().unwrap_or(0) as usize;
+        let mut scopes = Vec::new();
+        if frame < top {
+            scopes.push(json!({ "name": "உள்ளூர்  (locals)", "variablesReference": frame + 1, "expensive": false }));
+        }
+        scopes.push(json!({ "name": "பொது  (globals)", "variablesReference": top + 1, "expensive": false }));
+        Ok(json!({ "scopes": scopes }))
+    }
+
+    fn variables(&self, arguments: &Json) -> Result<Json, String> {
+        let reference = arguments["variablesReference"].as_u64().unwrap_or(0) as usize;
+        let Some(call) = reference.checked_sub(1) else {
+            return Ok(json!({ "variables": [] }));
+        };
+        let variables: Vec<Json> = self
+            .debugger()?
+            .variables(call)
+            .iter()
+            .map(|(name, value)| {
+                json!({ "name": name, "value": debug::shown(value), "variablesReference": 0 })
+            })
+            .collect();
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// Hovering over a name, or typing one into the debug console.
+    fn evaluate(&self, arguments: &Json) -> Result<Json, String> {
+        let name = arguments["expression"].as_str().unwrap_or_default().trim();
+        match self.debugger()?.lookup(name) {
+            Some(value) => Ok(json!({ "result": debug::shown(value), "variablesReference": 0 })),
+            None => Err(format!("'{}' இங்கு இல்லை  ('{}' is not defined here)", name, name)),
+        }
+    }
+
+    /// Run, pass on what the program printed, and say why it stopped. A
+    /// runtime error stops it where it failed, so the state that led there
+    /// can still be looked at; resuming after that ends the session.
+    fn resume(&mut self, how: Resume, reason: &str) -> io::Result<()> {
+        let Some(debugger) = self.debugger.as_mut() else {
+            return Ok(());
+        };
+        let result = debugger.resume(how);
+
+        let printed = host::take_output();
+        if !printed.is_empty() {
+            self.event("output", json!({ "category": "stdout", "output": printed }))?;
+        }
+        match result {
+            Ok(Stop::Finished) => {
+                self.event("exited", json!({ "exitCode": 0 }))?;
+                self.event("terminated", json!({}))
+            }
+            Ok(stop) => {
+                let reason = if stop == Stop::Breakpoint { "breakpoint" } else { reason };
+                self.event(
+                    "stopped",
+                    json!({ "reason": reason, "threadId": THREAD, "allThreadsStopped": true }),
+                )
+            }
+            // `வெளியேறு` is an ending, not a failure to stop at.
+            Err(_) if let Some(status) = host::take_exit() => {
+                self.event("exited", json!({ "exitCode": status }))?;
+                self.event("terminated", json!({}))
+            }
+            Err(message) => {
+                self.event(
+                    "output",
+                    json!({ "category": "stderr", "output": format!("✗ Runtime error: {}\n", message) }),
+                )?;
+                self.event(
+                    "stopped",
+                    json!({ "reason": "exception", "text": message, "threadId": THREAD, "allThreadsStopped": true }),
+                )
+            }
+        }
+    }
+}
+
+/// A `Source` for a file as positions name it.
+fn source(file: &str) -> Json {
+    let path = Path::new(file);
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let name = path.file_name().map_or_else(|| file.to_string(), |n| n.to_string_lossy().into_owned());
+    json!({ "name": name, "path": absolute })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message: &Json) -> String {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    /// Run a session and read back everything the adapter sent.
+    fn session(requests: &[Json]) -> Vec<Json> {
+        let input: String = requests.iter().map(frame).collect();
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).unwrap();
+
+        let mut reader = io::BufReader::new(output.as_slice());
+        std::iter::from_fn(|| read_message(&mut reader).unwrap()).collect()
+    }
+
+    fn request(seq: i64, command: &str, arguments: Json) -> Json {
+        json!({ "seq": seq, "type": "request", "command": command, "arguments": arguments })
+    }
+
+    #[test]
+    fn stops_at_a_breakpoint_and_shows_the_variables() {
+        let dir = std::env::temp_dir().join("etamil_dap_breakpoint");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("kaNakku.qmz");
+        std::fs::write(&file, "அ = 2;\nஅச்சு அ;\nஆ = அ * 3;\nஅச்சு ஆ;\n").unwrap();
+        let path = file.to_str().unwrap();
+
+        let sent = session(&[
+            request(1, "initialize", json!({ "adapterID": "etamil" })),
+            request(2, "launch", json!({ "program": path })),
+            request(3, "setBreakpoints", json!({ "source": { "path": path }, "breakpoints": [{ "line": 3 }] })),
+            request(4, "configurationDone", json!({})),
+            request(5, "stackTrace", json!({ "threadId": 1 })),
+            request(6, "variables", json!({ "variablesReference": 1 })),
+            request(7, "continue", json!({ "threadId": 1 })),
+            request(8, "disconnect", json!({})),
+        ]);
+
+        let event = |name: &'static str| sent.iter().filter(move |m| m["event"] == name);
+        let response = |seq: i64| sent.iter().find(|m| m["request_seq"] == seq).unwrap();
+
+        assert_eq!(event("initialized").count(), 1);
+        assert_eq!(response(3)["body"]["breakpoints"][0]["verified"], true);
+
+        let stopped: Vec<&Json> = event("stopped").collect();
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0]["body"]["reason"], "breakpoint");
+        assert_eq!(response(5)["body"]["stackFrames"][0]["line"], 3);
+        assert_eq!(
+            response(6)["body"]["variables"],
+            json!([{ "name": "அ", "value": "2", "variablesReference": 0 }])
+        );
+
+        // Printed output arrives as events, never on the protocol's stdout.
+        let printed: String = event("output")
+            .map(|m| m["body"]["output"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(printed, "2\n6\n");
+        assert_eq!(event("terminated").count(), 1);
+    }
+
+    #[test]
+    fn a_runtime_error_stops_where_it_happened() {
+        let dir = std::env::temp_dir().join("etamil_dap_error");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("pizY.qmz");
+        std::fs::write(&file, "அ = 0;\nஆ = 1 / அ;\n").unwrap();
+
+        let sent = session(&[
+            request(1, "initialize", json!({})),
+            request(2, "launch", json!({ "program": file.to_str().unwrap() })),
+            request(3, "configurationDone", json!({})),
+            request(4, "stackTrace", json!({ "threadId": 1 })),
+            request(5, "disconnect", json!({})),
+        ]);
+
+        let stopped = sent.iter().find(|m| m["event"] == "stopped").unwrap();
+        assert_eq!(stopped["body"]["reason"], "exception");
+        let trace = sent.iter().find(|m| m["request_seq"] == 4).unwrap();
+        assert_eq!(trace["body"]["stackFrames"][0]["line"], 2);
+    }
+
+    #[test]
+    fn a_program_that_does_not_load_fails_the_launch() {
+        let sent = session(&[
+            request(1, "initialize", json!({})),
+            request(2, "launch", json!({ "program": "/nowhere/illai.qmz" })),
+            request(3, "disconnect", json!({})),
+        ]);
+
+        let launch = sent.iter().find(|m| m["request_seq"] == 2).unwrap();
+        assert_eq!(launch["success"], false);
+        assert!(sent.iter().all(|m| m["event"] != "initialized"));
+    }
+}
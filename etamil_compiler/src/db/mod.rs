@@ -85,22 +85,44 @@ pub fn open(db_type: &str, connection: &str) -> Result<Box<dyn Database>, String
     }
 }
 
+/// A text column coming back out: an amount if `params_from` wrote one there,
+/// otherwise the text itself.
+pub fn text_value(text: String) -> Value {
+    match crate::money::from_text(&text) {
+        Some((amount, currency)) => Value::Money { amount, currency },
+        None => Value::String(text),
+    }
+}
+
 /// Convert an eTamil array of parameters into a slice the drivers can bind.
 /// Anything that is not an array is a mistake worth naming clearly, since
 /// the syntax always requires one.
+///
+/// An amount is bound as the text `1500.50 INR`, since no portable column type
+/// holds a currency; `text_value` reads it back as the amount it was.
 pub fn params_from(value: &Value) -> Result<Vec<Value>, String> {
     match value {
-        Value::Array(items) => Ok(items.clone()),
+        Value::Array(items) => Ok(items
+            .iter()
+            .map(|item| match item {
+                Value::Money { amount, currency } => {
+                    Value::String(crate::money::to_text(*amount, currency))
+                }
+                other => other.clone(),
+            })
+            .collect()),
         other => Err(format!(
             "அளவுருக்கள் ஒரு அணியாக இருக்க வேண்டும்  \
              (query parameters must be an array, got {})",
             match other {
                 Value::Number(_) => "a number",
+                Value::Money { .. } => "an amount",
                 Value::String(_) => "a string",
                 Value::Boolean(_) => "a boolean",
                 Value::Map(_) => "a record",
                 Value::Ok(_) | Value::Err(_) => "a result",
                 Value::Null => "nil",
+                Value::Function(_) => "a function",
                 Value::Array(_) => unreachable!(),
             }
         )),
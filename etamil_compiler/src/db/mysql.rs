@@ -19,7 +19,7 @@ use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use super::Database;
+use super::{text_value, Database};
 use crate::vm::Value;
 
 pub struct MysqlDatabase {
@@ -125,7 +125,7 @@ fn value_from(raw: &MyValue, column_type: ColumnType) -> Value {
             | ColumnType::MYSQL_TYPE_YEAR
             | ColumnType::MYSQL_TYPE_FLOAT
             | ColumnType::MYSQL_TYPE_DOUBLE => decimal_from_bytes(bytes),
-            _ => Value::String(String::from_utf8_lossy(bytes).to_string()),
+            _ => text_value(String::from_utf8_lossy(bytes).to_string()),
         },
 
         // Dates and times as ISO-8601 text, which is how the language handles
@@ -176,6 +176,39 @@ pub fn idle_count(db_type: &str, connection: &str) -> usize {
         .unwrap_or(0)
 }
 
+/// Every database the cache has seen, as `(backend, connection string)`:
+/// the ones a running program has opened. For the server's health check.
+pub fn known() -> Vec<(String, String)> {
+    let mut known: Vec<(String, String)> = cache()
+        .lock()
+        .map(|cache| cache.keys().cloned().collect())
+        .unwrap_or_default();
+    known.sort();
+    known
+}
+
+/// Close every idle connection, as a server does when it stops: a database
+/// told goodbye frees its session at once, where one whose socket simply goes
+/// holds it until its own timeout. How many were closed.
+pub fn close_idle() -> usize {
+    let idle: Vec<Box<dyn Database>> = match cache().lock() {
+        Ok(mut cache) => cache.drain().flat_map(|(_, idle)| idle).collect(),
+        Err(_) => return 0,
+    };
+    let count = idle.len();
+    for mut handle in idle {
+        let _ = handle.close();
+    }
+    count
+}
+
+/// Is this database answering? A connection is borrowed as a handler would
+/// borrow one, and asked `SELECT 1`.
+pub fn ping(db_type: &str, connection: &str) -> Result<(), String> {
+    let mut lease = checkout(db_type, connection)?;
+    lease.as_mut().query("SELECT 1", &[]).map(|_| ())
+}
+
 #[cfg(all(test, feature = "sqlite"))]
 mod tests {
     use super::*;
@@ -283,6 +316,16 @@ mod tests {
 
         assert_eq!(idle_count("SQLite", &path), 0);
     }
+
+    #[test]
+    fn a_database_a_program_opened_is_known_and_answers_a_ping() {
+        let path = temp_db("ping");
+        let _ = std::fs::remove_file(&path);
+        drop(checkout("SQLite", &path).unwrap());
+
+        assert!(known().contains(&("SQLite".to_string(), path.clone())));
+        assert_eq!(ping("SQLite", &path), Ok(()));
+    }
 }
 
 #[cfg(test)]
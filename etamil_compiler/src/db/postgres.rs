@@ -19,7 +19,7 @@ use std::collections::HashMap;
 use std::error::Error;
 use std::str::FromStr;
 
-use super::Database;
+use super::{text_value, Database};
 use crate::vm::Value;
 
 pub struct PostgresDatabase {
@@ -185,11 +185,12 @@ fn value_from(row: &postgres::Row, index: usize, ty: &Type) -> Result<Value, Str
             .unwrap_or(Value::Null),
 
         // A text column stays text even when it looks like a number: the
-        // schema already said what it is.
+        // schema already said what it is. An amount was written as text, so
+        // it is the one thing read back out of one.
         _ => row
             .try_get::<_, Option<String>>(index)
             .map_err(|e| read("a text", e))?
-            .map(Value::String)
+            .map(text_value)
             .unwrap_or(Value::Null),
     })
 }
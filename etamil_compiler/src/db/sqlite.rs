@@ -10,7 +10,7 @@ use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use super::Database;
+use super::{text_value, Database};
 use crate::vm::Value;
 
 pub struct SqliteDatabase {
@@ -55,12 +55,13 @@ fn value_from(raw: ValueRef<'_>) -> Value {
         ValueRef::Null => Value::Null,
         ValueRef::Integer(i) => Value::Number(Decimal::from(i)),
         // Text that parses as a decimal comes back as a number, which is what
-        // makes the text round-trip above lossless.
+        // makes the text round-trip above lossless. The same goes for an
+        // amount, which went in as `1500.50 INR`.
         ValueRef::Text(bytes) => {
             let text = String::from_utf8_lossy(bytes).to_string();
             match Decimal::from_str(&text) {
                 Ok(number) => Value::Number(number),
-                Err(_) => Value::String(text),
+                Err(_) => text_value(text),
             }
         }
         ValueRef::Real(f) => Decimal::from_str(&f.to_string())
@@ -0,0 +1,265 @@
+//! `etamil --debug FILE`: a debugger at the console.
+//!
+//! For the case `அச்சு` cannot reach: a ledger total is wrong, and the
+//! question is which line made it wrong. Set a breakpoint, run to it, and look
+//! at what every variable holds; step a statement at a time from there.
+//!
+//! The program's own input and output share the terminal with the debugger's.
+//! A `உள்ளிடு` reads the next line typed, the same as it would without the
+//! debugger, so the program has to be paused somewhere other than on that
+//! statement for the line to be a command.
+//!
+//! Everything that decides where to stop is in `vm::debug`, which the Debug
+//! Adapter Protocol front end in `dap.rs` shares.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::module;
+use crate::parser::Position;
+use crate::vm::debug::{self, Debugger, Resume, Stop};
+use crate::vm::BytecodeCompiler;
+
+const HELP: &str = "\
+  b, break [FILE:]LINE   stop when that line is reached
+  d, delete [FILE]       remove the breakpoints (in FILE, or everywhere)
+  c, continue            run to the next breakpoint
+  s, step                one statement, into a call if there is one
+  n, next                one statement, over a call
+  o, out                 run until this செயல் returns
+  p, print NAME          what a variable holds
+  v, vars [N]            every variable this call can see (N: a call from `stack`)
+  bt, stack              the calls in progress, innermost first
+  l, list                the lines around where it stopped
+  q, quit                stop debugging
+  h, help                this
+
+A blank line repeats the last command, so stepping is one key at a time.";
+
+/// Debug the program in `path`, reading commands from `input`.
+///
+/// Answers whether it ended well: run to completion, or left with `quit`,
+/// rather than failing to load or stopping on a runtime error.
+pub fn run(path: &Path, input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+    let ast = match module::load_file(path) {
+        Ok(ast) => ast,
+        Err(message) => {
+            writeln!(output, "✗ {}", message)?;
+            return Ok(false);
+        }
+    };
+    if let Err(errors) = crate::check::check(&ast) {
+        for error in &errors {
+            writeln!(output, "✗ {}", error)?;
+        }
+        return Ok(false);
+    }
+
+    let mut session = Session {
+        debugger: Debugger::new(BytecodeCompiler::compile_statements(ast)),
+        main: path.to_path_buf(),
+        sources: HashMap::new(),
+    };
+    writeln!(
+        output,
+        "eTamil பிழைநீக்கி  (debugger) — {}. `h` for help; `s` to start at the first statement, `c` to run to a breakpoint.",
+        path.display()
+    )?;
+
+    let mut last = String::new();
+    let mut lines = input.lines();
+    loop {
+        write!(output, "(etamil) ")?;
+        output.flush()?;
+        let Some(line) = lines.next() else {
+            // End of input: leave, as `quit` would.
+            writeln!(output)?;
+            return Ok(true);
+        };
+        let line = line?;
+        let line = match line.trim() {
+            "" => last.clone(),
+            typed => typed.to_string(),
+        };
+        last = line.clone();
+
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line.as_str(), ""),
+        };
+        match command {
+            "" => {}
+            "q" | "quit" => return Ok(true),
+            "h" | "help" => writeln!(output, "{}", HELP)?,
+            "b" | "break" => session.set_breakpoint(argument, &mut output)?,
+            "d" | "delete" => {
+                match argument {
+                    "" => session.debugger.clear_breakpoints(|_| true),
+                    file => session.debugger.clear_breakpoints(|at| same_file(at, Path::new(file))),
+                }
+                writeln!(output, "  முறிப்புப் புள்ளிகள் நீக்கப்பட்டன  (breakpoints removed)")?;
+            }
+            "c" | "continue" | "r" | "run" => {
+                if !session.resume(Resume::Continue, &mut output)? {
+                    return Ok(false);
+                }
+            }
+            "s" | "step" => {
+                if !session.resume(Resume::StepInto, &mut output)? {
+                    return Ok(false);
+                }
+            }
+            "n" | "next" => {
+                if !session.resume(Resume::StepOver, &mut output)? {
+                    return Ok(false);
+                }
+            }
+            "o" | "out" | "finish" => {
+                if !session.resume(Resume::StepOut, &mut output)? {
+                    return Ok(false);
+                }
+            }
+            "p" | "print" => match session.debugger.lookup(argument) {
+                Some(value) => writeln!(output, "  {} = {}", argument, debug::shown(value))?,
+                None => writeln!(
+                    output,
+                    "  '{}' இங்கு இல்லை  ('{}' is not defined here)",
+                    argument, argument
+                )?,
+            },
+            "v" | "vars" => {
+                let call = argument.parse().unwrap_or(0);
+                let variables = session.debugger.variables(call);
+                if variables.is_empty() {
+                    writeln!(output, "  (மாறிகள் இல்லை  — no variables)")?;
+                }
+                for (name, value) in variables {
+                    writeln!(output, "  {} = {}", name, debug::shown(&value))?;
+                }
+            }
+            "bt" | "stack" => {
+                for (number, call) in session.debugger.calls().iter().enumerate() {
+                    writeln!(output, "  #{} {} — {}", number, call.function, place(call.at.as_ref()))?;
+                }
+            }
+            "l" | "list" => session.list(&mut output)?,
+            unknown => writeln!(
+                output,
+                "  '{}' தெரியாத கட்டளை  (unknown command '{}'); `h` for help",
+                unknown, unknown
+            )?,
+        }
+    }
+}
+
+struct Session {
+    debugger: Debugger,
+    main: std::path::PathBuf,
+    /// The lines of each file shown so far, by the name positions carry.
+    sources: HashMap<String, Vec<String>>,
+}
+
+impl Session {
+    /// `12` in the file being debugged, or `lib.qmz:12` in an imported one.
+    fn set_breakpoint(&mut self, argument: &str, output: &mut impl Write) -> io::Result<()> {
+        let (file, line) = match argument.rsplit_once(':') {
+            Some((file, line)) => (Path::new(file).to_path_buf(), line),
+            None => (self.main.clone(), argument),
+        };
+        let Ok(line) = line.trim().parse::<usize>() else {
+            return writeln!(output, "  வரி எண் தேவை  (a line number is needed): break 12, or break lib.qmz:12");
+        };
+        match self.debugger.set_breakpoint(line, |at| same_file(at, &file)) {
+            Some(landed) => writeln!(
+                output,
+                "  முறிப்புப் புள்ளி  (breakpoint): {}, வரி {}  (line {})",
+                file.display(),
+                landed,
+                landed
+            ),
+            None => writeln!(
+                output,
+                "  {} இல் வரி {} அல்லது அதன் பின் கூற்று இல்லை  (no statement at or after line {} in {})",
+                file.display(),
+                line,
+                line,
+                file.display()
+            ),
+        }
+    }
+
+    /// Resume, and say where it stopped. `false` once the program has failed.
+    fn resume(&mut self, how: Resume, output: &mut impl Write) -> io::Result<bool> {
+        // The program prints to the same terminal: what the debugger has
+        // written must come out first.
+        output.flush()?;
+        match self.debugger.resume(how) {
+            Ok(Stop::Finished) => {
+                writeln!(output, "✓ நிரல் முடிந்தது  (the program finished)")?;
+                Ok(true)
+            }
+            Ok(stop) => {
+                if stop == Stop::Breakpoint {
+                    write!(output, "● ")?;
+                } else {
+                    write!(output, "→ ")?;
+                }
+                let at = self.debugger.position().cloned();
+                let text = at.as_ref().and_then(|at| self.line(at, at.line)).unwrap_or_default();
+                writeln!(output, "{}\n    {}", place(at.as_ref()), text.trim())?;
+                Ok(true)
+            }
+            Err(message) => {
+                writeln!(output, "✗ Runtime error: {}", message)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Five lines either side of the current one.
+    fn list(&mut self, output: &mut impl Write) -> io::Result<()> {
+        let Some(at) = self.debugger.position().cloned() else {
+            return writeln!(output, "  (இன்னும் தொடங்கவில்லை  — not started yet)");
+        };
+        for number in at.line.saturating_sub(5).max(1)..=at.line + 5 {
+            let Some(text) = self.line(&at, number) else {
+                break;
+            };
+            let marker = if number == at.line { "→" } else { " " };
+            writeln!(output, "{} {:>4}  {}", marker, number, text)?;
+        }
+        Ok(())
+    }
+
+    /// One line of the file `at` is in.
+    fn line(&mut self, at: &Position, number: usize) -> Option<String> {
+        let file = at.file.as_deref()?;
+        let lines = self.sources.entry(file.to_string()).or_insert_with(|| {
+            std::fs::read_to_string(file)
+                .map(|text| text.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        });
+        lines.get(number.checked_sub(1)?).cloned()
+    }
+}
+
+/// Whether a position's file is `path`. Positions name a file as the author
+/// would type it, relative to the working directory, so both are resolved
+/// before comparing.
+pub(crate) fn same_file(at: Option<&str>, path: &Path) -> bool {
+    let Some(at) = at else {
+        return false;
+    };
+    match (Path::new(at).canonicalize(), path.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(at) == path,
+    }
+}
+
+fn place(at: Option<&Position>) -> String {
+    match at {
+        Some(at) => at.to_string(),
+        None => "இடம் தெரியவில்லை  (position unknown)".to_string(),
+    }
+}
@@ -0,0 +1,557 @@
+//! `etamil fmt`: one layout for every `.qmz` file.
+//!
+//! The layout is decided from the tokens, not printed back from the AST. The
+//! tree forgets exactly what a formatter must keep: which of the three
+//! spellings each keyword was written in, how a number or a string literal was
+//! written (`1.50`, `"a\"b"`), and every comment. So the program is parsed
+//! first — a file that does not parse is refused, because where its statements
+//! end is not known — and then its tokens are laid out again:
+//!
+//! * one statement per line, indented four spaces per block;
+//! * `{` on the line that opens the block, `}` on a line of its own, and
+//!   `இன்றேல்` on the line after the `}` it follows;
+//! * a block the author wrote on one line — `(x) எனில் { திரும்பு y; }` —
+//!   stays on one line, as long as it has no block inside it;
+//! * one space around operators and after commas, none inside brackets;
+//! * a line break the author put inside a statement is kept, and the line it
+//!   starts is indented one level for each bracket still open. Long calls and
+//!   tables in nUlakam are broken by hand where they read best, and no rule
+//!   here would break them better;
+//! * at most one blank line in a row, and none just inside a block;
+//! * comments stay where they were, on their own line or after the code, with
+//!   the author's spacing before a trailing one so aligned columns survive.
+//!
+//! With a script chosen, every keyword is written in that script. Only
+//! keywords: a financial keyword used as a name — `வரி = 5;` — is the
+//! author's variable, and `வரி` and `vari` are two different variables.
+//!
+//! Before anything is returned, the result is tokenized again and compared
+//! with the original, token for token and comment for comment. A formatter
+//! that changed what a program means would be worse than none.
+//!
+//! Portable, like the lexer and parser it reads with.
+
+use crate::lexer::{self, Spanned, Token};
+use crate::parser::Parser;
+use crate::script::{self, Script};
+
+const INDENT: &str = "    ";
+
+/// Lay `source` out in the standard style, with every keyword in `script` if
+/// one is given.
+pub fn format(source: &str, script: Option<Script>) -> Result<String, String> {
+    let tokens = lexer::tokenize_with_comments(source).map_err(lex_errors)?;
+    let code: Vec<Spanned> = tokens
+        .iter()
+        .filter(|t| t.token != Token::Comment)
+        .cloned()
+        .collect();
+    Parser::new(code.iter()).parse().map_err(|e| e.to_string())?;
+
+    let roles = roles(&tokens);
+    let formatted = Layout::new(&tokens, &roles, script).write();
+
+    let again = lexer::tokenize_with_comments(&formatted).map_err(lex_errors)?;
+    if !same_program(&tokens, &again) {
+        return Err(
+            "வடிவமைப்பு நிரலை மாற்றியிருக்கும்; கோப்பு தொடப்படவில்லை  \
+             (formatting would have changed the program, so nothing was written — \
+             this is a bug in the formatter)"
+                .to_string(),
+        );
+    }
+    Ok(formatted)
+}
+
+fn lex_errors(errors: Vec<lexer::LexError>) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("\n  ")
+}
+
+/// The same tokens in the same order, and the same comments.
+fn same_program(before: &[Spanned], after: &[Spanned]) -> bool {
+    before.len() == after.len()
+        && before.iter().zip(after).all(|(a, b)| {
+            a.token == b.token && (a.token != Token::Comment || a.text.trim_end() == b.text)
+        })
+}
+
+// --- What each brace is ----------------------------------------------------
+
+/// `{` opens both a block and a record, and they are laid out differently, so
+/// every brace is classified before anything is written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    Other,
+    /// Statements. `inline` when the author wrote the whole block on one line
+    /// and it has no block inside it.
+    Block { inline: bool },
+    Record,
+}
+
+/// Whether a value can end at this token — so a `(` after it is a call, a
+/// `[` an index, a `-` subtraction, and a `{` the start of a block.
+fn ends_value(token: &Token, role: Role) -> bool {
+    match token {
+        Token::RParen | Token::RBracket | Token::Question => true,
+        Token::RBrace => role == Role::Record,
+        Token::Number(_)
+        | Token::Percentage(_)
+        | Token::Money(_)
+        | Token::String(_)
+        | Token::True
+        | Token::False
+        | Token::Null => true,
+        Token::Comment => false,
+        other => Parser::is_name(other),
+    }
+}
+
+fn roles(tokens: &[Spanned]) -> Vec<Role> {
+    let mut roles = vec![Role::Other; tokens.len()];
+    // Each open brace: where it is, and whether a block has opened inside it.
+    let mut open: Vec<(usize, bool)> = Vec::new();
+    let mut previous: Option<usize> = None;
+
+    for (i, t) in tokens.iter().enumerate() {
+        match t.token {
+            Token::Comment => continue,
+            Token::LBrace => {
+                // A block follows a value (`ஒவ்வொரு x இல் xs {`, `வழி பெறு, "/" {`),
+                // a closing parenthesis, a return type, or எனில்/சுற்று/இன்றேல்.
+                // A record follows anything that expects a value next.
+                let block = previous.is_some_and(|p| {
+                    let before = &tokens[p].token;
+                    ends_value(before, roles[p])
+                        || Parser::is_type_token(before)
+                        || matches!(before, Token::If | Token::Loop | Token::Else)
+                });
+                if block {
+                    roles[i] = Role::Block { inline: false };
+                    for (at, nested) in open.iter_mut().rev() {
+                        if matches!(roles[*at], Role::Block { .. }) {
+                            *nested = true;
+                            break;
+                        }
+                    }
+                } else {
+                    roles[i] = Role::Record;
+                }
+                open.push((i, false));
+            }
+            Token::RBrace => {
+                if let Some((at, nested)) = open.pop() {
+                    if roles[at] == Role::Record {
+                        roles[i] = Role::Record;
+                    } else {
+                        let inline = !nested && tokens[at].line == t.line;
+                        roles[at] = Role::Block { inline };
+                        roles[i] = Role::Block { inline };
+                    }
+                }
+            }
+            _ => {}
+        }
+        previous = Some(i);
+    }
+    roles
+}
+
+// --- Layout ----------------------------------------------------------------
+
+/// An open bracket, while writing.
+#[derive(Clone, Copy, PartialEq)]
+enum Open {
+    /// `value` for the body of a `செயல்` written as a value, after which the
+    /// statement it is part of carries on.
+    Block { inline: bool, value: bool },
+    /// `(`, `[`, or a record's `{`, and the indentation of the line it was
+    /// opened on.
+    Group { level: usize },
+}
+
+struct Layout<'a> {
+    tokens: &'a [Spanned],
+    roles: &'a [Role],
+    script: Option<Script>,
+    out: String,
+    open: Vec<Open>,
+    /// Blocks open that indent — every block but an inline one.
+    depth: usize,
+    /// The indentation of the line being written.
+    level: usize,
+    /// At the start of an output line, with nothing on it yet.
+    line_start: bool,
+    /// A statement has ended; the next token starts a new line.
+    pending_break: bool,
+    /// Somewhere inside a statement, so a new line continues it.
+    in_statement: bool,
+    /// Just after a block's `{`, where a blank line is never kept.
+    block_opened: bool,
+    /// The last token written that was not a comment.
+    previous: Option<usize>,
+    /// The last token written, comment or not.
+    previous_any: Option<usize>,
+    /// The previous token was a unary minus, which its operand follows directly.
+    unary: bool,
+    /// A `செயல்` has been written inside a statement, so the next block is
+    /// the body of a function value.
+    function_value: bool,
+}
+
+impl<'a> Layout<'a> {
+    fn new(tokens: &'a [Spanned], roles: &'a [Role], script: Option<Script>) -> Self {
+        Layout {
+            tokens,
+            roles,
+            script,
+            out: String::new(),
+            open: Vec::new(),
+            depth: 0,
+            level: 0,
+            line_start: true,
+            pending_break: false,
+            in_statement: false,
+            block_opened: false,
+            previous: None,
+            previous_any: None,
+            unary: false,
+            function_value: false,
+        }
+    }
+
+    fn write(mut self) -> String {
+        for i in 0..self.tokens.len() {
+            if self.tokens[i].token == Token::Comment {
+                self.comment(i);
+            } else {
+                self.token(i);
+            }
+            self.previous_any = Some(i);
+        }
+
+        let mut out = self.out.trim_end().to_string();
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+
+    fn token(&mut self, i: usize) {
+        let t = &self.tokens[i];
+        let role = self.roles[i];
+        let closes_block = t.token == Token::RBrace && role == Role::Block { inline: false };
+        let gap = self.lines_since_previous(i);
+
+        if closes_block {
+            self.depth = self.depth.saturating_sub(1);
+        }
+
+        // The author's own line breaks inside a statement are kept — except
+        // where this style puts the token on the same line regardless.
+        let keeps_author_break = self.in_statement
+            && gap > 0
+            && role != Role::Block { inline: false }
+            && !matches!(t.token, Token::Semicolon | Token::If | Token::Loop);
+
+        if self.pending_break || closes_block || keeps_author_break || self.line_start {
+            let blank = gap > 1 && !self.in_statement && !self.block_opened && !closes_block;
+            self.break_line(blank);
+            self.indent(self.continuation(&t.token, role));
+        } else if self.space_before(i) {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(&self.spelling(t));
+        self.block_opened = false;
+        if t.token == Token::Function {
+            self.function_value = self.in_statement;
+        }
+        self.unary = t.token == Token::Minus
+            && !self
+                .previous
+                .is_some_and(|p| ends_value(&self.tokens[p].token, self.roles[p]));
+        self.previous = Some(i);
+
+        match (&t.token, role) {
+            (Token::LBrace, Role::Block { inline }) => {
+                let value = std::mem::take(&mut self.function_value);
+                self.open.push(Open::Block { inline, value });
+                self.in_statement = false;
+                if !inline {
+                    self.depth += 1;
+                    self.pending_break = true;
+                    self.block_opened = true;
+                }
+            }
+            (Token::RBrace, Role::Block { .. }) => {
+                let value = matches!(self.open.pop(), Some(Open::Block { value: true, .. }));
+                // `});` — a function value's body closes, and the statement
+                // around it goes on.
+                self.in_statement = value;
+                self.pending_break = !value;
+            }
+            (Token::LParen | Token::LBracket, _) | (Token::LBrace, Role::Record) => {
+                self.open.push(Open::Group { level: self.level });
+                self.in_statement = true;
+            }
+            (Token::RParen | Token::RBracket, _) | (Token::RBrace, Role::Record) => {
+                self.open.pop();
+                self.in_statement = true;
+            }
+            (Token::Semicolon, _) => {
+                self.in_statement = false;
+                // Inside a one-line block the next statement follows on the
+                // same line.
+                if !matches!(self.open.last(), Some(Open::Block { inline: true, .. })) {
+                    self.pending_break = true;
+                }
+            }
+            _ => self.in_statement = true,
+        }
+    }
+
+    fn comment(&mut self, i: usize) {
+        let t = &self.tokens[i];
+        let text = t.text.trim_end();
+        let trailing = !self.line_start
+            && self
+                .previous_any
+                .is_some_and(|p| end_line(&self.tokens[p]) == t.line);
+
+        if trailing {
+            let p = &self.tokens[self.previous_any.unwrap_or(i)];
+            let spaces = t.column.saturating_sub(end_column(p)).max(1);
+            self.out.push_str(&" ".repeat(spaces));
+        } else {
+            let blank = self.lines_since_previous(i) > 1 && !self.in_statement && !self.block_opened;
+            self.break_line(blank);
+            self.indent(self.continuation(&t.token, Role::Other));
+        }
+
+        self.out.push_str(text);
+        self.out.push('\n');
+        self.line_start = true;
+        self.pending_break = false;
+        self.block_opened = false;
+    }
+
+    /// End the current line, if anything is on it, and leave one blank line
+    /// if asked.
+    fn break_line(&mut self, blank: bool) {
+        if !self.line_start {
+            self.out.push('\n');
+        }
+        if blank && !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+        self.line_start = true;
+        self.pending_break = false;
+    }
+
+    fn indent(&mut self, levels: usize) {
+        for _ in 0..levels {
+            self.out.push_str(INDENT);
+        }
+        self.level = levels;
+        self.line_start = false;
+    }
+
+    /// How far a line starting with `token` is indented: a new statement at
+    /// the block's depth, a line inside brackets one level past the line that
+    /// opened them, and a closing bracket level with that line. Two brackets
+    /// opened on one line — `f([` — are one level, not two.
+    fn continuation(&self, token: &Token, role: Role) -> usize {
+        if !self.in_statement {
+            return self.depth;
+        }
+        let closes_group = matches!(token, Token::RParen | Token::RBracket)
+            || (*token == Token::RBrace && role == Role::Record);
+        match self.open.last() {
+            Some(Open::Group { level }) if closes_group => *level,
+            Some(Open::Group { level }) => level + 1,
+            _ => self.depth + 1,
+        }
+    }
+
+    fn space_before(&self, i: usize) -> bool {
+        let t = &self.tokens[i];
+        let Some(p) = self.previous else {
+            return false;
+        };
+        let previous = &self.tokens[p].token;
+
+        match t.token {
+            Token::RParen
+            | Token::RBracket
+            | Token::Comma
+            | Token::Semicolon
+            | Token::Dot
+            | Token::Question
+            | Token::Colon => return false,
+            Token::RBrace => return self.roles[i] != Role::Record,
+            _ => {}
+        }
+        match previous {
+            Token::LParen | Token::LBracket | Token::Dot => return false,
+            Token::LBrace => return self.roles[p] != Role::Record,
+            _ => {}
+        }
+        if self.unary {
+            return false;
+        }
+        match t.token {
+            // A call: `நீளம்(x)`, but `அச்சு (x);`. A function value's
+            // parameters are written as a call's are: `செயல்(அ)`.
+            Token::LParen => !Parser::is_name(previous) && *previous != Token::Function,
+            // An index: `x[0]`, but `= [1, 2]`.
+            Token::LBracket => !ends_value(previous, self.roles[p]),
+            _ => true,
+        }
+    }
+
+    /// How a token is written: as the author wrote it, or a keyword in the
+    /// chosen script.
+    fn spelling(&self, t: &Spanned) -> String {
+        if let Some(script) = self.script
+            && !Parser::is_name(&t.token)
+            && let Some(keyword) = script::keyword(&t.text)
+        {
+            return keyword.spelling(script).to_string();
+        }
+        t.text.clone()
+    }
+
+    /// Source lines between the previous token's end and this one's start.
+    fn lines_since_previous(&self, i: usize) -> usize {
+        match self.previous_any {
+            Some(p) => self.tokens[i].line.saturating_sub(end_line(&self.tokens[p])),
+            None => 0,
+        }
+    }
+}
+
+/// The line a token ends on. A string literal may run over several.
+fn end_line(t: &Spanned) -> usize {
+    t.line + t.text.matches('\n').count()
+}
+
+/// The column just past a token's last character.
+fn end_column(t: &Spanned) -> usize {
+    match t.text.rsplit_once('\n') {
+        Some((_, last)) => last.chars().count() + 1,
+        None => t.column + t.text.chars().count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(source: &str) -> String {
+        format(source, None).unwrap()
+    }
+
+    #[test]
+    fn statements_go_one_to_a_line_and_blocks_indent() {
+        let source = "செயல் f(அ){\nதிரும்பு அ*2;}\nஒவ்வொரு x இல் [1,2] { அச்சு f( x );\nஅச்சு x; }\n";
+
+        assert_eq!(
+            fmt(source),
+            "செயல் f(அ) {\n    திரும்பு அ * 2;\n}\nஒவ்வொரு x இல் [1, 2] {\n    அச்சு f(x);\n    அச்சு x;\n}\n"
+        );
+    }
+
+    #[test]
+    fn a_function_value_closes_back_into_its_statement() {
+        let source = "x = சுருக்கு(அ, 0, செயல் (ம, n) {\nதிரும்பு ம + n;\n} );\nf = செயல்(n) { திரும்பு n; };\n";
+
+        assert_eq!(
+            fmt(source),
+            "x = சுருக்கு(அ, 0, செயல்(ம, n) {\n    திரும்பு ம + n;\n});\nf = செயல்(n) { திரும்பு n; };\n"
+        );
+    }
+
+    #[test]
+    fn else_goes_on_the_line_after_the_brace() {
+        let source = "(அ > 1) எனில் {\nஅச்சு 1;\n} இன்றேல் {\nஅச்சு 2;\n}\n";
+
+        assert_eq!(
+            fmt(source),
+            "(அ > 1) எனில் {\n    அச்சு 1;\n}\nஇன்றேல் {\n    அச்சு 2;\n}\n"
+        );
+    }
+
+    #[test]
+    fn a_one_line_block_stays_on_one_line() {
+        assert_eq!(fmt("(அ < 0) எனில் {திரும்பு 0;}\n"), "(அ < 0) எனில் { திரும்பு 0; }\n");
+    }
+
+    #[test]
+    fn comments_are_kept_where_they_were() {
+        let source = "// தலைப்பு\n\n\n\nஅ = 1;   // ஒன்று\nசெயல் f() {\n    // உள்ளே\n    திரும்பு 1;\n}\n";
+
+        assert_eq!(
+            fmt(source),
+            "// தலைப்பு\n\nஅ = 1;   // ஒன்று\nசெயல் f() {\n    // உள்ளே\n    திரும்பு 1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn a_break_inside_a_statement_is_kept_and_indented() {
+        let source = "ஓட்டம் = சமம்(ஓட்டம், 1,\n                \"message\");\nஅட்டவணை = {\n\"அ\":   1,\n\"ஆ\": 2\n};\n";
+
+        assert_eq!(
+            fmt(source),
+            "ஓட்டம் = சமம்(ஓட்டம், 1,\n    \"message\");\nஅட்டவணை = {\n    \"அ\": 1,\n    \"ஆ\": 2\n};\n"
+        );
+    }
+
+    #[test]
+    fn brackets_opened_together_indent_once() {
+        assert_eq!(
+            fmt("விடை = ஓட்டு([\n\"a\",\n\"b\"\n], 120);\n"),
+            "விடை = ஓட்டு([\n    \"a\",\n    \"b\"\n], 120);\n"
+        );
+    }
+
+    #[test]
+    fn records_indexes_and_negatives_are_spaced_as_written_by_hand() {
+        assert_eq!(
+            fmt("r = { அ : -1 , ஆ : [ x [ 0 ] ] };\nஅச்சு r . அ;\n"),
+            "r = {அ: -1, ஆ: [x[0]]};\nஅச்சு r.அ;\n"
+        );
+    }
+
+    #[test]
+    fn keywords_are_rewritten_into_one_script_but_names_are_not() {
+        let source = "வரி = 5;\nஎண் x = வரி;\n(x > 1) eZil { accu x; }\n";
+
+        assert_eq!(
+            format(source, Some(Script::Roman)).unwrap(),
+            "வரி = 5;\neN x = வரி;\n(x > 1) eZil { accu x; }\n"
+        );
+        assert_eq!(
+            format(source, Some(Script::Tamil)).unwrap(),
+            "வரி = 5;\nஎண் x = வரி;\n(x > 1) எனில் { அச்சு x; }\n"
+        );
+    }
+
+    #[test]
+    fn formatting_twice_changes_nothing() {
+        let source = "// c\nசெயல் f(எண் அ) எண் {\n(அ>0) எனில் {திரும்பு அ;}\nதிரும்பு சமம்(அ,\n1); // t\n}\n";
+        let once = fmt(source);
+
+        assert_eq!(fmt(&once), once);
+    }
+
+    #[test]
+    fn a_program_that_does_not_parse_is_refused() {
+        let error = format("அச்சு 1\n", None).unwrap_err();
+
+        assert!(error.contains("';'"), "{}", error);
+    }
+}
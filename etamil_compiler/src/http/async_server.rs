@@ -17,30 +17,125 @@
 //! its request. Here, a connection costs a task rather than a thread, and only
 //! the handler itself needs a thread.
 
-use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
 
-use crate::http::handler::dispatch;
+use crate::http::connection::{self, Incoming, Limits, Next};
+use crate::http::cors::Cors;
+use crate::http::files::{self, Mount};
+use crate::http::handler::{self, dispatch_streaming};
+use crate::http::idempotency::Idempotency;
+use crate::http::logging::{self, Logger};
+use crate::http::monitoring::{HealthChecker, MetricsCollector};
+use crate::http::observe::Observe;
+use crate::http::ratelimit::RateLimits;
+use crate::http::reload::Table;
+use crate::http::shutdown::{self, InFlight};
+use crate::http::websocket;
 use crate::http::{HttpRequest, HttpResponse};
-use crate::parser::Stmt;
+use crate::parser::{Hook, Stmt};
 use crate::vm::{Bytecode, BytecodeCompiler};
 
-/// One HTTP request may be at most this large, so a client cannot exhaust
-/// memory by promising a body it never sends.
-const MAX_REQUEST: usize = 1024 * 1024;
 const CHUNK: usize = 4096;
 
 pub struct AsyncHttpServer {
     host: String,
     port: u16,
-    /// Compiled once at registration and shared with every task; a request
-    /// should not pay to recompile its handler.
-    handlers: HashMap<String, Bytecode>,
+    /// Routes, முன் and பின் blocks, and கோப்புறை directories, compiled once
+    /// and shared with every task; see reload.rs for why behind a table.
+    routes: Arc<Table>,
     /// Timed jobs: how often, and what to run.
     schedules: Vec<(u64, Bytecode)>,
+    /// Set to serve HTTPS instead of HTTP; see tls.rs.
+    tls: Option<Arc<rustls::ServerConfig>>,
+    /// Timeouts and body sizes; see connection.rs.
+    limits: Arc<Limits>,
+    /// Whether /metrics is served, and what /health asks; see observe.rs.
+    observe: Observe,
+    /// Where the access log goes; see logging.rs.
+    logger: Logger,
+    /// Requests a client may make, per route prefix; see ratelimit.rs.
+    rate_limits: RateLimits,
+    /// Which other origins' pages may read the answers; see cors.rs.
+    cors: Cors,
+    /// Where a retried POST's answer is kept; see idempotency.rs.
+    idempotency: Idempotency,
+}
+
+/// What every connection's requests are answered from, shared by them all
+/// once the server has started.
+struct Service {
+    routes: Arc<Table>,
+    observe: Observe,
+    metrics: MetricsCollector,
+    health: HealthChecker,
+    logger: Logger,
+    rate_limits: RateLimits,
+    cors: Cors,
+    idempotency: Idempotency,
+    /// Requests being answered, for draining them at a stop.
+    in_flight: InFlight,
+}
+
+impl Service {
+    /// Answer one request — from the built-in routes, or by running the
+    /// program — then count it and log it.
+    fn answer(
+        &self,
+        request: &HttpRequest,
+        request_id: &str,
+        stream: Box<connection::Chunked>,
+        deadline: Option<Instant>,
+    ) -> HttpResponse {
+        let started = Instant::now();
+        let routes = self.routes.current();
+        let mut built_in = self.observe.answer(
+            request,
+            |key| routes.handlers.contains_key(key),
+            &self.metrics,
+            &self.health,
+        );
+        let route = match &built_in {
+            Some(_) => request.path.clone(),
+            None => match handler::route_of(&routes.handlers, request) {
+                Some(route) => {
+                    built_in = self.rate_limits.refuse(request);
+                    route.to_string()
+                }
+                // No route: a preflight for a path with one, or a file, if a
+                // கோப்புறை covers the path.
+                None => match handler::preflight(&routes.handlers, &self.cors, request) {
+                    Some((route, response)) => {
+                        built_in = Some(response);
+                        route.to_string()
+                    }
+                    None => match files::answer(&routes.mounts, request) {
+                        Some((mount, response)) => {
+                            built_in = Some(response);
+                            mount.prefix.clone()
+                        }
+                        None => "unmatched".to_string(),
+                    },
+                },
+            },
+        };
+        let mut response = match built_in {
+            Some(response) => response,
+            None => dispatch_streaming(&routes, &self.idempotency, request, Some(stream), deadline),
+        };
+        let elapsed = started.elapsed();
+        self.metrics.record(&request.method.to_uppercase(), &route, response.status_code, elapsed);
+        self.logger.log(logging::access_entry(request_id, request, response.status_code, elapsed));
+        if !response.streamed {
+            response.set_header("X-Request-Id", request_id);
+            self.cors.decorate(request, &mut response);
+        }
+        response
+    }
 }
 
 impl AsyncHttpServer {
@@ -48,15 +143,74 @@ impl AsyncHttpServer {
         AsyncHttpServer {
             host: host.to_string(),
             port,
-            handlers: HashMap::new(),
+            routes: Arc::new(Table::default()),
             schedules: Vec::new(),
+            tls: None,
+            limits: Arc::new(Limits::default()),
+            observe: Observe::default(),
+            logger: Logger::new(logging::LogLevel::Info),
+            rate_limits: RateLimits::default(),
+            cors: Cors::default(),
+            idempotency: Idempotency::default(),
         }
     }
 
+    /// Serve HTTPS, with this certificate and, if it names a client CA, only
+    /// to clients that present a certificate from it.
+    pub fn use_tls(&mut self, config: Arc<rustls::ServerConfig>) {
+        self.tls = Some(config);
+    }
+
+    /// Time out and size requests by these limits instead of the defaults.
+    pub fn use_limits(&mut self, limits: Limits) {
+        self.limits = Arc::new(limits);
+    }
+
     pub fn register_route(&mut self, method: &str, path: &str, handler: Vec<Stmt>) {
-        let bytecode = BytecodeCompiler::compile_statements(handler);
-        self.handlers
-            .insert(format!("{} {}", method.to_uppercase(), path), bytecode);
+        self.routes
+            .edit(|routes| routes.register_route(method, path, handler));
+    }
+
+    /// Serve /metrics, or check a Redis from /health, as this says.
+    pub fn use_observe(&mut self, observe: Observe) {
+        self.observe = observe;
+    }
+
+    /// Log requests at this level and to this output instead of info to
+    /// stdout.
+    pub fn use_logger(&mut self, logger: Logger) {
+        self.logger = logger;
+    }
+
+    /// Refuse clients that make more requests than these rules allow.
+    pub fn use_rate_limits(&mut self, rate_limits: RateLimits) {
+        self.rate_limits = rate_limits;
+    }
+
+    /// Answer cross-origin requests, and their preflights, by this policy.
+    pub fn use_cors(&mut self, cors: Cors) {
+        self.cors = cors;
+    }
+
+    /// Answer a POST sent again with its Idempotency-Key from what this keeps.
+    pub fn use_idempotency(&mut self, idempotency: Idempotency) {
+        self.idempotency = idempotency;
+    }
+
+    /// Serve a directory's files under a prefix, when no route matches.
+    pub fn register_mount(&mut self, mount: Mount) {
+        self.routes.edit(|routes| routes.register_mount(mount));
+    }
+
+    /// Register a block to run before or after every route under `prefix`.
+    pub fn register_middleware(&mut self, hook: Hook, prefix: &str, body: Vec<Stmt>) {
+        self.routes
+            .edit(|routes| routes.register_middleware(hook, prefix, body));
+    }
+
+    /// Register the setup every request starts with.
+    pub fn register_prelude(&mut self, prelude: Vec<Stmt>) {
+        self.routes.edit(|routes| routes.register_prelude(prelude));
     }
 
     /// Register a block to run on a timer.
@@ -70,11 +224,13 @@ impl AsyncHttpServer {
         self.schedules.push((seconds.max(1), bytecode));
     }
 
-    pub fn routes(&self) -> impl Iterator<Item = &String> {
-        self.handlers.keys()
+    /// The table the routes are answered from, for `--watch` to put new ones
+    /// in.
+    pub fn route_table(&self) -> Arc<Table> {
+        Arc::clone(&self.routes)
     }
 
-    /// Serve until Ctrl-C.
+    /// Serve until a stop is asked for; see shutdown.rs.
     pub async fn start(self) -> Result<(), Box<dyn std::error::Error>> {
         let address = format!("{}:{}", self.host, self.port);
         let listener = TcpListener::bind(&address).await?;
@@ -82,25 +238,53 @@ impl AsyncHttpServer {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         println!("🚀 eTamil HTTP Server (--async)");
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("📍 Listening on: http://{}", address);
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        // The bound address, so that `--port 0` says which port it got.
+        let bound = listener.local_addr().map_or(address, |bound| bound.to_string());
+        println!("📍 Listening on: {}://{}", scheme, bound);
         println!("📋 Registered Routes:");
-        let mut routes: Vec<&String> = self.handlers.keys().collect();
+        let registered = self.routes.current();
+        let mut routes: Vec<&String> = registered.handlers.keys().collect();
         routes.sort();
         for route in routes {
             println!("   {}", route);
         }
+        for route in self.observe.routes() {
+            println!("   {} (built in)", route);
+        }
+        for mount in &registered.mounts {
+            println!("   GET {} → {}", mount.prefix, mount.dir.display());
+        }
+        for rule in self.rate_limits.rules() {
+            println!("⏳ Rate limit: {}", rule);
+        }
+        println!("🌐 CORS: {}", self.cors.describe());
+        if let Some(store) = self.idempotency.describe() {
+            println!("🔁 Idempotency-Key: answers {}", store);
+        }
         println!("🧵 Handlers run on tokio's blocking pool; the VM stays synchronous");
         println!("   Press Ctrl-C to stop.");
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
+        // Flips when a stop is asked for; everything below that loops waits
+        // on it as well as on its own work.
+        let (stop, stopped) = tokio::sync::watch::channel(false);
+        shutdown::on_stop(move || {
+            let _ = stop.send(true);
+        });
+
         // Timed jobs run on the blocking pool like handlers, for the same
         // reason: the VM blocks, and so does whatever driver a job reaches for.
         for (index, (seconds, bytecode)) in self.schedules.into_iter().enumerate() {
             let label = format!("#{} every {}s", index + 1, seconds);
             println!("⏱️  Scheduled job {}", label);
+            let mut stop = stopped.clone();
             tokio::spawn(async move {
                 loop {
-                    tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+                    tokio::select! {
+                        _ = tokio::time::sleep(std::time::Duration::from_secs(seconds)) => {}
+                        _ = stop.wait_for(|stopping| *stopping) => break,
+                    }
                     let bytecode = bytecode.clone();
                     let label = label.clone();
                     // Awaited, so the next tick cannot start before this one
@@ -113,23 +297,56 @@ impl AsyncHttpServer {
             });
         }
 
-        // Shared rather than cloned per request: the handlers are read-only
-        // once the server has started.
-        let handlers = Arc::new(self.handlers);
+        // Shared rather than cloned per request: only `--watch` changes the
+        // routes once the server has started, and it swaps them whole.
+        let service = Arc::new(Service {
+            routes: self.routes,
+            observe: self.observe,
+            metrics: MetricsCollector::new(),
+            health: HealthChecker::new(),
+            logger: self.logger,
+            rate_limits: self.rate_limits,
+            cors: self.cors,
+            idempotency: self.idempotency,
+            in_flight: InFlight::default(),
+        });
+        let acceptor = self.tls.map(TlsAcceptor::from);
+        let limits = self.limits;
 
+        let mut stop = stopped;
         loop {
             tokio::select! {
-                // Shutdown wins over accepting more work.
-                _ = tokio::signal::ctrl_c() => {
-                    println!("\n⏹️  Shutting down.");
-                    return Ok(());
-                }
+                // Stopping wins over accepting more work.
+                _ = stop.wait_for(|stopping| *stopping) => break,
                 accepted = listener.accept() => {
                     match accepted {
-                        Ok((stream, _)) => {
-                            let handlers = Arc::clone(&handlers);
+                        Ok((stream, peer)) => {
+                            let peer = Some(peer.ip());
+                            let service = Arc::clone(&service);
+                            let acceptor = acceptor.clone();
+                            let limits = Arc::clone(&limits);
                             tokio::spawn(async move {
-                                serve_connection(stream, handlers).await;
+                                match acceptor {
+                                    None => serve_connection(stream, false, None, peer, service, limits).await,
+                                    // The handshake runs in this connection's
+                                    // own task, so a slow one holds up nobody;
+                                    // and it is part of the wait for the first
+                                    // request's head, so it cannot take longer.
+                                    Some(acceptor) => {
+                                        let handshake = tokio::time::timeout(
+                                            limits.header_timeout,
+                                            acceptor.accept(stream),
+                                        );
+                                        match handshake.await {
+                                            Ok(Ok(stream)) => {
+                                                let subject = crate::http::tls::client_subject(stream.get_ref().1);
+                                                serve_connection(stream, true, subject, peer, service, limits).await;
+                                            }
+                                            Ok(Err(e)) => eprintln!("⚠️  TLS handshake failed: {}", e),
+                                            Err(_) => eprintln!("⚠️  TLS handshake timed out"),
+                                        }
+                                    }
+                                }
                             });
                         }
                         Err(e) => eprintln!("⚠️  Connection error: {}", e),
@@ -137,90 +354,191 @@ impl AsyncHttpServer {
                 }
             }
         }
+
+        // Stopping: every connection closes after the request it is on. The
+        // wait polls, so it is done off the runtime, which the connections
+        // still need.
+        let timeout = limits.drain_timeout;
+        let unfinished = tokio::task::spawn_blocking(move || shutdown::drain(&service.in_flight, timeout))
+            .await
+            .unwrap_or(0);
+        shutdown::finish(unfinished, timeout);
+        Ok(())
     }
 }
 
-async fn serve_connection(mut stream: TcpStream, handlers: Arc<HashMap<String, Bytecode>>) {
-    let raw = match read_request(&mut stream).await {
-        Some(raw) => raw,
-        None => return,
-    };
-
-    let response = match HttpRequest::parse_bytes(&raw) {
-        Ok(request) => {
-            // The VM blocks — on the interpreter itself, and on whatever
-            // database driver the handler reaches for. Running it here would
-            // stall this runtime thread and every other task sharing it.
-            match tokio::task::spawn_blocking(move || dispatch(&handlers, &request)).await {
-                Ok(response) => response,
-                // The handler panicked. One request must not take the server
-                // with it, so it becomes a 500 like any other failure.
-                Err(e) => {
-                    eprintln!("❌ Handler task failed: {}", e);
-                    HttpResponse::internal_error("handler task failed")
+/// Serve requests off one connection until the client closes it, asks for it
+/// closed, runs out of time, or has sent `MAX_REQUESTS` — the same rules as
+/// the synchronous server, because `connection::Incoming` makes them for both.
+async fn serve_connection(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    secure: bool,
+    client_subject: Option<String>,
+    peer: Option<std::net::IpAddr>,
+    service: Arc<Service>,
+    limits: Arc<Limits>,
+) {
+    let mut incoming = Incoming::new(limits, Duration::ZERO);
+    let mut chunk = [0u8; CHUNK];
+
+    loop {
+        let (mut request, keep, deadline) = match incoming.next_step() {
+            Next::Request { request, keep, deadline } => (request, keep, deadline),
+            // Nothing of another request yet, and the server is stopping.
+            Next::Read(_) if shutdown::stopping() && incoming.between_requests() => break,
+            Next::Read(wait) => {
+                let wait = wait.min(shutdown::CHECK_EVERY);
+                match tokio::time::timeout(wait, stream.read(&mut chunk)).await {
+                    Ok(Ok(n)) => incoming.received(&chunk[..n]),
+                    // Out of time: the next turn says what that means.
+                    Err(_) => {}
+                    Ok(Err(_)) => break,
                 }
+                continue;
             }
-        }
-        Err(e) => HttpResponse::bad_request(&e.to_string()),
-    };
+            Next::Refuse(response) => {
+                send_and_close(&mut stream, response).await;
+                break;
+            }
+            Next::Close => break,
+        };
+        // Counted until its answer is written, not only until it is made: a
+        // drain that ended in between would exit with the answer unsent.
+        let serving = service.in_flight.serving();
+        request.client_subject = client_subject.clone();
+        request.peer = peer;
+        request.secure = secure;
+        let request_id = logging::request_id_of(&mut request);
+        // A WebSocket or an events stream is the connection's last request,
+        // and has no time limit: being open for an hour is what it is for.
+        let long_lived = connection::long_lived(&request);
+        let (keep, deadline) = if long_lived { (false, None) } else { (keep, deadline) };
 
-    let _ = stream.write_all(&response.to_http_bytes()).await;
-    let _ = stream.flush().await;
-}
+        // A handler that streams hands its parts to this task over a channel
+        // and this task writes them, since only it may touch the connection.
+        // The channel is bounded, so a handler cannot outrun a slow client by
+        // more than a few parts.
+        let (sender, mut parts) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+        let sink: connection::Sink = Box::new(move |bytes| {
+            sender.blocking_send(bytes.to_vec()).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the client has gone")
+            })
+        });
+        let mut chunked =
+            connection::Chunked::new(sink, keep).with_header("X-Request-Id", &request_id);
+        for (name, value) in service.cors.headers_for(&request) {
+            chunked = chunked.with_header(&name, &value);
+        }
 
-/// Read one whole request: headers, then exactly as many body bytes as
-/// `Content-Length` promises.
-///
-/// The same rule as the synchronous server. Reading a fixed amount once would
-/// truncate a larger body while leaving the request line and headers intact,
-/// so the parse would still succeed and the handler would act on half a
-/// payload.
-async fn read_request(stream: &mut TcpStream) -> Option<Vec<u8>> {
-    const SEPARATOR: &[u8] = b"\r\n\r\n";
-
-    let mut raw: Vec<u8> = Vec::with_capacity(CHUNK);
-    let mut chunk = [0u8; CHUNK];
-    let mut searched = 0usize;
-
-    let header_end = loop {
-        if let Some(offset) = raw[searched..]
-            .windows(SEPARATOR.len())
-            .position(|window| window == SEPARATOR)
-        {
-            break searched + offset + SEPARATOR.len();
+        // A WebSocket's frames come the other way, over a second channel: read
+        // here, where the connection is, and decoded by the handler's thread.
+        let mut from_client = None;
+        if let Some(key) = websocket::key_of(&request.headers) {
+            let (forward, mut arrived) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+            let runtime = tokio::runtime::Handle::current();
+            let source: connection::Source = Box::new(move |wait| {
+                let next = match wait {
+                    Some(wait) => runtime
+                        .block_on(tokio::time::timeout(wait, arrived.recv()))
+                        .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))?,
+                    None => runtime.block_on(arrived.recv()),
+                };
+                // The channel closes when the client does.
+                Ok(next.unwrap_or_default())
+            });
+            chunked = chunked.with_socket(key, source);
+            from_client = Some(forward);
         }
-        searched = raw.len().saturating_sub(SEPARATOR.len() - 1);
 
-        if raw.len() > MAX_REQUEST {
-            return None;
+        let service = Arc::clone(&service);
+        let (done, finished) = tokio::sync::oneshot::channel();
+        let run = move || {
+            let _ = done.send(service.answer(&request, &request_id, Box::new(chunked), deadline));
+        };
+        // The VM blocks — on the interpreter itself, and on whatever database
+        // driver the handler reaches for. Running it here would stall this
+        // runtime thread and every other task sharing it. A long-lived one
+        // gets a thread of its own rather than one of the blocking pool's, so
+        // a thousand open status screens cannot leave ordinary requests
+        // waiting for a thread.
+        if long_lived {
+            std::thread::spawn(run);
+        } else {
+            tokio::task::spawn_blocking(run);
         }
-        match stream.read(&mut chunk).await {
-            Ok(0) | Err(_) => return None,
-            Ok(n) => raw.extend_from_slice(&chunk[..n]),
+
+        // Runs until the handler is done with the channel, which for one that
+        // does not stream is straight away.
+        let mut broken = false;
+        loop {
+            let forward = from_client.clone();
+            tokio::select! {
+                part = parts.recv() => match part {
+                    Some(bytes) => {
+                        if stream.write_all(&bytes).await.is_err() || stream.flush().await.is_err() {
+                            broken = true;
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                // Only read once there is room to hand the bytes on, so a
+                // client cannot flood a handler that is not listening, and
+                // this task never waits on the handler while it might be
+                // waiting on this task.
+                (permit, read) = async {
+                    match forward {
+                        Some(forward) => (forward.reserve_owned().await, stream.read(&mut chunk).await),
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    let ended = match (permit, read) {
+                        (Ok(permit), Ok(n)) if n > 0 => {
+                            permit.send(chunk[..n].to_vec());
+                            false
+                        }
+                        _ => true,
+                    };
+                    if ended {
+                        from_client = None;
+                    }
+                }
+            }
         }
-    };
-
-    let content_length = String::from_utf8_lossy(&raw[..header_end])
-        .lines()
-        .find_map(|line| {
-            let (name, value) = line.split_once(':')?;
-            if name.trim().eq_ignore_ascii_case("content-length") {
-                value.trim().parse::<usize>().ok()
-            } else {
-                None
+        // From here a handler still streaming is told the client has gone.
+        drop(parts);
+        drop(from_client);
+
+        let mut response = match finished.await {
+            Ok(response) => response,
+            // The handler panicked. One request must not take the server with
+            // it, so it becomes a 500 like any other failure.
+            Err(_) => {
+                eprintln!("❌ Handler task failed");
+                HttpResponse::internal_error("handler task failed")
             }
-        })
-        .unwrap_or(0);
-
-    let want = header_end.saturating_add(content_length).min(MAX_REQUEST);
-    while raw.len() < want {
-        match stream.read(&mut chunk).await {
-            // Closed early: serve what arrived rather than dropping the
-            // connection without an answer.
-            Ok(0) | Err(_) => break,
-            Ok(n) => raw.extend_from_slice(&chunk[..n]),
+        };
+
+        if !response.streamed {
+            connection::mark(&mut response, keep && !shutdown::stopping());
+            broken |= stream.write_all(&response.to_http_bytes()).await.is_err()
+                || stream.flush().await.is_err();
+        }
+        drop(serving);
+        if broken || response.close || !keep || shutdown::stopping() {
+            break;
         }
+        incoming.answered();
     }
 
-    Some(raw)
+    // For TLS this also sends close_notify, without which a client cannot
+    // tell the end of the response from a connection cut short.
+    let _ = stream.shutdown().await;
+}
+
+/// Answer a request that could not be read. The connection ends after it:
+/// there is no telling where the next request would start.
+async fn send_and_close(stream: &mut (impl AsyncWrite + Unpin), mut response: HttpResponse) {
+    connection::mark(&mut response, false);
+    let _ = stream.write_all(&response.to_http_bytes()).await;
 }
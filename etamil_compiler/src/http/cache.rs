@@ -64,6 +64,43 @@ impl Cache {
         entries.remove(key);
     }
 
+    /// `set`, for a caller that is not async: the VM, which runs on a thread
+    /// of its own in either server. Must not be called from inside the
+    /// runtime, where blocking the thread would stall every task on it.
+    pub fn set_blocking(&self, key: String, value: Value, ttl_secs: u64) {
+        let expires_at = SystemTime::now() + Duration::from_secs(ttl_secs);
+        self.entries
+            .blocking_write()
+            .insert(key, CacheEntry { value, expires_at });
+    }
+
+    /// `get`, likewise.
+    pub fn get_blocking(&self, key: &str) -> Option<Value> {
+        let mut entries = self.entries.blocking_write();
+        match entries.get(key) {
+            Some(entry) if entry.is_expired() => {
+                entries.remove(key);
+                None
+            }
+            Some(entry) => Some(entry.value.clone()),
+            None => None,
+        }
+    }
+
+    /// `delete`, likewise.
+    pub fn delete_blocking(&self, key: &str) {
+        self.entries.blocking_write().remove(key);
+    }
+
+    /// `cleanup`, likewise, but only once more than `above` entries are held:
+    /// an entry nobody reads again is otherwise never removed.
+    pub fn sweep_blocking(&self, above: usize) {
+        let mut entries = self.entries.blocking_write();
+        if entries.len() > above {
+            entries.retain(|_, entry| !entry.is_expired());
+        }
+    }
+
     /// Clear all expired entries (cleanup)
     pub async fn cleanup(&self) {
         let mut entries = self.entries.write().await;
@@ -141,6 +178,22 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[test]
+    fn expired_entries_are_swept_only_above_the_threshold() {
+        let cache = Cache::new();
+        for key in ["a", "b", "c"] {
+            cache.set_blocking(key.to_string(), serde_json::json!(1), 0);
+        }
+        cache.set_blocking("kept".to_string(), serde_json::json!(1), 60);
+        std::thread::sleep(Duration::from_millis(100));
+
+        cache.sweep_blocking(4);
+        assert_eq!(cache.entries.blocking_read().len(), 4);
+        cache.sweep_blocking(3);
+        assert_eq!(cache.entries.blocking_read().len(), 1);
+        assert!(cache.get_blocking("kept").is_some());
+    }
+
     #[tokio::test]
     async fn test_cache_key_builder() {
         assert_eq!(CacheKey::user("123"), "user:123");
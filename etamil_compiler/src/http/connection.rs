@@ -0,0 +1,720 @@
+//! One connection, many requests.
+//!
+//! Both servers used to read one request, answer it and hang up, so every
+//! request paid for a fresh TCP handshake — and behind a load balancer that
+//! pools its upstream connections, for a TLS one as well. What the two
+//! servers share about keeping a connection open, and about streaming a
+//! response down it, lives here so they cannot disagree.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::http::handler::path_matches;
+use crate::http::shutdown;
+use crate::http::websocket::{self, Decoder, Message};
+use crate::http::{Frame, HttpRequest, HttpResponse};
+use crate::vm::ResponseStream;
+
+/// How many requests one connection may carry before the server closes it,
+/// so a single client cannot hold a worker for ever.
+pub const MAX_REQUESTS: usize = 100;
+
+/// The most a request's head may take, whatever its route allows its body.
+pub const MAX_HEAD: usize = 64 * 1024;
+
+/// How long each stage of a request may take, and how large a body may be.
+///
+/// Every stage has its own clock because each guards against a different
+/// client. One that trickles its headers a byte at a time holds a worker as
+/// surely as one that never finishes its body, and neither looks like a
+/// handler stuck in a loop.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// From the first byte of a request to the blank line after its headers.
+    pub header_timeout: Duration,
+    /// From the end of the headers to the end of the body.
+    pub body_timeout: Duration,
+    /// How long a handler may run; `None` for as long as it likes.
+    pub handler_timeout: Option<Duration>,
+    /// How long a kept-alive connection may sit quiet between requests.
+    pub idle_timeout: Duration,
+    /// How long a stopping server waits for the requests in hand.
+    pub drain_timeout: Duration,
+    /// The largest body a route takes unless `route_bodies` says otherwise.
+    pub max_body: usize,
+    /// Routes that take a different size, first match wins.
+    pub route_bodies: Vec<RouteBody>,
+}
+
+/// A body limit for one route: `POST /journal/upload=50MB`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteBody {
+    /// `None` for every method.
+    pub method: Option<String>,
+    /// A route path, with `:name` segments as in `வழி`.
+    pub pattern: String,
+    pub max: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            header_timeout: Duration::from_secs(10),
+            body_timeout: Duration::from_secs(30),
+            handler_timeout: Some(Duration::from_secs(60)),
+            idle_timeout: Duration::from_secs(5),
+            drain_timeout: Duration::from_secs(30),
+            max_body: 1024 * 1024,
+            route_bodies: Vec::new(),
+        }
+    }
+}
+
+impl Limits {
+    /// The limits from `--header-timeout` and its siblings, each falling back
+    /// to its `ETAMIL_*` variable and then to the default. A flag wins over
+    /// its variable, as the `--tls-*` flags do.
+    ///
+    /// `flags` are (flag, value) pairs in the order given, so
+    /// `--route-max-body` can be repeated.
+    pub fn resolve(flags: &[(String, String)]) -> Result<Limits, String> {
+        let setting = |flag: &str, variable: &str| {
+            flags
+                .iter()
+                .rev()
+                .find(|(name, _)| name == flag)
+                .map(|(_, value)| value.clone())
+                .or_else(|| std::env::var(variable).ok())
+        };
+        let seconds = |flag: &str, variable: &str| -> Result<Option<Duration>, String> {
+            setting(flag, variable)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<u64>()
+                        .map(Duration::from_secs)
+                        .map_err(|_| format!("{} needs a whole number of seconds, got '{}'", flag, value))
+                })
+                .transpose()
+        };
+
+        let mut limits = Limits::default();
+        if let Some(timeout) = seconds("--header-timeout", "ETAMIL_HEADER_TIMEOUT")? {
+            limits.header_timeout = timeout;
+        }
+        if let Some(timeout) = seconds("--body-timeout", "ETAMIL_BODY_TIMEOUT")? {
+            limits.body_timeout = timeout;
+        }
+        if let Some(timeout) = seconds("--idle-timeout", "ETAMIL_IDLE_TIMEOUT")? {
+            limits.idle_timeout = timeout;
+        }
+        if let Some(timeout) = seconds("--drain-timeout", "ETAMIL_DRAIN_TIMEOUT")? {
+            limits.drain_timeout = timeout;
+        }
+        // 0 is "no limit" here alone: a handler that may run for ever is a
+        // choice someone can make, a header that may take for ever is not.
+        if let Some(timeout) = seconds("--handler-timeout", "ETAMIL_HANDLER_TIMEOUT")? {
+            limits.handler_timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+        }
+        if let Some(size) = setting("--max-body", "ETAMIL_MAX_BODY") {
+            limits.max_body = parse_size(&size).map_err(|why| format!("--max-body: {}", why))?;
+        }
+
+        let routes: Vec<String> = flags
+            .iter()
+            .filter(|(name, _)| name == "--route-max-body")
+            .map(|(_, value)| value.clone())
+            .collect();
+        let routes = if routes.is_empty() {
+            std::env::var("ETAMIL_ROUTE_MAX_BODY")
+                .map(|value| value.split(',').map(str::to_string).collect())
+                .unwrap_or_default()
+        } else {
+            routes
+        };
+        for route in routes.iter().filter(|route| !route.trim().is_empty()) {
+            limits.route_bodies.push(parse_route_body(route)?);
+        }
+        Ok(limits)
+    }
+
+    /// The largest body this route takes.
+    pub fn max_body_for(&self, method: &str, path: &str) -> usize {
+        self.route_bodies
+            .iter()
+            .find(|route| {
+                route
+                    .method
+                    .as_deref()
+                    .is_none_or(|wanted| wanted.eq_ignore_ascii_case(method))
+                    && path_matches(&route.pattern, path)
+            })
+            .map_or(self.max_body, |route| route.max)
+    }
+}
+
+/// `50MB`, `512KB`, `1GB` or a plain number of bytes. Binary multiples, as
+/// the 1 MB default always was.
+pub fn parse_size(text: &str) -> Result<usize, String> {
+    let text = text.trim();
+    let upper = text.to_ascii_uppercase();
+    let (number, unit) = match upper.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => upper.split_at(at),
+        None => (upper.as_str(), ""),
+    };
+    let multiple: usize = match unit.trim() {
+        "" | "B" => 1,
+        "KB" | "K" => 1024,
+        "MB" | "M" => 1024 * 1024,
+        "GB" | "G" => 1024 * 1024 * 1024,
+        _ => return Err(format!("'{}' is not a size like 50MB", text)),
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiple))
+        .ok_or_else(|| format!("'{}' is not a size like 50MB", text))
+}
+
+/// `POST /journal/upload=50MB`, or without the method for every one.
+fn parse_route_body(text: &str) -> Result<RouteBody, String> {
+    let malformed = || {
+        format!(
+            "--route-max-body needs METHOD /path=SIZE, like 'POST /journal/upload=50MB', got '{}'",
+            text
+        )
+    };
+    let (route, size) = text.rsplit_once('=').ok_or_else(malformed)?;
+    let max = parse_size(size).map_err(|why| format!("--route-max-body: {}", why))?;
+    let (method, pattern) = match route.trim().split_once(char::is_whitespace) {
+        Some((method, pattern)) => (Some(method.to_uppercase()), pattern.trim()),
+        None => (None, route.trim()),
+    };
+    if !pattern.starts_with('/') {
+        return Err(malformed());
+    }
+    Ok(RouteBody { method, pattern: pattern.to_string(), max })
+}
+
+/// What a server should do next with a connection.
+pub enum Next {
+    /// Serve this request. `keep` says whether the connection stays open
+    /// after it, `deadline` when its handler has to be done by.
+    Request {
+        request: HttpRequest,
+        keep: bool,
+        deadline: Option<Instant>,
+    },
+    /// Read more, waiting no longer than this.
+    Read(Duration),
+    /// Answer with this and close: the request cannot be served, and there
+    /// is no telling where the next one would begin.
+    Refuse(HttpResponse),
+    /// Close without a word — the client went quiet between requests, or
+    /// left.
+    Close,
+}
+
+/// The read side of one connection, shared by both servers: the bytes not yet
+/// served, and the clocks that say how long the client has left to send them.
+pub struct Incoming {
+    limits: Arc<Limits>,
+    buffer: Vec<u8>,
+    closed: bool,
+    served: usize,
+    /// When the request being read began: its first byte, or for the first
+    /// request on a connection, the connection itself.
+    began: Option<Instant>,
+    /// When its head was complete.
+    headed: Option<Instant>,
+    /// When the last response went out; the idle clock runs from here.
+    answered: Instant,
+    /// How long the connection waited for a worker before anyone read it,
+    /// which comes off the first handler's time.
+    queued: Duration,
+}
+
+impl Incoming {
+    pub fn new(limits: Arc<Limits>, queued: Duration) -> Self {
+        let now = Instant::now();
+        Incoming {
+            limits,
+            buffer: Vec::new(),
+            closed: false,
+            served: 0,
+            began: Some(now),
+            headed: None,
+            answered: now,
+            queued,
+        }
+    }
+
+    /// Bytes from the client; none at all means it has stopped sending.
+    pub fn received(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            self.closed = true;
+            return;
+        }
+        self.began.get_or_insert_with(Instant::now);
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The response to the last request has gone; the idle clock starts.
+    pub fn answered(&mut self) {
+        let now = Instant::now();
+        self.answered = now;
+        // Part of the next request may have arrived with the last one.
+        if !self.buffer.is_empty() {
+            self.began = Some(now);
+        }
+    }
+
+    /// Is the connection between requests, with nothing of the next one in
+    /// hand? Then it can be closed without cutting anyone off.
+    pub fn between_requests(&self) -> bool {
+        self.began.is_none() && self.buffer.is_empty()
+    }
+
+    pub fn next_step(&mut self) -> Next {
+        let now = Instant::now();
+        match HttpRequest::frame(&self.buffer, self.closed) {
+            Frame::Request(request, used) => {
+                let request = *request;
+                let max = self.limits.max_body_for(&request.method, &request.path);
+                if request.body_bytes.len() > max {
+                    return Next::Refuse(too_large(max));
+                }
+                self.buffer.drain(..used);
+                self.served += 1;
+                self.began = None;
+                self.headed = None;
+                let keep = !self.closed && self.served < MAX_REQUESTS && keep_alive(&request);
+                let queued = std::mem::take(&mut self.queued);
+                // A limit too long to be a point in time is no limit at all.
+                let deadline = self
+                    .limits
+                    .handler_timeout
+                    .and_then(|timeout| now.checked_add(timeout.saturating_sub(queued)));
+                Next::Request { request, keep, deadline }
+            }
+            Frame::Invalid(why) => Next::Refuse(HttpResponse::bad_request(&why)),
+            Frame::Incomplete if self.closed => Next::Close,
+            Frame::Incomplete => self.waiting(now),
+        }
+    }
+
+    /// Part of a request is in hand, or none of the next: how long to wait
+    /// for more, or what to say if the wait is over.
+    fn waiting(&mut self, now: Instant) -> Next {
+        let (started, allowed) = match HttpRequest::head_of(&self.buffer) {
+            Some(Ok((head, body_so_far))) => {
+                let max = self.limits.max_body_for(&head.method, &head.path);
+                let declared = head
+                    .header("content-length")
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                // Said as soon as the head is in, rather than after reading a
+                // body that was always going to be refused.
+                if declared > max || body_so_far > max {
+                    return Next::Refuse(too_large(max));
+                }
+                (*self.headed.get_or_insert(now), self.limits.body_timeout)
+            }
+            Some(Err(why)) => return Next::Refuse(HttpResponse::bad_request(&why)),
+            None if self.buffer.len() > MAX_HEAD => {
+                return Next::Refuse(HttpResponse::custom(
+                    431,
+                    r#"{"error": "request headers too large"}"#.to_string(),
+                ));
+            }
+            None => match self.began {
+                Some(began) => (began, self.limits.header_timeout),
+                None => {
+                    let left = self.limits.idle_timeout.saturating_sub(now - self.answered);
+                    return if left.is_zero() { Next::Close } else { Next::Read(left) };
+                }
+            },
+        };
+        let left = allowed.saturating_sub(now - started);
+        if left.is_zero() {
+            Next::Refuse(HttpResponse::custom(
+                408,
+                r#"{"error": "request not received in time"}"#.to_string(),
+            ))
+        } else {
+            Next::Read(left)
+        }
+    }
+}
+
+fn too_large(max: usize) -> HttpResponse {
+    HttpResponse::custom(
+        413,
+        format!(r#"{{"error": "request body larger than {} bytes"}}"#, max),
+    )
+}
+
+/// Does the client want the connection kept open after this request?
+///
+/// HTTP/1.1 keeps it unless told `Connection: close`; HTTP/1.0 closes it
+/// unless told `Connection: keep-alive`.
+pub fn keep_alive(request: &HttpRequest) -> bool {
+    let asked = |token: &str| {
+        request.header("connection").is_some_and(|value| {
+            value
+                .split(',')
+                .any(|option| option.trim().eq_ignore_ascii_case(token))
+        })
+    };
+    if request.version.eq_ignore_ascii_case("HTTP/1.0") {
+        asked("keep-alive")
+    } else {
+        !asked("close")
+    }
+}
+
+/// Will this request hold its connection for as long as the client stays:
+/// a WebSocket, or an events stream? Such a request is served on a thread of
+/// its own, outside the worker pool, and without a handler time limit —
+/// being open for an hour is what it is for.
+///
+/// An events stream is recognised by `Accept: text/event-stream`, which is
+/// what a browser's EventSource sends.
+pub fn long_lived(request: &HttpRequest) -> bool {
+    websocket::key_of(&request.headers).is_some()
+        || request
+            .header("accept")
+            .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+/// Tell the client whether the connection stays open after this response.
+pub fn mark(response: &mut HttpResponse, keep: bool) {
+    response.set_header("Connection", if keep { "keep-alive" } else { "close" });
+}
+
+/// Where a streamed response's bytes go: the connection itself for the
+/// synchronous server, a channel to the connection's task for the async one.
+pub type Sink = Box<dyn FnMut(&[u8]) -> std::io::Result<()> + Send>;
+
+/// Where a WebSocket's incoming bytes come from: whatever has arrived, waiting
+/// at most the given time, or for ever with `None`. Empty once the client has
+/// closed the connection; `TimedOut` or `WouldBlock` if nothing came in time.
+pub type Source = Box<dyn FnMut(Option<Duration>) -> std::io::Result<Vec<u8>> + Send>;
+
+/// A response sent as it is made, with `Transfer-Encoding: chunked`.
+///
+/// Each பதில்_பகுதி becomes one chunk, so a report over every row goes out a
+/// row at a time and the server never holds the whole of it.
+///
+/// Given a `Source` for a request that asked for a WebSocket, it can instead
+/// be upgraded into one, after which each part is a text message.
+pub struct Chunked {
+    sink: Sink,
+    keep: bool,
+    /// Set by the server rather than the handler, such as `X-Request-Id`.
+    extra: Vec<(String, String)>,
+    socket: Option<Socket>,
+    finished: bool,
+}
+
+/// The WebSocket side of a connection.
+struct Socket {
+    key: String,
+    source: Source,
+    decoder: Decoder,
+    open: bool,
+}
+
+impl Chunked {
+    pub fn new(sink: Sink, keep: bool) -> Self {
+        Chunked { sink, keep, extra: Vec::new(), socket: None, finished: false }
+    }
+
+    /// A header every streamed response on this stream carries.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.extra.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Let a handler upgrade this request, which asked for a WebSocket with
+    /// `key`, reading the client's frames from `source`.
+    pub fn with_socket(mut self, key: &str, source: Source) -> Self {
+        self.socket = Some(Socket {
+            key: key.to_string(),
+            source,
+            decoder: Decoder::new(),
+            open: false,
+        });
+        self
+    }
+
+    /// Close the socket with `code`, and say why to the handler.
+    fn refuse(&mut self, code: u16, why: &str) -> String {
+        let _ = (self.sink)(&websocket::close_frame(code));
+        if let Some(socket) = self.socket.as_mut() {
+            socket.open = false;
+        }
+        self.finished = true;
+        why.to_string()
+    }
+}
+
+impl ResponseStream for Chunked {
+    fn start(&mut self, status: u16, headers: Vec<(String, String)>) -> Result<(), String> {
+        let mut response = HttpResponse::custom(status, String::new());
+        for (name, value) in headers {
+            response.set_header(&name, &value);
+        }
+        // Neither can describe a body that is not yet made, whatever the
+        // handler said.
+        response.headers.retain(|name, _| {
+            !name.eq_ignore_ascii_case("content-length")
+                && !name.eq_ignore_ascii_case("transfer-encoding")
+        });
+        response.set_header("Transfer-Encoding", "chunked");
+        for (name, value) in &self.extra {
+            response.set_header(name, value);
+        }
+        mark(&mut response, self.keep);
+        (self.sink)(&response.head_bytes()).map_err(|e| e.to_string())
+    }
+
+    fn part(&mut self, data: &[u8]) -> Result<(), String> {
+        // An events stream or a socket never ends by itself; a stop ends it
+        // here, as a client going would.
+        if shutdown::stopping() {
+            return Err("the server is stopping".to_string());
+        }
+        if let Some(socket) = &self.socket
+            && socket.open
+        {
+            // A message that is not text would fail the client's own check
+            // and close the socket from its side.
+            let text = String::from_utf8_lossy(data);
+            return (self.sink)(&websocket::frame(websocket::TEXT, text.as_bytes()))
+                .map_err(|e| e.to_string());
+        }
+        if self.finished {
+            return Err("the response has already ended".to_string());
+        }
+        // An empty chunk is the end of the body, so an empty part is skipped
+        // rather than ending the response early.
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut chunk = format!("{:x}\r\n", data.len()).into_bytes();
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(b"\r\n");
+        (self.sink)(&chunk).map_err(|e| e.to_string())
+    }
+
+    /// Once only: a handler that closed its events stream or socket itself
+    /// is finished again by the server when it returns.
+    fn finish(&mut self) -> Result<(), String> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        match self.socket.as_mut() {
+            Some(socket) if socket.open => {
+                socket.open = false;
+                (self.sink)(&websocket::close_frame(websocket::NORMAL)).map_err(|e| e.to_string())
+            }
+            _ => (self.sink)(b"0\r\n\r\n").map_err(|e| e.to_string()),
+        }
+    }
+
+    fn upgrade(&mut self) -> Result<(), String> {
+        let Some(socket) = self.socket.as_mut() else {
+            return Err("this request did not ask for a WebSocket".to_string());
+        };
+        let head = websocket::handshake(&socket.key, &self.extra);
+        socket.open = true;
+        (self.sink)(&head).map_err(|e| e.to_string())
+    }
+
+    fn receive(&mut self, wait: Option<Duration>) -> Result<Option<String>, String> {
+        // A wait too long to be a point in time is no deadline at all.
+        let deadline = wait.and_then(|wait| Instant::now().checked_add(wait));
+        loop {
+            let Some(socket) = self.socket.as_mut().filter(|socket| socket.open) else {
+                return Err("the WebSocket is closed".to_string());
+            };
+            let message = match socket.decoder.next_message() {
+                Ok(message) => message,
+                Err(code) => return Err(self.refuse(code, "the client broke the WebSocket protocol")),
+            };
+            match message {
+                Some(Message::Text(text)) => return Ok(Some(text)),
+                Some(Message::Binary(bytes)) => {
+                    return Ok(Some(String::from_utf8_lossy(&bytes).into_owned()));
+                }
+                Some(Message::Ping(payload)) => {
+                    (self.sink)(&websocket::frame(websocket::PONG, &payload))
+                        .map_err(|e| e.to_string())?;
+                    continue;
+                }
+                Some(Message::Pong) => continue,
+                // Answered in kind, as the RFC asks, and then it is over.
+                Some(Message::Close(_)) => {
+                    return Err(self.refuse(websocket::NORMAL, "the client closed the WebSocket"));
+                }
+                None => {}
+            }
+
+            let left = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if left == Some(Duration::ZERO) {
+                return Ok(None);
+            }
+            match (socket.source)(left) {
+                Ok(bytes) if bytes.is_empty() => {
+                    socket.open = false;
+                    self.finished = true;
+                    return Err("the client has gone".to_string());
+                }
+                Ok(bytes) => socket.decoder.push(&bytes),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None);
+                }
+                Err(e) => {
+                    socket.open = false;
+                    self.finished = true;
+                    return Err(e.to_string());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn request(raw: &str) -> HttpRequest {
+        HttpRequest::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn http_1_1_stays_open_unless_told_to_close() {
+        assert!(keep_alive(&request("GET / HTTP/1.1\r\n\r\n")));
+        assert!(!keep_alive(&request("GET / HTTP/1.1\r\nConnection: close\r\n\r\n")));
+        assert!(!keep_alive(&request("GET / HTTP/1.0\r\n\r\n")));
+        assert!(keep_alive(&request("GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n")));
+    }
+
+    #[test]
+    fn sizes_read_the_way_people_write_them() {
+        assert_eq!(parse_size("50MB"), Ok(50 * 1024 * 1024));
+        assert_eq!(parse_size("512kb"), Ok(512 * 1024));
+        assert_eq!(parse_size("2048"), Ok(2048));
+        assert!(parse_size("fifty").is_err());
+        assert!(parse_size("5 parsecs").is_err());
+    }
+
+    // The journal-upload route takes 50 MB; everything else keeps 1 MB.
+    #[test]
+    fn a_route_can_take_a_larger_body_than_the_rest() {
+        let flags = vec![(
+            "--route-max-body".to_string(),
+            "POST /journal/:batch=50MB".to_string(),
+        )];
+        let limits = Limits::resolve(&flags).unwrap();
+
+        assert_eq!(limits.max_body_for("POST", "/journal/2026-10"), 50 * 1024 * 1024);
+        assert_eq!(limits.max_body_for("PUT", "/journal/2026-10"), 1024 * 1024);
+        assert_eq!(limits.max_body_for("POST", "/invoices"), 1024 * 1024);
+    }
+
+    #[test]
+    fn a_limit_that_does_not_parse_is_an_error_not_a_default() {
+        let flags = vec![("--header-timeout".to_string(), "soon".to_string())];
+        assert!(Limits::resolve(&flags).is_err());
+        let flags = vec![("--route-max-body".to_string(), "/upload".to_string())];
+        assert!(Limits::resolve(&flags).is_err());
+    }
+
+    #[test]
+    fn a_handler_timeout_too_long_for_a_deadline_is_no_limit() {
+        let flags = vec![("--handler-timeout".to_string(), u64::MAX.to_string())];
+        let limits = Limits::resolve(&flags).unwrap();
+        let mut incoming = Incoming::new(Arc::new(limits), Duration::ZERO);
+        incoming.received(b"GET / HTTP/1.1\r\n\r\n");
+
+        match incoming.next_step() {
+            Next::Request { deadline, .. } => assert_eq!(deadline, None),
+            _ => panic!("a whole request was not served"),
+        }
+    }
+
+    #[test]
+    fn a_body_too_large_is_refused_once_its_head_is_in() {
+        let mut incoming = Incoming::new(Arc::new(Limits::default()), Duration::ZERO);
+        incoming.received(b"POST /x HTTP/1.1\r\nContent-Length: 2000000\r\n\r\nabc");
+
+        match incoming.next_step() {
+            Next::Refuse(response) => assert_eq!(response.status_code, 413),
+            _ => panic!("a 2 MB body was not refused"),
+        }
+    }
+
+    #[test]
+    fn a_request_that_stops_arriving_times_out() {
+        let limits = Limits { header_timeout: Duration::ZERO, ..Limits::default() };
+        let mut incoming = Incoming::new(Arc::new(limits), Duration::ZERO);
+        incoming.received(b"GET / HT");
+
+        match incoming.next_step() {
+            Next::Refuse(response) => assert_eq!(response.status_code, 408),
+            _ => panic!("a request past its header timeout was waited for"),
+        }
+    }
+
+    #[test]
+    fn parts_go_out_as_chunks_and_finish_ends_the_body() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let into = Arc::clone(&written);
+        let mut stream = Chunked::new(
+            Box::new(move |bytes| {
+                into.lock().unwrap().extend_from_slice(bytes);
+                Ok(())
+            }),
+            true,
+        );
+
+        stream.start(200, vec![("Content-Type".into(), "text/csv".into())]).unwrap();
+        stream.part(b"id,amount\n").unwrap();
+        stream.part(b"").unwrap();
+        stream.part(b"1,1500\n").unwrap();
+        stream.finish().unwrap();
+
+        let written = String::from_utf8(written.lock().unwrap().clone()).unwrap();
+        let (head, body) = written.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains("Transfer-Encoding: chunked"));
+        assert!(!head.contains("Content-Length"));
+        assert_eq!(body, "a\r\nid,amount\n\r\n7\r\n1,1500\n\r\n0\r\n\r\n");
+    }
+
+    #[test]
+    fn a_wait_too_long_for_a_deadline_waits_without_one() {
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let into = Arc::clone(&asked);
+        let mut stream = Chunked::new(Box::new(|_| Ok(())), true).with_socket(
+            "dGhlIHNhbXBsZSBub25jZQ==",
+            Box::new(move |wait| {
+                into.lock().unwrap().push(wait);
+                Err(std::io::ErrorKind::TimedOut.into())
+            }),
+        );
+
+        stream.upgrade().unwrap();
+        assert_eq!(stream.receive(Some(Duration::MAX)), Ok(None));
+        assert_eq!(*asked.lock().unwrap(), vec![None]);
+    }
+}
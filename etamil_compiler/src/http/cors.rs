@@ -0,0 +1,388 @@
+//! Cross-origin requests: `--cors-origin https://dash.example.com`.
+//!
+//! A browser page served from one origin may only read the answer to a
+//! request it makes to another if that answer says so, in its
+//! `Access-Control-*` headers; a request it cannot make plainly — a PUT, a
+//! JSON body, an `Authorization` header — it first asks about with an
+//! `OPTIONS` preflight. The server writes both from one policy, so a handler
+//! never sets them by hand and a dashboard on another origin is answered the
+//! same way by every route.
+//!
+//! The policy is the origins allowed, any by default; the methods, by default
+//! those the path has routes for; the request headers, by default
+//! `Content-Type` alone, or `*` for whatever the preflight asks; whether
+//! cookies and `Authorization` may be sent, `--cors-credentials`, which needs
+//! the origins named; and how long a browser may remember a preflight's
+//! answer. Each flag has an `ETAMIL_CORS_*` variable.
+//!
+//! A preflight for a path with routes is answered 204 here, without running
+//! anything, unless the program has its own `தெரிவு` route for it. One from an
+//! origin not allowed is answered 403. Other requests from such an origin are
+//! served as usual but without the headers, so the browser will not let the
+//! page read them.
+
+use crate::http::{HttpRequest, HttpResponse};
+
+/// Which origins may read the answers.
+#[derive(Debug, Clone, PartialEq)]
+enum Origins {
+    Any,
+    /// As configured, without a trailing `/`.
+    Only(Vec<String>),
+}
+
+/// The server's CORS policy.
+#[derive(Debug, Clone)]
+pub struct Cors {
+    origins: Origins,
+    /// `None` for the methods each path has routes for.
+    methods: Option<Vec<String>>,
+    /// Request headers a preflight may ask for; `*` for any.
+    headers: Vec<String>,
+    credentials: bool,
+    /// Seconds a browser may cache a preflight's answer.
+    max_age: Option<u64>,
+}
+
+impl Default for Cors {
+    /// Any origin, as the server has always answered.
+    fn default() -> Self {
+        Cors {
+            origins: Origins::Any,
+            methods: None,
+            headers: vec!["Content-Type".to_string()],
+            credentials: false,
+            max_age: None,
+        }
+    }
+}
+
+impl Cors {
+    /// The policy from `--cors-origin` and its siblings, or their `ETAMIL_CORS_*`
+    /// variables. Every `--cors-origin` counts, and each may list several
+    /// separated by commas; of the others the last given wins.
+    pub fn resolve(flags: &[(String, String)]) -> Result<Cors, String> {
+        let setting = |flag: &str, variable: &str| {
+            flags
+                .iter()
+                .rev()
+                .find(|(name, _)| name == flag)
+                .map(|(_, value)| value.clone())
+                .or_else(|| std::env::var(variable).ok())
+        };
+        let list = |value: &str| -> Vec<String> {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+
+        let mut cors = Cors::default();
+
+        let mut origins: Vec<String> = flags
+            .iter()
+            .filter(|(name, _)| name == "--cors-origin")
+            .flat_map(|(_, value)| list(value))
+            .collect();
+        if origins.is_empty() {
+            origins = std::env::var("ETAMIL_CORS_ORIGINS")
+                .map(|value| list(&value))
+                .unwrap_or_default();
+        }
+        if !origins.is_empty() && origins.iter().all(|origin| origin != "*") {
+            let named = origins
+                .iter()
+                .map(|origin| parse_origin(origin))
+                .collect::<Result<Vec<_>, _>>()?;
+            cors.origins = Origins::Only(named);
+        } else if origins.len() > 1 {
+            return Err("--cors-origin '*' is every origin, and cannot be listed with others".to_string());
+        }
+
+        if let Some(methods) = setting("--cors-methods", "ETAMIL_CORS_METHODS") {
+            let methods = list(&methods);
+            if let Some(bad) = methods.iter().find(|method| !is_token(method)) {
+                return Err(format!("--cors-methods: '{}' is not a method", bad));
+            }
+            cors.methods = Some(methods.iter().map(|method| method.to_uppercase()).collect());
+        }
+        if let Some(headers) = setting("--cors-headers", "ETAMIL_CORS_HEADERS") {
+            let headers = list(&headers);
+            if let Some(bad) = headers.iter().find(|header| *header != "*" && !is_token(header)) {
+                return Err(format!("--cors-headers: '{}' is not a header name", bad));
+            }
+            cors.headers = headers;
+        }
+        if let Some(credentials) = setting("--cors-credentials", "ETAMIL_CORS_CREDENTIALS") {
+            cors.credentials = match credentials.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => true,
+                "false" | "no" | "0" | "" => false,
+                other => {
+                    return Err(format!("ETAMIL_CORS_CREDENTIALS needs true or false, got '{}'", other));
+                }
+            };
+        }
+        if let Some(seconds) = setting("--cors-max-age", "ETAMIL_CORS_MAX_AGE") {
+            cors.max_age = Some(seconds.trim().parse::<u64>().map_err(|_| {
+                format!("--cors-max-age needs a whole number of seconds, got '{}'", seconds)
+            })?);
+        }
+
+        // A browser refuses credentials sent to `*`: said now, rather than by
+        // every dashboard's console once the server is up.
+        if cors.credentials && cors.origins == Origins::Any {
+            return Err(
+                "--cors-credentials needs the origins named with --cors-origin, not any origin"
+                    .to_string(),
+            );
+        }
+        Ok(cors)
+    }
+
+    /// The policy in a line, for the startup banner.
+    pub fn describe(&self) -> String {
+        let mut line = match &self.origins {
+            Origins::Any => "any origin".to_string(),
+            Origins::Only(origins) => origins.join(", "),
+        };
+        if self.credentials {
+            line.push_str(", with credentials");
+        }
+        line
+    }
+
+    /// Is this an `OPTIONS` a browser sent to ask about a request, rather than
+    /// one a program might answer itself?
+    pub fn is_preflight(request: &HttpRequest) -> bool {
+        request.method.eq_ignore_ascii_case("OPTIONS")
+            && request.headers.contains_key("origin")
+            && request.headers.contains_key("access-control-request-method")
+    }
+
+    /// What to send back as `Access-Control-Allow-Origin`, or `None` when the
+    /// request's origin may not read the answer.
+    fn allowed_origin(&self, request: &HttpRequest) -> Option<String> {
+        match &self.origins {
+            Origins::Any => Some("*".to_string()),
+            Origins::Only(origins) => {
+                let origin = request.headers.get("origin")?;
+                let origin = origin.trim().trim_end_matches('/');
+                origins
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+                    .then(|| origin.to_string())
+            }
+        }
+    }
+
+    /// The headers an answer to this request carries, preflight or not.
+    ///
+    /// With the origins named the answer depends on the `Origin` asked from,
+    /// so it says so in `Vary` whether or not this one was allowed: a cache
+    /// must not hand one origin's answer to another.
+    pub fn headers_for(&self, request: &HttpRequest) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(origin) = self.allowed_origin(request) {
+            headers.push(("Access-Control-Allow-Origin".to_string(), origin));
+            if self.credentials {
+                headers.push(("Access-Control-Allow-Credentials".to_string(), "true".to_string()));
+            }
+        }
+        if matches!(self.origins, Origins::Only(_)) {
+            headers.push(("Vary".to_string(), "Origin".to_string()));
+        }
+        headers
+    }
+
+    /// Add the policy's headers to an answer. A `Vary` already there is added
+    /// to rather than replaced.
+    pub fn decorate(&self, request: &HttpRequest, response: &mut HttpResponse) {
+        for (name, value) in self.headers_for(request) {
+            let existing = response
+                .headers
+                .iter()
+                .find(|(other, _)| other.eq_ignore_ascii_case(&name))
+                .map(|(_, existing)| existing.clone());
+            match existing {
+                Some(existing) if name == "Vary" => {
+                    let merged = format!("{}, {}", existing, value);
+                    response.set_header(&name, &merged);
+                }
+                _ => response.set_header(&name, &value),
+            }
+        }
+    }
+
+    /// The answer to a preflight for a path with routes for `routed`.
+    pub fn preflight(&self, request: &HttpRequest, routed: &[&str]) -> HttpResponse {
+        if self.allowed_origin(request).is_none() {
+            let mut refused =
+                HttpResponse::custom(403, r#"{"error": "origin not allowed"}"#.to_string());
+            self.decorate(request, &mut refused);
+            return refused;
+        }
+
+        let mut response = HttpResponse::custom(204, String::new());
+        let methods = match &self.methods {
+            Some(methods) => methods.join(", "),
+            None => routed.join(", "),
+        };
+        response.set_header("Access-Control-Allow-Methods", &methods);
+        let headers = if self.headers.iter().any(|header| header == "*") {
+            // Whatever was asked: `*` itself means nothing to a browser
+            // sending credentials.
+            request
+                .headers
+                .get("access-control-request-headers")
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            self.headers.join(", ")
+        };
+        if !headers.is_empty() {
+            response.set_header("Access-Control-Allow-Headers", &headers);
+        }
+        if let Some(seconds) = self.max_age {
+            response.set_header("Access-Control-Max-Age", &seconds.to_string());
+        }
+        self.decorate(request, &mut response);
+        response
+    }
+}
+
+/// `https://dash.example.com` or `http://localhost:5173`: a scheme and a host,
+/// with a port if need be, and no path.
+fn parse_origin(origin: &str) -> Result<String, String> {
+    let origin = origin.trim_end_matches('/');
+    let host = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"));
+    match host {
+        Some(host) if !host.is_empty() && !host.contains('/') => Ok(origin.to_string()),
+        _ => Err(format!(
+            "--cors-origin needs an origin like https://dash.example.com, or *, got '{}'",
+            origin
+        )),
+    }
+}
+
+/// An HTTP token: what a method or a header name is made of.
+fn is_token(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        let mut raw = format!("{} /api/kaNakku HTTP/1.1\r\n", method);
+        for (name, value) in headers {
+            raw.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        raw.push_str("\r\n");
+        HttpRequest::parse(&raw).unwrap()
+    }
+
+    fn cors(flags: &[(&str, &str)]) -> Result<Cors, String> {
+        let flags: Vec<(String, String)> = flags
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Cors::resolve(&flags)
+    }
+
+    #[test]
+    fn the_policy_is_read_from_its_flags() {
+        let policy = cors(&[
+            ("--cors-origin", "https://dash.example.com/, http://localhost:5173"),
+            ("--cors-origin", "https://admin.example.com"),
+            ("--cors-methods", "get, post"),
+            ("--cors-credentials", "true"),
+            ("--cors-max-age", "600"),
+        ])
+        .unwrap();
+        assert_eq!(
+            policy.origins,
+            Origins::Only(vec![
+                "https://dash.example.com".to_string(),
+                "http://localhost:5173".to_string(),
+                "https://admin.example.com".to_string(),
+            ])
+        );
+        assert_eq!(policy.methods, Some(vec!["GET".to_string(), "POST".to_string()]));
+        assert!(policy.credentials);
+        assert_eq!(policy.max_age, Some(600));
+
+        assert!(cors(&[("--cors-origin", "dash.example.com")]).unwrap_err().contains("an origin like"));
+        assert!(cors(&[("--cors-origin", "*, https://a.example")]).is_err());
+        assert!(cors(&[("--cors-max-age", "a day")]).unwrap_err().contains("whole number"));
+        assert!(cors(&[("--cors-origin", "*"), ("--cors-credentials", "true")])
+            .unwrap_err()
+            .contains("needs the origins named"));
+    }
+
+    #[test]
+    fn a_named_origin_is_echoed_and_another_gets_nothing() {
+        let policy = cors(&[
+            ("--cors-origin", "https://dash.example.com"),
+            ("--cors-credentials", "true"),
+        ])
+        .unwrap();
+
+        let mut allowed = HttpResponse::success(200, "OK".to_string());
+        allowed.set_header("Vary", "Accept-Encoding");
+        policy.decorate(&request("GET", &[("Origin", "https://dash.example.com")]), &mut allowed);
+        assert_eq!(allowed.headers["Access-Control-Allow-Origin"], "https://dash.example.com");
+        assert_eq!(allowed.headers["Access-Control-Allow-Credentials"], "true");
+        assert_eq!(allowed.headers["Vary"], "Accept-Encoding, Origin");
+
+        let mut other = HttpResponse::success(200, "OK".to_string());
+        policy.decorate(&request("GET", &[("Origin", "https://evil.example")]), &mut other);
+        assert!(!other.headers.contains_key("Access-Control-Allow-Origin"));
+        assert_eq!(other.headers["Vary"], "Origin");
+    }
+
+    #[test]
+    fn a_preflight_is_answered_with_the_methods_and_headers_allowed() {
+        let asking = |origin: &str| {
+            request(
+                "OPTIONS",
+                &[
+                    ("Origin", origin),
+                    ("Access-Control-Request-Method", "PUT"),
+                    ("Access-Control-Request-Headers", "authorization, content-type"),
+                ],
+            )
+        };
+        assert!(Cors::is_preflight(&asking("https://dash.example.com")));
+        assert!(!Cors::is_preflight(&request("OPTIONS", &[])));
+
+        let policy = cors(&[
+            ("--cors-origin", "https://dash.example.com"),
+            ("--cors-headers", "*"),
+            ("--cors-max-age", "600"),
+        ])
+        .unwrap();
+        let answer = policy.preflight(&asking("https://dash.example.com"), &["GET", "PUT"]);
+        assert_eq!(answer.status_code, 204);
+        assert_eq!(answer.headers["Access-Control-Allow-Methods"], "GET, PUT");
+        assert_eq!(answer.headers["Access-Control-Allow-Headers"], "authorization, content-type");
+        assert_eq!(answer.headers["Access-Control-Max-Age"], "600");
+
+        let refused = policy.preflight(&asking("https://evil.example"), &["GET", "PUT"]);
+        assert_eq!(refused.status_code, 403);
+        assert!(!refused.headers.contains_key("Access-Control-Allow-Origin"));
+
+        // By default: any origin, and only Content-Type.
+        let answer = Cors::default().preflight(&asking("https://anywhere.example"), &["PUT"]);
+        assert_eq!(answer.headers["Access-Control-Allow-Origin"], "*");
+        assert_eq!(answer.headers["Access-Control-Allow-Headers"], "Content-Type");
+        assert!(!answer.headers.contains_key("Vary"));
+    }
+}
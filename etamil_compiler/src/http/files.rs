@@ -0,0 +1,447 @@
+//! A directory served under a path prefix: `கோப்புறை "/assets", "dist";`.
+//!
+//! பதில்_கோப்பு sends one file a route names, which is right for a statement
+//! the route has just written and wrong for a front-end bundle, where it
+//! means a route per file. A mount answers every GET and HEAD under its
+//! prefix from the directory instead, in both servers, and only when no route
+//! matched: a route the program wrote is more specific than a directory.
+//!
+//! What a browser and a proxy expect of a file server is all here: a
+//! Content-Type by extension, an `ETag` and `Last-Modified` so an unchanged
+//! file costs a 304 rather than its bytes, one byte range of a large file —
+//! a viewer asks for a statement PDF a page at a time — and a `.gz` beside a
+//! file sent in its place to a client that accepts gzip.
+//!
+//! Nothing outside the directory is reachable: not by `..`, encoded or not,
+//! and not by a symbolic link that points out. Dot files are not served at
+//! all, since `.env` and `.git` are the ones a bundle directory has by
+//! mistake. முன் blocks do not run before a file; a directory that needs a
+//! check first is a route with பதில்_கோப்பு.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::http::{HttpRequest, HttpResponse};
+
+/// One directory and the prefix it is served under.
+#[derive(Debug, Clone)]
+pub struct Mount {
+    /// `/assets`, or `/` for the whole site; never a trailing slash.
+    pub prefix: String,
+    pub dir: PathBuf,
+    /// Seconds a client may keep a file without asking again. Without one it
+    /// asks every time, and is answered 304 while the file is unchanged.
+    pub max_age: Option<u64>,
+}
+
+impl Mount {
+    pub fn new(prefix: &str, dir: impl Into<PathBuf>, max_age: Option<u64>) -> Self {
+        Mount {
+            prefix: format!("/{}", prefix.trim_matches('/')),
+            dir: dir.into(),
+            max_age,
+        }
+    }
+
+    /// What of `path` lies under this mount, if any of it does: `/assets`
+    /// covers `/assets` and `/assets/app.js`, not `/assetsx`.
+    fn rest<'p>(&self, path: &'p str) -> Option<&'p str> {
+        if self.prefix == "/" {
+            return Some(path);
+        }
+        let rest = path.strip_prefix(&self.prefix)?;
+        (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+    }
+
+    /// The file `rest` names, if it is one and inside the directory.
+    fn resolve(&self, rest: &str) -> Option<PathBuf> {
+        let mut path = self.dir.clone();
+        for segment in rest.split('/').filter(|segment| !segment.is_empty()) {
+            let segment = percent_decode(segment)?;
+            // `..` and every dot file start with a dot. A separator, a drive
+            // colon or a NUL would only be here if it arrived encoded.
+            if segment.starts_with('.') || segment.contains(['/', '\\', ':', '\0']) {
+                return None;
+            }
+            path.push(segment);
+        }
+        if path.is_dir() {
+            path.push("index.html");
+        }
+        // A link inside the directory can still point outside it.
+        let root = self.dir.canonicalize().ok()?;
+        let real = path.canonicalize().ok()?;
+        (real.starts_with(&root) && real.is_file()).then_some(real)
+    }
+}
+
+/// Answer a GET or HEAD from the mount whose prefix is the longest that
+/// covers the path, with the mount it came from. `None` for any other
+/// request, which is then the router's to refuse.
+pub fn answer<'m>(mounts: &'m [Mount], request: &HttpRequest) -> Option<(&'m Mount, HttpResponse)> {
+    let head = request.method.eq_ignore_ascii_case("HEAD");
+    if !head && !request.method.eq_ignore_ascii_case("GET") {
+        return None;
+    }
+    let (mount, rest) = mounts
+        .iter()
+        .filter_map(|mount| mount.rest(&request.path).map(|rest| (mount, rest)))
+        .max_by_key(|(mount, _)| mount.prefix.len())?;
+    let response = match mount.resolve(rest) {
+        Some(file) => serve(mount, &file, request, head),
+        None => HttpResponse::not_found(),
+    };
+    Some((mount, response))
+}
+
+fn serve(mount: &Mount, file: &Path, request: &HttpRequest, head: bool) -> HttpResponse {
+    let mut compressed = file.as_os_str().to_owned();
+    compressed.push(".gz");
+    let compressed = PathBuf::from(compressed);
+    let has_gzip = compressed.is_file();
+    let gzip = has_gzip && accepts_gzip(request.headers.get("accept-encoding"));
+    let chosen = if gzip { compressed.as_path() } else { file };
+
+    let Ok(metadata) = std::fs::metadata(chosen) else {
+        return HttpResponse::not_found();
+    };
+    let size = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs() as i64);
+    // Size and time are enough to tell versions of a file apart, and cost
+    // nothing to compute; hashing every file on every request would not.
+    let etag = format!("\"{:x}-{:x}{}\"", size, modified, if gzip { "-gz" } else { "" });
+
+    let mut response = HttpResponse::success(200, String::new());
+    response.set_header("Content-Type", content_type(file));
+    response.set_header("ETag", &etag);
+    if let Some(date) = http_date(modified) {
+        response.set_header("Last-Modified", &date);
+    }
+    let cache = match mount.max_age {
+        Some(seconds) => format!("public, max-age={}", seconds),
+        None => "no-cache".to_string(),
+    };
+    response.set_header("Cache-Control", &cache);
+    response.set_header("Accept-Ranges", "bytes");
+    if has_gzip {
+        response.set_header("Vary", "Accept-Encoding");
+    }
+    if gzip {
+        response.set_header("Content-Encoding", "gzip");
+    }
+
+    if not_modified(request, &etag, modified) {
+        response.status_code = 304;
+        response.status_text = HttpResponse::status_text_for_code(304).to_string();
+        response.headers.remove("Content-Type");
+        response.headers.remove("Content-Length");
+        response.bytes = Some(Vec::new());
+        return response;
+    }
+
+    let range = request
+        .headers
+        .get("range")
+        .filter(|_| still_current(request, &etag, modified))
+        .and_then(|range| byte_range(range, size));
+    let (start, length) = match range {
+        None => (0, size),
+        Some(Ok((first, last))) => {
+            response.status_code = 206;
+            response.set_header("Content-Range", &format!("bytes {}-{}/{}", first, last, size));
+            (first, last - first + 1)
+        }
+        Some(Err(())) => {
+            response.status_code = 416;
+            response.set_header("Content-Range", &format!("bytes */{}", size));
+            (0, 0)
+        }
+    };
+    response.status_text = HttpResponse::status_text_for_code(response.status_code).to_string();
+    response.set_header("Content-Length", &length.to_string());
+
+    // HEAD is told the length it would have been sent, and sent nothing.
+    if head {
+        response.bytes = Some(Vec::new());
+        return response;
+    }
+    match read_range(chosen, start, length) {
+        Ok(bytes) => response.bytes = Some(bytes),
+        Err(e) => {
+            return HttpResponse::internal_error(&format!("cannot read '{}': {}", chosen.display(), e));
+        }
+    }
+    response
+}
+
+fn read_range(path: &Path, start: u64, length: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::with_capacity(length as usize);
+    file.take(length).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Whether the client's copy is current: `If-None-Match` if it sent one,
+/// since an ETag is exact, and otherwise `If-Modified-Since`.
+fn not_modified(request: &HttpRequest, etag: &str, modified: i64) -> bool {
+    if let Some(tags) = request.headers.get("if-none-match") {
+        return tags.split(',').any(|tag| {
+            let tag = tag.trim();
+            tag == "*" || tag.trim_start_matches("W/") == etag
+        });
+    }
+    request
+        .headers
+        .get("if-modified-since")
+        .and_then(|since| parse_http_date(since))
+        .is_some_and(|since| modified <= since)
+}
+
+/// `If-Range`: a range is only for the version of the file the client already
+/// has part of. Otherwise the whole file is sent, not a piece of another one.
+fn still_current(request: &HttpRequest, etag: &str, modified: i64) -> bool {
+    match request.headers.get("if-range").map(|value| value.trim()) {
+        None => true,
+        Some(tag) if tag.starts_with('"') => tag == etag,
+        Some(date) => parse_http_date(date) == Some(modified),
+    }
+}
+
+/// The one range `Range: bytes=…` asks for, first and last byte inclusive,
+/// or `Err` if it starts past the end. Several ranges, or a unit other than
+/// bytes, are ignored and the whole file sent, as the RFC allows.
+fn byte_range(header: &str, size: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last) = spec.split_once('-')?;
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        // `bytes=-500`: the last 500 bytes.
+        let suffix: u64 = last.parse().ok()?;
+        if suffix == 0 || size == 0 {
+            return Some(Err(()));
+        }
+        return Some(Ok((size.saturating_sub(suffix), size - 1)));
+    }
+    let first: u64 = first.parse().ok()?;
+    let last = match last {
+        "" => u64::MAX,
+        last => last.parse().ok()?,
+    };
+    if last < first {
+        return None;
+    }
+    if first >= size {
+        return Some(Err(()));
+    }
+    Some(Ok((first, last.min(size - 1))))
+}
+
+fn accepts_gzip(header: Option<&String>) -> bool {
+    header.is_some_and(|header| {
+        header.split(',').any(|coding| {
+            let mut parts = coding.split(';');
+            let name = parts.next().unwrap_or("").trim();
+            // `gzip;q=0` is a refusal, not an offer.
+            let refused = parts.any(|parameter| {
+                parameter
+                    .trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !refused
+        })
+    })
+}
+
+/// The date form HTTP headers use: `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn http_date(seconds: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(seconds, 0)
+        .map(|time| time.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+}
+
+fn parse_http_date(text: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc2822(text.trim())
+        .ok()
+        .map(|time| time.timestamp())
+}
+
+/// A Content-Type by extension, for what a front end and its reports are
+/// made of. Anything else is bytes, which a browser downloads rather than
+/// guesses at.
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "zip" => "application/zip",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        _ => "application/octet-stream",
+    }
+}
+
+/// One segment of a path, percent-decoded. Unlike a query, `+` is a plus.
+/// `None` if what it decodes to is not UTF-8.
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(hex) if bytes[i] == b'%' => std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(head: &str) -> HttpRequest {
+        HttpRequest::parse(&format!("{}\r\n\r\n", head)).expect("parses")
+    }
+
+    /// A directory with a page, a statement and its compressed copy, and a
+    /// secret beside it that nothing should reach.
+    fn site(name: &str) -> Mount {
+        let root = std::env::temp_dir().join(format!("etamil_files_{}", name));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("public/reports")).unwrap();
+        std::fs::write(root.join("public/index.html"), "<h1>கடை</h1>").unwrap();
+        std::fs::write(root.join("public/reports/statement.pdf"), b"%PDF-0123456789").unwrap();
+        std::fs::write(root.join("public/app.js"), "console.log(1)").unwrap();
+        std::fs::write(root.join("public/app.js.gz"), b"\x1f\x8bzipped").unwrap();
+        std::fs::write(root.join("public/.env"), "SECRET=1").unwrap();
+        std::fs::write(root.join("secret.txt"), "keys").unwrap();
+        Mount::new("/assets/", root.join("public"), None)
+    }
+
+    fn get(mounts: &[Mount], head: &str) -> HttpResponse {
+        answer(mounts, &request(head)).expect("under the mount").1
+    }
+
+    #[test]
+    fn a_directory_serves_its_index_and_files_by_type() {
+        let mounts = [site("index")];
+        let page = get(&mounts, "GET /assets HTTP/1.1");
+        assert_eq!(page.status_code, 200);
+        assert_eq!(page.headers["Content-Type"], "text/html; charset=utf-8");
+        assert_eq!(page.bytes.as_deref(), Some("<h1>கடை</h1>".as_bytes()));
+
+        let pdf = get(&mounts, "GET /assets/reports/statement.pdf HTTP/1.1");
+        assert_eq!(pdf.headers["Content-Type"], "application/pdf");
+        assert!(answer(&mounts, &request("GET /assetsx/app.js HTTP/1.1")).is_none());
+        assert!(answer(&mounts, &request("POST /assets/app.js HTTP/1.1")).is_none());
+    }
+
+    #[test]
+    fn nothing_outside_the_directory_or_hidden_in_it_is_served() {
+        let mounts = [site("traversal")];
+        for path in [
+            "/assets/../secret.txt",
+            "/assets/%2e%2e/secret.txt",
+            "/assets/reports/%2E%2E%2F..%2Fsecret.txt",
+            "/assets/.env",
+            "/assets/missing.css",
+        ] {
+            let response = get(&mounts, &format!("GET {} HTTP/1.1", path));
+            assert_eq!(response.status_code, 404, "{}", path);
+        }
+    }
+
+    #[test]
+    fn an_unchanged_file_is_answered_not_modified() {
+        let mounts = [site("etag")];
+        let first = get(&mounts, "GET /assets/index.html HTTP/1.1");
+        let etag = first.headers["ETag"].clone();
+        let again = get(&mounts, &format!("GET /assets/index.html HTTP/1.1\r\nIf-None-Match: {}", etag));
+        assert_eq!(again.status_code, 304);
+        assert_eq!(again.bytes.as_deref(), Some(&[][..]));
+
+        let since = first.headers["Last-Modified"].clone();
+        let dated = get(&mounts, &format!("GET /assets/index.html HTTP/1.1\r\nIf-Modified-Since: {}", since));
+        assert_eq!(dated.status_code, 304);
+    }
+
+    #[test]
+    fn a_range_is_sent_as_partial_content() {
+        let mounts = [site("range")];
+        let part = get(&mounts, "GET /assets/reports/statement.pdf HTTP/1.1\r\nRange: bytes=5-9");
+        assert_eq!(part.status_code, 206);
+        assert_eq!(part.headers["Content-Range"], "bytes 5-9/15");
+        assert_eq!(part.bytes.as_deref(), Some(&b"01234"[..]));
+
+        let tail = get(&mounts, "GET /assets/reports/statement.pdf HTTP/1.1\r\nRange: bytes=-3");
+        assert_eq!(tail.bytes.as_deref(), Some(&b"789"[..]));
+
+        let past = get(&mounts, "GET /assets/reports/statement.pdf HTTP/1.1\r\nRange: bytes=99-");
+        assert_eq!(past.status_code, 416);
+        assert_eq!(past.headers["Content-Range"], "bytes */15");
+    }
+
+    #[test]
+    fn a_compressed_copy_goes_to_a_client_that_accepts_gzip() {
+        let mounts = [site("gzip")];
+        let zipped = get(&mounts, "GET /assets/app.js HTTP/1.1\r\nAccept-Encoding: br, gzip");
+        assert_eq!(zipped.headers["Content-Encoding"], "gzip");
+        assert_eq!(zipped.headers["Content-Type"], "text/javascript; charset=utf-8");
+        assert_eq!(zipped.bytes.as_deref(), Some(&b"\x1f\x8bzipped"[..]));
+
+        let plain = get(&mounts, "GET /assets/app.js HTTP/1.1\r\nAccept-Encoding: gzip;q=0");
+        assert!(!plain.headers.contains_key("Content-Encoding"));
+        assert_eq!(plain.headers["Vary"], "Accept-Encoding");
+        assert_ne!(plain.headers["ETag"], zipped.headers["ETag"]);
+    }
+}
@@ -1,10 +1,14 @@
 // Request Handler Module
 
 use std::collections::HashMap;
+use std::time::Instant;
 
+use crate::http::cors::Cors;
+use crate::http::files::Mount;
+use crate::http::idempotency::{Claim, Idempotency};
 use crate::http::{HttpRequest, HttpResponse};
-use crate::parser::Stmt;
-use crate::vm::{Bytecode, VM, Value};
+use crate::parser::{Hook, Stmt};
+use crate::vm::{Bytecode, ResponseStream, VM, Value};
 
 /// Make one request readable from eTamil.
 ///
@@ -31,6 +35,12 @@ pub fn bind_request(vm: &mut VM, request: &HttpRequest, path_params: &HashMap<St
         "request_body".to_string(),
         Value::String(request.body.clone()),
     );
+    // The server has put one there by the time a handler runs, whether the
+    // client sent it or not; see logging::request_id_of.
+    vm.variables.insert(
+        "request_id".to_string(),
+        Value::String(request.headers.get("x-request-id").cloned().unwrap_or_default()),
+    );
 
     // A multipart request carries form fields and files. The fields become
     // text; the files stay as bytes in the VM and the handler is told what
@@ -111,6 +121,27 @@ pub fn bind_request(vm: &mut VM, request: &HttpRequest, path_params: &HashMap<St
         Value::Map(as_value_map(path_params)),
     );
 
+    // Parsed, so a handler need not pick `headers["cookie"]` apart itself.
+    // The session cookie is among them, but only its verified id reaches the
+    // VM's session; see http::session.
+    let cookies = request
+        .headers
+        .get("cookie")
+        .map(|header| crate::http::session::parse_cookies(header))
+        .unwrap_or_default();
+    vm.session = crate::http::session::session_of(&cookies);
+    vm.secure = request.secure;
+    vm.variables
+        .insert("cookies".to_string(), Value::Map(as_value_map(&cookies)));
+
+    // Who is calling, when the server verified a client certificate: empty
+    // otherwise, like the multipart names, so a handler can compare against
+    // it without first asking whether it exists.
+    vm.variables.insert(
+        "client_subject".to_string(),
+        Value::String(request.client_subject.clone().unwrap_or_default()),
+    );
+
     // Also as param_<name>, which is how path parameters have always been
     // exposed; programs written against that keep working.
     for (name, value) in path_params {
@@ -159,6 +190,13 @@ pub fn response_from(vm: &VM) -> HttpResponse {
             response.set_header(name, &value.to_string());
         }
     }
+    // குக்கீ_அமை's, apart from the headers: பதில் replaces those, and a
+    // cookie set before it must still go out.
+    if let Some(Value::Array(cookies)) = vm.variables.get("response_cookies") {
+        for cookie in cookies {
+            response.set_header("Set-Cookie", &cookie.to_string());
+        }
+    }
 
     // பதில்_கோப்பு names a file to send instead of a body. The bytes are read
     // here rather than in the language, because a PDF is not a சரம் and
@@ -231,37 +269,279 @@ pub fn extract_path_params(pattern: &str, path: &str) -> HashMap<String, String>
         .collect()
 }
 
+/// A முன் or பின் block, compiled, and the paths it runs around.
+#[derive(Clone)]
+pub struct Middleware {
+    pub hook: Hook,
+    pub prefix: String,
+    pub bytecode: Bytecode,
+}
+
+impl Middleware {
+    /// Is `path` under this block's prefix? By whole segments, so "/api"
+    /// covers /api and /api/kaNakku but not /apiary.
+    pub fn covers(&self, path: &str) -> bool {
+        let prefix = self.prefix.split('/').filter(|p| !p.is_empty());
+        let mut path = path.split('/').filter(|p| !p.is_empty());
+        prefix.into_iter().all(|part| path.next() == Some(part))
+    }
+}
+
+/// Everything a program answers requests with: its routes, the முன் and பின்
+/// blocks around them, and the கோப்புறை directories beside them. Held by
+/// each server in a `reload::Table`, so `--watch` can put a new one in while
+/// requests are still being answered from the old.
+#[derive(Clone, Default)]
+pub struct Routes {
+    /// Compiled once at registration; a request should not pay to
+    /// recompile the handler every time.
+    pub handlers: HashMap<String, Bytecode>,
+    /// In the order they were written.
+    pub middleware: Vec<Middleware>,
+    pub mounts: Vec<Mount>,
+    /// The file's top-level setup, run once at the start of each request.
+    /// The blocks and the route share its VM and carry only the file's
+    /// functions, so the setup neither runs again for each of them nor
+    /// undoes what a முன் block set.
+    pub prelude: Option<Bytecode>,
+}
+
+impl Routes {
+    /// Add a route. The statements are compiled here, once.
+    pub fn register_route(&mut self, method: &str, path: &str, handler: Vec<Stmt>) {
+        let bytecode = crate::vm::BytecodeCompiler::compile_statements(handler);
+        self.handlers
+            .insert(format!("{} {}", method.to_uppercase(), path), bytecode);
+    }
+
+    /// Add a block to run before or after every route under `prefix`.
+    pub fn register_middleware(&mut self, hook: Hook, prefix: &str, body: Vec<Stmt>) {
+        let bytecode = crate::vm::BytecodeCompiler::compile_statements(body);
+        self.middleware.push(Middleware {
+            hook,
+            prefix: prefix.to_string(),
+            bytecode,
+        });
+    }
+
+    /// Serve a directory's files under a prefix, when no route matches.
+    pub fn register_mount(&mut self, mount: Mount) {
+        self.mounts.push(mount);
+    }
+
+    /// Set the setup every request starts with.
+    pub fn register_prelude(&mut self, prelude: Vec<Stmt>) {
+        self.prelude = Some(crate::vm::BytecodeCompiler::compile_statements(prelude));
+    }
+}
+
+/// The route a request is for, as it was registered — `GET /ANY/:kuRi` —
+/// with its handler. An exact match wins over a pattern.
+fn find_route<'a>(
+    handlers: &'a HashMap<String, Bytecode>,
+    request: &HttpRequest,
+) -> Option<(&'a str, &'a Bytecode)> {
+    let method = request.method.to_uppercase();
+    let exact_key = format!("{} {}", method, request.path);
+
+    if let Some((route_key, bytecode)) = handlers.get_key_value(&exact_key) {
+        return Some((route_key, bytecode));
+    }
+    handlers.iter().find_map(|(route_key, bytecode)| {
+        let (route_method, pattern) = route_key.split_once(' ')?;
+        if route_method != method || !path_matches(pattern, &request.path) {
+            return None;
+        }
+        Some((route_key.as_str(), bytecode))
+    })
+}
+
+/// The pattern of the route a request is for, to count it under in the
+/// server's metrics: `/ANY/:kuRi`, however many orders there are.
+pub fn route_of<'a>(handlers: &'a HashMap<String, Bytecode>, request: &HttpRequest) -> Option<&'a str> {
+    find_route(handlers, request)
+        .and_then(|(route_key, _)| route_key.split_once(' '))
+        .map(|(_, pattern)| pattern)
+}
+
+/// The answer to a CORS preflight by the server's policy, and the pattern to
+/// count it under, when some route serves the path it asks about. Anything
+/// else — a path with no routes, an `OPTIONS` no browser sent — is `None`
+/// and is routed as usual, to a 404 if nothing else.
+///
+/// Only asked when no route matched: a program with its own `தெரிவு` route
+/// for a path answers its preflights itself.
+pub fn preflight<'a>(
+    handlers: &'a HashMap<String, Bytecode>,
+    cors: &Cors,
+    request: &HttpRequest,
+) -> Option<(&'a str, HttpResponse)> {
+    if !Cors::is_preflight(request) {
+        return None;
+    }
+    let mut pattern = None;
+    let mut methods = Vec::new();
+    for route_key in handlers.keys() {
+        let Some((method, route)) = route_key.split_once(' ') else {
+            continue;
+        };
+        if !path_matches(route, &request.path) {
+            continue;
+        }
+        methods.push(method);
+        if pattern.is_none() || route == request.path {
+            pattern = Some(route);
+        }
+    }
+    methods.sort_unstable();
+    methods.dedup();
+    Some((pattern?, cors.preflight(request, &methods)))
+}
+
 /// Find the handler for a request and run it.
 ///
 /// Shared by both servers, so the sync and async paths cannot disagree about
 /// what a route means. An exact match wins; failing that, a pattern with
 /// `:params` is tried, and either way the handler is bound and read back
 /// identically.
-pub fn dispatch(handlers: &HashMap<String, Bytecode>, request: &HttpRequest) -> HttpResponse {
-    let method = request.method.to_uppercase();
-    let exact_key = format!("{} {}", method, request.path);
+pub fn dispatch(routes: &Routes, request: &HttpRequest) -> HttpResponse {
+    dispatch_streaming(routes, &Idempotency::default(), request, None, None)
+}
 
-    let matched = match handlers.get(&exact_key) {
-        Some(bytecode) => Some((bytecode, HashMap::new())),
-        None => handlers.iter().find_map(|(route_key, bytecode)| {
-            let (route_method, pattern) = route_key.split_once(' ')?;
-            if route_method != method || !path_matches(pattern, &request.path) {
-                return None;
-            }
-            Some((bytecode, extract_path_params(pattern, &request.path)))
-        }),
+/// As `dispatch`, with the connection handed to the handler so பதில்_பகுதி can
+/// send each part as it is made. When the handler streamed, its response has
+/// gone out already and what comes back only says so.
+///
+/// A handler that fails after it started streaming cannot turn into a 500:
+/// the status line has been sent. The stream is left without its last chunk
+/// instead, so the client sees a broken response rather than a short one that
+/// looks complete.
+///
+/// The file's setup runs first, then the முன் blocks covering the path, in
+/// the order they were written, in the same VM as the route — so what one
+/// sets, the route can read. One that answers with பதில் is the answer: the blocks after it and
+/// the route are skipped. The பின் blocks then run with the response in
+/// `response_status`, `response_body` and `response_headers`, free to add to
+/// it or log it. They do not run after a failure, which is answered 500
+/// whatever they would have said, nor after a streamed response, whose head
+/// has already gone.
+///
+/// A handler still running at `deadline` is stopped and answered 504. One
+/// whose deadline passed before it could start — the request waited that
+/// long for a worker — is not started at all, and answered 503: the server is
+/// too busy, and running it anyway would only make it busier.
+///
+/// A POST carrying an `Idempotency-Key` is claimed in `idempotency` first.
+pub fn dispatch_streaming(
+    routes: &Routes,
+    idempotency: &Idempotency,
+    request: &HttpRequest,
+    stream: Option<Box<dyn ResponseStream>>,
+    deadline: Option<Instant>,
+) -> HttpResponse {
+    let (bytecode, path_params) = match find_route(&routes.handlers, request) {
+        Some((route_key, bytecode)) => {
+            let pattern = route_key.split_once(' ').map_or("", |(_, pattern)| pattern);
+            (bytecode, extract_path_params(pattern, &request.path))
+        }
+        None => return HttpResponse::not_found(),
     };
 
-    let (bytecode, path_params) = match matched {
-        Some(found) => found,
-        None => return HttpResponse::not_found(),
+    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        let mut busy =
+            HttpResponse::custom(503, r#"{"error": "server too busy"}"#.to_string());
+        busy.set_header("Retry-After", "1");
+        return busy;
+    }
+
+    // A POST sent again with the Idempotency-Key it was first sent with is
+    // answered from what was kept; its handler does not run twice.
+    let ticket = match idempotency.claim(request) {
+        Claim::Untracked => None,
+        Claim::First(ticket) => Some(ticket),
+        Claim::Answered(response) => return response,
     };
+    let response = run_request(routes, bytecode, &path_params, request, stream, deadline);
+    if let Some(ticket) = ticket {
+        idempotency.settle(ticket, &response);
+    }
+    response
+}
 
+/// Run a matched request: the file's setup, its முன் blocks, its route and
+/// its பின் blocks, in one VM.
+fn run_request(
+    routes: &Routes,
+    bytecode: &Bytecode,
+    path_params: &HashMap<String, String>,
+    request: &HttpRequest,
+    stream: Option<Box<dyn ResponseStream>>,
+    deadline: Option<Instant>,
+) -> HttpResponse {
     let mut vm = VM::new();
-    bind_request(&mut vm, request, &path_params);
+    bind_request(&mut vm, request, path_params);
+    vm.response_stream = stream;
+
+    let around = |hook: Hook| {
+        routes
+            .middleware
+            .iter()
+            .filter(move |block| block.hook == hook && block.covers(&request.path))
+    };
 
-    match vm.execute(bytecode.clone()) {
+    let mut outcome = Ok(());
+    let before = around(Hook::Before).map(|block| &block.bytecode);
+    for part in routes.prelude.iter().chain(before) {
+        outcome = run_part(&mut vm, part, deadline);
+        if outcome.is_err() || answered(&vm) {
+            break;
+        }
+    }
+    if outcome.is_ok() && !answered(&vm) {
+        // A header a முன் block added survives the route's பதில், which
+        // otherwise replaces them all; the route's own win a clash.
+        let added = vm.variables.remove("response_headers");
+        outcome = run_part(&mut vm, bytecode, deadline);
+        if let Some(Value::Map(added)) = added
+            && !vm.response_started
+        {
+            let mut fields = added;
+            if let Some(Value::Map(own)) = vm.variables.remove("response_headers") {
+                fields.extend(own);
+            }
+            vm.variables
+                .insert("response_headers".to_string(), Value::Map(fields));
+        }
+    }
+    if outcome.is_ok() && !vm.response_started {
+        for block in around(Hook::After) {
+            outcome = run_part(&mut vm, &block.bytecode, deadline);
+            if outcome.is_err() {
+                break;
+            }
+        }
+    }
+
+    if vm.response_started
+        && let Some(mut stream) = vm.response_stream.take()
+    {
+        let status = response_from(&vm).status_code;
+        let clean = match &outcome {
+            Ok(_) => stream.finish().is_ok(),
+            Err(e) => {
+                eprintln!("❌ Handler execution error after streaming began: {}", e);
+                false
+            }
+        };
+        return HttpResponse::streamed(status, clean);
+    }
+
+    match outcome {
         Ok(_) => response_from(&vm),
+        Err(e) if vm.timed_out => {
+            eprintln!("❌ Handler timed out: {}", e);
+            HttpResponse::custom(504, r#"{"error": "handler timed out"}"#.to_string())
+        }
         Err(e) => {
             eprintln!("❌ Handler execution error: {}", e);
             HttpResponse::internal_error(&format!("Handler error: {}", e))
@@ -269,6 +549,23 @@ pub fn dispatch(handlers: &HashMap<String, Bytecode>, request: &HttpRequest) ->
     }
 }
 
+/// Run one of the programs that make up a request — the setup, a முன் block,
+/// the route, a பின் block — in the VM the others ran in.
+fn run_part(vm: &mut VM, bytecode: &Bytecode, deadline: Option<Instant>) -> Result<(), String> {
+    // The VM resumes where the last program ended; this one starts at its own
+    // beginning.
+    vm.instruction_pointer = 0;
+    match deadline {
+        Some(deadline) => vm.execute_until(bytecode.clone(), deadline),
+        None => vm.execute(bytecode.clone()),
+    }
+}
+
+/// Has a முன் block answered the request itself?
+fn answered(vm: &VM) -> bool {
+    vm.response_started || vm.variables.contains_key("response_status")
+}
+
 /// Run one tick of a scheduled job.
 ///
 /// A fresh VM, like a request — so a job sees no leftover state from its last
@@ -319,6 +616,9 @@ mod tests {
             query_params: HashMap::new(),
             body: String::new(),
             body_bytes: Vec::new(),
+            client_subject: None,
+            peer: None,
+            secure: false,
         }
     }
 
@@ -388,9 +688,131 @@ mod tests {
         }
     }
 
+    #[test]
+    fn the_client_subject_is_bound_and_empty_without_a_certificate() {
+        let mut vm = VM::new();
+        bind_request(&mut vm, &request("GET", "/"), &HashMap::new());
+        assert_eq!(text_var(&vm, "client_subject"), "");
+
+        let mut req = request("GET", "/");
+        req.client_subject = Some("CN=upi-partner,O=Example Bank,C=IN".to_string());
+        bind_request(&mut vm, &req, &HashMap::new());
+        assert_eq!(text_var(&vm, "client_subject"), "CN=upi-partner,O=Example Bank,C=IN");
+    }
+
     #[test]
     fn a_handler_that_sets_nothing_still_answers_200() {
         let vm = VM::new();
         assert_eq!(response_from(&vm).status_code, 200);
     }
+
+    fn parse(source: &str) -> Vec<Stmt> {
+        let tokens = crate::lexer::tokenize(source).expect("lexes");
+        crate::parser::Parser::new(tokens.iter()).parse().expect("parses")
+    }
+
+    fn compile(source: &str) -> Bytecode {
+        crate::vm::BytecodeCompiler::compile_statements(parse(source))
+    }
+
+    fn block(hook: Hook, prefix: &str, source: &str) -> Middleware {
+        Middleware { hook, prefix: prefix.to_string(), bytecode: compile(source) }
+    }
+
+    fn routes() -> HashMap<String, Bytecode> {
+        let mut handlers = HashMap::new();
+        handlers.insert(
+            "GET /api/kaNakku".to_string(),
+            compile(r#"பதில் 200, "கணக்கு " & பயனர்;"#),
+        );
+        handlers
+    }
+
+    fn around(middleware: &[Middleware]) -> Routes {
+        Routes { handlers: routes(), middleware: middleware.to_vec(), ..Routes::default() }
+    }
+
+    #[test]
+    fn a_preflight_for_a_routed_path_is_answered_without_running_it() {
+        let mut handlers = routes();
+        handlers.insert("PUT /api/kaNakku".to_string(), compile(r#"பதில் 500, "ran";"#));
+        let mut asking = request("OPTIONS", "/api/kaNakku");
+        asking.headers.insert("origin".to_string(), "https://dash.example.com".to_string());
+        asking.headers.insert("access-control-request-method".to_string(), "PUT".to_string());
+
+        let (pattern, answer) = preflight(&handlers, &Cors::default(), &asking).expect("answered");
+        assert_eq!(pattern, "/api/kaNakku");
+        assert_eq!(answer.status_code, 204);
+        assert_eq!(answer.headers["Access-Control-Allow-Methods"], "GET, PUT");
+
+        asking.path = "/api/vERu".to_string();
+        assert!(preflight(&handlers, &Cors::default(), &asking).is_none());
+        assert!(preflight(&handlers, &Cors::default(), &request("OPTIONS", "/api/kaNakku")).is_none());
+    }
+
+    #[test]
+    fn a_prefix_covers_whole_segments_only() {
+        let api = block(Hook::Before, "/api", "");
+        assert!(api.covers("/api"));
+        assert!(api.covers("/api/kaNakku/1000"));
+        assert!(!api.covers("/apiary"));
+        assert!(!api.covers("/"));
+        assert!(block(Hook::Before, "/", "").covers("/anything"));
+    }
+
+    // What a முன் block sets, the route reads: the token check runs once, and
+    // the route is handed who is calling.
+    #[test]
+    fn a_before_block_runs_first_in_the_routes_vm() {
+        let middleware = [block(Hook::Before, "/api", r#"பயனர் = "ravi";"#)];
+        let response = dispatch(&around(&middleware), &request("GET", "/api/kaNakku"));
+        assert_eq!(response.body, "கணக்கு ravi");
+    }
+
+    // The setup runs once, ahead of the blocks: run again for the route, it
+    // would put back what the முன் block replaced.
+    #[test]
+    fn the_setup_runs_once_before_the_blocks() {
+        let mut routes = around(&[block(Hook::Before, "/api", r#"பயனர் = பயனர் & " ravi";"#)]);
+        routes.register_prelude(parse(r#"பயனர் = "guest";"#));
+        let response = dispatch(&routes, &request("GET", "/api/kaNakku"));
+        assert_eq!(response.body, "கணக்கு guest ravi");
+    }
+
+    #[test]
+    fn a_before_block_that_answers_stops_the_route() {
+        let middleware = [
+            block(Hook::Before, "/api", r#"பதில் 401, "no token";"#),
+            block(Hook::Before, "/api", r#"பதில் 500, "never reached";"#),
+        ];
+        let response = dispatch(&around(&middleware), &request("GET", "/api/kaNakku"));
+        assert_eq!(response.status_code, 401);
+        assert_eq!(response.body, "no token");
+    }
+
+    #[test]
+    fn an_after_block_sees_the_response_and_adds_a_header() {
+        let middleware = [
+            block(Hook::Before, "/", r#"பயனர் = "ravi"; பதில்_தலைப்பு("X-Before", "1");"#),
+            block(
+                Hook::After,
+                "/api",
+                r#"பதில்_தலைப்பு("X-Status", response_status);"#,
+            ),
+        ];
+        let response = dispatch(&around(&middleware), &request("GET", "/api/kaNakku"));
+        assert_eq!(response.headers.get("X-Status").map(String::as_str), Some("200"));
+        // Added before the route, and kept through its பதில்.
+        assert_eq!(response.headers.get("X-Before").map(String::as_str), Some("1"));
+    }
+
+    #[test]
+    fn blocks_under_another_prefix_do_not_run() {
+        let middleware = [
+            block(Hook::Before, "/admin", r#"பதில் 403, "admins only";"#),
+            block(Hook::Before, "/", r#"பயனர் = "ravi";"#),
+        ];
+        let response = dispatch(&around(&middleware), &request("GET", "/api/kaNakku"));
+        assert_eq!(response.status_code, 200);
+    }
 }
@@ -0,0 +1,547 @@
+//! `Idempotency-Key`: a retried POST answered as it was the first time.
+//!
+//! A client that times out waiting on `POST /orders` cannot tell whether the
+//! order was placed. Sending it again may place it twice; not sending it may
+//! lose it. With `--idempotency`, a POST or PATCH carrying an
+//! `Idempotency-Key` header runs once: its answer is kept under the key, and
+//! the same request sent again with the same key gets that answer back,
+//! marked `Idempotent-Replayed: true`, without its handler running. The key
+//! sent again with a different body is a mistake in the client and is
+//! answered 422; sent again while the first is still running, 409 with a
+//! `Retry-After`.
+//!
+//! A key belongs to its method, its path, and the `Authorization` and session
+//! cookie it came with, so one client cannot read another's answer by
+//! guessing its key.
+//! Answers are kept for a day. A 5xx, or an answer that was streamed or is not
+//! text, is not kept: the request did not finish as its client meant, and
+//! running it again is what a retry is for.
+//!
+//! Answers are kept in memory, in a SQLite file, or in the Redis named by
+//! `--redis`, which several servers behind one load balancer need. A store
+//! that does not answer is answered 503. That is the opposite of what
+//! `ratelimit` does, on purpose: letting the request through is the double
+//! charge this is here to prevent, and the client can retry with the same key.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::db::Database;
+use crate::http::{HttpRequest, HttpResponse, session};
+use crate::redis::{Connection, Reply};
+use crate::vm::Value;
+
+/// The request header, lower-cased as `headers` holds it.
+pub const HEADER: &str = "idempotency-key";
+
+/// How long an answer is kept, in seconds.
+pub const TTL_SECS: u64 = 24 * 60 * 60;
+
+/// How long a key is held for a request still running. Past it the request
+/// is taken to have died with its server, and the key is free again.
+const PENDING_SECS: u64 = 10 * 60;
+
+/// Longer than any key a client library makes; a UUID is 36.
+const LONGEST_KEY: usize = 255;
+
+/// Expired entries are swept out of memory once there are more than this.
+const PRUNE_AT: usize = 10_000;
+
+const TABLE: &str = "CREATE TABLE IF NOT EXISTS etamil_idempotency \
+                     (key TEXT PRIMARY KEY, entry TEXT NOT NULL, until INTEGER NOT NULL)";
+
+/// What is kept under a key: the body it was first sent with, and what it was
+/// answered, once it has been.
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    fingerprint: String,
+    answer: Option<Answer>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Answer {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+    cookies: Vec<String>,
+}
+
+impl Answer {
+    fn replay(self) -> HttpResponse {
+        let mut response = HttpResponse::custom(self.status, self.body);
+        response.headers = self.headers;
+        response.cookies = self.cookies;
+        response.set_header("Idempotent-Replayed", "true");
+        response
+    }
+}
+
+enum Store {
+    Memory(Mutex<HashMap<String, (Instant, Entry)>>),
+    /// The file, for the startup banner, and one connection to it.
+    Sqlite(String, Mutex<Box<dyn Database>>),
+    /// `host:port`. A connection per operation, as `session` does.
+    Redis(String),
+}
+
+/// What to do with a request, before its handler runs.
+pub enum Claim {
+    /// Nothing: not a POST or PATCH with a key, or the server keeps none.
+    Untracked,
+    /// The first time the key is seen. Run the handler, then `settle`.
+    First(Ticket),
+    /// Seen before: the kept answer, or why there is none to give.
+    Answered(HttpResponse),
+}
+
+/// A key held for a request that is running.
+pub struct Ticket {
+    key: String,
+    fingerprint: String,
+}
+
+/// Where a server keeps its answers, if it keeps any. The default keeps none,
+/// and the header is not looked at.
+#[derive(Default)]
+pub struct Idempotency {
+    store: Option<Store>,
+}
+
+impl Idempotency {
+    /// Keep answers where `--idempotency`, or `ETAMIL_IDEMPOTENCY`, says:
+    /// `memory`, `redis` for the server's `--redis`, or `sqlite:PATH`.
+    pub fn resolve(flag: Option<String>, redis: Option<&str>) -> Result<Idempotency, String> {
+        let store = match flag.or_else(|| std::env::var("ETAMIL_IDEMPOTENCY").ok()) {
+            Some(spec) if !spec.trim().is_empty() => Some(Store::open(&spec, redis)?),
+            _ => None,
+        };
+        Ok(Idempotency { store })
+    }
+
+    /// Where answers are kept, for the startup banner, if they are.
+    pub fn describe(&self) -> Option<String> {
+        self.store.as_ref().map(Store::describe)
+    }
+
+    /// Hold this request's key, or answer it from what is kept under it.
+    pub fn claim(&self, request: &HttpRequest) -> Claim {
+        match &self.store {
+            Some(store) => store.claim(request),
+            None => Claim::Untracked,
+        }
+    }
+
+    /// Keep the answer a held key's request was given, or let the key go.
+    pub fn settle(&self, ticket: Ticket, response: &HttpResponse) {
+        if let Some(store) = &self.store {
+            store.settle(ticket, response);
+        }
+    }
+}
+
+impl Store {
+    fn open(spec: &str, redis: Option<&str>) -> Result<Store, String> {
+        match spec.trim() {
+            "memory" => Ok(Store::Memory(Mutex::new(HashMap::new()))),
+            "redis" => match redis {
+                Some(address) => Ok(Store::Redis(address.to_string())),
+                None => Err("--idempotency redis needs --redis, or ETAMIL_REDIS".to_string()),
+            },
+            other => match other.strip_prefix("sqlite:") {
+                Some(path) if !path.is_empty() => {
+                    let mut database = crate::db::open("SQLite", path)?;
+                    database.execute(TABLE, &[])?;
+                    Ok(Store::Sqlite(path.to_string(), Mutex::new(database)))
+                }
+                _ => Err(format!(
+                    "--idempotency needs memory, redis or sqlite:PATH, got '{}'",
+                    other
+                )),
+            },
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Store::Memory(_) => "kept in memory".to_string(),
+            Store::Sqlite(path, _) => format!("kept in {}", path),
+            Store::Redis(address) => format!("kept in Redis at {}", address),
+        }
+    }
+
+    fn claim(&self, request: &HttpRequest) -> Claim {
+        if !matches!(request.method.as_str(), "POST" | "PATCH") {
+            return Claim::Untracked;
+        }
+        let Some(key) = request.header(HEADER) else {
+            return Claim::Untracked;
+        };
+        if key.is_empty() || key.len() > LONGEST_KEY {
+            return Claim::Answered(HttpResponse::bad_request(
+                "Idempotency-Key must be 1 to 255 characters",
+            ));
+        }
+
+        let cookies = session::parse_cookies(request.header("cookie").unwrap_or(""));
+        let scope = [
+            request.method.as_str(),
+            request.path.as_str(),
+            request.header("authorization").unwrap_or(""),
+            cookies.get(session::COOKIE).map_or("", String::as_str),
+            key,
+        ];
+        let ticket = Ticket {
+            key: digest(scope.join("\n").as_bytes()),
+            fingerprint: digest(&request.body_bytes),
+        };
+        match self.begin(&ticket) {
+            Ok(None) => Claim::First(ticket),
+            Ok(Some(entry)) if entry.fingerprint != ticket.fingerprint => Claim::Answered(
+                HttpResponse::custom(
+                    422,
+                    r#"{"error": "Idempotency-Key was used with a different body"}"#.to_string(),
+                ),
+            ),
+            Ok(Some(Entry { answer: Some(answer), .. })) => Claim::Answered(answer.replay()),
+            Ok(Some(_)) => {
+                let mut running = HttpResponse::custom(
+                    409,
+                    r#"{"error": "a request with this Idempotency-Key is still running"}"#
+                        .to_string(),
+                );
+                running.set_header("Retry-After", "1");
+                Claim::Answered(running)
+            }
+            Err(why) => {
+                eprintln!("⚠️  Idempotency-Key store: {}", why);
+                let mut down = HttpResponse::custom(
+                    503,
+                    r#"{"error": "Idempotency-Key store unavailable"}"#.to_string(),
+                );
+                down.set_header("Retry-After", "1");
+                Claim::Answered(down)
+            }
+        }
+    }
+
+    fn settle(&self, ticket: Ticket, response: &HttpResponse) {
+        let kept = response.status_code < 500 && !response.streamed && response.bytes.is_none();
+        let outcome = if kept {
+            let entry = Entry {
+                fingerprint: ticket.fingerprint.clone(),
+                answer: Some(Answer {
+                    status: response.status_code,
+                    headers: response.headers.clone(),
+                    body: response.body.clone(),
+                    cookies: response.cookies.clone(),
+                }),
+            };
+            self.finish(&ticket, &entry)
+        } else {
+            self.forget(&ticket)
+        };
+        if let Err(why) = outcome {
+            eprintln!("⚠️  Idempotency-Key store: {}", why);
+        }
+    }
+
+    /// Hold the key for a request about to run: `None` if it was free, or
+    /// what is already kept under it.
+    fn begin(&self, ticket: &Ticket) -> Result<Option<Entry>, String> {
+        let pending = Entry {
+            fingerprint: ticket.fingerprint.clone(),
+            answer: None,
+        };
+        match self {
+            Store::Memory(entries) => {
+                let mut entries = entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let now = Instant::now();
+                if entries.len() > PRUNE_AT {
+                    entries.retain(|_, (until, _)| *until > now);
+                }
+                if let Some((until, entry)) = entries.get(&ticket.key)
+                    && *until > now
+                {
+                    return Ok(Some(entry.clone()));
+                }
+                let until = now + Duration::from_secs(PENDING_SECS);
+                entries.insert(ticket.key.clone(), (until, pending));
+                Ok(None)
+            }
+            Store::Sqlite(_, database) => {
+                let mut database = database.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let now = unix_now();
+                database.execute(
+                    "DELETE FROM etamil_idempotency WHERE until < ?",
+                    &[Value::Number(now.into())],
+                )?;
+                let held = database.execute(
+                    "INSERT OR IGNORE INTO etamil_idempotency (key, entry, until) VALUES (?, ?, ?)",
+                    &[
+                        Value::String(ticket.key.clone()),
+                        Value::String(encode(&pending)?),
+                        Value::Number((now + PENDING_SECS).into()),
+                    ],
+                )?;
+                if held == 1 {
+                    return Ok(None);
+                }
+                let rows = database.query(
+                    "SELECT entry FROM etamil_idempotency WHERE key = ?",
+                    &[Value::String(ticket.key.clone())],
+                )?;
+                match rows.first() {
+                    Some(Value::Map(row)) => match row.get("entry") {
+                        Some(Value::String(text)) => decode(text).map(Some),
+                        _ => Err("an entry that is not text".to_string()),
+                    },
+                    _ => Err("a key that was held is gone".to_string()),
+                }
+            }
+            Store::Redis(address) => {
+                let key = redis_key(ticket);
+                let arguments = [
+                    key.clone(),
+                    encode(&pending)?,
+                    "NX".to_string(),
+                    "EX".to_string(),
+                    PENDING_SECS.to_string(),
+                ];
+                if !matches!(redis(address, "SET", &arguments)?, Reply::Nil) {
+                    return Ok(None);
+                }
+                match redis(address, "GET", &[key])? {
+                    Reply::Bulk(text) => decode(&text).map(Some),
+                    _ => Err("a key that was held is gone".to_string()),
+                }
+            }
+        }
+    }
+
+    /// Keep the answer under a held key, for a day.
+    fn finish(&self, ticket: &Ticket, entry: &Entry) -> Result<(), String> {
+        match self {
+            Store::Memory(entries) => {
+                let until = Instant::now() + Duration::from_secs(TTL_SECS);
+                entries
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .insert(ticket.key.clone(), (until, entry.clone()));
+                Ok(())
+            }
+            Store::Sqlite(_, database) => database
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .execute(
+                    "UPDATE etamil_idempotency SET entry = ?, until = ? WHERE key = ?",
+                    &[
+                        Value::String(encode(entry)?),
+                        Value::Number((unix_now() + TTL_SECS).into()),
+                        Value::String(ticket.key.clone()),
+                    ],
+                )
+                .map(|_| ()),
+            Store::Redis(address) => {
+                let arguments = [
+                    redis_key(ticket),
+                    encode(entry)?,
+                    "EX".to_string(),
+                    TTL_SECS.to_string(),
+                ];
+                redis(address, "SET", &arguments).map(|_| ())
+            }
+        }
+    }
+
+    /// Let a held key go, so the request can be tried again.
+    fn forget(&self, ticket: &Ticket) -> Result<(), String> {
+        match self {
+            Store::Memory(entries) => {
+                entries
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .remove(&ticket.key);
+                Ok(())
+            }
+            Store::Sqlite(_, database) => database
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .execute(
+                    "DELETE FROM etamil_idempotency WHERE key = ?",
+                    &[Value::String(ticket.key.clone())],
+                )
+                .map(|_| ()),
+            Store::Redis(address) => redis(address, "DEL", &[redis_key(ticket)]).map(|_| ()),
+        }
+    }
+}
+
+/// SHA-256, as lowercase hex. A key is hashed so that what is stored says
+/// nothing of the token it was sent with.
+fn digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn encode(entry: &Entry) -> Result<String, String> {
+    serde_json::to_string(entry).map_err(|e| e.to_string())
+}
+
+fn decode(text: &str) -> Result<Entry, String> {
+    serde_json::from_str(text).map_err(|e| format!("an entry that does not read: {}", e))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+fn redis_key(ticket: &Ticket) -> String {
+    format!("idempotency:{}", ticket.key)
+}
+
+fn redis(address: &str, command: &str, arguments: &[String]) -> Result<Reply, String> {
+    match Connection::open(address)?.command(command, arguments)? {
+        Reply::Error(said) => Err(format!("Redis at {}: {}", address, said)),
+        reply => Ok(reply),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(key: &str, body: &str) -> HttpRequest {
+        let raw = format!(
+            "POST /orders HTTP/1.1\r\nIdempotency-Key: {}\r\nContent-Length: {}\r\n\r\n{}",
+            key,
+            body.len(),
+            body
+        );
+        HttpRequest::parse(&raw).unwrap()
+    }
+
+    fn signed_in(key: &str, session: &str) -> HttpRequest {
+        let raw = format!(
+            "POST /orders HTTP/1.1\r\nIdempotency-Key: {}\r\n\
+             Cookie: mozi=ta; etamil_session={}\r\nContent-Length: 2\r\n\r\n{{}}",
+            key, session
+        );
+        HttpRequest::parse(&raw).unwrap()
+    }
+
+    fn placed() -> HttpResponse {
+        let mut response = HttpResponse::success(201, r#"{"order": 1}"#.to_string());
+        response.set_header("Set-Cookie", "last_order=1");
+        response
+    }
+
+    fn stores() -> Vec<Store> {
+        let mut stores = vec![Store::open("memory", None).unwrap()];
+        #[cfg(feature = "sqlite")]
+        stores.push(Store::open("sqlite::memory:", None).unwrap());
+        stores
+    }
+
+    #[test]
+    fn a_repeat_is_answered_from_what_was_kept() {
+        for store in stores() {
+            let Claim::First(ticket) = store.claim(&order("k1", r#"{"item": 7}"#)) else {
+                panic!("the first request should run");
+            };
+            store.settle(ticket, &placed());
+
+            let Claim::Answered(replay) = store.claim(&order("k1", r#"{"item": 7}"#)) else {
+                panic!("the repeat should be answered");
+            };
+            assert_eq!(replay.status_code, 201);
+            assert_eq!(replay.body, r#"{"order": 1}"#);
+            assert_eq!(replay.cookies, vec!["last_order=1".to_string()]);
+            assert_eq!(replay.headers.get("Idempotent-Replayed").map(String::as_str), Some("true"));
+        }
+    }
+
+    #[test]
+    fn a_key_reused_with_another_body_is_refused() {
+        for store in stores() {
+            let Claim::First(ticket) = store.claim(&order("k2", r#"{"item": 7}"#)) else {
+                panic!("the first request should run");
+            };
+            assert!(matches!(
+                store.claim(&order("k2", r#"{"item": 8}"#)),
+                Claim::Answered(HttpResponse { status_code: 422, .. })
+            ));
+            assert!(matches!(
+                store.claim(&order("k2", r#"{"item": 7}"#)),
+                Claim::Answered(HttpResponse { status_code: 409, .. })
+            ));
+            store.settle(ticket, &placed());
+        }
+    }
+
+    #[test]
+    fn a_failure_lets_the_key_go() {
+        for store in stores() {
+            let Claim::First(ticket) = store.claim(&order("k3", "{}")) else {
+                panic!("the first request should run");
+            };
+            store.settle(ticket, &HttpResponse::internal_error("gateway down"));
+            assert!(matches!(store.claim(&order("k3", "{}")), Claim::First(_)));
+        }
+    }
+
+    // A browser signed in by cookie sends no Authorization: without its
+    // session in the scope, any other session guessing the key was answered
+    // with its order.
+    #[test]
+    fn a_key_belongs_to_its_session() {
+        for store in stores() {
+            let Claim::First(ticket) = store.claim(&signed_in("k5", "ravi.sig")) else {
+                panic!("the first request should run");
+            };
+            store.settle(ticket, &placed());
+
+            assert!(matches!(store.claim(&signed_in("k5", "ravi.sig")), Claim::Answered(_)));
+            assert!(matches!(store.claim(&signed_in("k5", "mala.sig")), Claim::First(_)));
+        }
+    }
+
+    #[test]
+    fn only_posts_and_patches_with_a_key_are_looked_at() {
+        let store = Store::open("memory", None).unwrap();
+        let raw = "GET /orders HTTP/1.1\r\nIdempotency-Key: k4\r\n\r\n";
+        assert!(matches!(
+            store.claim(&HttpRequest::parse(raw).unwrap()),
+            Claim::Untracked
+        ));
+        let raw = "POST /orders HTTP/1.1\r\nContent-Length: 0\r\n\r\n";
+        assert!(matches!(
+            store.claim(&HttpRequest::parse(raw).unwrap()),
+            Claim::Untracked
+        ));
+    }
+
+    fn refused(spec: &str) -> String {
+        match Store::open(spec, None) {
+            Err(why) => why,
+            Ok(_) => panic!("'{}' should be refused", spec),
+        }
+    }
+
+    #[test]
+    fn a_store_is_named_or_refused() {
+        assert!(refused("redis").contains("--redis"));
+        assert!(refused("disk").contains("sqlite:PATH"));
+        assert_eq!(
+            Store::open("redis", Some("127.0.0.1:6379")).unwrap().describe(),
+            "kept in Redis at 127.0.0.1:6379"
+        );
+    }
+}
@@ -3,8 +3,13 @@
 
 use chrono::Utc;
 use serde::{Serialize, Deserialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::http::HttpRequest;
 
 /// Log Level enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -17,6 +22,17 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
+    /// A level as an operator writes it: `debug`, `INFO`, `warn`, `error`.
+    pub fn parse(text: &str) -> Result<LogLevel, String> {
+        match text.trim().to_ascii_uppercase().as_str() {
+            "DEBUG" => Ok(LogLevel::Debug),
+            "INFO" => Ok(LogLevel::Info),
+            "WARN" | "WARNING" => Ok(LogLevel::Warn),
+            "ERROR" => Ok(LogLevel::Error),
+            _ => Err(format!("'{}' is not a log level: debug, info, warn or error", text)),
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         match self {
             LogLevel::Debug => "DEBUG",
@@ -143,6 +159,20 @@ pub enum LogOutput {
     Stderr,
     /// In-memory buffer (for testing)
     Memory(Arc<Mutex<Vec<LogEntry>>>),
+    /// Appended to a file, one JSON line per entry
+    File(Arc<Mutex<std::fs::File>>),
+}
+
+impl LogOutput {
+    /// Append to the file at `path`, creating it if it is not there.
+    pub fn file(path: &str) -> Result<LogOutput, String> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map(|file| LogOutput::File(Arc::new(Mutex::new(file))))
+            .map_err(|e| format!("cannot open log file '{}': {}", path, e))
+    }
 }
 
 impl Logger {
@@ -160,6 +190,29 @@ impl Logger {
         self
     }
 
+    /// A logger writing only to `output`, not to stdout as well.
+    pub fn writing_to(min_level: LogLevel, output: LogOutput) -> Self {
+        Logger {
+            min_level,
+            outputs: vec![output],
+        }
+    }
+
+    /// The server's logger: `--log-level` and `--log-file`, or
+    /// `ETAMIL_LOG_LEVEL` and `ETAMIL_LOG_FILE`. Info to stdout when neither
+    /// says otherwise.
+    pub fn resolve(level: Option<String>, file: Option<String>) -> Result<Logger, String> {
+        let level = match level.or_else(|| std::env::var("ETAMIL_LOG_LEVEL").ok()) {
+            Some(text) => LogLevel::parse(&text).map_err(|why| format!("--log-level: {}", why))?,
+            None => LogLevel::Info,
+        };
+        let output = match file.or_else(|| std::env::var("ETAMIL_LOG_FILE").ok()) {
+            Some(path) => LogOutput::file(&path).map_err(|why| format!("--log-file: {}", why))?,
+            None => LogOutput::Stdout,
+        };
+        Ok(Logger::writing_to(level, output))
+    }
+
     /// Log a message
     pub fn log(&self, entry: LogEntry) {
         if LogLevel::from_str(&entry.level) >= self.min_level {
@@ -203,6 +256,13 @@ impl Logger {
                         buf.push(entry.clone());
                     }
                 }
+                // One write per line, under the lock, so two requests logging
+                // at once cannot interleave inside each other's JSON.
+                LogOutput::File(file) => {
+                    if let Ok(mut file) = file.lock() {
+                        let _ = writeln!(file, "{}", json);
+                    }
+                }
             }
         }
     }
@@ -223,13 +283,53 @@ impl LogLevel {
 /// Generate a unique request ID
 pub fn generate_request_id() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
-    
+
+    // The clock alone is not unique: two workers can read the same instant,
+    // and on some platforms it only moves every microsecond or so.
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
     let nanos = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_nanos())
         .unwrap_or(0);
-    
-    format!("req-{:x}", nanos)
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+    format!("req-{:x}-{:x}", nanos, sequence)
+}
+
+/// The request's ID: the `X-Request-Id` it came with, so a proxy's logs and
+/// this server's can be joined on it, or a new one. Either way it is left in
+/// the request's headers, which is where a handler reads it from.
+///
+/// One sent by the client is only kept if it looks like an ID — short, and
+/// nothing but visible ASCII — since it is written into every log line for
+/// the request and echoed back in the response.
+pub fn request_id_of(request: &mut HttpRequest) -> String {
+    let sent = request.headers.get("x-request-id").filter(|id| {
+        !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic())
+    });
+    match sent {
+        Some(id) => id.clone(),
+        None => {
+            let id = generate_request_id();
+            request.headers.insert("x-request-id".to_string(), id.clone());
+            id
+        }
+    }
+}
+
+/// The access-log line for one answered request.
+pub fn access_entry(request_id: &str, request: &HttpRequest, status: u16, elapsed: Duration) -> LogEntry {
+    // A server error is something to be told about; the rest is traffic.
+    let level = if status >= 500 { LogLevel::Error } else { LogLevel::Info };
+    LogEntry::new(level, "request").with_request(RequestContext {
+        request_id: request_id.to_string(),
+        method: request.method.clone(),
+        path: request.path.clone(),
+        timestamp: Utc::now().to_rfc3339(),
+        status_code: Some(status),
+        duration_ms: Some(elapsed.as_millis() as u64),
+    })
 }
 
 #[cfg(test)]
@@ -272,6 +372,53 @@ mod tests {
         assert!(json.contains("DB_CONNECTION"));
     }
 
+    fn request_with(headers: &[(&str, &str)]) -> HttpRequest {
+        let mut raw = "GET /kaNakku HTTP/1.1\r\n".to_string();
+        for (name, value) in headers {
+            raw.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        raw.push_str("\r\n");
+        HttpRequest::parse(&raw).unwrap()
+    }
+
+    #[test]
+    fn a_request_id_sent_by_a_proxy_is_kept() {
+        let mut request = request_with(&[("X-Request-Id", "lb-7f3a9c")]);
+        assert_eq!(request_id_of(&mut request), "lb-7f3a9c");
+    }
+
+    // It goes into every log line and back out in a header, so a newline or
+    // a megabyte of it is not accepted as an ID.
+    #[test]
+    fn a_request_id_that_is_not_an_id_is_replaced() {
+        let long = "x".repeat(500);
+        let mut request = request_with(&[("X-Request-Id", &long)]);
+        let id = request_id_of(&mut request);
+        assert!(id.starts_with("req-"), "{}", id);
+        assert_eq!(request.headers.get("x-request-id"), Some(&id));
+    }
+
+    #[test]
+    fn a_file_output_gets_one_json_line_per_entry() {
+        let path = std::env::temp_dir().join("etamil_logging_file.log");
+        let _ = std::fs::remove_file(&path);
+        let output = LogOutput::file(path.to_str().unwrap()).unwrap();
+        let logger = Logger::writing_to(LogLevel::Warn, output);
+
+        logger.info("below the level");
+        let request = request_with(&[]);
+        logger.log(access_entry("req-1", &request, 503, Duration::from_millis(12)));
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", written);
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["level"], "ERROR");
+        assert_eq!(entry["request"]["request_id"], "req-1");
+        assert_eq!(entry["request"]["status_code"], 503);
+        assert_eq!(entry["request"]["duration_ms"], 12);
+    }
+
     #[test]
     fn test_request_id_generation() {
         let id1 = generate_request_id();
@@ -1,12 +1,11 @@
 // HTTP Server Module for eTamil Backend
 // Provides synchronous HTTP server capabilities for Minimum Viable Backend
 
-use std::collections::HashMap;
 use std::net::{TcpListener, TcpStream};
 use std::io::{Read, Write};
-use std::sync::{mpsc, Mutex};
-use std::time::Instant;
-use crate::parser::Stmt;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::parser::{Hook, Stmt};
 
 pub mod router;
 pub mod request;
@@ -20,9 +19,21 @@ pub mod monitoring;
 pub mod auth;       // Backend milestone 4: Authentication & Authorization
 pub mod cache;      // Backend milestone 4: Caching Layer
 pub mod resilience; // Backend milestone 4: Circuit breakers, retries, timeouts
+pub mod tls;        // HTTPS for both servers, and client certificates
+pub mod connection; // keep-alive, and responses streamed in chunks
+pub mod observe;    // the built-in /health and /metrics
+pub mod websocket;  // RFC 6455 framing, for handlers that upgrade
+pub mod openapi;    // the routes as an OpenAPI document
+pub mod files;      // கோப்புறை: a directory served under a prefix
+pub mod session;    // cookies, and sessions kept under a signed one
+pub mod ratelimit;  // --rate-limit: a token bucket per client
+pub mod cors;       // --cors-origin: who may read the answers, and preflights
+pub mod shutdown;   // SIGTERM, Ctrl-C and வழங்கி_நிறுத்து: drain, then stop
+pub mod reload;     // --watch: new routes in while the server runs
+pub mod idempotency; // --idempotency: a retried POST answered as the first was
 
 pub use self::router::Router;
-pub use self::request::HttpRequest;
+pub use self::request::{Frame, HttpRequest};
 pub use self::response::HttpResponse;
 pub use self::handler::{bind_request, response_from};
 pub use self::async_server::AsyncHttpServer;
@@ -38,14 +49,28 @@ pub struct HttpServer {
     pub host: String,
     pub port: u16,
     pub router: Router,
-    /// Compiled once at registration; a request should not pay to
-    /// recompile the handler every time.
-    pub handlers: HashMap<String, crate::vm::Bytecode>,
+    /// Routes, முன் and பின் blocks, and கோப்புறை directories; see
+    /// reload.rs for why they are behind a table.
+    pub routes: Arc<reload::Table>,
     pub logger: Logger,
     pub metrics: MetricsCollector,
     pub health_checker: HealthChecker,
     /// Timed jobs: how often, and what to run.
     pub schedules: Vec<(u64, crate::vm::Bytecode)>,
+    /// Set to serve HTTPS instead of HTTP; see tls.rs.
+    pub tls: Option<Arc<rustls::ServerConfig>>,
+    /// Timeouts and body sizes; see connection.rs.
+    pub limits: Arc<connection::Limits>,
+    /// Whether /metrics is served, and what /health asks; see observe.rs.
+    pub observe: observe::Observe,
+    /// Requests a client may make, per route prefix; see ratelimit.rs.
+    pub rate_limits: ratelimit::RateLimits,
+    /// Which other origins' pages may read the answers; see cors.rs.
+    pub cors: cors::Cors,
+    /// Where a retried POST's answer is kept; see idempotency.rs.
+    pub idempotency: idempotency::Idempotency,
+    /// Requests being answered, for draining them at a stop.
+    in_flight: shutdown::InFlight,
 }
 
 impl HttpServer {
@@ -55,11 +80,18 @@ impl HttpServer {
             host: host.to_string(),
             port,
             router: Router::new(),
-            handlers: HashMap::new(),
+            routes: Arc::new(reload::Table::default()),
             logger: Logger::new(LogLevel::Info),
             metrics: MetricsCollector::new(),
             health_checker: HealthChecker::new(),
             schedules: Vec::new(),
+            tls: None,
+            limits: Arc::new(connection::Limits::default()),
+            observe: observe::Observe::default(),
+            rate_limits: ratelimit::RateLimits::default(),
+            cors: cors::Cors::default(),
+            idempotency: idempotency::Idempotency::default(),
+            in_flight: shutdown::InFlight::default(),
         }
     }
 
@@ -69,14 +101,52 @@ impl HttpServer {
             host: host.to_string(),
             port,
             router: Router::new(),
-            handlers: HashMap::new(),
+            routes: Arc::new(reload::Table::default()),
             logger,
             metrics: MetricsCollector::new(),
             health_checker: HealthChecker::new(),
             schedules: Vec::new(),
+            tls: None,
+            limits: Arc::new(connection::Limits::default()),
+            observe: observe::Observe::default(),
+            rate_limits: ratelimit::RateLimits::default(),
+            cors: cors::Cors::default(),
+            idempotency: idempotency::Idempotency::default(),
+            in_flight: shutdown::InFlight::default(),
         }
     }
 
+    /// Serve HTTPS, with this certificate and, if it names a client CA, only
+    /// to clients that present a certificate from it.
+    pub fn use_tls(&mut self, config: Arc<rustls::ServerConfig>) {
+        self.tls = Some(config);
+    }
+
+    /// Time out and size requests by these limits instead of the defaults.
+    pub fn use_limits(&mut self, limits: connection::Limits) {
+        self.limits = Arc::new(limits);
+    }
+
+    /// Serve /metrics, or check a Redis from /health, as this says.
+    pub fn use_observe(&mut self, observe: observe::Observe) {
+        self.observe = observe;
+    }
+
+    /// Refuse clients that make more requests than these rules allow.
+    pub fn use_rate_limits(&mut self, rate_limits: ratelimit::RateLimits) {
+        self.rate_limits = rate_limits;
+    }
+
+    /// Answer cross-origin requests, and their preflights, by this policy.
+    pub fn use_cors(&mut self, cors: cors::Cors) {
+        self.cors = cors;
+    }
+
+    /// Answer a POST sent again with its Idempotency-Key from what this keeps.
+    pub fn use_idempotency(&mut self, idempotency: idempotency::Idempotency) {
+        self.idempotency = idempotency;
+    }
+
     /// Register a block to run on a timer.
     ///
     /// The interval is the gap *between* runs, not a fixed rate: a job slower
@@ -87,13 +157,28 @@ impl HttpServer {
         self.schedules.push((seconds.max(1), bytecode));
     }
 
+    /// Register a block to run before or after every route under `prefix`.
+    pub fn register_middleware(&mut self, hook: Hook, prefix: &str, body: Vec<Stmt>) {
+        self.routes
+            .edit(|routes| routes.register_middleware(hook, prefix, body));
+    }
+
+    /// Serve a directory's files under a prefix, when no route matches.
+    pub fn register_mount(&mut self, mount: files::Mount) {
+        self.routes.edit(|routes| routes.register_mount(mount));
+    }
+
+    /// Register the setup every request starts with.
+    pub fn register_prelude(&mut self, prelude: Vec<Stmt>) {
+        self.routes.edit(|routes| routes.register_prelude(prelude));
+    }
+
     /// Register a route with an eTamil handler.
     ///
     /// The statements are compiled here, once, rather than on every request.
     pub fn register_route(&mut self, method: &str, path: &str, handler: Vec<Stmt>) {
-        let bytecode = crate::vm::BytecodeCompiler::compile_statements(handler);
-        let route_key = format!("{} {}", method.to_uppercase(), path);
-        self.handlers.insert(route_key.clone(), bytecode);
+        self.routes
+            .edit(|routes| routes.register_route(method, path, handler));
         self.router.add_route(method, path);
         
         // Log route registration
@@ -108,11 +193,27 @@ impl HttpServer {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         println!("🚀 eTamil HTTP Server Started (Backend milestone 3 - Production Logging & Error Handling)");
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("📍 Listening on: http://{}", addr);
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        // The bound address, so that `--port 0` says which port it got.
+        let bound = listener.local_addr().map_or(addr, |bound| bound.to_string());
+        println!("📍 Listening on: {}://{}", scheme, bound);
         println!("📋 Registered Routes:");
         for route in self.router.routes.iter() {
             println!("   {} {}", route.method, route.path);
         }
+        for route in self.observe.routes() {
+            println!("   {} (built in)", route);
+        }
+        for mount in &self.routes.current().mounts {
+            println!("   GET {} → {}", mount.prefix, mount.dir.display());
+        }
+        for rule in self.rate_limits.rules() {
+            println!("⏳ Rate limit: {}", rule);
+        }
+        println!("🌐 CORS: {}", self.cors.describe());
+        if let Some(store) = self.idempotency.describe() {
+            println!("🔁 Idempotency-Key: answers {}", store);
+        }
         println!("📊 Metrics & Logging: Enabled");
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
@@ -129,8 +230,10 @@ impl HttpServer {
         let workers = Self::worker_count();
         println!("🧵 Worker threads: {}\n", workers);
 
-        let (sender, receiver) = mpsc::channel::<TcpStream>();
-        let receiver = Mutex::new(receiver);
+        // Each connection carries when it was accepted: time spent waiting
+        // for a worker comes off its first handler's time limit.
+        let (sender, receiver) = mpsc::channel::<(TcpStream, Instant)>();
+        let receiver = &Mutex::new(receiver);
 
         std::thread::scope(|scope| {
             // Timed jobs get their own threads, outside the request pool, so a
@@ -138,14 +241,15 @@ impl HttpServer {
             for (index, (seconds, bytecode)) in self.schedules.iter().enumerate() {
                 let label = format!("#{} every {}s", index + 1, seconds);
                 println!("⏱️  Scheduled job {}", label);
-                scope.spawn(move || loop {
-                    std::thread::sleep(std::time::Duration::from_secs(*seconds));
-                    handler::run_scheduled(&label, bytecode);
+                scope.spawn(move || {
+                    while shutdown::pause(Duration::from_secs(*seconds)) {
+                        handler::run_scheduled(&label, bytecode);
+                    }
                 });
             }
 
             for _ in 0..workers {
-                scope.spawn(|| {
+                scope.spawn(move || {
                     loop {
                         // The guard is released before the request is served,
                         // otherwise the pool would serialise on the queue.
@@ -157,17 +261,36 @@ impl HttpServer {
                             queue.recv()
                         };
                         match job {
-                            Ok(stream) => self.serve_connection(stream),
+                            Ok((stream, accepted)) => {
+                                self.serve_connection(scope, stream, accepted.elapsed())
+                            }
                             Err(_) => break, // listener closed
                         }
                     }
                 });
             }
 
+            // The accept below waits for the next connection, so a stop makes
+            // one to wake it.
+            if let Ok(mut address) = listener.local_addr() {
+                if address.ip().is_unspecified() {
+                    address.set_ip(match address {
+                        std::net::SocketAddr::V4(_) => std::net::Ipv4Addr::LOCALHOST.into(),
+                        std::net::SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+                    });
+                }
+                shutdown::on_stop(move || {
+                    let _ = TcpStream::connect(address);
+                });
+            }
+
             for stream in listener.incoming() {
+                if shutdown::stopping() {
+                    break;
+                }
                 match stream {
                     Ok(tcp_stream) => {
-                        if sender.send(tcp_stream).is_err() {
+                        if sender.send((tcp_stream, Instant::now())).is_err() {
                             break; // no workers left
                         }
                     }
@@ -178,6 +301,15 @@ impl HttpServer {
                     }
                 }
             }
+
+            // Stopping: the workers finish what was accepted and go.
+            drop(sender);
+            let unfinished = shutdown::drain(&self.in_flight, self.limits.drain_timeout);
+            shutdown::finish(unfinished, self.limits.drain_timeout);
+            if unfinished > 0 {
+                // Leaving the scope would wait for them after all.
+                std::process::exit(0);
+            }
         });
 
         Ok(())
@@ -197,126 +329,275 @@ impl HttpServer {
             .max(1)
     }
 
-    /// Read one whole request: headers first, then exactly as many body bytes
-    /// as `Content-Length` promises.
+    /// Take one connection, over TLS if the server was given a certificate,
+    /// and serve it.
     ///
-    /// This used to be a single 4 KB read. Anything larger arrived cut in
-    /// half, and because the request line and headers were intact the parse
-    /// still succeeded — so a truncated journal batch looked like a valid
-    /// request carrying half a payload, which is precisely the silent wrong
-    /// answer this project refuses everywhere else.
-    ///
-    /// Returns None if the connection closes early or the request exceeds
-    /// `MAX_REQUEST`, so a client cannot exhaust memory by promising a body
-    /// it never sends.
-    fn read_request(stream: &mut TcpStream) -> Option<Vec<u8>> {
-        const CHUNK: usize = 4096;
-        const MAX_REQUEST: usize = 1024 * 1024;
-        const SEPARATOR: &[u8] = b"\r\n\r\n";
-
-        let mut raw: Vec<u8> = Vec::with_capacity(CHUNK);
-        let mut chunk = [0u8; CHUNK];
-        let mut searched = 0usize;
-
-        // Headers, up to the blank line that ends them.
-        let header_end = loop {
-            if let Some(offset) = raw[searched..]
-                .windows(SEPARATOR.len())
-                .position(|window| window == SEPARATOR)
-            {
-                break searched + offset + SEPARATOR.len();
-            }
-            // Only the tail can still be part of a split separator, so the
-            // next scan need not start from the beginning again.
-            searched = raw.len().saturating_sub(SEPARATOR.len() - 1);
+    /// `scope` is the server's, so a request that holds its connection open —
+    /// a WebSocket, an events stream — can be moved to a thread of its own;
+    /// see `serve_stream`.
+    fn serve_connection<'scope>(
+        &'scope self,
+        scope: &'scope std::thread::Scope<'scope, '_>,
+        tcp_stream: TcpStream,
+        queued: Duration,
+    ) {
+        // A handle on the socket itself, so each read can be given however
+        // long is left on its clock even when TLS is wrapped around it. A
+        // timeout belongs to the socket, not the handle, so setting it here
+        // sets it for the stream being read.
+        let Ok(socket) = tcp_stream.try_clone() else {
+            return;
+        };
+        // The handshake is part of the wait for the first request's head.
+        let _ = socket.set_read_timeout(Some(self.limits.header_timeout));
 
-            if raw.len() > MAX_REQUEST {
-                return None;
-            }
-            match stream.read(&mut chunk) {
-                Ok(0) => return None,
-                Ok(n) => raw.extend_from_slice(&chunk[..n]),
-                Err(_) => return None,
+        let Some(config) = &self.tls else {
+            self.serve_stream(scope, tcp_stream, socket, None, queued, drop);
+            return;
+        };
+
+        let connection = match rustls::ServerConnection::new(Arc::clone(config)) {
+            Ok(connection) => connection,
+            Err(e) => {
+                self.logger.log(LogEntry::new(LogLevel::Error, format!("TLS setup failed: {}", e)));
+                return;
             }
         };
+        let mut stream = rustls::StreamOwned::new(connection, tcp_stream);
+
+        // The handshake is finished before anything is read, so that a client
+        // certificate has been verified — or the connection refused — before
+        // the request is even looked at.
+        while stream.conn.is_handshaking() {
+            if let Err(e) = stream.conn.complete_io(&mut stream.sock) {
+                let log_entry =
+                    LogEntry::new(LogLevel::Warn, format!("TLS handshake failed: {}", e));
+                self.logger.log(log_entry);
+                return;
+            }
+        }
+        let client_subject = crate::http::tls::client_subject(&stream.conn);
+
+        self.serve_stream(scope, stream, socket, client_subject, queued, |mut stream| {
+            stream.conn.send_close_notify();
+            let _ = stream.flush();
+        });
+    }
 
-        // Content-Length, read off the headers already in hand.
-        let content_length = String::from_utf8_lossy(&raw[..header_end])
-            .lines()
-            .find_map(|line| {
-                let (name, value) = line.split_once(':')?;
-                if name.trim().eq_ignore_ascii_case("content-length") {
-                    value.trim().parse::<usize>().ok()
-                } else {
-                    None
+    /// Serve requests off one connection until the client closes it, asks
+    /// for it closed, runs out of time, or has sent `MAX_REQUESTS`; see
+    /// `connection::Incoming`, which decides which.
+    ///
+    /// The stream is shared behind a lock because a handler that streams its
+    /// response writes to it from inside the VM. It is handed to `close` at
+    /// the end so TLS can say goodbye properly.
+    ///
+    /// A request that will hold the connection for as long as the client
+    /// stays — see `connection::long_lived` — is the connection's last. It is
+    /// moved to a thread of its own, and this worker goes back to the pool:
+    /// a payment-status screen left open all afternoon must not take a
+    /// worker from everyone else for all of it.
+    fn serve_stream<'scope, S>(
+        &'scope self,
+        scope: &'scope std::thread::Scope<'scope, '_>,
+        stream: S,
+        socket: TcpStream,
+        client_subject: Option<String>,
+        queued: Duration,
+        close: fn(S),
+    ) where
+        S: Read + Write + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(stream));
+        let mut incoming = connection::Incoming::new(Arc::clone(&self.limits), queued);
+        let mut chunk = [0u8; 4096];
+
+        loop {
+            match incoming.next_step() {
+                connection::Next::Request { request, .. } if connection::long_lived(&request) => {
+                    scope.spawn(move || {
+                        self.serve_request(request, &shared, &socket, false, None, client_subject);
+                        if let Some(stream) = Arc::try_unwrap(shared).ok().and_then(|s| s.into_inner().ok()) {
+                            close(stream);
+                        }
+                    });
+                    return;
                 }
-            })
-            .unwrap_or(0);
-
-        let want = header_end.saturating_add(content_length).min(MAX_REQUEST);
-        while raw.len() < want {
-            match stream.read(&mut chunk) {
-                // The client closed early. Serve what did arrive rather than
-                // dropping the connection without an answer.
-                Ok(0) => break,
-                Ok(n) => raw.extend_from_slice(&chunk[..n]),
-                Err(_) => break,
+                connection::Next::Request { request, keep, deadline } => {
+                    let response = self.serve_request(
+                        request,
+                        &shared,
+                        &socket,
+                        keep,
+                        deadline,
+                        client_subject.clone(),
+                    );
+                    if response.close || !keep || shutdown::stopping() {
+                        break;
+                    }
+                    incoming.answered();
+                }
+                // Nothing of another request yet, and the server is stopping.
+                connection::Next::Read(_) if shutdown::stopping() && incoming.between_requests() => break,
+                connection::Next::Read(wait) => {
+                    let _ = socket.set_read_timeout(Some(wait.min(shutdown::CHECK_EVERY)));
+                    let read = match shared.lock() {
+                        Ok(mut stream) => stream.read(&mut chunk),
+                        Err(_) => break,
+                    };
+                    match read {
+                        Ok(n) => incoming.received(&chunk[..n]),
+                        // Out of time: the next turn says what that means.
+                        Err(e)
+                            if matches!(
+                                e.kind(),
+                                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                            ) => {}
+                        Err(_) => break,
+                    }
+                }
+                connection::Next::Refuse(response) => {
+                    if response.status_code == 400 {
+                        let log_entry =
+                            LogEntry::new(LogLevel::Error, "Failed to parse HTTP request")
+                                .with_error("HTTP_PARSE_ERROR", &response.body);
+                        self.logger.log(log_entry);
+                    }
+                    self.metrics.record_request("/", "UNKNOWN", 0, false);
+                    Self::send_and_close(&shared, response);
+                    break;
+                }
+                connection::Next::Close => break,
             }
         }
 
-        Some(raw)
+        if let Some(stream) = Arc::try_unwrap(shared).ok().and_then(|s| s.into_inner().ok()) {
+            close(stream);
+        }
     }
 
-    /// Read one request off a connection, run it, and write the response.
-    fn serve_connection(&self, mut tcp_stream: TcpStream) {
-        let request_id = generate_request_id();
+    /// Run one request and answer it, unless its handler already did by
+    /// streaming.
+    fn serve_request<S>(
+        &self,
+        mut request: HttpRequest,
+        shared: &Arc<Mutex<S>>,
+        socket: &TcpStream,
+        keep: bool,
+        deadline: Option<Instant>,
+        client_subject: Option<String>,
+    ) -> HttpResponse
+    where
+        S: Read + Write + Send + 'static,
+    {
         let start_time = Instant::now();
-
-        let request_raw = match Self::read_request(&mut tcp_stream) {
-            Some(raw) => raw,
-            None => return,
+        let _serving = self.in_flight.serving();
+        let routes = self.routes.current();
+        let request_id = logging::request_id_of(&mut request);
+        request.client_subject = client_subject;
+        request.peer = socket.peer_addr().ok().map(|address| address.ip());
+        request.secure = self.tls.is_some();
+
+        let into = Arc::clone(shared);
+        let sink: connection::Sink = Box::new(move |bytes| {
+            let mut stream = into
+                .lock()
+                .map_err(|_| std::io::Error::other("connection lock poisoned"))?;
+            stream.write_all(bytes)?;
+            stream.flush()
+        });
+        let mut stream = connection::Chunked::new(sink, keep).with_header("X-Request-Id", &request_id);
+        for (name, value) in self.cors.headers_for(&request) {
+            stream = stream.with_header(&name, &value);
+        }
+        if let Some(key) = websocket::key_of(&request.headers)
+            && let Ok(socket) = socket.try_clone()
+        {
+            let from = Arc::clone(shared);
+            let source: connection::Source = Box::new(move |wait| {
+                // None is no timeout at all; a zero timeout is an error.
+                socket.set_read_timeout(wait)?;
+                let mut chunk = vec![0u8; 4096];
+                let n = from
+                    .lock()
+                    .map_err(|_| std::io::Error::other("connection lock poisoned"))?
+                    .read(&mut chunk)?;
+                chunk.truncate(n);
+                Ok(chunk)
+            });
+            stream = stream.with_socket(key, source);
+        }
+        let stream = Box::new(stream);
+        let mut built_in = self.observe.answer(
+            &request,
+            |key| routes.handlers.contains_key(key),
+            &self.metrics,
+            &self.health_checker,
+        );
+        let route = match &built_in {
+            Some(_) => request.path.clone(),
+            None => match handler::route_of(&routes.handlers, &request) {
+                Some(route) => {
+                    built_in = self.rate_limits.refuse(&request);
+                    route.to_string()
+                }
+                // No route: a preflight for a path with one, or a file, if a
+                // கோப்புறை covers the path.
+                None => match handler::preflight(&routes.handlers, &self.cors, &request) {
+                    Some((route, response)) => {
+                        built_in = Some(response);
+                        route.to_string()
+                    }
+                    None => match files::answer(&routes.mounts, &request) {
+                        Some((mount, response)) => {
+                            built_in = Some(response);
+                            mount.prefix.clone()
+                        }
+                        None => "unmatched".to_string(),
+                    },
+                },
+            },
+        };
+        let mut response = match built_in {
+            Some(response) => response,
+            None => handler::dispatch_streaming(
+                &routes,
+                &self.idempotency,
+                &request,
+                Some(stream),
+                deadline,
+            ),
         };
 
-        match HttpRequest::parse_bytes(&request_raw) {
-            Ok(request) => {
-                let mut req_context = std::collections::HashMap::new();
-                req_context.insert("request_id".to_string(), request_id);
-
-                let log_entry =
-                    LogEntry::new(LogLevel::Info, "Incoming request").with_context(req_context);
-                self.logger.log(log_entry);
-
-                let response = self.handle_request(&request);
-                let duration = start_time.elapsed().as_millis() as u64;
-
-                self.metrics.record_request(
-                    &request.path,
-                    &request.method,
-                    duration,
-                    response.status_code < 400,
-                );
-
-                let _ = tcp_stream.write_all(&response.to_http_bytes());
-            }
-            Err(e) => {
-                let log_entry = LogEntry::new(LogLevel::Error, "Failed to parse HTTP request")
-                    .with_error("HTTP_PARSE_ERROR", &e.to_string());
-                self.logger.log(log_entry);
-
-                let error_response = HttpResponse::bad_request(&e.to_string());
-                let _ = tcp_stream.write_all(&error_response.to_http_bytes());
-                self.metrics.record_request("/", "UNKNOWN", 0, false);
-            }
+        let elapsed = start_time.elapsed();
+        self.metrics.record(&request.method.to_uppercase(), &route, response.status_code, elapsed);
+        self.logger.log(logging::access_entry(&request_id, &request, response.status_code, elapsed));
+
+        if !response.streamed {
+            response.set_header("X-Request-Id", &request_id);
+            self.cors.decorate(&request, &mut response);
+            connection::mark(&mut response, keep && !shutdown::stopping());
+            let written = match shared.lock() {
+                Ok(mut stream) => stream
+                    .write_all(&response.to_http_bytes())
+                    .and_then(|_| stream.flush()),
+                Err(_) => Err(std::io::Error::other("connection lock poisoned")),
+            };
+            response.close |= written.is_err();
         }
+        response
     }
 
-    /// Handle an incoming HTTP request.
-    ///
-    /// Route matching and execution live in `handler::dispatch`, shared with
-    /// the async server so the two cannot disagree about what a route means.
-    fn handle_request(&self, request: &HttpRequest) -> HttpResponse {
-        handler::dispatch(&self.handlers, request)
+    /// Answer a request that could not be read, and give up on the connection:
+    /// there is no telling where the next request would start.
+    fn send_and_close<S: Write>(shared: &Mutex<S>, mut response: HttpResponse) {
+        connection::mark(&mut response, false);
+        if let Ok(mut stream) = shared.lock() {
+            let _ = stream.write_all(&response.to_http_bytes());
+            let _ = stream.flush();
+        }
     }
+
 }
 
 #[cfg(test)]
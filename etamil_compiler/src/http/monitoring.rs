@@ -5,7 +5,7 @@ use chrono::Utc;
 use serde::{Serialize, Deserialize};
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Request metrics
 #[allow(dead_code)]
@@ -58,8 +58,34 @@ pub struct MetricsCollector {
     start_time: Instant,
     total_requests: Arc<Mutex<u64>>,
     failed_requests: Arc<Mutex<u64>>,
-    response_times: Arc<Mutex<Vec<u64>>>,
+    response_times: Arc<Mutex<Timings>>,
     endpoint_metrics: Arc<Mutex<HashMap<String, EndpointMetrics>>>,
+    routes: Arc<Mutex<HashMap<(String, String), RouteSeries>>>,
+}
+
+/// What `get_metrics` reports about response times, kept as running totals.
+/// Every time used to be kept in a Vec, which on a server that stays up is
+/// memory that only grows.
+#[derive(Debug, Clone, Copy, Default)]
+struct Timings {
+    count: u64,
+    sum_ms: u64,
+    min_ms: u64,
+    max_ms: u64,
+}
+
+/// Upper bounds, in seconds, of the latency histogram's buckets: Prometheus'
+/// own defaults, which span a cached lookup to a slow report.
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// One route's requests, for `/metrics`.
+#[derive(Debug, Clone, Default)]
+struct RouteSeries {
+    statuses: HashMap<u16, u64>,
+    /// How many requests took no longer than each bound in BUCKETS.
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    seconds: f64,
 }
 
 /// Per-endpoint metrics
@@ -80,8 +106,9 @@ impl MetricsCollector {
             start_time: Instant::now(),
             total_requests: Arc::new(Mutex::new(0)),
             failed_requests: Arc::new(Mutex::new(0)),
-            response_times: Arc::new(Mutex::new(Vec::new())),
+            response_times: Arc::new(Mutex::new(Timings::default())),
             endpoint_metrics: Arc::new(Mutex::new(HashMap::new())),
+            routes: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -99,7 +126,10 @@ impl MetricsCollector {
 
         // Record response time
         if let Ok(mut times) = self.response_times.lock() {
-            times.push(duration_ms);
+            times.min_ms = if times.count == 0 { duration_ms } else { times.min_ms.min(duration_ms) };
+            times.max_ms = times.max_ms.max(duration_ms);
+            times.count += 1;
+            times.sum_ms += duration_ms;
         }
 
         // Update endpoint metrics
@@ -125,19 +155,100 @@ impl MetricsCollector {
         }
     }
 
+    /// Record an answered request against the route that answered it.
+    ///
+    /// `route` is the pattern — `/ANY/:kuRi`, not `/ANY/ORD-001` — so a
+    /// route is one series however many orders there are. A series per path
+    /// would grow with every id anyone asked for, and Prometheus with it.
+    pub fn record(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.record_request(route, method, elapsed.as_millis() as u64, status < 400);
+
+        let seconds = elapsed.as_secs_f64();
+        if let Ok(mut routes) = self.routes.lock() {
+            let series = routes
+                .entry((method.to_string(), route.to_string()))
+                .or_default();
+            *series.statuses.entry(status).or_insert(0) += 1;
+            for (bound, count) in BUCKETS.iter().zip(series.buckets.iter_mut()) {
+                if seconds <= *bound {
+                    *count += 1;
+                }
+            }
+            series.count += 1;
+            series.seconds += seconds;
+        }
+    }
+
+    /// Everything recorded, in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut routes: Vec<((String, String), RouteSeries)> = self
+            .routes
+            .lock()
+            .map(|routes| routes.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        // Sorted, so one scrape can be compared with the next by eye.
+        routes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut out = String::new();
+        out.push_str("# HELP etamil_requests_total Requests answered, by route and status.\n");
+        out.push_str("# TYPE etamil_requests_total counter\n");
+        for ((method, route), series) in &routes {
+            let mut statuses: Vec<_> = series.statuses.iter().collect();
+            statuses.sort();
+            for (status, count) in statuses {
+                out.push_str(&format!(
+                    "etamil_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}\n",
+                    label(method),
+                    label(route),
+                    status,
+                    count
+                ));
+            }
+        }
+
+        out.push_str("# HELP etamil_request_duration_seconds Time to answer a request, by route.\n");
+        out.push_str("# TYPE etamil_request_duration_seconds histogram\n");
+        for ((method, route), series) in &routes {
+            let labels = format!("method=\"{}\",route=\"{}\"", label(method), label(route));
+            for (bound, count) in BUCKETS.iter().zip(series.buckets.iter()) {
+                out.push_str(&format!(
+                    "etamil_request_duration_seconds_bucket{{{},le=\"{}\"}} {}\n",
+                    labels, bound, count
+                ));
+            }
+            out.push_str(&format!(
+                "etamil_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}\n",
+                labels, series.count
+            ));
+            out.push_str(&format!(
+                "etamil_request_duration_seconds_sum{{{}}} {}\n",
+                labels, series.seconds
+            ));
+            out.push_str(&format!(
+                "etamil_request_duration_seconds_count{{{}}} {}\n",
+                labels, series.count
+            ));
+        }
+
+        out.push_str("# HELP etamil_uptime_seconds Seconds since the server started.\n");
+        out.push_str("# TYPE etamil_uptime_seconds gauge\n");
+        out.push_str(&format!(
+            "etamil_uptime_seconds {}\n",
+            self.start_time.elapsed().as_secs_f64()
+        ));
+        out
+    }
+
     /// Get current metrics
     pub fn get_metrics(&self) -> RequestMetrics {
         let total = self.total_requests.lock().map(|t| *t).unwrap_or(0);
         let failed = self.failed_requests.lock().map(|f| *f).unwrap_or(0);
-        let times = self.response_times.lock().map(|t| t.clone()).unwrap_or_default();
+        let times = self.response_times.lock().map(|t| *t).unwrap_or_default();
 
-        let (min_time, max_time, avg_time) = if times.is_empty() {
-            (0, 0, 0.0)
+        let avg_time = if times.count == 0 {
+            0.0
         } else {
-            let min = *times.iter().min().unwrap_or(&0);
-            let max = *times.iter().max().unwrap_or(&0);
-            let avg = times.iter().sum::<u64>() as f64 / times.len() as f64;
-            (min, max, avg)
+            times.sum_ms as f64 / times.count as f64
         };
 
         let uptime = self.start_time.elapsed();
@@ -150,10 +261,10 @@ impl MetricsCollector {
         RequestMetrics {
             total_requests: total,
             failed_requests: failed,
-            total_time_ms: times.iter().sum(),
+            total_time_ms: times.sum_ms,
             avg_response_time_ms: avg_time,
-            min_response_time_ms: min_time,
-            max_response_time_ms: max_time,
+            min_response_time_ms: times.min_ms,
+            max_response_time_ms: times.max_ms,
             rps,
         }
     }
@@ -186,6 +297,15 @@ impl Default for MetricsCollector {
     }
 }
 
+/// A label value as the exposition format wants it: a route is written by
+/// whoever wrote the program, and a quote in one would end the label early.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
 /// Health checker
 pub struct HealthChecker {
     checks: Arc<Mutex<HashMap<String, Box<dyn Fn() -> HealthCheckItem + Send>>>>,
@@ -310,6 +430,31 @@ mod tests {
         assert_eq!(metrics.failed_requests, 1);
     }
 
+    // A bucket counts every request no slower than its bound, so the counts
+    // only grow towards +Inf.
+    #[test]
+    fn a_request_is_counted_in_every_bucket_it_fits() {
+        let collector = MetricsCollector::new();
+        collector.record("GET", "/kaNakku/:id", 200, Duration::from_millis(30));
+        collector.record("GET", "/kaNakku/:id", 404, Duration::from_millis(3));
+
+        let text = collector.to_prometheus();
+        let bucket = |le: &str| {
+            format!(
+                "etamil_request_duration_seconds_bucket{{method=\"GET\",route=\"/kaNakku/:id\",le=\"{}\"}}",
+                le
+            )
+        };
+        assert!(text.contains(&format!("{} 1\n", bucket("0.005"))), "{}", text);
+        assert!(text.contains(&format!("{} 1\n", bucket("0.025"))), "{}", text);
+        assert!(text.contains(&format!("{} 2\n", bucket("0.05"))), "{}", text);
+        assert!(text.contains(&format!("{} 2\n", bucket("+Inf"))), "{}", text);
+        assert!(text.contains(
+            "etamil_requests_total{method=\"GET\",route=\"/kaNakku/:id\",status=\"404\"} 1"
+        ));
+        assert_eq!(collector.get_metrics().failed_requests, 1);
+    }
+
     #[test]
     fn test_health_checker() {
         let checker = HealthChecker::new();
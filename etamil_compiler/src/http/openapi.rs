@@ -0,0 +1,276 @@
+//! An OpenAPI 3 document for a program's `வழி` routes.
+//!
+//! Every route's method and path is already in the program, `:id` parameters
+//! and all, so a client need not be handed a spec written separately and
+//! left to drift from the code. What the program cannot say on its own is
+//! what a route takes and answers; a route may say so in a record after its
+//! path, which the VM never runs — what it takes (`ஏற்பது`), what it answers
+//! with each status (`விடைகள்`), its query (`வினா`) and a `சுருக்கம்`:
+//!
+//! ```text
+//! வழி இடு, "/ANY/:kuRi", {
+//!     சுருக்கம்: "Mark an order paid",
+//!     வினா: {kuRippu: "ஈர்ம?"},
+//!     ஏற்பது: {qokY: "ரொக்கம்", kuRippu: "சொல்?"},
+//!     விடைகள்: {"200": {nilY: "சொல்"}, "404": {பிழை: "சொல்"}}
+//! } { … }
+//! ```
+//!
+//! A schema is a type keyword in a string, a record of schemas, or a list of
+//! one schema. A `?` after a keyword makes that field optional; every other
+//! field is required. `etamil openapi` prints the document, and `--openapi`
+//! serves it from both servers as `/openapi.json`.
+
+use serde_json::{json, Map, Value};
+
+use crate::http::HttpResponse;
+use crate::parser::{DeclaredType, Expr, Stmt};
+
+/// The OpenAPI version written. 3.0 rather than 3.1: more of the generators
+/// a partner will point at this still read only 3.0.
+const VERSION: &str = "3.0.3";
+
+/// The document for every route in `program`, titled `title`.
+///
+/// A route whose path is not a literal, or whose record is not one this
+/// understands, is an error naming the route: a document that silently left
+/// it out would be wrong in a way nobody notices until a client is.
+pub fn document(title: &str, program: &[Stmt]) -> Result<Value, String> {
+    let mut paths = Map::new();
+    for stmt in program {
+        let Stmt::DefineRoute { method, path, spec, .. } = stmt else {
+            continue;
+        };
+        let Expr::String(path) = path else {
+            return Err(format!("{} route: the path must be a literal, got {:?}", method, path));
+        };
+        let operation = operation(path, spec.as_ref())
+            .map_err(|why| format!("{} {}: {}", method, path, why))?;
+
+        let entry = paths
+            .entry(template(path))
+            .or_insert_with(|| Value::Object(Map::new()));
+        // The router takes the first route that matches, so the document does too.
+        if let Value::Object(methods) = entry {
+            methods.entry(method.to_lowercase()).or_insert(operation);
+        }
+    }
+
+    Ok(json!({
+        "openapi": VERSION,
+        "info": {"title": title, "version": "1.0.0"},
+        "paths": paths,
+    }))
+}
+
+/// The document as `/openapi.json` answers it.
+pub fn response(document: &str) -> HttpResponse {
+    let mut response = HttpResponse::success(200, document.to_string());
+    response.set_header("Content-Type", "application/json");
+    response
+}
+
+/// `/ANY/:kuRi` as OpenAPI writes it: `/ANY/{kuRi}`.
+fn template(path: &str) -> String {
+    path.split('/')
+        .map(|part| match part.strip_prefix(':') {
+            Some(name) => format!("{{{}}}", name),
+            None => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// One operation: its parameters from the path and `வினா`, and whatever else
+/// the route's record says.
+fn operation(path: &str, spec: Option<&Expr>) -> Result<Value, String> {
+    let fields = match spec {
+        None => &Vec::new(),
+        Some(Expr::RecordLiteral(fields)) => fields,
+        Some(other) => return Err(format!("the route's description must be a record, got {:?}", other)),
+    };
+
+    let mut operation = Map::new();
+    // A path parameter is whatever text was in the path; the handler reads
+    // it as a string, so that is what it is.
+    let mut parameters: Vec<Value> = path
+        .split('/')
+        .filter_map(|part| part.strip_prefix(':'))
+        .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+        .collect();
+    let mut responses = Map::new();
+
+    for (key, value) in fields {
+        match key.as_str() {
+            "சுருக்கம்" | "curukkam" => match value {
+                Expr::String(summary) => {
+                    operation.insert("summary".to_string(), json!(summary));
+                }
+                other => return Err(format!("{} must be a string, got {:?}", key, other)),
+            },
+            "வினா" | "viZA" => {
+                let Expr::RecordLiteral(query) = value else {
+                    return Err(format!("{} must be a record of the query's parameters", key));
+                };
+                for (name, schema_of) in query {
+                    let (schema, required) = schema(schema_of)?;
+                    parameters.push(json!({
+                        "name": name, "in": "query", "required": required, "schema": schema,
+                    }));
+                }
+            }
+            "ஏற்பது" | "ERpaqu" => {
+                let (schema, _) = schema(value)?;
+                operation.insert(
+                    "requestBody".to_string(),
+                    json!({"required": true, "content": {"application/json": {"schema": schema}}}),
+                );
+            }
+            "விடைகள்" | "vitYkaL" => {
+                let Expr::RecordLiteral(answers) = value else {
+                    return Err(format!("{} must be a record from status to schema", key));
+                };
+                for (status, body) in answers {
+                    let code: u16 = status
+                        .parse()
+                        .ok()
+                        .filter(|code| (100..600).contains(code))
+                        .ok_or_else(|| format!("{} is not an HTTP status", status))?;
+                    let mut answer = json!({"description": HttpResponse::status_text_for_code(code)});
+                    // இன்மை: a status with no body, as a 204 is.
+                    if !matches!(body, Expr::Null) {
+                        let (schema, _) = schema(body)?;
+                        answer["content"] = json!({"application/json": {"schema": schema}});
+                    }
+                    responses.insert(status.clone(), answer);
+                }
+            }
+            other => {
+                return Err(format!(
+                    "{} is not something a route can say: write சுருக்கம், வினா, ஏற்பது or விடைகள்",
+                    other
+                ));
+            }
+        }
+    }
+
+    if !parameters.is_empty() {
+        operation.insert("parameters".to_string(), Value::Array(parameters));
+    }
+    // OpenAPI requires at least one response; a route that said nothing
+    // answers 200 with whatever it answers.
+    if responses.is_empty() {
+        responses.insert("200".to_string(), json!({"description": "OK"}));
+    }
+    operation.insert("responses".to_string(), Value::Object(responses));
+    Ok(Value::Object(operation))
+}
+
+/// A JSON Schema for an annotation, and whether a field of this schema is
+/// required.
+fn schema(expr: &Expr) -> Result<(Value, bool), String> {
+    match expr {
+        Expr::String(word) => {
+            let (word, required) = match word.trim().strip_suffix('?') {
+                Some(word) => (word.trim(), false),
+                None => (word.trim(), true),
+            };
+            let declared = DeclaredType::from_keyword(word).ok_or_else(|| {
+                format!("\"{}\" is not a type: write எண், சொல், ஈர்ம, அணி, பொருள், தேதி or ரொக்கம்", word)
+            })?;
+            Ok((type_schema(declared), required))
+        }
+        Expr::RecordLiteral(fields) => {
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            for (name, field) in fields {
+                let (schema, needed) = schema(field)?;
+                if needed {
+                    required.push(json!(name));
+                }
+                properties.insert(name.clone(), schema);
+            }
+            let mut object = json!({"type": "object", "properties": properties});
+            if !required.is_empty() {
+                object["required"] = Value::Array(required);
+            }
+            Ok((object, true))
+        }
+        Expr::ArrayLiteral(items) => match items.as_slice() {
+            [] => Ok((json!({"type": "array"}), true)),
+            [item] => Ok((json!({"type": "array", "items": schema(item)?.0}), true)),
+            _ => Err("a list schema holds one schema, for every item".to_string()),
+        },
+        other => Err(format!("{:?} is not a schema: write a type keyword in quotes, a record or a list", other)),
+    }
+}
+
+fn type_schema(declared: DeclaredType) -> Value {
+    match declared {
+        DeclaredType::Number => json!({"type": "number"}),
+        DeclaredType::Text => json!({"type": "string"}),
+        DeclaredType::Boolean => json!({"type": "boolean"}),
+        DeclaredType::Array => json!({"type": "array"}),
+        DeclaredType::Record => json!({"type": "object"}),
+        DeclaredType::Date => json!({"type": "string", "format": "date"}),
+        // The shape ஜேசான்_ஆக்கு writes an amount in, currency and all.
+        DeclaredType::Money => json!({
+            "type": "object",
+            "properties": {"amount": {"type": "number"}, "currency": {"type": "string"}},
+            "required": ["amount", "currency"],
+        }),
+        DeclaredType::Any => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document_of(source: &str) -> Result<Value, String> {
+        let tokens = crate::lexer::tokenize(source).expect("lexes");
+        let program = crate::parser::Parser::new(tokens.iter()).parse().expect("parses");
+        document("kadai", &program)
+    }
+
+    #[test]
+    fn a_path_parameter_becomes_a_templated_path_and_a_parameter() {
+        let doc = document_of("வழி பெறு, \"/ANY/:kuRi\" { பதில் 200, \"x\"; }").unwrap();
+        let get = &doc["paths"]["/ANY/{kuRi}"]["get"];
+        assert_eq!(get["parameters"][0]["name"], "kuRi");
+        assert_eq!(get["parameters"][0]["in"], "path");
+        assert_eq!(get["responses"]["200"]["description"], "OK");
+    }
+
+    #[test]
+    fn a_routes_record_gives_its_body_query_and_answers() {
+        let doc = document_of(
+            r#"வழி பதி, "/ANY", {
+                சுருக்கம்: "Place an order",
+                வினா: {pakkam: "எண்?"},
+                ஏற்பது: {vAtikkYyALar: "சொல்", varicYkaL: [{kuRi: "eN", aLavu: "எண்"}], qokY: "ரொக்கம்"},
+                விடைகள்: {"201": {kuRi: "எண்"}, "400": {பிழை: "சொல்"}}
+            } { பதில் 201, "x"; }"#,
+        )
+        .unwrap();
+        let post = &doc["paths"]["/ANY"]["post"];
+        assert_eq!(post["summary"], "Place an order");
+        assert_eq!(post["parameters"][0]["in"], "query");
+        assert_eq!(post["parameters"][0]["required"], false);
+
+        let body = &post["requestBody"]["content"]["application/json"]["schema"];
+        assert_eq!(body["required"], json!(["vAtikkYyALar", "varicYkaL", "qokY"]));
+        assert_eq!(body["properties"]["varicYkaL"]["items"]["properties"]["aLavu"]["type"], "number");
+        assert_eq!(body["properties"]["qokY"]["properties"]["currency"]["type"], "string");
+
+        assert_eq!(post["responses"]["201"]["description"], "Created");
+        assert_eq!(post["responses"]["400"]["content"]["application/json"]["schema"]["properties"]["பிழை"]["type"], "string");
+    }
+
+    #[test]
+    fn a_word_that_is_not_a_type_is_refused_with_the_route_named() {
+        let error = document_of("வழி பெறு, \"/x\", {விடைகள்: {\"200\": \"integer\"}} { பதில் 200, \"x\"; }")
+            .unwrap_err();
+        assert!(error.starts_with("GET /x: \"integer\" is not a type"), "{}", error);
+    }
+}
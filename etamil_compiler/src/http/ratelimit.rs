@@ -0,0 +1,400 @@
+//! Per-client rate limits: `--rate-limit "/api/vari=60/min"`.
+//!
+//! Each rule gives the routes under a prefix a token bucket per client: it
+//! holds as many requests as the rule allows, a burst that size goes through
+//! at once, and it refills steadily over the period. A client with an empty
+//! bucket is answered 429, with a `Retry-After` saying when it will hold one
+//! again, and its handler never runs.
+//!
+//! A client is its address unless the rule says otherwise — an API key
+//! header, `by header:X-Api-Key`, or the `sub` of its bearer token,
+//! `by subject`. Behind a proxy every address is the proxy's, so a rule there
+//! keys by the header the proxy sets, `by header:X-Real-IP`. A request
+//! without the header or a valid token falls back to its address.
+//!
+//! The buckets are kept in memory, or in the Redis named by `--redis`, so
+//! that every server behind one load balancer draws on the same bucket. A
+//! Redis that does not answer lets requests through with a warning: a public
+//! rate lookup that goes down whenever Redis blinks is worse than one that is
+//! briefly unlimited. Rules apply to routes; the built-in endpoints and
+//! `கோப்புறை` files are not limited.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::http::handler::path_matches;
+use crate::http::{HttpRequest, HttpResponse};
+use crate::redis::{Connection, Reply};
+
+/// A bucket refilled to the brim is forgotten once there are more than this
+/// many, so a scan from a thousand addresses does not grow the map for ever.
+const PRUNE_AT: usize = 10_000;
+
+/// One `--rate-limit`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// As written, for the startup banner and the Redis key.
+    pub spec: String,
+    /// `None` for every method.
+    pub method: Option<String>,
+    /// A route path, with `:name` segments as in `வழி`, covering every path
+    /// under it by whole segments.
+    pub prefix: String,
+    /// Requests per period, which is also the largest burst.
+    pub requests: u32,
+    pub per: Duration,
+    pub key: Key,
+}
+
+/// Who a bucket belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    Address,
+    /// A request header's value, lower-cased as `headers` holds it.
+    Header(String),
+    /// The `sub` of the request's bearer token, verified.
+    Subject,
+}
+
+struct Bucket {
+    tokens: f64,
+    at: Instant,
+}
+
+enum Store {
+    Memory(Mutex<HashMap<String, Bucket>>),
+    /// `host:port`, and one connection kept open between requests.
+    Redis(String, Mutex<Option<Connection>>),
+}
+
+/// The rules, and where their buckets are kept.
+pub struct RateLimits {
+    rules: Vec<Rule>,
+    store: Store,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits {
+            rules: Vec::new(),
+            store: Store::Memory(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+/// Take a token from a bucket, as refilled by now: `None` if there was one,
+/// or how many seconds until there will be.
+const TAKE: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) + tonumber(time[2]) / 1000000
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'at')
+local tokens = tonumber(bucket[1]) or capacity
+local at = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - at) * rate)
+local wait = 0
+if tokens >= 1 then tokens = tokens - 1 else wait = math.ceil((1 - tokens) / rate) end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'at', tostring(now))
+redis.call('EXPIRE', KEYS[1], math.ceil(capacity / rate) + 1)
+return wait
+"#;
+
+impl RateLimits {
+    /// The rules from every `--rate-limit`, or from `ETAMIL_RATE_LIMIT`
+    /// separated by commas, kept in the Redis at `redis` if there is one.
+    pub fn resolve(flags: &[String], redis: Option<&str>) -> Result<RateLimits, String> {
+        let specs: Vec<String> = if flags.is_empty() {
+            std::env::var("ETAMIL_RATE_LIMIT")
+                .map(|value| value.split(',').map(str::to_string).collect())
+                .unwrap_or_default()
+        } else {
+            flags.to_vec()
+        };
+        let rules = specs
+            .iter()
+            .filter(|spec| !spec.trim().is_empty())
+            .map(|spec| parse_rule(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let store = match redis {
+            Some(address) => Store::Redis(address.to_string(), Mutex::new(None)),
+            None => Store::Memory(Mutex::new(HashMap::new())),
+        };
+        Ok(RateLimits { rules, store })
+    }
+
+    /// The rules as written, for the startup banner.
+    pub fn rules(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.spec.as_str())
+    }
+
+    /// A 429 for this request if its client has used up the first rule that
+    /// covers it, or `None` to let it through.
+    pub fn refuse(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        let rule = self.rules.iter().find(|rule| {
+            rule.method
+                .as_deref()
+                .is_none_or(|wanted| wanted.eq_ignore_ascii_case(&request.method))
+                && covers(&rule.prefix, &request.path)
+        })?;
+        let bucket = format!("ratelimit:{}:{}", rule.spec, client_of(rule, request));
+        let wait = self.take(rule, &bucket)?;
+
+        let mut refused =
+            HttpResponse::custom(429, r#"{"error": "too many requests"}"#.to_string());
+        refused.set_header("Retry-After", &wait.to_string());
+        Some(refused)
+    }
+
+    /// Take a token from a bucket: `None` if there was one, or the whole
+    /// seconds until there will be.
+    fn take(&self, rule: &Rule, bucket: &str) -> Option<u64> {
+        let capacity = f64::from(rule.requests);
+        let rate = capacity / rule.per.as_secs_f64();
+        match &self.store {
+            Store::Memory(buckets) => {
+                let mut buckets = buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let now = Instant::now();
+                if buckets.len() > PRUNE_AT {
+                    buckets.retain(|_, bucket| {
+                        bucket.tokens + now.duration_since(bucket.at).as_secs_f64() * rate < capacity
+                    });
+                }
+                let bucket = buckets
+                    .entry(bucket.to_string())
+                    .or_insert(Bucket { tokens: capacity, at: now });
+                bucket.tokens = (bucket.tokens + now.duration_since(bucket.at).as_secs_f64() * rate)
+                    .min(capacity);
+                bucket.at = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    None
+                } else {
+                    Some(((1.0 - bucket.tokens) / rate).ceil().max(1.0) as u64)
+                }
+            }
+            Store::Redis(address, connection) => {
+                let arguments = [
+                    TAKE.to_string(),
+                    "1".to_string(),
+                    bucket.to_string(),
+                    rule.requests.to_string(),
+                    rate.to_string(),
+                ];
+                let mut connection = connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let reply = match connection.as_mut() {
+                    Some(open) => open.command("EVAL", &arguments),
+                    None => Connection::open(address).and_then(|mut open| {
+                        let reply = open.command("EVAL", &arguments);
+                        *connection = Some(open);
+                        reply
+                    }),
+                };
+                match reply {
+                    Ok(Reply::Integer(0)) => None,
+                    Ok(Reply::Integer(wait)) => Some(wait.max(1) as u64),
+                    Ok(other) => {
+                        eprintln!("⚠️  Rate limit not applied: Redis at {} answered {:?}", address, other);
+                        None
+                    }
+                    Err(why) => {
+                        // Dropped, so the next request opens a fresh one.
+                        *connection = None;
+                        eprintln!("⚠️  Rate limit not applied: {}", why);
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Is `path` under `prefix`? By whole segments, so "/api" covers /api and
+/// /api/vari but not /apiary, and `:name` stands for any one segment.
+fn covers(prefix: &str, path: &str) -> bool {
+    let depth = prefix.split('/').filter(|part| !part.is_empty()).count();
+    let under: Vec<&str> = path
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .take(depth)
+        .collect();
+    under.len() == depth && path_matches(prefix, &format!("/{}", under.join("/")))
+}
+
+/// Who the request is, as the rule counts clients.
+fn client_of(rule: &Rule, request: &HttpRequest) -> String {
+    let named = match &rule.key {
+        Key::Address => None,
+        Key::Header(name) => request
+            .headers
+            .get(name)
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| format!("header:{}", value.trim())),
+        Key::Subject => request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| crate::http::auth::read_token(token.trim()).ok())
+            .and_then(|claims| serde_json::from_str::<serde_json::Value>(&claims).ok())
+            .and_then(|claims| match &claims["sub"] {
+                serde_json::Value::String(subject) => Some(subject.clone()),
+                serde_json::Value::Number(subject) => Some(subject.to_string()),
+                _ => None,
+            })
+            .map(|subject| format!("subject:{}", subject)),
+    };
+    named.unwrap_or_else(|| match request.peer {
+        Some(address) => format!("address:{}", address),
+        None => "address:unknown".to_string(),
+    })
+}
+
+/// `GET /api/vari=60/min`, the method optional, then ` by header:X-Api-Key`
+/// or ` by subject` if the client is not its address.
+fn parse_rule(spec: &str) -> Result<Rule, String> {
+    let malformed = || {
+        format!(
+            "--rate-limit needs [METHOD] /path=N/PERIOD [by KEY], like '/api/vari=60/min' \
+             or 'POST /api=600/hour by header:X-Api-Key', got '{}'",
+            spec
+        )
+    };
+    let spec = spec.trim();
+    let (limit, key) = match spec.rsplit_once(" by ") {
+        Some((limit, key)) => (limit, Some(key.trim())),
+        None => (spec, None),
+    };
+    let key = match key {
+        None | Some("address") | Some("ip") => Key::Address,
+        Some("subject") => Key::Subject,
+        Some(other) => match other.strip_prefix("header:") {
+            Some(name) if !name.trim().is_empty() => Key::Header(name.trim().to_lowercase()),
+            _ => {
+                return Err(format!(
+                    "--rate-limit: '{}' is not a client: use ip, header:NAME or subject",
+                    other
+                ));
+            }
+        },
+    };
+
+    let (route, rate) = limit.rsplit_once('=').ok_or_else(malformed)?;
+    let (requests, period) = rate.trim().split_once('/').ok_or_else(malformed)?;
+    let requests: u32 = requests
+        .trim()
+        .parse()
+        .ok()
+        .filter(|requests| *requests > 0)
+        .ok_or_else(malformed)?;
+    let per = match period.trim() {
+        "s" | "sec" | "second" => Duration::from_secs(1),
+        "m" | "min" | "minute" => Duration::from_secs(60),
+        "h" | "hour" => Duration::from_secs(60 * 60),
+        "d" | "day" => Duration::from_secs(24 * 60 * 60),
+        other => {
+            return Err(format!(
+                "--rate-limit: '{}' is not a period: use sec, min, hour or day",
+                other
+            ));
+        }
+    };
+    let (method, prefix) = match route.trim().split_once(char::is_whitespace) {
+        Some((method, prefix)) => (Some(method.to_uppercase()), prefix.trim()),
+        None => (None, route.trim()),
+    };
+    if !prefix.starts_with('/') {
+        return Err(malformed());
+    }
+    Ok(Rule {
+        spec: spec.to_string(),
+        method,
+        prefix: prefix.to_string(),
+        requests,
+        per,
+        key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        let mut raw = format!("{} {} HTTP/1.1\r\n", method, path);
+        for (name, value) in headers {
+            raw.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        raw.push_str("\r\n");
+        let mut request = HttpRequest::parse(&raw).unwrap();
+        request.peer = Some("203.0.113.7".parse().unwrap());
+        request
+    }
+
+    fn limits(specs: &[&str]) -> RateLimits {
+        let specs: Vec<String> = specs.iter().map(|spec| spec.to_string()).collect();
+        RateLimits::resolve(&specs, None).unwrap()
+    }
+
+    #[test]
+    fn a_rule_is_read_with_its_method_period_and_client() {
+        let rule = parse_rule("POST /api/:kuRi=600/hour by header:X-Api-Key").unwrap();
+        assert_eq!(rule.method.as_deref(), Some("POST"));
+        assert_eq!(rule.prefix, "/api/:kuRi");
+        assert_eq!(rule.requests, 600);
+        assert_eq!(rule.per, Duration::from_secs(3600));
+        assert_eq!(rule.key, Key::Header("x-api-key".to_string()));
+
+        assert_eq!(parse_rule("/api=5/min").unwrap().key, Key::Address);
+        assert!(parse_rule("/api=5/fortnight").unwrap_err().contains("not a period"));
+        assert!(parse_rule("/api=5/min by cookie").unwrap_err().contains("not a client"));
+        assert!(parse_rule("api=5").is_err());
+    }
+
+    #[test]
+    fn a_prefix_covers_whole_segments() {
+        assert!(covers("/api", "/api"));
+        assert!(covers("/api", "/api/vari/18"));
+        assert!(!covers("/api", "/apiary"));
+        assert!(covers("/", "/anything"));
+        assert!(covers("/kadai/:kuRi", "/kadai/7/vilY"));
+        assert!(!covers("/kadai/:kuRi", "/kadai"));
+    }
+
+    #[test]
+    fn a_client_that_empties_its_bucket_is_told_when_to_come_back() {
+        let limits = limits(&["/api/vari=2/min", "/api=100/min"]);
+        let lookup = request("GET", "/api/vari/18", &[]);
+        assert!(limits.refuse(&lookup).is_none());
+        assert!(limits.refuse(&lookup).is_none());
+
+        let refused = limits.refuse(&lookup).expect("the third is refused");
+        assert_eq!(refused.status_code, 429);
+        assert_eq!(refused.status_text, "Too Many Requests");
+        let wait: u64 = refused.headers["Retry-After"].parse().unwrap();
+        assert!((1..=30).contains(&wait), "{}", wait);
+
+        // Another route, under another rule, still has its own bucket.
+        assert!(limits.refuse(&request("GET", "/api/kaNakku", &[])).is_none());
+    }
+
+    #[test]
+    fn clients_keyed_by_header_have_a_bucket_each() {
+        let limits = limits(&["/api=1/min by header:X-Api-Key"]);
+        let with = |key: &str| request("GET", "/api/vari", &[("X-Api-Key", key)]);
+        assert!(limits.refuse(&with("kadai-1")).is_none());
+        assert!(limits.refuse(&with("kadai-2")).is_none());
+        assert!(limits.refuse(&with("kadai-1")).is_some());
+        // No key at all: the address, which has not been counted yet.
+        assert!(limits.refuse(&request("GET", "/api/vari", &[])).is_none());
+    }
+
+    #[test]
+    fn a_redis_that_does_not_answer_lets_requests_through() {
+        let specs = vec!["/api=1/min".to_string()];
+        let limits = RateLimits::resolve(&specs, Some("127.0.0.1:1")).unwrap();
+        let lookup = request("GET", "/api/vari", &[]);
+        assert!(limits.refuse(&lookup).is_none());
+        assert!(limits.refuse(&lookup).is_none());
+    }
+}
@@ -0,0 +1,113 @@
+//! `--watch`: serving a program's new routes as soon as it is saved.
+//!
+//! The server keeps its routes in a `Table`. Each request takes the table's
+//! routes as they are when it starts and is answered from those to the end,
+//! so putting new ones in — what `watch` does whenever the program or one of
+//! its imports changes — never drops a connection or changes a request
+//! partway through. A program that no longer loads or checks is reported and
+//! the old routes stay: a typo saved mid-edit should not take the server
+//! down with it.
+//!
+//! Only the routes, `முன்` / `பின்` blocks and `கோப்புறை` directories are
+//! reloaded. `இடைவெளி` jobs, and everything the command line says, are as
+//! they were when the server started.
+
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::http::handler::Routes;
+use crate::http::shutdown;
+
+/// How often the program's files are looked at.
+const POLL: Duration = Duration::from_millis(500);
+
+/// The routes a server is answering from, replaceable while it runs.
+#[derive(Default)]
+pub struct Table {
+    current: RwLock<Arc<Routes>>,
+}
+
+impl Table {
+    /// The routes to answer one request from.
+    pub fn current(&self) -> Arc<Routes> {
+        let current = self.current.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Arc::clone(&current)
+    }
+
+    /// Answer every request from here on from these.
+    pub fn replace(&self, routes: Routes) {
+        *self.current.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(routes);
+    }
+
+    /// Change the routes in place, for registering them before the server
+    /// starts. A request already answering from the old ones keeps them.
+    pub fn edit(&self, change: impl FnOnce(&mut Routes)) {
+        let mut current = self.current.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        change(Arc::make_mut(&mut current));
+    }
+}
+
+/// When each file was last changed, or `None` for one that has gone.
+fn stamps(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| std::fs::metadata(file).and_then(|meta| meta.modified()).ok())
+        .collect()
+}
+
+/// Watch `files` from a thread of its own and, whenever one changes, put the
+/// routes `load` makes into `table`. `load` also says which files the new
+/// program was made from, since an edit can add or drop an import. Stops when
+/// the server does.
+pub fn watch<F>(table: Arc<Table>, files: Vec<PathBuf>, mut load: F)
+where
+    F: FnMut() -> Result<(Routes, Vec<PathBuf>), String> + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut files = files;
+        let mut seen = stamps(&files);
+        while shutdown::pause(POLL) {
+            if stamps(&files) == seen {
+                continue;
+            }
+            // An editor may write a file in more than one go; the next look
+            // finds it finished.
+            if !shutdown::pause(POLL) {
+                break;
+            }
+            match load() {
+                Ok((routes, sources)) => {
+                    println!("🔄 Reloaded: {} route(s)", routes.handlers.len());
+                    table.replace(routes);
+                    files = sources;
+                }
+                Err(why) => {
+                    eprintln!("✗ {}", why);
+                    eprintln!("   Still serving the program as it was.");
+                }
+            }
+            seen = stamps(&files);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_request_keeps_the_routes_it_started_with() {
+        let table = Table::default();
+        table.edit(|routes| routes.register_route("GET", "/pazaiya", Vec::new()));
+        let answering = table.current();
+
+        let mut new = Routes::default();
+        new.register_route("GET", "/puthiya", Vec::new());
+        table.replace(new);
+
+        assert!(answering.handlers.contains_key("GET /pazaiya"));
+        assert!(table.current().handlers.contains_key("GET /puthiya"));
+        assert!(!table.current().handlers.contains_key("GET /pazaiya"));
+    }
+}
@@ -23,9 +23,105 @@ pub struct HttpRequest {
     /// lenient decode replaces every byte it dislikes. Anything that might
     /// not be text reads this instead.
     pub body_bytes: Vec<u8>,
+    /// The subject of the certificate the client presented, when the server
+    /// requires one and the handshake verified it. Set by the server from the
+    /// connection, never from anything the request itself says.
+    pub client_subject: Option<String>,
+    /// The address the connection came from. Set by the server, like
+    /// `client_subject`; behind a proxy it is the proxy's.
+    pub peer: Option<std::net::IpAddr>,
+    /// Whether it came in over TLS. Set by the server, like `client_subject`.
+    pub secure: bool,
+}
+
+/// What the bytes read so far off a connection hold.
+#[derive(Debug)]
+pub enum Frame {
+    /// Not a whole request yet; read more.
+    Incomplete,
+    /// One whole request, and how many bytes of the buffer it took. Anything
+    /// after those is the start of the next request, sent without waiting for
+    /// this one's answer.
+    Request(Box<HttpRequest>, usize),
+    /// Bytes that cannot be a request. The connection cannot go on after
+    /// this: there is no telling where the next request would begin.
+    Invalid(String),
 }
 
 impl HttpRequest {
+    /// Take the first whole request off the front of what a connection has
+    /// sent, however its body is framed.
+    ///
+    /// A body is either `Content-Length` bytes long or, with
+    /// `Transfer-Encoding: chunked`, a run of sized chunks ending in an empty
+    /// one — which is how several payment gateways send webhooks. Reading
+    /// only `Content-Length` used to hand those handlers an empty body. When
+    /// both are given, the chunking wins, as RFC 9112 says: a server and a
+    /// proxy disagreeing about where a body ends is how one request gets
+    /// smuggled inside another.
+    ///
+    /// `closed` says the client has stopped sending. A `Content-Length` body
+    /// cut short is then served as far as it got, as it always was; a chunked
+    /// one cannot be, since its last chunk is the only sign it was complete.
+    pub fn frame(buffer: &[u8], closed: bool) -> Frame {
+        let Some((head_end, body_at)) = head_end(buffer) else {
+            return Frame::Incomplete;
+        };
+        let head = String::from_utf8_lossy(&buffer[..head_end]);
+        let mut request = match Self::parse_head(&head) {
+            Ok(request) => request,
+            Err(why) => return Frame::Invalid(why),
+        };
+
+        let (body, used) = match request.header("transfer-encoding") {
+            Some(coding) if coding.eq_ignore_ascii_case("chunked") => {
+                match dechunk(&buffer[body_at..]) {
+                    Ok(Some((body, length))) => (body, body_at + length),
+                    Ok(None) if closed => {
+                        return Frame::Invalid(
+                            "the request ended partway through its chunked body".to_string(),
+                        );
+                    }
+                    Ok(None) => return Frame::Incomplete,
+                    Err(why) => return Frame::Invalid(why),
+                }
+            }
+            Some(coding) => {
+                return Frame::Invalid(format!("unsupported Transfer-Encoding '{}'", coding));
+            }
+            None => {
+                let length = match request.header("content-length") {
+                    None => 0,
+                    Some(value) => match value.trim().parse::<usize>() {
+                        Ok(length) => length,
+                        Err(_) => {
+                            return Frame::Invalid(format!("bad Content-Length '{}'", value));
+                        }
+                    },
+                };
+                let available = buffer.len() - body_at;
+                if available < length && !closed {
+                    return Frame::Incomplete;
+                }
+                let length = length.min(available);
+                (buffer[body_at..body_at + length].to_vec(), body_at + length)
+            }
+        };
+
+        request.body = String::from_utf8_lossy(&body).into_owned();
+        request.body_bytes = body;
+        Frame::Request(Box::new(request), used)
+    }
+
+    /// The head of a request whose body is still arriving, and how much of
+    /// the body is in hand — enough to refuse a body that is too large
+    /// without waiting for the rest of it. `None` until the head is complete.
+    pub(crate) fn head_of(buffer: &[u8]) -> Option<Result<(HttpRequest, usize), String>> {
+        let (head_end, body_at) = head_end(buffer)?;
+        let head = String::from_utf8_lossy(&buffer[..head_end]);
+        Some(Self::parse_head(&head).map(|request| (request, buffer.len() - body_at)))
+    }
+
     /// Parse an HTTP request from text. Convenience over `parse_bytes` for
     /// callers that already hold a String — a test fixture, mostly.
     pub fn parse(raw: &str) -> Result<Self, String> {
@@ -94,6 +190,9 @@ impl HttpRequest {
             // Both filled in by parse_bytes, which is the only caller.
             body: String::new(),
             body_bytes: Vec::new(),
+            client_subject: None,
+            peer: None,
+            secure: false,
         })
     }
 
@@ -140,6 +239,76 @@ impl HttpRequest {
     }
 }
 
+/// Where the head ends and the body begins, if the blank line that separates
+/// them has arrived. Bare LF is accepted as well as CRLF, as in parse_bytes.
+///
+/// One pass that stops at the first blank line: this runs again on every
+/// read while a body arrives, so it must not search the body.
+fn head_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    buffer.iter().enumerate().find_map(|(at, &byte)| {
+        if byte != b'\n' {
+            return None;
+        }
+        let line_end = if at > 0 && buffer[at - 1] == b'\r' { at - 1 } else { at };
+        match &buffer[at + 1..] {
+            [b'\n', ..] => Some((line_end, at + 2)),
+            [b'\r', b'\n', ..] => Some((line_end, at + 3)),
+            _ => None,
+        }
+    })
+}
+
+/// Decode a chunked body from the start of `input`: the body, and how many
+/// bytes it took up. `None` until the last, empty, chunk and the trailers
+/// after it have all arrived.
+///
+/// Chunk extensions are ignored and so are trailers, which is what RFC 9112
+/// allows a recipient that has no use for them to do.
+///
+/// Like `head_end` this runs on every read while the body arrives, so it
+/// steps over each chunk's data and only copies it out once the last chunk
+/// is in.
+fn dechunk(input: &[u8]) -> Result<Option<(Vec<u8>, usize)>, String> {
+    let mut chunks: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut at = 0;
+    loop {
+        let Some(line_end) = find_bytes(&input[at..], b"\r\n") else {
+            return Ok(None);
+        };
+        let line = String::from_utf8_lossy(&input[at..at + line_end]);
+        let size_text = line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_text, 16)
+            .map_err(|_| format!("bad chunk size '{}'", size_text))?;
+        at += line_end + 2;
+
+        if size == 0 {
+            // Trailers, if any, then the blank line that ends the body.
+            loop {
+                let Some(end) = find_bytes(&input[at..], b"\r\n") else {
+                    return Ok(None);
+                };
+                at += end + 2;
+                if end == 0 {
+                    let body = chunks.into_iter().flat_map(|chunk| &input[chunk]).copied().collect();
+                    return Ok(Some((body, at)));
+                }
+            }
+        }
+
+        let Some(end) = at.checked_add(size).filter(|end| end.checked_add(2).is_some()) else {
+            return Err(format!("chunk size '{}' is too large", size_text));
+        };
+        if input.len() < end + 2 {
+            return Ok(None);
+        }
+        if &input[end..end + 2] != b"\r\n" {
+            return Err("a chunk was longer than its size said".to_string());
+        }
+        chunks.push(at..end);
+        at = end + 2;
+    }
+}
+
 /// Percent-decode one query-string component, treating '+' as a space.
 ///
 /// Query values are the only way a GET route receives an argument, and this
@@ -262,6 +431,69 @@ mod tests {
         assert_eq!(req.query_param("note"), Some("two words"));
     }
 
+    fn whole(frame: Frame) -> (HttpRequest, usize) {
+        match frame {
+            Frame::Request(request, used) => (*request, used),
+            other => panic!("expected a whole request, got {:?}", other),
+        }
+    }
+
+    // Several payment gateways send webhooks chunked. Reading only
+    // Content-Length handed those handlers an empty body.
+    #[test]
+    fn a_chunked_body_is_put_back_together() {
+        let raw = b"POST /webhook HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                    7\r\n{\"qokY\"\r\n6;ext=1\r\n:1500}\r\n0\r\nX-Trailer: t\r\n\r\n";
+        let (request, used) = whole(HttpRequest::frame(raw, false));
+
+        assert_eq!(request.body, "{\"qokY\":1500}");
+        assert_eq!(used, raw.len());
+    }
+
+    #[test]
+    fn a_chunked_body_is_incomplete_until_its_last_chunk() {
+        let raw = b"POST /webhook HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n";
+        assert!(matches!(HttpRequest::frame(raw, false), Frame::Incomplete));
+        assert!(matches!(HttpRequest::frame(raw, true), Frame::Invalid(_)));
+    }
+
+    // The smuggling case: a body framed two ways is read the way RFC 9112
+    // says, by its chunks, and nothing after them leaks into it.
+    #[test]
+    fn chunking_wins_over_content_length() {
+        let raw = b"POST /x HTTP/1.1\r\nContent-Length: 4\r\nTransfer-Encoding: chunked\r\n\r\n\
+                    2\r\nok\r\n0\r\n\r\nGET /next HTTP/1.1\r\n\r\n";
+        let (request, used) = whole(HttpRequest::frame(raw, false));
+
+        assert_eq!(request.body, "ok");
+        assert!(raw[used..].starts_with(b"GET /next"));
+    }
+
+    #[test]
+    fn pipelined_requests_come_off_one_at_a_time() {
+        let raw = b"POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcGET /b HTTP/1.1\r\n\r\n";
+        let (first, used) = whole(HttpRequest::frame(raw, false));
+        assert_eq!((first.path.as_str(), first.body.as_str()), ("/a", "abc"));
+
+        let (second, rest) = whole(HttpRequest::frame(&raw[used..], false));
+        assert_eq!(second.path, "/b");
+        assert_eq!(used + rest, raw.len());
+    }
+
+    #[test]
+    fn a_body_not_yet_arrived_is_waited_for() {
+        let raw = b"POST /a HTTP/1.1\r\nContent-Length: 10\r\n\r\nabc";
+        assert!(matches!(HttpRequest::frame(raw, false), Frame::Incomplete));
+        // Unless the client has gone: then it is served as far as it got.
+        assert_eq!(whole(HttpRequest::frame(raw, true)).0.body, "abc");
+    }
+
+    #[test]
+    fn a_bad_chunk_size_is_refused() {
+        let raw = b"POST /a HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+        assert!(matches!(HttpRequest::frame(raw, false), Frame::Invalid(_)));
+    }
+
     // Malformed input arrives from the network; it must not panic.
     #[test]
     fn a_stray_percent_is_left_alone() {
@@ -1,8 +1,8 @@
 // Backend milestone 4: Resilience Patterns (Circuit Breaker, Retries, Timeouts)
 
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 /// Circuit breaker states
@@ -31,118 +31,160 @@ impl Default for CircuitBreakerConfig {
     }
 }
 
+/// What a breaker has seen, behind one lock so a state and its counts never
+/// disagree.
+struct Counts {
+    state: CircuitBreakerState,
+    failures: u32,
+    successes: u32,
+    last_failure: Option<Instant>,
+    /// When the one call let through half-open went out, until it settles.
+    probe: Option<Instant>,
+}
+
 /// Circuit breaker implementation
+///
+/// The lock is a std one and never held across an await, so the same breaker
+/// serves async callers through `try_request` and the VM, which blocks,
+/// through `admit`, `succeeded` and `failed`.
 pub struct CircuitBreaker {
-    state: Arc<tokio::sync::RwLock<CircuitBreakerState>>,
-    failure_count: Arc<AtomicU32>,
-    success_count: Arc<AtomicU32>,
-    last_failure_time: Arc<tokio::sync::RwLock<Option<SystemTime>>>,
+    counts: Mutex<Counts>,
     config: CircuitBreakerConfig,
 }
 
 impl CircuitBreaker {
     pub fn new(config: CircuitBreakerConfig) -> Self {
         Self {
-            state: Arc::new(tokio::sync::RwLock::new(CircuitBreakerState::Closed)),
-            failure_count: Arc::new(AtomicU32::new(0)),
-            success_count: Arc::new(AtomicU32::new(0)),
-            last_failure_time: Arc::new(tokio::sync::RwLock::new(None)),
+            counts: Mutex::new(Counts {
+                state: CircuitBreakerState::Closed,
+                failures: 0,
+                successes: 0,
+                last_failure: None,
+                probe: None,
+            }),
             config,
         }
     }
 
+    fn counts(&self) -> std::sync::MutexGuard<'_, Counts> {
+        self.counts.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn state(&self) -> CircuitBreakerState {
+        self.counts().state
+    }
+
     pub async fn get_state(&self) -> CircuitBreakerState {
-        *self.state.read().await
+        self.state()
     }
 
-    pub async fn record_success(&self) {
-        let state = *self.state.read().await;
-        match state {
-            CircuitBreakerState::Closed => {
-                self.failure_count.store(0, Ordering::SeqCst);
+    /// May a call go through now? An open breaker lets one call through,
+    /// half-open, once it has been open for `timeout`, and no other until
+    /// that one succeeds or fails; until then, how long is left. A probe
+    /// that has not settled within `timeout` is taken as lost, and another
+    /// goes in its place.
+    pub fn admit(&self) -> Result<(), Duration> {
+        let mut counts = self.counts();
+        match counts.state {
+            CircuitBreakerState::Closed => Ok(()),
+            CircuitBreakerState::Open => {
+                let elapsed = counts.last_failure.map(|at| at.elapsed()).unwrap_or_default();
+                if elapsed <= self.config.timeout {
+                    return Err(self.config.timeout - elapsed);
+                }
+                counts.state = CircuitBreakerState::HalfOpen;
+                counts.successes = 0;
+                counts.probe = Some(Instant::now());
+                Ok(())
             }
             CircuitBreakerState::HalfOpen => {
-                let succ = self.success_count.fetch_add(1, Ordering::SeqCst);
-                if succ + 1 >= self.config.success_threshold {
-                    let mut s = self.state.write().await;
-                    *s = CircuitBreakerState::Closed;
-                    self.failure_count.store(0, Ordering::SeqCst);
-                    self.success_count.store(0, Ordering::SeqCst);
+                let out = counts.probe.map(|at| at.elapsed());
+                match out {
+                    Some(out) if out <= self.config.timeout => Err(self.config.timeout - out),
+                    _ => {
+                        counts.probe = Some(Instant::now());
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn succeeded(&self) {
+        let mut counts = self.counts();
+        counts.probe = None;
+        match counts.state {
+            CircuitBreakerState::Closed => counts.failures = 0,
+            CircuitBreakerState::HalfOpen => {
+                counts.successes += 1;
+                if counts.successes >= self.config.success_threshold {
+                    counts.state = CircuitBreakerState::Closed;
+                    counts.failures = 0;
+                    counts.successes = 0;
                 }
             }
             CircuitBreakerState::Open => {}
         }
     }
 
-    pub async fn record_failure(&self) {
-        let mut state = self.state.write().await;
-        let fail_count = self.failure_count.fetch_add(1, Ordering::SeqCst);
+    pub fn failed(&self) {
+        let mut counts = self.counts();
+        counts.probe = None;
+        counts.failures += 1;
+        counts.last_failure = Some(Instant::now());
+        // Half-open is one chance: a failure there opens it again whatever
+        // the count.
+        if counts.failures >= self.config.failure_threshold
+            || counts.state == CircuitBreakerState::HalfOpen
+        {
+            counts.state = CircuitBreakerState::Open;
+        }
+    }
 
-        *self.last_failure_time.write().await = Some(SystemTime::now());
+    pub async fn record_success(&self) {
+        self.succeeded();
+    }
 
-        if fail_count + 1 >= self.config.failure_threshold {
-            *state = CircuitBreakerState::Open;
-        }
+    pub async fn record_failure(&self) {
+        self.failed();
     }
 
     pub async fn try_request<F, T>(&self, f: F) -> Result<T, String>
     where
         F: std::future::Future<Output = Result<T, String>>,
     {
-        let state = *self.state.read().await;
-
-        match state {
-            CircuitBreakerState::Closed => {
-                match f.await {
-                    Ok(result) => {
-                        self.record_success().await;
-                        Ok(result)
-                    }
-                    Err(e) => {
-                        self.record_failure().await;
-                        Err(e)
-                    }
-                }
-            }
-            CircuitBreakerState::Open => {
-                let last_failure = *self.last_failure_time.read().await;
-                if let Some(last_time) = last_failure {
-                    let elapsed = last_time.elapsed().unwrap_or(Duration::from_secs(0));
-                    if elapsed > self.config.timeout {
-                        let mut s = self.state.write().await;
-                        *s = CircuitBreakerState::HalfOpen;
-                        self.success_count.store(0, Ordering::SeqCst);
-                        // Proceed with request in HalfOpen state
-                        return match f.await {
-                            Ok(result) => {
-                                self.record_success().await;
-                                Ok(result)
-                            }
-                            Err(e) => {
-                                self.record_failure().await;
-                                Err(e)
-                            }
-                        };
-                    }
-                }
-                Err("Circuit breaker is open".to_string())
+        if self.admit().is_err() {
+            return Err("Circuit breaker is open".to_string());
+        }
+        match f.await {
+            Ok(result) => {
+                self.succeeded();
+                Ok(result)
             }
-            CircuitBreakerState::HalfOpen => {
-                match f.await {
-                    Ok(result) => {
-                        self.record_success().await;
-                        Ok(result)
-                    }
-                    Err(e) => {
-                        self.record_failure().await;
-                        Err(e)
-                    }
-                }
+            Err(e) => {
+                self.failed();
+                Err(e)
             }
         }
     }
 }
 
+/// The breaker called `name`, shared by every caller in the process that
+/// names it. The first to ask makes it with `config`; later callers get that
+/// one, whatever they pass, so one gateway has one breaker.
+pub fn breaker(name: &str, config: CircuitBreakerConfig) -> Arc<CircuitBreaker> {
+    static BREAKERS: OnceLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> = OnceLock::new();
+    let mut breakers = BREAKERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    Arc::clone(
+        breakers
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(config))),
+    )
+}
+
 /// Retry configuration
 #[derive(Clone)]
 pub struct RetryConfig {
@@ -173,12 +215,19 @@ impl Retry {
         Self { config }
     }
 
+    /// How long to wait before retry number `retry`, counting from 1:
+    /// `initial_delay`, growing by `backoff_factor`, never past `max_delay`.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let grown = self.config.initial_delay.as_secs_f64()
+            * self.config.backoff_factor.powi(retry.saturating_sub(1) as i32);
+        Duration::from_secs_f64(grown.min(self.config.max_delay.as_secs_f64()))
+    }
+
     pub async fn execute<F, Fut, T>(&self, mut f: F) -> Result<T, String>
     where
         F: FnMut() -> Fut,
         Fut: std::future::Future<Output = Result<T, String>>,
     {
-        let mut delay = self.config.initial_delay;
         let mut attempt = 0;
 
         loop {
@@ -190,20 +239,113 @@ impl Retry {
                         return Err(format!("Max retries exceeded: {}", e));
                     }
 
-                    sleep(delay).await;
-
                     // Exponential backoff
-                    let next_delay_secs =
-                        delay.as_secs_f64() * self.config.backoff_factor;
-                    delay = Duration::from_secs_f64(next_delay_secs.min(
-                        self.config.max_delay.as_secs_f64(),
-                    ));
+                    sleep(self.delay(attempt)).await;
                 }
             }
         }
     }
 }
 
+/// How an outbound call from eTamil — `வலை_காப்பு_அனுப்பு` — is retried, and
+/// which breaker it goes through.
+#[derive(Clone)]
+pub struct Policy {
+    pub retry: RetryConfig,
+    /// Statuses that are tried again, as a failure to connect is. They count
+    /// against the breaker too; any other status is an answer.
+    pub retry_on: Vec<u16>,
+    /// The breaker, by name, and what opens it.
+    pub breaker: Option<(String, CircuitBreakerConfig)>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            retry: RetryConfig::default(),
+            retry_on: vec![429, 502, 503, 504],
+            breaker: None,
+        }
+    }
+}
+
+/// Make an outbound request under `policy`, blocking, for the VM.
+///
+/// Each try goes through the breaker, so a gateway that is down opens it
+/// within a few calls and every call after that fails at once — a தவறு the
+/// program can answer with "try again later" — instead of each one waiting
+/// out its retries. Whatever the last try answered is the answer, whatever
+/// its status; only a request that never got one, or one the breaker did not
+/// let through, is a failure.
+pub fn send(
+    policy: &Policy,
+    method: &str,
+    url: &str,
+    body: Option<&str>,
+    headers: &[(String, String)],
+) -> Result<crate::net::Response, String> {
+    let breaker = policy
+        .breaker
+        .as_ref()
+        .map(|(name, config)| (name, breaker(name, config.clone())));
+    let retry = Retry::new(policy.retry.clone());
+    let mut tries = 0;
+
+    loop {
+        if let Some((name, breaker)) = &breaker
+            && let Err(left) = breaker.admit()
+        {
+            return Err(format!(
+                "'{}' சுற்று திறந்துள்ளது, அழைக்கப்படவில்லை  \
+                 (the '{}' circuit is open, so the call was not made; it lets one through in {}s)",
+                name,
+                name,
+                left.as_secs().max(1)
+            ));
+        }
+        tries += 1;
+        let answered = crate::net::request(method, url, body, headers);
+        let failed = match &answered {
+            Ok(response) => policy.retry_on.contains(&response.status),
+            Err(_) => true,
+        };
+        if let Some((_, breaker)) = &breaker {
+            if failed {
+                breaker.failed();
+            } else {
+                breaker.succeeded();
+            }
+        }
+        if !failed || tries > policy.retry.max_retries {
+            return answered.map_err(|why| match tries {
+                1 => why,
+                _ => format!("{} முயற்சிகளுக்குப் பின்  (after {} attempts): {}", tries, tries, why),
+            });
+        }
+
+        let mut wait = retry.delay(tries);
+        // A gateway that says when to come back is believed, up to the
+        // longest wait the policy allows.
+        if let Ok(response) = &answered
+            && let Some(after) = retry_after(response)
+        {
+            wait = wait.max(after).min(policy.retry.max_delay);
+        }
+        std::thread::sleep(wait);
+    }
+}
+
+/// A `Retry-After` given in seconds. The date form is rare from an API and
+/// not worth a date parser here.
+fn retry_after(response: &crate::net::Response) -> Option<Duration> {
+    response
+        .headers
+        .iter()
+        .find(|(name, _)| name == "retry-after")
+        .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
 /// Request timeout helper
 pub async fn with_timeout<F, T>(
     duration: Duration,
@@ -251,6 +393,85 @@ mod tests {
         assert_eq!(cb.get_state().await, CircuitBreakerState::Open);
     }
 
+    #[test]
+    fn an_open_breaker_lets_one_call_through_after_its_timeout() {
+        let cb = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            success_threshold: 1,
+            timeout: Duration::from_millis(50),
+        });
+        cb.failed();
+        assert!(cb.admit().is_ok());
+        cb.failed();
+        assert!(cb.admit().is_err());
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(cb.admit().is_ok());
+        assert_eq!(cb.state(), CircuitBreakerState::HalfOpen);
+        cb.succeeded();
+        assert_eq!(cb.state(), CircuitBreakerState::Closed);
+    }
+
+    #[test]
+    fn a_half_open_breaker_lets_one_probe_through_at_a_time() {
+        let cb = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            success_threshold: 2,
+            timeout: Duration::from_millis(200),
+        }));
+        cb.failed();
+        std::thread::sleep(Duration::from_millis(250));
+
+        let ready = Arc::new(std::sync::Barrier::new(8));
+        let callers: Vec<_> = (0..8)
+            .map(|_| {
+                let cb = Arc::clone(&cb);
+                let ready = Arc::clone(&ready);
+                std::thread::spawn(move || {
+                    ready.wait();
+                    cb.admit()
+                })
+            })
+            .collect();
+        let admitted: Vec<_> = callers.into_iter().map(|caller| caller.join().unwrap()).collect();
+        assert_eq!(admitted.iter().filter(|admitted| admitted.is_ok()).count(), 1);
+        assert!(admitted.iter().all(|admitted| match admitted {
+            Ok(()) => true,
+            Err(left) => *left <= Duration::from_millis(200),
+        }));
+
+        // Settled, so the next probe may go.
+        cb.succeeded();
+        assert_eq!(cb.state(), CircuitBreakerState::HalfOpen);
+        assert!(cb.admit().is_ok());
+        assert!(cb.admit().is_err());
+        cb.succeeded();
+        assert_eq!(cb.state(), CircuitBreakerState::Closed);
+        assert!(cb.admit().is_ok() && cb.admit().is_ok());
+    }
+
+    #[test]
+    fn a_breaker_is_shared_by_name() {
+        let first = breaker("resilience-test", CircuitBreakerConfig::default());
+        let again = breaker("resilience-test", CircuitBreakerConfig {
+            failure_threshold: 1,
+            ..Default::default()
+        });
+        assert!(Arc::ptr_eq(&first, &again));
+    }
+
+    #[test]
+    fn backoff_grows_to_its_limit() {
+        let retry = Retry::new(RetryConfig {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            ..Default::default()
+        });
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(200));
+        assert_eq!(retry.delay(3), Duration::from_millis(300));
+    }
+
     #[tokio::test]
     async fn test_retry_success() {
         let retry = Retry::new(RetryConfig::default());
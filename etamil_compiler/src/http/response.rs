@@ -13,6 +13,15 @@ pub struct HttpResponse {
     /// `String` for the obvious reason: a PDF is not valid UTF-8, and going
     /// through a String would replace every byte it did not like.
     pub bytes: Option<Vec<u8>>,
+    /// The handler streamed this response with பதில்_பகுதி, so it has been
+    /// written to the connection already and there is nothing left to send.
+    pub streamed: bool,
+    /// The connection cannot carry another request after this one — a
+    /// streamed response that broke off partway, say.
+    pub close: bool,
+    /// Each `Set-Cookie`, in the order set. Not in `headers`: a response may
+    /// set several cookies, and a map keeps only the last.
+    pub cookies: Vec<String>,
 }
 
 impl HttpResponse {
@@ -28,20 +37,26 @@ impl HttpResponse {
             headers,
             body,
             bytes: None,
+            streamed: false,
+            close: false,
+            cookies: Vec::new(),
         }
     }
 
+    /// What dispatch hands back for a response the handler streamed itself.
+    /// `clean` is false when the stream broke off, so the client never saw its
+    /// end and the connection has to go.
+    pub fn streamed(status_code: u16, clean: bool) -> Self {
+        let mut response = HttpResponse::custom(status_code, String::new());
+        response.streamed = true;
+        response.close = !clean;
+        response
+    }
+
     /// Create a 200 OK response
     pub fn success(status_code: u16, body: String) -> Self {
-        let status_text = match status_code {
-            200 => "OK",
-            201 => "Created",
-            202 => "Accepted",
-            204 => "No Content",
-            _ => "OK",
-        };
-        
-        HttpResponse::new(status_code, status_text, body)
+        // Whatever status a handler chose: a 401 from பதில் is not "401 OK".
+        HttpResponse::new(status_code, Self::status_text_for_code(status_code), body)
     }
 
     /// Create a 400 Bad Request response
@@ -68,8 +83,15 @@ impl HttpResponse {
         HttpResponse::new(status_code, status_text, body)
     }
 
-    /// Set a response header
+    /// Set a response header, replacing one of the same name however it was
+    /// capitalised. A `Set-Cookie` adds a cookie rather than replacing the
+    /// last one.
     pub fn set_header(&mut self, name: &str, value: &str) {
+        if name.eq_ignore_ascii_case("set-cookie") {
+            self.cookies.push(value.to_string());
+            return;
+        }
+        self.headers.retain(|other, _| !other.eq_ignore_ascii_case(name));
         self.headers.insert(name.to_string(), value.to_string());
     }
 
@@ -78,29 +100,31 @@ impl HttpResponse {
     /// Bytes, not a String: the head is text but the body need not be, and a
     /// response carrying a PDF has to arrive as the bytes that were read.
     pub fn to_http_bytes(&self) -> Vec<u8> {
+        let mut out = self.head_bytes();
+        match &self.bytes {
+            Some(raw) => out.extend_from_slice(raw),
+            None => out.extend_from_slice(self.body.as_bytes()),
+        }
+        out
+    }
+
+    /// The status line and headers, up to and including the blank line.
+    pub fn head_bytes(&self) -> Vec<u8> {
         let mut head = format!("HTTP/1.1 {} {}\r\n", self.status_code, self.status_text);
 
         for (name, value) in &self.headers {
             head.push_str(&format!("{}: {}\r\n", name, value));
         }
-
-        // Add CORS headers for MVP
-        head.push_str("Access-Control-Allow-Origin: *\r\n");
-        head.push_str("Access-Control-Allow-Methods: GET, POST, PUT, DELETE, OPTIONS\r\n");
-        head.push_str("Access-Control-Allow-Headers: Content-Type\r\n");
+        for cookie in &self.cookies {
+            head.push_str(&format!("Set-Cookie: {}\r\n", cookie));
+        }
 
         head.push_str("\r\n");
-
-        let mut out = head.into_bytes();
-        match &self.bytes {
-            Some(raw) => out.extend_from_slice(raw),
-            None => out.extend_from_slice(self.body.as_bytes()),
-        }
-        out
+        head.into_bytes()
     }
 
     /// Get status text for a status code
-    fn status_text_for_code(code: u16) -> &'static str {
+    pub(crate) fn status_text_for_code(code: u16) -> &'static str {
         match code {
             100 => "Continue",
             101 => "Switching Protocols",
@@ -108,6 +132,7 @@ impl HttpResponse {
             201 => "Created",
             202 => "Accepted",
             204 => "No Content",
+            206 => "Partial Content",
             300 => "Multiple Choices",
             301 => "Moved Permanently",
             302 => "Found",
@@ -117,11 +142,17 @@ impl HttpResponse {
             403 => "Forbidden",
             404 => "Not Found",
             405 => "Method Not Allowed",
+            408 => "Request Timeout",
             409 => "Conflict",
+            413 => "Content Too Large",
+            416 => "Range Not Satisfiable",
+            429 => "Too Many Requests",
+            431 => "Request Header Fields Too Large",
             500 => "Internal Server Error",
             501 => "Not Implemented",
             502 => "Bad Gateway",
             503 => "Service Unavailable",
+            504 => "Gateway Timeout",
             _ => "Unknown",
         }
     }
@@ -138,6 +169,12 @@ mod tests {
         assert_eq!(resp.body, "Hello, World!");
     }
 
+    #[test]
+    fn a_handlers_status_gets_its_own_reason_phrase() {
+        let resp = HttpResponse::success(401, "no token".to_string());
+        assert_eq!(resp.status_text, "Unauthorized");
+    }
+
     #[test]
     fn test_create_error_response() {
         let resp = HttpResponse::not_found();
@@ -179,4 +216,15 @@ mod tests {
         
         assert_eq!(resp.headers.get("X-Custom"), Some(&"value".to_string()));
     }
+
+    #[test]
+    fn every_cookie_set_is_sent() {
+        let mut resp = HttpResponse::success(200, "OK".to_string());
+        resp.set_header("Set-Cookie", "mozi=ta; Path=/");
+        resp.set_header("set-cookie", "theme=dark; Path=/");
+
+        let head = String::from_utf8(resp.head_bytes()).unwrap();
+        assert!(head.contains("Set-Cookie: mozi=ta; Path=/\r\n"), "{}", head);
+        assert!(head.contains("Set-Cookie: theme=dark; Path=/\r\n"), "{}", head);
+    }
 }
@@ -0,0 +1,257 @@
+//! Cookies, and the sessions kept on the server under one.
+//!
+//! A handler is given the request's cookies as `cookies`, a record from name
+//! to value, and sets its own with குக்கீ_அமை. A session is a record of text
+//! the server keeps between one request and the next, read and written with
+//! அமர்வு_பெறு and அமர்வு_அமை: a back-office screen remembers who signed in
+//! without putting it all in a token the browser carries and anyone can read.
+//!
+//! The browser holds only the session's id, in the `etamil_session` cookie,
+//! signed with HMAC-SHA256 (`net::sign`) under `ETAMIL_SESSION_SECRET`. An id
+//! that does not carry its signature is no session at all, so guessing or
+//! fixing one buys nothing. The cookie is `HttpOnly` and `SameSite=Lax`, and
+//! `Secure` when the request came in over TLS: a script cannot read it,
+//! another site cannot post with it, and once given over HTTPS it is never
+//! sent in the clear.
+//!
+//! Sessions are kept in the server's memory, in an `http::cache::Cache`, or in
+//! Redis when the server was given one with `--redis` — which is what several
+//! servers behind one load balancer need, since a session held by one is not
+//! held by the others. Either way a session lasts a day from when it was last
+//! written.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::http::cache::Cache;
+use crate::redis::{Connection, Reply};
+
+/// The cookie the session's id travels in.
+pub const COOKIE: &str = "etamil_session";
+
+/// How long a session outlives its last write, in seconds.
+pub const TTL_SECS: u64 = 24 * 60 * 60;
+
+const SECRET_ENV: &str = "ETAMIL_SESSION_SECRET";
+
+/// Sessions in memory that have expired are swept out once there are more
+/// than this many, as `http::ratelimit` does with its buckets: otherwise only
+/// reading one removes it, and most are never read again.
+const PRUNE_AT: usize = 10_000;
+
+enum Store {
+    Memory(Cache),
+    /// `host:port`. A connection per operation, since the VM that asks holds
+    /// none of its own a session could borrow; see `VM::cache`.
+    Redis(String),
+}
+
+static STORE: OnceLock<Store> = OnceLock::new();
+
+/// Keep sessions in the Redis at `address` rather than in memory. Called once,
+/// before the server starts; a later call changes nothing.
+pub fn keep_in_redis(address: &str) {
+    let _ = STORE.set(Store::Redis(address.to_string()));
+}
+
+fn store() -> &'static Store {
+    STORE.get_or_init(|| Store::Memory(Cache::new()))
+}
+
+/// The signing secret, read once. Unset, a random one good for this process
+/// only, as `http::auth` does for tokens: every session ends at a restart,
+/// which is noisy but safe.
+fn secret() -> &'static str {
+    static SECRET: OnceLock<String> = OnceLock::new();
+
+    SECRET.get_or_init(|| match std::env::var(SECRET_ENV) {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ => {
+            eprintln!(
+                "⚠️  {} is not set — using a random per-process secret. \
+                 Sessions will not survive a restart. Set {} in production.",
+                SECRET_ENV, SECRET_ENV
+            );
+            format!("{}{}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4())
+        }
+    })
+}
+
+/// A `Cookie:` header's pairs. Where a name is sent twice the first is kept:
+/// browsers send the cookie with the longest path first, and that is the one
+/// meant for this request.
+pub fn parse_cookies(header: &str) -> HashMap<String, String> {
+    let mut cookies = HashMap::new();
+    for pair in header.split(';') {
+        let Some((name, value)) = pair.split_once('=') else {
+            continue;
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|inner| inner.strip_suffix('"'))
+            .unwrap_or(value);
+        cookies
+            .entry(name.to_string())
+            .or_insert_with(|| value.to_string());
+    }
+    cookies
+}
+
+/// The session these cookies name, if they name one with a good signature.
+pub fn session_of(cookies: &HashMap<String, String>) -> Option<String> {
+    let (id, signature) = cookies.get(COOKIE)?.rsplit_once('.')?;
+    (!id.is_empty() && crate::net::verify(secret(), id, signature)).then(|| id.to_string())
+}
+
+/// A new session's id. Nothing is stored until something is written to it.
+pub fn start() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// The `Set-Cookie` that gives the browser a session, or renews its expiry.
+/// `Secure` only for a request that came in over TLS: a browser keeps a
+/// `Secure` cookie from a plain `http://` answer to itself, so a server
+/// without TLS would start a new session on every request.
+pub fn cookie_for(id: &str, secure: bool) -> String {
+    format!(
+        "{}={}.{}; Path=/; Max-Age={}; {}",
+        COOKIE,
+        id,
+        crate::net::sign(secret(), id),
+        TTL_SECS,
+        attributes(secure)
+    )
+}
+
+/// The `Set-Cookie` that takes it away again.
+pub fn ended_cookie(secure: bool) -> String {
+    format!("{}=; Path=/; Max-Age=0; {}", COOKIE, attributes(secure))
+}
+
+fn attributes(secure: bool) -> &'static str {
+    if secure {
+        "HttpOnly; Secure; SameSite=Lax"
+    } else {
+        "HttpOnly; SameSite=Lax"
+    }
+}
+
+fn key_of(id: &str) -> String {
+    format!("session:{}", id)
+}
+
+/// One value from a session, or `None` when it holds nothing by that name —
+/// or has expired, or never existed.
+pub fn get(id: &str, name: &str) -> Result<Option<String>, String> {
+    match store() {
+        Store::Memory(cache) => Ok(cache
+            .get_blocking(&key_of(id))
+            .and_then(|session| session.get(name).and_then(|v| v.as_str()).map(String::from))),
+        Store::Redis(address) => {
+            match redis(address, "HGET", &[key_of(id), name.to_string()])? {
+                Reply::Bulk(value) => Ok(Some(value)),
+                _ => Ok(None),
+            }
+        }
+    }
+}
+
+/// Write one value to a session, creating it if need be, and start its day
+/// again.
+pub fn set(id: &str, name: &str, value: &str) -> Result<(), String> {
+    match store() {
+        Store::Memory(cache) => {
+            // Two requests of one session may write at once; each reads the
+            // session and writes it back whole, so one must wait for the other.
+            static WRITING: Mutex<()> = Mutex::new(());
+            let _writing = WRITING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+            let key = key_of(id);
+            let mut session = match cache.get_blocking(&key) {
+                Some(serde_json::Value::Object(fields)) => fields,
+                _ => serde_json::Map::new(),
+            };
+            session.insert(name.to_string(), serde_json::Value::String(value.to_string()));
+            cache.sweep_blocking(PRUNE_AT);
+            cache.set_blocking(key, serde_json::Value::Object(session), TTL_SECS);
+            Ok(())
+        }
+        Store::Redis(address) => {
+            redis(address, "HSET", &[key_of(id), name.to_string(), value.to_string()])?;
+            redis(address, "EXPIRE", &[key_of(id), TTL_SECS.to_string()])?;
+            Ok(())
+        }
+    }
+}
+
+/// Forget a session and everything in it.
+pub fn end(id: &str) -> Result<(), String> {
+    match store() {
+        Store::Memory(cache) => {
+            cache.delete_blocking(&key_of(id));
+            Ok(())
+        }
+        Store::Redis(address) => redis(address, "DEL", &[key_of(id)]).map(|_| ()),
+    }
+}
+
+fn redis(address: &str, command: &str, arguments: &[String]) -> Result<Reply, String> {
+    match Connection::open(address)?.command(command, arguments)? {
+        Reply::Error(said) => Err(format!("Redis at {}: {}", address, said)),
+        reply => Ok(reply),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_cookie_header_becomes_its_pairs() {
+        let cookies = parse_cookies("mozi=ta; theme=\"dark\"; junk; mozi=en; =x");
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies["mozi"], "ta");
+        assert_eq!(cookies["theme"], "dark");
+    }
+
+    #[test]
+    fn only_a_signed_id_is_a_session() {
+        let id = start();
+        let cookie = cookie_for(&id, true);
+        let value = cookie
+            .strip_prefix("etamil_session=")
+            .and_then(|rest| rest.split(';').next())
+            .unwrap();
+        let sent = |value: &str| parse_cookies(&format!("etamil_session={}", value));
+
+        assert_eq!(session_of(&sent(value)), Some(id.clone()));
+        assert_eq!(session_of(&sent(&id)), None);
+        assert_eq!(session_of(&sent(&format!("{}.{}", start(), value.rsplit('.').next().unwrap()))), None);
+    }
+
+    #[test]
+    fn the_cookie_is_secure_only_over_tls() {
+        assert!(cookie_for(&start(), true).ends_with("; HttpOnly; Secure; SameSite=Lax"));
+        assert!(cookie_for(&start(), false).ends_with("; Max-Age=86400; HttpOnly; SameSite=Lax"));
+        assert_eq!(ended_cookie(true), "etamil_session=; Path=/; Max-Age=0; HttpOnly; Secure; SameSite=Lax");
+        assert_eq!(ended_cookie(false), "etamil_session=; Path=/; Max-Age=0; HttpOnly; SameSite=Lax");
+    }
+
+    #[test]
+    fn a_session_keeps_what_was_written_until_it_ends() {
+        let id = start();
+        assert_eq!(get(&id, "payar").unwrap(), None);
+        set(&id, "payar", "kumar").unwrap();
+        set(&id, "pAttiram", "kaNakkar").unwrap();
+        assert_eq!(get(&id, "payar").unwrap().as_deref(), Some("kumar"));
+        assert_eq!(get(&id, "pAttiram").unwrap().as_deref(), Some("kaNakkar"));
+
+        end(&id).unwrap();
+        assert_eq!(get(&id, "payar").unwrap(), None);
+    }
+}
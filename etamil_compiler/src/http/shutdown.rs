@@ -0,0 +1,193 @@
+//! Stopping a server cleanly: SIGTERM, Ctrl-C, or `வழங்கி_நிறுத்து`.
+//!
+//! A deployment replaces a server by sending it SIGTERM and, some seconds
+//! later, SIGKILL. In between, the server should stop taking connections and
+//! finish the requests it has — a payment half-posted is worse than one never
+//! started — and then give back what it holds. So a stop is asked for here,
+//! once, by whichever comes first, and each server does the rest: its accept
+//! loop ends, every connection is closed after the request it is on, timed
+//! jobs stop between runs, an events stream's next part fails, and whatever
+//! is still running at `--drain-timeout` is cut off. The database connections
+//! the pool holds idle are then closed rather than dropped.
+//!
+//! A second signal does not wait for any of that.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+use std::time::{Duration, Instant};
+
+/// How often a connection waiting for its next request looks up to see
+/// whether the server is stopping. Its own clocks are kept by
+/// `connection::Incoming`, so a read cut short only comes round again.
+pub const CHECK_EVERY: Duration = Duration::from_millis(250);
+
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+/// Woken when a stop is asked for, for anyone waiting in `pause`.
+static WAKE: (Mutex<()>, Condvar) = (Mutex::new(()), Condvar::new());
+
+type Hook = Box<dyn Fn() + Send>;
+
+/// What each server asked to have done when a stop comes: waking its
+/// accept loop, which is otherwise blocked until the next connection.
+static ON_STOP: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+
+/// Ask every server in this process to stop. Asking again changes nothing.
+pub fn stop() {
+    if STOPPING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    {
+        let (lock, wake) = &WAKE;
+        let _held = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        wake.notify_all();
+    }
+    let hooks = ON_STOP.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for hook in hooks.iter() {
+        hook();
+    }
+}
+
+/// Has a stop been asked for?
+pub fn stopping() -> bool {
+    STOPPING.load(Ordering::SeqCst)
+}
+
+/// Run `hook` when a stop is asked for, or now if one already has been.
+pub fn on_stop(hook: impl Fn() + Send + 'static) {
+    let mut hooks = ON_STOP.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if stopping() {
+        hook();
+        return;
+    }
+    hooks.push(Box::new(hook));
+}
+
+/// Wait for `wait`, or less if a stop is asked for meanwhile. False once the
+/// server is stopping: a timed job's loop is `while pause(interval) { .. }`.
+pub fn pause(wait: Duration) -> bool {
+    let (lock, wake) = &WAKE;
+    let held = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let _ = wake.wait_timeout_while(held, wait, |_| !stopping());
+    !stopping()
+}
+
+/// Stop on SIGTERM or Ctrl-C, and on a second one exit at once. Set up once,
+/// by the binary: a library that took the process's signals for itself would
+/// be a poor guest in someone else's.
+pub fn listen() {
+    static LISTENING: Once = Once::new();
+
+    LISTENING.call_once(|| {
+        std::thread::spawn(|| {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    eprintln!("⚠️  Cannot listen for signals: {}", e);
+                    return;
+                }
+            };
+            runtime.block_on(async {
+                signalled().await;
+                println!("\n⏹️  Stopping: finishing the requests in hand (again to stop at once)");
+                stop();
+                signalled().await;
+                println!("\n⏹️  Stopped.");
+                std::process::exit(130);
+            });
+        });
+    });
+}
+
+async fn signalled() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Counts the requests a server is in the middle of, for `drain`.
+#[derive(Default)]
+pub struct InFlight(AtomicUsize);
+
+/// One request, counted until it is dropped.
+pub struct Serving<'a>(&'a InFlight);
+
+impl InFlight {
+    pub fn serving(&self) -> Serving<'_> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Serving(self)
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Serving<'_> {
+    fn drop(&mut self) {
+        self.0.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Wait for the requests in hand to finish, for no longer than `timeout` —
+/// or until they have, when it is too long to be a point in time. How many
+/// were still running when it ran out.
+pub fn drain(in_flight: &InFlight, timeout: Duration) -> usize {
+    let deadline = Instant::now().checked_add(timeout);
+    while in_flight.count() > 0 && deadline.is_none_or(|deadline| Instant::now() < deadline) {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    in_flight.count()
+}
+
+/// The last of a stop, once the requests have drained: close the pooled
+/// database connections, and say how it went.
+pub fn finish(unfinished: usize, timeout: Duration) {
+    let closed = crate::db::pool::close_idle();
+    if unfinished > 0 {
+        eprintln!(
+            "⚠️  {} request(s) still running after {}s; stopping without them",
+            unfinished,
+            timeout.as_secs()
+        );
+    }
+    println!("⏹️  Stopped ({} database connection(s) closed).", closed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_request_is_counted_until_it_is_done() {
+        let in_flight = InFlight::default();
+        let serving = in_flight.serving();
+        assert_eq!(in_flight.count(), 1);
+        assert_eq!(drain(&in_flight, Duration::from_millis(50)), 1);
+
+        drop(serving);
+        assert_eq!(drain(&in_flight, Duration::from_secs(5)), 0);
+    }
+
+    #[test]
+    fn a_drain_timeout_too_long_for_a_deadline_waits_until_drained() {
+        let in_flight = InFlight::default();
+        let serving = in_flight.serving();
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                drop(serving);
+            });
+            assert_eq!(drain(&in_flight, Duration::from_secs(u64::MAX)), 0);
+        });
+    }
+}
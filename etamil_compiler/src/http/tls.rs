@@ -0,0 +1,359 @@
+//! HTTPS for `--server` and `--async`, and client certificates for partners
+//! who call us with mutual TLS.
+//!
+//! mtls.rs is the other direction: the certificate *we* present when a
+//! handler calls out to a bank. This is the certificate we present to callers,
+//! and, when a client CA is configured, the check that each caller presents
+//! one of its own. The variables are named apart so that one deployment can
+//! do both:
+//!
+//!   --tls-cert, ETAMIL_TLS_SERVER_CERT       this server's certificate chain, PEM
+//!   --tls-key, ETAMIL_TLS_SERVER_KEY         its private key, PEM
+//!   --tls-client-ca, ETAMIL_TLS_CLIENT_CA    require client certificates issued
+//!                                            by this CA, PEM — optional
+//!
+//! A flag wins over its variable. With a client CA, a connection without a
+//! certificate that chains to it fails the handshake and never reaches a
+//! handler; one that does has its subject bound as `client_subject`, so a
+//! route can tell which partner is calling.
+
+use std::sync::Arc;
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+
+/// Where this server's certificate, key and client CA are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsSettings {
+    pub cert: String,
+    pub key: String,
+    pub client_ca: Option<String>,
+}
+
+impl TlsSettings {
+    /// The settings from flags, with the environment filling in any a flag
+    /// did not give. `None` when HTTPS was not asked for at all.
+    ///
+    /// Half an identity is an error rather than a quiet fall back to plain
+    /// HTTP: an operator who named a certificate meant to serve HTTPS, and a
+    /// partner sending account numbers over the result would not notice.
+    pub fn resolve(
+        cert: Option<String>,
+        key: Option<String>,
+        client_ca: Option<String>,
+    ) -> Result<Option<Self>, String> {
+        let cert = cert.or_else(|| std::env::var("ETAMIL_TLS_SERVER_CERT").ok());
+        let key = key.or_else(|| std::env::var("ETAMIL_TLS_SERVER_KEY").ok());
+        let client_ca = client_ca.or_else(|| std::env::var("ETAMIL_TLS_CLIENT_CA").ok());
+
+        match (cert, key) {
+            (Some(cert), Some(key)) => Ok(Some(TlsSettings { cert, key, client_ca })),
+            (None, None) if client_ca.is_none() => Ok(None),
+            (None, None) => Err(
+                "a client CA was given without a server certificate and key: client \
+                 certificates are checked during the HTTPS handshake, so HTTPS has to be on"
+                    .to_string(),
+            ),
+            (Some(_), None) => Err(
+                "a server certificate was given without its key (--tls-key or ETAMIL_TLS_SERVER_KEY)"
+                    .to_string(),
+            ),
+            (None, Some(_)) => Err(
+                "a server key was given without its certificate (--tls-cert or ETAMIL_TLS_SERVER_CERT)"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+/// Read the files and build what both servers hand each connection.
+pub fn server_config(settings: &TlsSettings) -> Result<Arc<ServerConfig>, String> {
+    let chain = read_certificates(&settings.cert)?;
+    if chain.is_empty() {
+        return Err(format!("'{}' holds no certificate", settings.cert));
+    }
+    let key = read_private_key(&settings.key)?;
+
+    // Named rather than taken from the process default, as in mtls.rs: with no
+    // default installed, building a config panics.
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS versions could not be selected: {}", e))?;
+
+    let builder = match &settings.client_ca {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            let issuers = read_certificates(ca_path)?;
+            if issuers.is_empty() {
+                return Err(format!("client CA '{}' holds no certificate", ca_path));
+            }
+            for certificate in issuers {
+                roots
+                    .add(certificate)
+                    .map_err(|e| format!("client CA '{}' was not usable: {}", ca_path, e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| format!("client CA '{}' was not usable: {}", ca_path, e))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder
+        .with_single_cert(chain, key)
+        .map_err(|e| format!("the certificate and key were not accepted together: {}", e))?;
+    // Both servers speak HTTP/1.1 and nothing else; saying so stops a client
+    // that prefers h2 from trying it.
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// The verified client's subject, as `CN=...,O=...`, once the handshake is
+/// done. `None` without a client certificate — including whenever no client
+/// CA is configured, since then none is asked for.
+pub fn client_subject(connection: &rustls::ServerConnection) -> Option<String> {
+    connection
+        .peer_certificates()?
+        .first()
+        .and_then(|certificate| subject_of(certificate))
+}
+
+pub(crate) fn read_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let pem = std::fs::read(path).map_err(|e| format!("'{}' could not be read: {}", path, e))?;
+    rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("'{}' is not a PEM certificate: {}", path, e))
+}
+
+/// A private key in whichever of the three PEM shapes it was written in.
+///
+/// Which one you get depends on what produced it, and an operator handed a key
+/// by their bank should not have to know or convert it.
+pub(crate) fn read_private_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    let pem = std::fs::read(path).map_err(|e| format!("'{}' could not be read: {}", path, e))?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|e| format!("'{}' is not a PEM private key: {}", path, e))?
+        .ok_or_else(|| {
+            format!(
+                "'{}' holds no private key  (expected PKCS#8, SEC1 or PKCS#1 PEM)",
+                path
+            )
+        })
+}
+
+/// The subject of a DER certificate, in the RFC 4514 form: most specific
+/// first, `CN=partner.example,O=Example Bank,C=IN`.
+///
+/// Read by hand rather than through an X.509 crate. rustls has already
+/// verified the certificate; all that is left is to walk to one field, and a
+/// parser for the whole format would be a great deal of code to carry for it.
+pub fn subject_of(der: &[u8]) -> Option<String> {
+    let (_, certificate, _) = element(der, SEQUENCE)?;
+    let (_, mut tbs, _) = element(certificate, SEQUENCE)?;
+
+    // [0] version is optional; serial number, signature algorithm, issuer and
+    // validity come before the subject.
+    if tbs.first() == Some(&VERSION) {
+        tbs = element(tbs, VERSION)?.2;
+    }
+    for tag in [INTEGER, SEQUENCE, SEQUENCE, SEQUENCE] {
+        tbs = element(tbs, tag)?.2;
+    }
+    let (_, mut name, _) = element(tbs, SEQUENCE)?;
+
+    let mut relative_names = Vec::new();
+    while !name.is_empty() {
+        let (_, mut set, rest) = element(name, SET)?;
+        name = rest;
+
+        let mut attributes = Vec::new();
+        while !set.is_empty() {
+            let (_, pair, rest) = element(set, SEQUENCE)?;
+            set = rest;
+            let (_, oid, value) = element(pair, OID)?;
+            let (tag, value, _) = any_element(value)?;
+            attributes.push(format!("{}={}", attribute_name(oid), escape(&text(tag, value))));
+        }
+        relative_names.push(attributes.join("+"));
+    }
+
+    relative_names.reverse();
+    Some(relative_names.join(","))
+}
+
+const INTEGER: u8 = 0x02;
+const OID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const VERSION: u8 = 0xA0;
+
+/// One DER element with the tag expected: (tag, contents, what follows).
+fn element(input: &[u8], tag: u8) -> Option<(u8, &[u8], &[u8])> {
+    any_element(input).filter(|(found, _, _)| *found == tag)
+}
+
+fn any_element(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let length = rest[..count]
+            .iter()
+            .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        (length, &rest[count..])
+    };
+    if rest.len() < length {
+        return None;
+    }
+    Some((tag, &rest[..length], &rest[length..]))
+}
+
+/// The short name RFC 4514 gives an attribute, or its dotted OID.
+fn attribute_name(oid: &[u8]) -> String {
+    let dotted = dotted(oid);
+    let short = match dotted.as_str() {
+        "2.5.4.3" => "CN",
+        "2.5.4.5" => "serialNumber",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "ST",
+        "2.5.4.9" => "STREET",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "0.9.2342.19200300.100.1.1" => "UID",
+        "0.9.2342.19200300.100.1.25" => "DC",
+        "1.2.840.113549.1.9.1" => "emailAddress",
+        _ => return dotted,
+    };
+    short.to_string()
+}
+
+fn dotted(oid: &[u8]) -> String {
+    let mut arcs: Vec<u64> = Vec::new();
+    let mut arc = 0u64;
+    for byte in oid {
+        arc = (arc << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        }
+    }
+    arcs.iter().map(u64::to_string).collect::<Vec<_>>().join(".")
+}
+
+/// An attribute's value as text. Anything that is not one of the string types
+/// is written as `#` and its DER in hex, as RFC 4514 says.
+fn text(tag: u8, value: &[u8]) -> String {
+    match tag {
+        // UTF8String, PrintableString, IA5String
+        0x0C | 0x13 | 0x16 => String::from_utf8_lossy(value).into_owned(),
+        // TeletexString, read as Latin-1, which is what it holds in practice
+        0x14 => value.iter().map(|&byte| byte as char).collect(),
+        // BMPString
+        0x1E => {
+            let units: Vec<u16> = value
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => {
+            let mut hex = format!("#{:02x}{:02x}", tag, value.len());
+            for byte in value {
+                hex.push_str(&format!("{:02x}", byte));
+            }
+            hex
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for (index, c) in value.chars().enumerate() {
+        let special = matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';')
+            || (index == 0 && matches!(c, ' ' | '#'));
+        if special {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair};
+
+    fn certificate(names: &[(DnType, &str)]) -> Vec<u8> {
+        let mut params = CertificateParams::new(vec!["partner.example".to_string()]).unwrap();
+        let mut subject = DistinguishedName::new();
+        for (kind, value) in names {
+            subject.push(kind.clone(), *value);
+        }
+        params.distinguished_name = subject;
+        let key = KeyPair::generate().unwrap();
+        params.self_signed(&key).unwrap().der().to_vec()
+    }
+
+    #[test]
+    fn the_subject_reads_most_specific_first() {
+        let der = certificate(&[
+            (DnType::CountryName, "IN"),
+            (DnType::OrganizationName, "Example Bank"),
+            (DnType::CommonName, "upi-partner"),
+        ]);
+        assert_eq!(
+            subject_of(&der).as_deref(),
+            Some("CN=upi-partner,O=Example Bank,C=IN")
+        );
+    }
+
+    #[test]
+    fn special_characters_in_a_name_are_escaped() {
+        let der = certificate(&[(DnType::OrganizationName, "Kumar, Sons + Co")]);
+        assert_eq!(subject_of(&der).as_deref(), Some("O=Kumar\\, Sons \\+ Co"));
+    }
+
+    #[test]
+    fn rubbish_is_not_a_subject() {
+        assert_eq!(subject_of(b"not a certificate"), None);
+        assert_eq!(subject_of(&[]), None);
+    }
+
+    #[test]
+    fn half_an_identity_is_an_error() {
+        let outcome = TlsSettings::resolve(Some("cert.pem".to_string()), None, None);
+        // The variable could be set in the environment running the tests.
+        if std::env::var("ETAMIL_TLS_SERVER_KEY").is_err() {
+            assert!(outcome.unwrap_err().contains("without its key"));
+        }
+        let outcome = TlsSettings::resolve(
+            Some("cert.pem".to_string()),
+            Some("key.pem".to_string()),
+            Some("ca.pem".to_string()),
+        );
+        assert_eq!(
+            outcome.unwrap(),
+            Some(TlsSettings {
+                cert: "cert.pem".to_string(),
+                key: "key.pem".to_string(),
+                client_ca: Some("ca.pem".to_string()),
+            })
+        );
+    }
+}
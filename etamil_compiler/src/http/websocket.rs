@@ -0,0 +1,313 @@
+//! WebSocket, over the framing RFC 6455 actually specifies.
+//!
+//! Implemented here rather than taken from tungstenite for the reason RESP and
+//! the router were: the protocol a handler needs is small — a handshake, text
+//! messages, ping and close — and both servers already own the socket, one
+//! blocking and one async. A crate would want to own it instead, and would
+//! need a second implementation for each server to give it one.
+//!
+//! This module knows nothing about sockets. `Decoder` is fed whatever bytes
+//! arrived and hands back whole messages; `frame` makes the bytes to send.
+//! `connection::Chunked` is what reads and writes them, for either server.
+
+use sha1::{Digest, Sha1};
+
+/// The GUID every server appends to the client's key, by the RFC.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The largest message a client may send: a payment-status screen sends a
+/// few words, and nothing here wants a megabyte held in memory for it.
+pub const MAX_MESSAGE: usize = 1024 * 1024;
+
+pub const TEXT: u8 = 0x1;
+pub const BINARY: u8 = 0x2;
+pub const CLOSE: u8 = 0x8;
+pub const PING: u8 = 0x9;
+pub const PONG: u8 = 0xA;
+const CONTINUATION: u8 = 0x0;
+
+/// Close codes this side sends.
+pub const NORMAL: u16 = 1000;
+pub const PROTOCOL_ERROR: u16 = 1002;
+pub const NOT_TEXT: u16 = 1007;
+pub const TOO_BIG: u16 = 1009;
+
+/// What a client sent, once its frames are put back together.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong,
+    /// The code the client gave, if it gave one.
+    Close(Option<u16>),
+}
+
+/// Is this request asking to become a WebSocket? Its `Sec-WebSocket-Key`, if
+/// so. Headers are read lower-cased, as `HttpRequest` stores them.
+pub fn key_of(headers: &std::collections::HashMap<String, String>) -> Option<&str> {
+    let says = |name: &str, token: &str| {
+        headers.get(name).is_some_and(|value| {
+            value.split(',').any(|part| part.trim().eq_ignore_ascii_case(token))
+        })
+    };
+    if !says("upgrade", "websocket") || !says("connection", "upgrade") {
+        return None;
+    }
+    if headers.get("sec-websocket-version").map(|v| v.trim()) != Some("13") {
+        return None;
+    }
+    headers
+        .get("sec-websocket-key")
+        .map(|key| key.trim())
+        .filter(|key| !key.is_empty())
+}
+
+/// `Sec-WebSocket-Accept` for a client's key: SHA-1 of the key and the GUID,
+/// in base64. Proves to the client that this server read its handshake.
+pub fn accept_key(key: &str) -> String {
+    let digest = Sha1::digest(format!("{}{}", key, GUID).as_bytes());
+    base64(&digest)
+}
+
+/// The `101 Switching Protocols` head that completes the handshake, with any
+/// headers the server adds to every response.
+pub fn handshake(key: &str, extra: &[(String, String)]) -> Vec<u8> {
+    let mut head = String::from("HTTP/1.1 101 Switching Protocols\r\n");
+    head.push_str("Upgrade: websocket\r\nConnection: Upgrade\r\n");
+    head.push_str(&format!("Sec-WebSocket-Accept: {}\r\n", accept_key(key)));
+    for (name, value) in extra {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    head.into_bytes()
+}
+
+/// One frame, as a server sends it: whole, and unmasked.
+pub fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(payload.len() + 10);
+    bytes.push(0x80 | opcode);
+    match payload.len() {
+        n if n < 126 => bytes.push(n as u8),
+        n if n <= u16::MAX as usize => {
+            bytes.push(126);
+            bytes.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            bytes.push(127);
+            bytes.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// A close frame with its code.
+pub fn close_frame(code: u16) -> Vec<u8> {
+    frame(CLOSE, &code.to_be_bytes())
+}
+
+/// Puts a client's frames back together into messages.
+///
+/// A protocol error comes back as the close code to answer it with.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    /// A message split over several frames: its opcode, and what has arrived.
+    partial: Option<(u8, Vec<u8>)>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The next whole message, if enough has arrived for one.
+    pub fn next_message(&mut self) -> Result<Option<Message>, u16> {
+        loop {
+            let Some((fin, opcode, payload)) = self.next_frame()? else {
+                return Ok(None);
+            };
+            match opcode {
+                CLOSE => {
+                    let code = (payload.len() >= 2).then(|| u16::from_be_bytes([payload[0], payload[1]]));
+                    return Ok(Some(Message::Close(code)));
+                }
+                PING => return Ok(Some(Message::Ping(payload))),
+                PONG => return Ok(Some(Message::Pong)),
+                TEXT | BINARY => {
+                    if self.partial.is_some() {
+                        return Err(PROTOCOL_ERROR);
+                    }
+                    if fin {
+                        return Self::whole(opcode, payload).map(Some);
+                    }
+                    self.partial = Some((opcode, payload));
+                }
+                CONTINUATION => {
+                    let Some((_, so_far)) = self.partial.as_mut() else {
+                        return Err(PROTOCOL_ERROR);
+                    };
+                    if so_far.len() + payload.len() > MAX_MESSAGE {
+                        return Err(TOO_BIG);
+                    }
+                    so_far.extend_from_slice(&payload);
+                    if fin {
+                        let (opcode, payload) = self.partial.take().unwrap_or_default();
+                        return Self::whole(opcode, payload).map(Some);
+                    }
+                }
+                _ => return Err(PROTOCOL_ERROR),
+            }
+        }
+    }
+
+    fn whole(opcode: u8, payload: Vec<u8>) -> Result<Message, u16> {
+        if opcode == BINARY {
+            return Ok(Message::Binary(payload));
+        }
+        String::from_utf8(payload)
+            .map(Message::Text)
+            .map_err(|_| NOT_TEXT)
+    }
+
+    /// One frame off the front of the buffer: whether it is the last of its
+    /// message, its opcode, and its payload unmasked.
+    fn next_frame(&mut self) -> Result<Option<(bool, u8, Vec<u8>)>, u16> {
+        let buffer = &self.buffer;
+        if buffer.len() < 2 {
+            return Ok(None);
+        }
+        let fin = buffer[0] & 0x80 != 0;
+        // No extension was agreed, so none of the reserved bits may be set.
+        if buffer[0] & 0x70 != 0 {
+            return Err(PROTOCOL_ERROR);
+        }
+        let opcode = buffer[0] & 0x0F;
+        // Every frame from a client is masked; one that is not is refused,
+        // as the RFC requires.
+        if buffer[1] & 0x80 == 0 {
+            return Err(PROTOCOL_ERROR);
+        }
+        let (length, mut at) = match buffer[1] & 0x7F {
+            126 if buffer.len() >= 4 => (u16::from_be_bytes([buffer[2], buffer[3]]) as u64, 4),
+            127 if buffer.len() >= 10 => {
+                let mut raw = [0u8; 8];
+                raw.copy_from_slice(&buffer[2..10]);
+                (u64::from_be_bytes(raw), 10)
+            }
+            126 | 127 => return Ok(None),
+            n => (n as u64, 2),
+        };
+        // Control frames are small and never split.
+        if opcode & 0x8 != 0 && (length > 125 || !fin) {
+            return Err(PROTOCOL_ERROR);
+        }
+        if length > MAX_MESSAGE as u64 {
+            return Err(TOO_BIG);
+        }
+        let length = length as usize;
+        if buffer.len() < at + 4 + length {
+            return Ok(None);
+        }
+        let mask = [buffer[at], buffer[at + 1], buffer[at + 2], buffer[at + 3]];
+        at += 4;
+        let payload = buffer[at..at + length]
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte ^ mask[i % 4])
+            .collect();
+        self.buffer.drain(..at + length);
+        Ok(Some((fin, opcode, payload)))
+    }
+}
+
+/// Standard base64, padded: only ever the twenty bytes of a SHA-1 here.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let n = (group[0] as u32) << 16
+            | (*group.get(1).unwrap_or(&0) as u32) << 8
+            | *group.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame as a client sends it: masked.
+    fn client_frame(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xfa, 0x21, 0x3d];
+        let mut bytes = vec![first];
+        match payload.len() {
+            n if n < 126 => bytes.push(0x80 | n as u8),
+            n => {
+                bytes.push(0x80 | 126);
+                bytes.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+        }
+        bytes.extend_from_slice(&mask);
+        bytes.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        bytes
+    }
+
+    // The worked example in RFC 6455, section 1.3.
+    #[test]
+    fn the_accept_key_is_the_one_the_rfc_gives() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn a_message_arriving_a_byte_at_a_time_comes_out_whole() {
+        let mut decoder = Decoder::new();
+        for byte in client_frame(0x81, "நிலை: வெற்றி".as_bytes()) {
+            assert_eq!(decoder.next_message(), Ok(None));
+            decoder.push(&[byte]);
+        }
+        assert_eq!(decoder.next_message(), Ok(Some(Message::Text("நிலை: வெற்றி".to_string()))));
+        assert_eq!(decoder.next_message(), Ok(None));
+    }
+
+    #[test]
+    fn a_message_split_over_frames_is_put_back_together() {
+        let mut decoder = Decoder::new();
+        decoder.push(&client_frame(0x01, b"UPI "));
+        // A ping may arrive between the parts of a message.
+        decoder.push(&client_frame(0x89, b"?"));
+        decoder.push(&client_frame(0x80, b"SUCCESS"));
+        assert_eq!(decoder.next_message(), Ok(Some(Message::Ping(b"?".to_vec()))));
+        assert_eq!(decoder.next_message(), Ok(Some(Message::Text("UPI SUCCESS".to_string()))));
+    }
+
+    #[test]
+    fn an_unmasked_frame_from_a_client_is_a_protocol_error() {
+        let mut decoder = Decoder::new();
+        decoder.push(&frame(TEXT, b"hi"));
+        assert_eq!(decoder.next_message(), Err(PROTOCOL_ERROR));
+    }
+
+    #[test]
+    fn a_long_frame_is_sent_with_its_extended_length() {
+        let bytes = frame(TEXT, &[b'x'; 300]);
+        assert_eq!(&bytes[..4], &[0x81, 126, 0x01, 0x2C]);
+        assert_eq!(bytes.len(), 304);
+
+        let mut decoder = Decoder::new();
+        decoder.push(&client_frame(0x81, &[b'x'; 300]));
+        assert_eq!(decoder.next_message(), Ok(Some(Message::Text("x".repeat(300)))));
+    }
+}
@@ -8,16 +8,25 @@ use std::str::FromStr;
 
 #[derive(Logos, Debug, PartialEq, Clone)]
 #[logos(skip r"[ \t\n\f]+")]
-// A line comment must run to the end of the line, so the greedy repetition
-// is intentional here; logos 0.16.1+ requires saying so explicitly.
-#[logos(skip(r"//[^\n]*", allow_greedy = true))]
 pub enum Token {
+    // A line comment. `tokenize` drops these, so the parser never sees one;
+    // `tokenize_with_comments` keeps them for the formatter, which has to put
+    // every comment back. It must run to the end of the line, so the greedy
+    // repetition is intentional; logos 0.16.1+ requires saying so explicitly.
+    #[regex(r"//[^\n]*", allow_greedy = true)] Comment,
+
+    // Each keyword lists its Tamil spelling, then its romanization in the
+    // scheme of scripts/transliterate.py, then its English alias if it has
+    // one. A spelling after those is an older romanization from before the
+    // scheme was settled (docs/ROADMAP.md item 6): still accepted, so existing
+    // programs keep working, and rewritten by `etamil convert --migrate`.
+
     // --- Core Financial & Accounting ---
     #[regex("வரவு|varavu")] Credit,
     #[regex("பற்று|paRRu")] Debit,
     #[regex("இருப்பு|iruppu")] Balance,
     #[regex("வீதம்|vIqam")] Rate,
-    #[regex("சொத்து|soqqu")] Asset,
+    #[regex("சொத்து|coqqu|soqqu")] Asset,
     #[regex("பொறுப்பு|poRuppu")] Liability,
     #[regex("பங்கு|pawku")] Equity,
     #[regex("வருவாய்|varuvAy")] Revenue,
@@ -34,7 +43,7 @@ pub enum Token {
     #[regex("கடன்|kataZ")] Loan,
     #[regex("நிதி|niqi")] Finance,
     #[regex("அறிக்கை|aRikkY")] Statement,
-    #[regex("மதிப்பீடு|matippIDu")] Valuation,
+    #[regex("மதிப்பீடு|maqippItu|matippIDu")] Valuation,
     #[regex("கடன்_அட்டை|kataZ_attY")] CreditCard,
     #[regex("பணம்|paNam")] Cash,
     #[regex("வங்கி|vawki")] Bank,
@@ -46,18 +55,18 @@ pub enum Token {
     #[regex("நடப்பு|natappu")] Current,
     #[regex("நடப்பிலில்லா|natappilillA")] NonCurrent,
     #[regex("முகவரி|mukavari")] Address,
-    #[regex("தொகை|toqai")] Amount,
+    #[regex("தொகை|qokY|toqai")] Amount,
     #[regex("நாணயம்|nANayam")] Currency,
-    #[regex("பரிவர்த்தனை|parivarttaZai")] Transaction,
+    #[regex("பரிவர்த்தனை|parivarqqaZY|parivarttaZai")] Transaction,
     #[regex("தேய்மானம்|qEymAZam")] Depreciation,
-    #[regex("கடன்தீர்ப்பு|kataZ_qIrppu")] Amortization,
-    #[regex("மதிப்புயர்வு|matippuyarvu")] Appreciation,
-    #[regex("மூலதனம்|mUlataZam")] Capital,
+    #[regex("கடன்தீர்ப்பு|kataZqIrppu|kataZ_qIrppu")] Amortization,
+    #[regex("மதிப்புயர்வு|maqippuyarvu|matippuyarvu")] Appreciation,
+    #[regex("மூலதனம்|mUlaqaZam|mUlataZam")] Capital,
     #[regex("இருப்பாய்வு|iruppAyvu")] TrialBalance,
     #[regex("இருப்புநிலை|iruppunilY")] BalanceSheet,
-    #[regex("வருமான_அறிக்கை|varumAZ_aRikkY")] IncomeStatement,
+    #[regex("வருமான_அறிக்கை|varumAZa_aRikkY|varumAZ_aRikkY")] IncomeStatement,
     #[regex("பணப்புழக்கம்|paNappuzakkam")] CashFlow,
-    #[regex("வருமான_வரி|varumAZ_vari")] IncomeTax,
+    #[regex("வருமான_வரி|varumAZa_vari|varumAZ_vari")] IncomeTax,
     #[regex("வணிகவரி|vaNikavari|_GST")] GST,
     #[regex("வரியறிக்கை|variyaRikkY|_ITR")] ITR,
 
@@ -120,6 +129,7 @@ pub enum Token {
     #[regex("தரவு|qaravu")] DataType,
     #[regex("பொருள்|poruL")] ObjectType,
     #[regex("தேதி|qEqi")] DateType,
+    #[regex("ரொக்கம்|rokkam")] MoneyType,
     #[regex("மெய்|mey")] True,
     #[regex("பொய்|poy")] False,
     #[regex("இன்மை|iZmY")] Null,
@@ -182,11 +192,11 @@ pub enum Token {
     
     // --- Database Operations ---
     #[regex("அட்டை|attY|_table")] Table,
-    #[regex("தொகுப்பு|toguippu|_collection")] Collection,
+    #[regex("தொகுப்பு|qokuppu|_collection|toguippu")] Collection,
     #[regex("நிரை|nirY|_row")] Row,
     #[regex("பத்தி|paqqi|_column")] Column,
     #[regex("விசை|vicY|_key")] Key,
-    #[regex("தனிக_விசை|taZik_vicY|_primaryKey")] PrimaryKey,
+    #[regex("தனிக_விசை|qaZika_vicY|_primaryKey|taZik_vicY")] PrimaryKey,
     #[regex("வெளி_விசை|veLi_vicY|_foreignKey")] ForeignKey,
     #[regex("குறியீடு|kuRiyItu|_index")] Index,
     #[regex("அட்டை_ஆக்கு|attY_Akku|_createTable")] CreateTable,
@@ -210,18 +220,24 @@ pub enum Token {
 
     // --- REST API & HTTP ---
     #[regex("தள_இடை|qaLa_itY|_api")] API,
-    #[regex("இறுதி_புள்ளி|iraqi_pulli|_endpoint")] Endpoint,
-    #[regex("வழி|vazhi|_route")] Route,
+    #[regex("இறுதி_புள்ளி|iRuqi_puLLi|_endpoint|iraqi_pulli")] Endpoint,
+    #[regex("வழி|vazi|_route|vazhi")] Route,
     // Runs a block on a timer under --server / --async. Romanization from
     // scripts/transliterate.py, never by hand.
     #[regex("இடைவெளி|itYveLi|_every")] Every,
+    // Blocks run before and after every route under a path prefix; lifted
+    // out at startup like வழி and இடைவெளி.
+    #[regex("முன்|muZ|_before")] Before,
+    #[regex("பின்|piZ|_after")] After,
+    // Serves a directory's files under a path prefix; lifted out like வழி.
+    #[regex("கோப்புறை|kOppuRY|_mount")] Mount,
     #[regex("கோரிக்கை|kOrikkY|_request")] Request,
-    #[regex("பதில்|paDil|_response")] Response,
-    #[regex("தலைப்பு|talYppu|_header")] Header,
-    #[regex("உடல்|uqal|_body")] Body,
+    #[regex("பதில்|paqil|_response|paDil")] Response,
+    #[regex("தலைப்பு|qalYppu|_header|talYppu")] Header,
+    #[regex("உடல்|utal|_body|uqal")] Body,
     #[regex("அளவுரு|aLavuru|_param")] Param,
     #[regex("வினா_அளவுரு|viZA_aLavuru|_queryParam")] QueryParam,
-    #[regex("பாதை_அளவுரு|pAtY_aLavuru|_pathParam")] PathParam,
+    #[regex("பாதை_அளவுரு|pAqY_aLavuru|_pathParam|pAtY_aLavuru")] PathParam,
     #[regex("ஜேசான்_உரை|jEcAZ_urY|_jsonBody")] JSONBody,
     #[regex("உரலி|urali|_url")] URL,
     #[regex("புரவலன்|puravalaZ|_host")] Host,
@@ -233,14 +249,14 @@ pub enum Token {
     #[regex("அழி|azi|_delete")] HttpDelete,
     #[regex("ஒட்டு|ottu|_patch")] HttpPatch,
     #[regex("தெரிவு|qerivu|_options")] HttpOptions,
-    #[regex("தலை|talY|_head")] HttpHead,
+    #[regex("தலை|qalY|_head|talY")] HttpHead,
     #[regex("நிலை_குறி|nilY_kuRi|_statusCode")] StatusCode,
     #[regex("நிலை_செய்தி|nilY_ceyqi|_statusMessage")] StatusMessage,
     #[regex("உறுதி|uRuqi|_auth")] Auth,
     #[regex("குறிதாங்கி|kuRiqAwki|_bearerToken")] BearerToken,
     #[regex("உரை_வகை|urY_vakY|_contentType")] ContentType,
     #[regex("சேவை|cEvY|_serve")] Serve,
-    #[regex("வழங்கி_தொடங்கு|vazawki_toqotawku|_startServer")] StartServer,
+    #[regex("வழங்கி_தொடங்கு|vazawki_qotawku|_startServer|vazawki_toqotawku")] StartServer,
     #[regex("வழங்கி_நிறுத்து|vazawki_niRuqqu|_stopServer")] StopServer,
     
     // --- Encryption & Security ---
@@ -263,6 +279,9 @@ pub enum Token {
         Decimal::from_str(num_str).ok().map(|n| n / Decimal::from(100))
     })] Percentage(Decimal),
     #[regex(r"[0-9]+(\.[0-9]+)?", |lex| Decimal::from_str(lex.slice()).ok())] Number(Decimal),
+    // An amount with its currency: ₹1500.50, $20. The symbol becomes the ISO
+    // code here, so nothing past the lexer has to know which symbol was used.
+    #[regex(r"[₹$€£¥][0-9]+(\.[0-9]+)?", |lex| money(lex.slice()))] Money((Decimal, String)),
     #[regex(r#""([^"\\]|\\.)*""#, |lex| {
         let raw = lex.slice();
         unescape(&raw[1..raw.len() - 1])
@@ -298,6 +317,14 @@ pub enum Token {
 }
 
 
+/// `₹1500.50` as an amount and a currency code.
+fn money(literal: &str) -> Option<(Decimal, String)> {
+    let digits = literal.find(|c: char| c.is_ascii_digit())?;
+    let currency = crate::money::code_for_symbol(&literal[..digits])?;
+    let amount = Decimal::from_str(&literal[digits..]).ok()?;
+    Some((amount, currency.to_string()))
+}
+
 /// Turn the escape sequences in a string literal into the characters they name.
 ///
 /// The literal text used to be kept exactly as written, so `"a\nb"` was four
@@ -423,6 +450,16 @@ impl std::fmt::Display for LexError {
 /// rather than silently discarded, so a mistyped character is a visible
 /// error instead of a program that quietly means something else.
 pub fn tokenize(source: &str) -> Result<Vec<Spanned>, Vec<LexError>> {
+    tokenize_with_comments(source)
+        .map(|tokens| tokens.into_iter().filter(|t| t.token != Token::Comment).collect())
+}
+
+/// Tokenize, keeping every `//` comment as a `Token::Comment` in place.
+///
+/// For tools that write source back out — the formatter — and so must not lose
+/// what the author wrote between the statements. The parser does not accept
+/// comment tokens; give it `tokenize`.
+pub fn tokenize_with_comments(source: &str) -> Result<Vec<Spanned>, Vec<LexError>> {
     // Windows editors — Notepad, and VS Code in some configurations — save
     // UTF-8 with a byte-order mark. It is invisible, it is the first thing in
     // the file, and treating it as a lexical error made every such program
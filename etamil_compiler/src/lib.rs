@@ -23,6 +23,15 @@
 pub mod lexer;
 pub mod parser;
 pub mod check;
+// Currency symbols and codes; the lexer reads `₹1500.50` with it.
+pub mod money;
+// Diagnostics and symbols for an editor, over the three modules above. Shared
+// by the browser bindings and the language server.
+pub mod analysis;
+// Every keyword's spellings, read out of lexer.rs at compile time.
+pub mod script;
+// `etamil fmt`. Works on text alone, so an editor in a browser could use it.
+pub mod formatter;
 
 // --- Everything below needs an OS ---
 
@@ -32,6 +41,17 @@ pub mod module;
 // Reads stdin, writes stdout.
 #[cfg(not(target_family = "wasm"))]
 pub mod repl;
+// The same, framed as the Language Server Protocol; answers from `analysis`.
+#[cfg(not(target_family = "wasm"))]
+pub mod lsp;
+// `--debug` at the console and `--dap` for an editor, both over vm::debug.
+#[cfg(not(target_family = "wasm"))]
+pub mod debugger;
+#[cfg(not(target_family = "wasm"))]
+pub mod dap;
+// `etamil test`: runs each test in a VM of its own, with its output captured.
+#[cfg(not(target_family = "wasm"))]
+pub mod testing;
 // Portable: HMAC-SHA256 over hmac/sha2/subtle, all pure Rust.
 pub mod signing;
 #[cfg(not(target_family = "wasm"))]
@@ -0,0 +1,728 @@
+//! `etamil --lsp`: a language server over stdin and stdout.
+//!
+//! Every answer comes from `analysis`, which is also what the browser editor
+//! on etamil.in asks through `wasm.rs` — so an editor speaking the Language
+//! Server Protocol sees the same diagnostics the command line prints, and the
+//! same names the browser completes. Nothing here parses eTamil on its own.
+//!
+//! What it serves:
+//!
+//! * diagnostics, on every open and every edit;
+//! * go-to-definition, following `இறக்கு` into the imported file by the same
+//!   search `module.rs` uses to run the program;
+//! * hover, showing a variable's declared type or a `செயல்`'s signature;
+//! * document symbols;
+//! * completion: the names in scope, and every keyword and builtin in each of
+//!   its spellings — Tamil, romanized and English where it has one;
+//! * formatting, by `etamil fmt`'s rules.
+//!
+//! The keyword and builtin tables are read out of `lexer.rs` (by `script.rs`)
+//! and `interpreter.rs` themselves, by the rule
+//! `scripts/generate_editor_support.py` uses for the VS Code extension. A
+//! second hand-kept list is what drifted last time.
+//!
+//! Full-text sync only. eTamil files are small, and re-analysing a whole
+//! buffer costs less than the bookkeeping incremental edits would need.
+//!
+//! The protocol is JSON-RPC with `Content-Length` framing, a few dozen lines
+//! over `serde_json` — the same choice the HTTP server made about routing,
+//! rather than a framework and an async runtime for one process per editor.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde_json::{Value as Json, json};
+
+use crate::analysis::{self, Symbol};
+use crate::formatter;
+use crate::module;
+use crate::script::{is_name_char, keywords};
+
+// --- Framing ---------------------------------------------------------------
+//
+// The Debug Adapter Protocol frames its messages the same way; `dap.rs` uses
+// these two as well.
+
+/// One message, or `None` at the end of input.
+pub(crate) fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "a message arrived without Content-Length")
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub(crate) fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+// --- The server ------------------------------------------------------------
+
+/// Serve until the client says `exit`, or closes the stream.
+///
+/// Answers whether the session ended the way the protocol asks — `shutdown`
+/// and then `exit` — which is what decides the process's exit status.
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+    let mut server = Server::default();
+
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        if method == "exit" {
+            return Ok(server.shut_down);
+        }
+
+        let params = &message["params"];
+        let result = match method {
+            "initialize" => Some(initialize()),
+            "shutdown" => {
+                server.shut_down = true;
+                Some(Json::Null)
+            }
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                let uri = document["uri"].as_str().unwrap_or_default().to_string();
+                let text = document["text"].as_str().unwrap_or_default().to_string();
+                server.documents.insert(uri.clone(), text);
+                write_message(&mut output, &server.publish(&uri))?;
+                None
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+                // Full sync: the last change is the whole document.
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    server.documents.insert(uri.clone(), text.to_string());
+                }
+                write_message(&mut output, &server.publish(&uri))?;
+                None
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                server.documents.remove(uri);
+                // Closing a file takes its errors out of the problems list.
+                write_message(
+                    &mut output,
+                    &json!({
+                        "jsonrpc": "2.0",
+                        "method": "textDocument/publishDiagnostics",
+                        "params": { "uri": uri, "diagnostics": [] },
+                    }),
+                )?;
+                None
+            }
+            "textDocument/hover" => Some(server.at(params, Server::hover)),
+            "textDocument/definition" => Some(server.at(params, Server::definition)),
+            "textDocument/completion" => Some(server.at(params, Server::completion)),
+            "textDocument/documentSymbol" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                Some(server.document_symbols(uri))
+            }
+            "textDocument/formatting" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                Some(server.formatting(uri))
+            }
+            _ => {
+                // A request must be answered even when it is not understood;
+                // a notification must not be.
+                if !message["id"].is_null() {
+                    write_message(
+                        &mut output,
+                        &json!({
+                            "jsonrpc": "2.0",
+                            "id": message["id"],
+                            "error": { "code": -32601, "message": format!("unsupported: {}", method) },
+                        }),
+                    )?;
+                }
+                None
+            }
+        };
+
+        if let Some(result) = result {
+            write_message(
+                &mut output,
+                &json!({ "jsonrpc": "2.0", "id": message["id"], "result": result }),
+            )?;
+        }
+    }
+
+    Ok(false)
+}
+
+fn initialize() -> Json {
+    json!({
+        "capabilities": {
+            // 1 is Full: every change sends the whole document.
+            "textDocumentSync": 1,
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "documentSymbolProvider": true,
+            "completionProvider": {},
+            "documentFormattingProvider": true,
+        },
+        "serverInfo": { "name": "etamil", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+#[derive(Default)]
+struct Server {
+    /// Open buffers by URI. An imported file that is open is read from here,
+    /// so going to a definition sees unsaved edits.
+    documents: HashMap<String, String>,
+    shut_down: bool,
+}
+
+impl Server {
+    fn text(&self, uri: &str) -> Option<String> {
+        if let Some(text) = self.documents.get(uri) {
+            return Some(text.clone());
+        }
+        std::fs::read_to_string(path_of(uri)?).ok()
+    }
+
+    fn publish(&self, uri: &str) -> Json {
+        let text = self.documents.get(uri).map(String::as_str).unwrap_or_default();
+        let diagnostics: Vec<Json> = analysis::diagnostics(text)
+            .iter()
+            .map(|d| {
+                json!({
+                    "range": range(text, d.line, d.column, d.length),
+                    // 1 is Error; see `Diagnostic::severity`.
+                    "severity": 1,
+                    "source": "etamil",
+                    "code": d.stage,
+                    "message": d.message,
+                })
+            })
+            .collect();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        })
+    }
+
+    /// Answer a request about one position: find the document and the word
+    /// under the cursor, and hand both to `answer`.
+    fn at(&self, params: &Json, answer: fn(&Self, &Cursor) -> Json) -> Json {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(text) = self.text(uri) else {
+            return Json::Null;
+        };
+        let line = params["position"]["line"].as_u64().unwrap_or(0) as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or(0) as usize;
+        let (line, column) = from_lsp(&text, line, character);
+        let word = word_at(&text, line, column);
+        answer(self, &Cursor { uri, text: &text, line, column, word })
+    }
+
+    fn hover(&self, cursor: &Cursor) -> Json {
+        let word = cursor.word.as_str();
+        if word.is_empty() {
+            return Json::Null;
+        }
+
+        let shown = if let Some(symbol) = cursor.visible().into_iter().find(|s| s.name == word) {
+            describe(&symbol, None)
+        } else if let Some((file, symbol)) = self.imported(cursor, word) {
+            describe(&symbol, Some(&file))
+        } else if let Some(keyword) = keywords().iter().find(|k| k.forms.iter().any(|f| f == word)) {
+            format!("**{}** — {}\n\n{}", keyword.forms.join(" · "), keyword.section, keyword.token)
+        } else if let Some(builtin) = builtins().iter().find(|b| b.forms.iter().any(|f| f == word)) {
+            format!("**{}**\n\n{}", builtin.forms.join(" · "), builtin.doc)
+        } else {
+            return Json::Null;
+        };
+
+        json!({ "contents": { "kind": "markdown", "value": shown } })
+    }
+
+    fn definition(&self, cursor: &Cursor) -> Json {
+        let word = cursor.word.as_str();
+        if word.is_empty() {
+            return Json::Null;
+        }
+        if let Some(symbol) = cursor.visible().into_iter().find(|s| s.name == word) {
+            return location(cursor.uri, cursor.text, &symbol);
+        }
+        match self.imported(cursor, word) {
+            Some((file, symbol)) => {
+                let uri = uri_of(&file);
+                let text = self.text(&uri).unwrap_or_default();
+                location(&uri, &text, &symbol)
+            }
+            None => Json::Null,
+        }
+    }
+
+    /// A top-level name one of the cursor's file's imports declares, searched
+    /// depth first in import order — the order `module.rs` splices them in.
+    fn imported(&self, cursor: &Cursor, word: &str) -> Option<(PathBuf, Symbol)> {
+        let base = path_of(cursor.uri)
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from("."));
+        let mut visited = HashSet::new();
+        self.search(cursor.text, &base, word, &mut visited)
+    }
+
+    fn search(
+        &self,
+        text: &str,
+        base: &Path,
+        word: &str,
+        visited: &mut HashSet<PathBuf>,
+    ) -> Option<(PathBuf, Symbol)> {
+        for relative in analysis::imports(text) {
+            let Some(found) = module::locate(&relative, base) else {
+                continue;
+            };
+            let file = found.canonicalize().unwrap_or(found);
+            if !visited.insert(file.clone()) {
+                continue;
+            }
+            let Some(imported) = self.text(&uri_of(&file)) else {
+                continue;
+            };
+            if let Some(symbol) = analysis::symbols(&imported)
+                .into_iter()
+                .find(|s| s.owner.is_none() && s.name == word)
+            {
+                return Some((file, symbol));
+            }
+            let inner = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            if let Some(hit) = self.search(&imported, &inner, word, visited) {
+                return Some(hit);
+            }
+        }
+        None
+    }
+
+    fn completion(&self, cursor: &Cursor) -> Json {
+        // LSP completion item kinds: 3 Function, 6 Variable, 14 Keyword.
+        let mut items: Vec<Json> = cursor
+            .visible()
+            .iter()
+            .map(|symbol| {
+                json!({
+                    "label": symbol.name,
+                    "kind": if symbol.kind == "function" { 3 } else { 6 },
+                    "detail": symbol.detail,
+                })
+            })
+            .collect();
+
+        // Each spelling is its own item, so typing in any script finds it; the
+        // detail shows the others.
+        for keyword in keywords() {
+            let detail = keyword.forms.join(" · ");
+            for form in &keyword.forms {
+                items.push(json!({ "label": form, "kind": 14, "detail": detail }));
+            }
+        }
+        for builtin in builtins() {
+            let detail = builtin.forms.join(" · ");
+            for form in &builtin.forms {
+                items.push(json!({
+                    "label": form,
+                    "kind": 3,
+                    "detail": detail,
+                    "documentation": builtin.doc,
+                }));
+            }
+        }
+
+        json!({ "isIncomplete": false, "items": items })
+    }
+
+    /// The whole document, replaced by its formatted text. A buffer that does
+    /// not parse is left as it is; its diagnostics already say why.
+    fn formatting(&self, uri: &str) -> Json {
+        let Some(text) = self.text(uri) else {
+            return Json::Null;
+        };
+        match formatter::format(&text, None) {
+            Ok(formatted) if formatted != text => {
+                // Just past the last character, in the compiler's coordinates.
+                let lines = text.split('\n').count();
+                let width = text.rsplit('\n').next().unwrap_or_default().chars().count();
+                let end = to_lsp(&text, lines, width + 1);
+                json!([{
+                    "range": { "start": { "line": 0, "character": 0 }, "end": end },
+                    "newText": formatted,
+                }])
+            }
+            _ => json!([]),
+        }
+    }
+
+    fn document_symbols(&self, uri: &str) -> Json {
+        let Some(text) = self.text(uri) else {
+            return json!([]);
+        };
+        // LSP symbol kinds: 12 Function, 13 Variable.
+        let symbols: Vec<Json> = analysis::symbols(&text)
+            .iter()
+            .map(|symbol| {
+                let mut entry = json!({
+                    "name": symbol.name,
+                    "kind": if symbol.kind == "function" { 12 } else { 13 },
+                    "location": location(uri, &text, symbol),
+                });
+                if let Some(owner) = &symbol.owner {
+                    entry["containerName"] = json!(owner);
+                }
+                entry
+            })
+            .collect();
+        json!(symbols)
+    }
+}
+
+/// Where a request is pointing, in the compiler's coordinates.
+struct Cursor<'a> {
+    uri: &'a str,
+    text: &'a str,
+    line: usize,
+    column: usize,
+    word: String,
+}
+
+impl Cursor<'_> {
+    fn visible(&self) -> Vec<Symbol> {
+        analysis::symbols_at(self.text, self.line, self.column)
+    }
+}
+
+/// Hover text for a declared name: the signature for a `செயல்`, the declared
+/// type for a variable, and which file it came from when it was imported.
+fn describe(symbol: &Symbol, file: Option<&Path>) -> String {
+    let mut shown = match symbol.kind {
+        "function" => format!("```etamil\nசெயல் {}{}\n```", symbol.name, symbol.detail),
+        _ if !symbol.detail.is_empty() => format!("**{}**: {}", symbol.name, symbol.detail),
+        kind => format!("**{}** ({})", symbol.name, kind),
+    };
+    if let Some(file) = file {
+        let name = file.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        shown.push_str(&format!("\n\nஇறக்கு \"{}\"", name));
+    }
+    shown
+}
+
+// --- Positions -------------------------------------------------------------
+//
+// The compiler counts lines and columns from 1, in characters. LSP counts from
+// 0, in UTF-16 code units. Tamil is entirely in the Basic Multilingual Plane,
+// so the two agree on every letter of it — but not on an emoji in a string,
+// and getting that wrong would shift every marker after it on the line.
+
+fn line_text(text: &str, line: usize) -> &str {
+    text.lines().nth(line.saturating_sub(1)).unwrap_or_default()
+}
+
+fn to_lsp(text: &str, line: usize, column: usize) -> Json {
+    let units: usize = line_text(text, line)
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum();
+    json!({ "line": line.saturating_sub(1), "character": units })
+}
+
+fn from_lsp(text: &str, line: usize, character: usize) -> (usize, usize) {
+    let mut units = 0;
+    let mut column = 1;
+    for ch in line_text(text, line + 1).chars() {
+        if units >= character {
+            break;
+        }
+        units += ch.len_utf16();
+        column += 1;
+    }
+    (line + 1, column)
+}
+
+fn range(text: &str, line: usize, column: usize, length: usize) -> Json {
+    json!({ "start": to_lsp(text, line, column), "end": to_lsp(text, line, column + length) })
+}
+
+fn location(uri: &str, text: &str, symbol: &Symbol) -> Json {
+    json!({
+        "uri": uri,
+        "range": range(text, symbol.line, symbol.column, symbol.name.chars().count()),
+    })
+}
+
+/// The name the cursor is on or just after — just after, because that is
+/// where the cursor is while a completion is being asked for.
+fn word_at(text: &str, line: usize, column: usize) -> String {
+    let chars: Vec<char> = line_text(text, line).chars().collect();
+    let cursor = column.saturating_sub(1).min(chars.len());
+    let start = (0..cursor).rev().take_while(|&i| is_name_char(chars[i])).last().unwrap_or(cursor);
+    let end = (cursor..chars.len()).take_while(|&i| is_name_char(chars[i])).last().map_or(cursor, |i| i + 1);
+    chars[start..end].iter().collect()
+}
+
+// --- URIs ------------------------------------------------------------------
+
+/// The file a `file://` URI names. Tamil file names arrive percent-encoded.
+fn path_of(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // `file:///C:/x` on Windows: the path starts at the drive letter.
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => rest.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+fn uri_of(path: &Path) -> String {
+    let text = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !text.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            // The drive letter's colon stays literal, as editors write it.
+            b':' => uri.push(':'),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+// --- Builtins, from the compiler's own source ------------------------------
+
+struct Builtin {
+    forms: Vec<String>,
+    doc: String,
+}
+
+/// Every builtin `call_builtin` answers to, with the comment above its arm.
+fn builtins() -> &'static [Builtin] {
+    static TABLE: OnceLock<Vec<Builtin>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let lines: Vec<&str> = include_str!("vm/interpreter.rs").lines().collect();
+        let mut table = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let Some(arm) = line.trim().strip_suffix("=> {") else {
+                continue;
+            };
+            let forms: Vec<String> = arm
+                .split('|')
+                .map(|form| form.trim().trim_matches('"').to_string())
+                .collect();
+            let quoted = arm.split('|').all(|form| {
+                let form = form.trim();
+                form.len() > 2 && form.starts_with('"') && form.ends_with('"')
+            });
+            // A builtin's first spelling is Tamil; `"GET" | "POST"` is not one.
+            let tamil = forms[0].chars().any(|ch| ('\u{0B80}'..='\u{0BFF}').contains(&ch));
+            if !quoted || forms.len() < 2 || !tamil {
+                continue;
+            }
+
+            let doc: Vec<&str> = lines[..index]
+                .iter()
+                .rev()
+                .map(|line| line.trim())
+                .take_while(|line| line.starts_with("//"))
+                .map(|line| line.trim_start_matches('/').trim())
+                .collect();
+            // The line that names the builtin, or the nearest one.
+            let doc = doc
+                .iter()
+                .find(|line| line.starts_with(forms[0].as_str()))
+                .or(doc.first())
+                .map(|line| line.to_string())
+                .unwrap_or_default();
+            table.push(Builtin { forms, doc });
+        }
+        table
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run one session and hand back every message the server wrote.
+    fn session(messages: &[Json]) -> Vec<Json> {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, message).unwrap();
+        }
+        let mut output = Vec::new();
+        serve(io::Cursor::new(input), &mut output).unwrap();
+
+        let mut reader = io::Cursor::new(output);
+        let mut replies = Vec::new();
+        while let Some(reply) = read_message(&mut reader).unwrap() {
+            replies.push(reply);
+        }
+        replies
+    }
+
+    fn open(uri: &str, text: &str) -> Json {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": uri, "languageId": "etamil", "version": 1, "text": text } },
+        })
+    }
+
+    fn request(id: u64, method: &str, uri: &str, line: u64, character: u64) -> Json {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": {
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character },
+            },
+        })
+    }
+
+    fn reply(replies: &[Json], id: u64) -> &Json {
+        &replies.iter().find(|r| r["id"] == json!(id)).expect("no reply")["result"]
+    }
+
+    #[test]
+    fn a_type_error_is_published_with_its_range() {
+        let replies = session(&[open("file:///tmp/a.qmz", "ஈர்ம கொடியா = \"ஆம்\";\n")]);
+
+        let diagnostics = &replies[0]["params"]["diagnostics"];
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(diagnostics[0]["range"]["start"], json!({ "line": 0, "character": 5 }));
+        assert!(diagnostics[0]["message"].as_str().unwrap().contains("கொடியா"));
+    }
+
+    #[test]
+    fn hover_shows_a_declared_type_and_a_signature() {
+        let uri = "file:///tmp/hover.qmz";
+        let text = "எண் வருவாய் = 5;\nசெயல் வரி(எண் அ) எண் { திரும்பு அ; }\nஅச்சு வரி(வருவாய்);\n";
+        let replies = session(&[
+            open(uri, text),
+            request(1, "textDocument/hover", uri, 2, 12),
+            request(2, "textDocument/hover", uri, 2, 7),
+        ]);
+
+        let variable = reply(&replies, 1)["contents"]["value"].as_str().unwrap();
+        assert!(variable.contains("எண் (eN, a number)"), "{}", variable);
+        let function = reply(&replies, 2)["contents"]["value"].as_str().unwrap();
+        assert!(function.contains("வரி(எண் அ) எண்"), "{}", function);
+    }
+
+    #[test]
+    fn definition_follows_an_import() {
+        let dir = std::env::temp_dir().join("etamil_lsp_definition");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("uthavi.qmz"), "// உதவி\nசெயல் இரட்டி(அ) { திரும்பு அ * 2; }\n").unwrap();
+        let main = dir.join("main.qmz");
+        let uri = uri_of(&main);
+
+        let replies = session(&[
+            open(&uri, "இறக்கு \"uthavi.qmz\";\nஅச்சு இரட்டி(2);\n"),
+            request(1, "textDocument/definition", &uri, 1, 8),
+        ]);
+
+        let found = reply(&replies, 1);
+        assert!(found["uri"].as_str().unwrap().ends_with("uthavi.qmz"), "{}", found);
+        assert_eq!(found["range"]["start"], json!({ "line": 1, "character": 6 }));
+    }
+
+    #[test]
+    fn completion_offers_every_spelling_of_a_keyword() {
+        let uri = "file:///tmp/complete.qmz";
+        let replies = session(&[open(uri, "அ = 1;\n"), request(1, "textDocument/completion", uri, 1, 0)]);
+
+        let labels: Vec<&str> = reply(&replies, 1)["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap())
+            .collect();
+        for spelling in ["மற்றும்", "maRRum", "_and", "நீளம்", "அ"] {
+            assert!(labels.contains(&spelling), "missing {}", spelling);
+        }
+    }
+
+    #[test]
+    fn formatting_replaces_the_whole_document() {
+        let uri = "file:///tmp/format.qmz";
+        let replies = session(&[
+            open(uri, "அ=1;\nஅச்சு அ;"),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "textDocument/formatting",
+                "params": { "textDocument": { "uri": uri }, "options": { "tabSize": 4, "insertSpaces": true } },
+            }),
+        ]);
+
+        let edits = reply(&replies, 1);
+        assert_eq!(edits[0]["newText"], json!("அ = 1;\nஅச்சு அ;\n"));
+        assert_eq!(edits[0]["range"]["end"], json!({ "line": 1, "character": 8 }));
+    }
+
+    #[test]
+    fn an_unknown_request_is_answered_with_an_error() {
+        let replies = session(&[request(7, "textDocument/rename", "file:///tmp/x.qmz", 0, 0)]);
+
+        assert_eq!(replies[0]["id"], json!(7));
+        assert_eq!(replies[0]["error"]["code"], json!(-32601));
+    }
+
+    #[test]
+    fn a_tamil_file_name_survives_the_uri() {
+        let path = PathBuf::from("/tmp/கணக்கு.qmz");
+
+        assert_eq!(path_of(&uri_of(&path)), Some(path));
+    }
+}
@@ -3,9 +3,11 @@
 // whole crate twice.
 use std::io::{self, Read};
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use etamil_compiler::http::{AsyncHttpServer, HttpServer};
+use etamil_compiler::http::handler::Routes;
+use etamil_compiler::http::{AsyncHttpServer, HttpServer, reload, shutdown};
 use etamil_compiler::{module, parser, vm};
 #[cfg(feature = "llvm")]
 use etamil_compiler::codegen;
@@ -34,17 +36,55 @@ fn print_help() {
     println!("USAGE:");
     println!("    etamil [OPTIONS] <FILE>");
     println!("    cat program.qmz | etamil [OPTIONS]");
+    println!("    etamil fmt [--check] [--script tamil|roman|english] [FILE|DIR ...]");
+    println!("    etamil convert [--to tamil|roman|english] [--migrate] [FILE|DIR ...]");
+    println!("    etamil test [--filter TEXT] [--format human|tap|junit] [FILE|DIR ...]");
+    println!("    etamil build [-o OUT.qmzc] <FILE>");
+    println!("    etamil run <FILE.qmzc|FILE>");
+    println!("    etamil openapi [-o OUT.json] <FILE>");
     println!();
     println!("OPTIONS:");
     println!("    --vm               Run on the bytecode VM (default)");
     println!("    --check            Lex, parse and type check only — never runs the program");
     println!("    --repl             Interactive shell: type an expression, see what it comes to");
+    println!("    --lsp              Language server on stdin/stdout, for editors");
+    println!("    --debug            Step through FILE: breakpoints, stepping, variables");
+    println!("    --dap              Debug Adapter Protocol on stdin/stdout, for editors");
     println!("    --server           Start the synchronous HTTP server");
     println!("    --async            Concurrent server: async accept, blocking handlers");
     println!("                       இடைவெளி blocks run on a timer under either server");
     println!("    --llvm             LLVM backend (requires --features llvm; Linux/macOS)");
     println!("    --host <HOST>      Server bind address (default: 127.0.0.1)");
     println!("    --port <PORT>      Server port (default: 8080)");
+    println!("    --tls-cert <PEM>   Serve HTTPS with this certificate chain (or ETAMIL_TLS_SERVER_CERT)");
+    println!("    --tls-key <PEM>    ...and this private key (or ETAMIL_TLS_SERVER_KEY)");
+    println!("    --tls-client-ca <PEM>  Require client certificates from this CA (or ETAMIL_TLS_CLIENT_CA);");
+    println!("                       the verified subject is bound as client_subject");
+    println!("    --header-timeout <S>   Seconds a request may take to send its headers (default: 10)");
+    println!("    --body-timeout <S>     ...and then its body (default: 30)");
+    println!("    --handler-timeout <S>  Seconds a handler may run, then 504; 0 for no limit (default: 60)");
+    println!("    --idle-timeout <S>     Seconds a kept-alive connection may sit quiet (default: 5)");
+    println!("    --drain-timeout <S>    Seconds SIGTERM or Ctrl-C waits for the requests in hand (default: 30)");
+    println!("    --max-body <SIZE>      Largest request body, e.g. 1MB (default: 1MB)");
+    println!("    --route-max-body \"POST /journal/upload=50MB\"");
+    println!("                       A larger or smaller limit for one route; repeatable");
+    println!("    --rate-limit \"/api/vari=60/min\"");
+    println!("                       Requests a client may make under a prefix, 429 past it; repeatable.");
+    println!("                       Add \"by header:X-Api-Key\" or \"by subject\" to count other than by address");
+    println!("    --cors-origin <ORIGIN> An origin whose pages may read the answers, or *; repeatable");
+    println!("                       (default: any origin). Preflights are answered for every route");
+    println!("    --cors-methods <LIST>  Methods a preflight allows (default: the path's routes')");
+    println!("    --cors-headers <LIST>  Request headers a preflight allows, or * (default: Content-Type)");
+    println!("    --cors-credentials     Let cookies and Authorization be sent; needs --cors-origin");
+    println!("    --cors-max-age <S>     Seconds a browser may remember a preflight's answer");
+    println!("    --idempotency <STORE>  Answer a POST sent again with its Idempotency-Key as it was");
+    println!("                       answered first: memory, redis or sqlite:PATH (or ETAMIL_IDEMPOTENCY)");
+    println!("    --metrics          Serve GET /metrics, in the Prometheus text format");
+    println!("    --redis <HOST:PORT>    A Redis for sessions, rate limits and GET /health (or ETAMIL_REDIS)");
+    println!("    --log-level <LEVEL>    debug, info, warn or error (or ETAMIL_LOG_LEVEL; default: info)");
+    println!("    --log-file <PATH>      Append the JSON access log here instead of stdout (or ETAMIL_LOG_FILE)");
+    println!("    --openapi          Serve GET /openapi.json, the routes as an OpenAPI 3 document");
+    println!("    --watch            Serve the program's routes again whenever FILE or an import is saved");
     println!("    -h, --help         Show this message");
     println!("    -V, --version      Show the version");
     println!();
@@ -54,6 +94,474 @@ fn print_help() {
     println!("    etamil --server --port 8080 examples/backend/hello_server.qmz");
     println!("    cat program.qmz | etamil --check     # errors only, nothing runs");
     println!("    etamil --repl                        # try something without a file");
+    println!("    etamil --lsp                         # started by an editor, not by hand");
+    println!("    etamil --debug ledger.qmz            # then: break 12, continue, vars");
+    println!("    etamil fmt --check nUlakam           # list files not yet in the standard layout");
+    println!("    etamil convert --to tamil program.qmz  # keywords in Tamil, nothing else touched");
+    println!("    etamil convert --migrate examples    # old romanizations, e.g. soqqu, to the scheme");
+    println!("    etamil test --filter kAcu nUlakam    # only the tests whose name contains kAcu");
+    println!("    etamil test --format junit > report.xml  # for CI");
+    println!("    etamil build report.qmz              # writes report.qmzc, imports and all");
+    println!("    etamil run report.qmzc               # no parsing, no checking, just the VM");
+    println!("    etamil openapi -o api.json kadai.qmz # the routes, for a partner's client generator");
+}
+
+/// `etamil fmt`: rewrite files in the standard layout.
+///
+/// Files and directories are rewritten in place — a directory means every
+/// `.qmz` file under it. With none, stdin is formatted to stdout. `--check`
+/// writes nothing and lists the files that would change, exiting 1 if there
+/// are any, which is what CI wants. A file that does not parse is reported and
+/// left alone; the rest are still formatted.
+fn format_command(args: &[String]) -> ! {
+    let mut check = false;
+    let mut script = None;
+    let mut paths = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--check" => check = true,
+            "--script" => {
+                let Some(name) = args.get(i + 1) else {
+                    eprintln!("✗ --script needs a value: tamil, roman or english");
+                    std::process::exit(2);
+                };
+                match etamil_compiler::script::Script::from_name(name) {
+                    Ok(chosen) => script = Some(chosen),
+                    Err(message) => {
+                        eprintln!("✗ {}", message);
+                        std::process::exit(2);
+                    }
+                }
+                i += 1;
+            }
+            arg if !arg.starts_with('-') => paths.push(std::path::PathBuf::from(arg)),
+            unknown => {
+                eprintln!("✗ Unknown option for fmt: {}", unknown);
+                eprintln!("   Run `etamil --help` to see the available options.");
+                std::process::exit(2);
+            }
+        }
+        i += 1;
+    }
+
+    if paths.is_empty() {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .expect("Unable to read from stdin");
+        match etamil_compiler::formatter::format(&source, script) {
+            Ok(formatted) if check => std::process::exit(if formatted == source { 0 } else { 1 }),
+            Ok(formatted) => {
+                print!("{}", formatted);
+                std::process::exit(0);
+            }
+            Err(message) => {
+                eprintln!("✗ {}", message);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if path.is_dir() {
+            collect_sources(path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut failed = false;
+    for file in &files {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("✗ {}: {}", file.display(), e);
+                failed = true;
+                continue;
+            }
+        };
+        let formatted = match etamil_compiler::formatter::format(&source, script) {
+            Ok(formatted) => formatted,
+            Err(message) => {
+                eprintln!("✗ {}: {}", file.display(), message);
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{}", file.display());
+            failed = true;
+        } else if let Err(e) = std::fs::write(file, formatted) {
+            eprintln!("✗ {}: {}", file.display(), e);
+            failed = true;
+        }
+    }
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+/// `etamil convert`: rewrite keywords into another script, or bring old
+/// romanizations up to the current scheme with `--migrate`.
+///
+/// Unlike `fmt` this changes nothing but keyword tokens, so a file keeps its
+/// layout and its comments exactly. Files and directories are rewritten in
+/// place; with none, stdin is converted to stdout. A name that `--migrate`
+/// renames is reported on stderr, since code outside the file may use it.
+fn convert_command(args: &[String]) -> ! {
+    let mut to = None;
+    let mut migrate = false;
+    let mut paths = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--migrate" => migrate = true,
+            "--to" => {
+                let Some(name) = args.get(i + 1) else {
+                    eprintln!("✗ --to needs a value: tamil, roman or english");
+                    std::process::exit(2);
+                };
+                match etamil_compiler::script::Script::from_name(name) {
+                    Ok(chosen) => to = Some(chosen),
+                    Err(message) => {
+                        eprintln!("✗ {}", message);
+                        std::process::exit(2);
+                    }
+                }
+                i += 1;
+            }
+            arg if !arg.starts_with('-') => paths.push(std::path::PathBuf::from(arg)),
+            unknown => {
+                eprintln!("✗ Unknown option for convert: {}", unknown);
+                eprintln!("   Run `etamil --help` to see the available options.");
+                std::process::exit(2);
+            }
+        }
+        i += 1;
+    }
+    if to.is_none() && !migrate {
+        eprintln!("✗ convert needs --to tamil|roman|english, --migrate, or both");
+        std::process::exit(2);
+    }
+
+    let report = |file: &str, renamed: &[(String, String)]| {
+        for (old, new) in renamed {
+            eprintln!(
+                "ℹ️  {}: பெயர் '{}' → '{}' மாற்றப்பட்டது  (name '{}' renamed to '{}'; update any code outside this file that uses it)",
+                file, old, new, old, new
+            );
+        }
+    };
+
+    if paths.is_empty() {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .expect("Unable to read from stdin");
+        match etamil_compiler::script::convert(&source, to, migrate) {
+            Ok(converted) => {
+                report("<stdin>", &converted.renamed);
+                print!("{}", converted.text);
+                std::process::exit(0);
+            }
+            Err(message) => {
+                eprintln!("✗ {}", message);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if path.is_dir() {
+            collect_sources(path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut failed = false;
+    for file in &files {
+        let converted = std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                etamil_compiler::script::convert(&source, to, migrate)
+                    .map(|converted| (source, converted))
+            });
+        let (source, converted) = match converted {
+            Ok(pair) => pair,
+            Err(message) => {
+                eprintln!("✗ {}: {}", file.display(), message);
+                failed = true;
+                continue;
+            }
+        };
+        report(&file.display().to_string(), &converted.renamed);
+        if converted.text == source {
+            continue;
+        }
+        if let Err(e) = std::fs::write(file, converted.text) {
+            eprintln!("✗ {}: {}", file.display(), e);
+            failed = true;
+        }
+    }
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+/// `etamil test`: run every test under the paths given, or under the current
+/// directory. What counts as a test is described in `testing.rs`.
+///
+/// The report goes to stdout in the format asked for, so `--format junit` can
+/// be redirected straight into a file a CI system reads. Exits 1 if any test
+/// failed, and 2 for a mistake on the command line.
+fn test_command(args: &[String]) -> ! {
+    use etamil_compiler::testing;
+
+    let mut filter: Option<String> = None;
+    let mut format = testing::Format::Human;
+    let mut paths = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--filter" => {
+                let Some(text) = args.get(i + 1) else {
+                    eprintln!("✗ --filter needs a value: part of a test's name");
+                    std::process::exit(2);
+                };
+                filter = Some(text.clone());
+                i += 1;
+            }
+            "--format" => {
+                let Some(name) = args.get(i + 1) else {
+                    eprintln!("✗ --format needs a value: human, tap or junit");
+                    std::process::exit(2);
+                };
+                match testing::Format::from_name(name) {
+                    Ok(chosen) => format = chosen,
+                    Err(message) => {
+                        eprintln!("✗ {}", message);
+                        std::process::exit(2);
+                    }
+                }
+                i += 1;
+            }
+            arg if !arg.starts_with('-') => paths.push(std::path::PathBuf::from(arg)),
+            unknown => {
+                eprintln!("✗ Unknown option for test: {}", unknown);
+                eprintln!("   Run `etamil --help` to see the available options.");
+                std::process::exit(2);
+            }
+        }
+        i += 1;
+    }
+    if paths.is_empty() {
+        paths.push(std::path::PathBuf::from("."));
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if path.is_dir() {
+            collect_sources(path, &mut files);
+        } else if path.exists() {
+            files.push(path.clone());
+        } else {
+            eprintln!("✗ {}: கோப்பு இல்லை  (no such file)", path.display());
+            std::process::exit(2);
+        }
+    }
+
+    let cases: Vec<_> = testing::discover(&files)
+        .into_iter()
+        .filter(|case| filter.as_ref().is_none_or(|text| case.name().contains(text.as_str())))
+        .collect();
+    if cases.is_empty() {
+        eprintln!("⚠️  சோதனைகள் எதுவும் கிடைக்கவில்லை  (no tests found)");
+    }
+    let outcomes: Vec<_> = cases.into_iter().map(testing::run).collect();
+
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = testing::report(&outcomes, format, &mut stdout) {
+        eprintln!("✗ {}", e);
+        std::process::exit(2);
+    }
+    let failed = outcomes.iter().any(|outcome| outcome.failure.is_some());
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+/// `etamil build`: compile a program, and everything it imports, to a `.qmzc`
+/// file that `etamil run` executes without going back to the source.
+///
+/// The program is checked first, exactly as a run would check it, so a
+/// `.qmzc` never holds a program the checker would have refused. The output
+/// goes beside the source unless `-o` names somewhere else. Imports are
+/// resolved now, against this machine's ETAMIL_PATH, and compiled in: the
+/// `.qmzc` needs neither them nor the source to run.
+fn build_command(args: &[String]) -> ! {
+    use etamil_compiler::vm::bytecode::artifact;
+
+    let mut output: Option<std::path::PathBuf> = None;
+    let mut source: Option<std::path::PathBuf> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-o" | "--output" => {
+                let Some(path) = args.get(i + 1) else {
+                    eprintln!("✗ -o needs a value: the .qmzc file to write");
+                    std::process::exit(2);
+                };
+                output = Some(std::path::PathBuf::from(path));
+                i += 1;
+            }
+            arg if !arg.starts_with('-') && source.is_none() => {
+                source = Some(std::path::PathBuf::from(arg))
+            }
+            arg if !arg.starts_with('-') => {
+                eprintln!("✗ build takes one FILE, got a second: {}", arg);
+                std::process::exit(2);
+            }
+            unknown => {
+                eprintln!("✗ Unknown option for build: {}", unknown);
+                eprintln!("   Run `etamil --help` to see the available options.");
+                std::process::exit(2);
+            }
+        }
+        i += 1;
+    }
+    let Some(source) = source else {
+        eprintln!("✗ build needs a FILE: the program to compile");
+        std::process::exit(2);
+    };
+    let output = output.unwrap_or_else(|| source.with_extension("qmzc"));
+
+    let ast = match module::load_file(&source) {
+        Ok(ast) => ast,
+        Err(message) => {
+            eprintln!("✗ {}", message);
+            std::process::exit(1);
+        }
+    };
+    if let Err(errors) = etamil_compiler::check::check(&ast) {
+        for error in &errors {
+            eprintln!("✗ {}", error);
+        }
+        std::process::exit(1);
+    }
+
+    let bytecode = vm::bytecode::compiler::BytecodeCompiler::compile_statements(ast);
+    let written = artifact::encode(&bytecode)
+        .and_then(|bytes| std::fs::write(&output, bytes).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("✗ {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    println!("✓ Built {} ({} instructions)", output.display(), bytecode.len());
+    std::process::exit(0);
+}
+
+/// `etamil openapi`: the program's routes as an OpenAPI 3 document.
+///
+/// Printed to stdout, or written to the file `-o` names. The program is loaded
+/// and checked as a run would be, so a route that would not start is not
+/// documented either; nothing runs.
+fn openapi_command(args: &[String]) -> ! {
+    let mut output: Option<std::path::PathBuf> = None;
+    let mut source: Option<std::path::PathBuf> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-o" | "--output" => {
+                let Some(path) = args.get(i + 1) else {
+                    eprintln!("✗ -o needs a value: the .json file to write");
+                    std::process::exit(2);
+                };
+                output = Some(std::path::PathBuf::from(path));
+                i += 1;
+            }
+            arg if !arg.starts_with('-') && source.is_none() => {
+                source = Some(std::path::PathBuf::from(arg))
+            }
+            arg if !arg.starts_with('-') => {
+                eprintln!("✗ openapi takes one FILE, got a second: {}", arg);
+                std::process::exit(2);
+            }
+            unknown => {
+                eprintln!("✗ Unknown option for openapi: {}", unknown);
+                eprintln!("   Run `etamil --help` to see the available options.");
+                std::process::exit(2);
+            }
+        }
+        i += 1;
+    }
+    let Some(source) = source else {
+        eprintln!("✗ openapi needs a FILE: the program whose routes to describe");
+        std::process::exit(2);
+    };
+
+    let ast = match module::load_file(&source) {
+        Ok(ast) => ast,
+        Err(message) => {
+            eprintln!("✗ {}", message);
+            std::process::exit(1);
+        }
+    };
+    if let Err(errors) = etamil_compiler::check::check(&ast) {
+        for error in &errors {
+            eprintln!("✗ {}", error);
+        }
+        std::process::exit(1);
+    }
+
+    let document = openapi_document(&ast, &source);
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, document + "\n") {
+                eprintln!("✗ {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+        None => println!("{}", document),
+    }
+    std::process::exit(0);
+}
+
+/// The OpenAPI document for a program, titled with its file's name. A route
+/// the document cannot describe stops here, naming the route.
+fn openapi_document(ast: &[parser::Stmt], source: &Path) -> String {
+    let title = source
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "eTamil".to_string());
+    match etamil_compiler::http::openapi::document(&title, ast) {
+        Ok(document) => serde_json::to_string_pretty(&document).unwrap_or_default(),
+        Err(why) => {
+            eprintln!("✗ {}", why);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Every `.qmz` file under `dir`, in a stable order.
+fn collect_sources(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_sources(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "qmz") {
+            files.push(path);
+        }
+    }
 }
 
 /// `--check`: report every error the front end can find, and run nothing.
@@ -92,14 +600,51 @@ fn check_only(loaded: Result<Vec<parser::Stmt>, String>) -> ! {
 
 fn main() {
     // Parse command line arguments
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    // A subcommand comes first, and takes the rest of the line.
+    if args.get(1).map(String::as_str) == Some("fmt") {
+        format_command(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("convert") {
+        convert_command(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("test") {
+        test_command(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("build") {
+        build_command(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("openapi") {
+        openapi_command(&args[2..]);
+    }
+    // `etamil run FILE` is `etamil FILE`, spelled the way it reads next to
+    // `etamil build`. Either way a `.qmzc` is run from its bytecode.
+    if args.get(1).map(String::as_str) == Some("run") {
+        args.remove(1);
+    }
     let mut use_vm = true;  // Default: use VM executor
     let mut use_http_server = false;
     let mut use_async_server = false;  // Backend milestone 2: New async server flag
     let mut check_only_mode = false;
     let mut repl_mode = false;
+    let mut lsp_mode = false;
+    let mut debug_mode = false;
+    let mut dap_mode = false;
     let mut server_host = "127.0.0.1".to_string();
     let mut server_port = 8080u16;
+    let mut tls_cert = None;
+    let mut tls_key = None;
+    let mut tls_client_ca = None;
+    let mut limit_flags: Vec<(String, String)> = Vec::new();
+    let mut rate_limit_flags: Vec<String> = Vec::new();
+    let mut cors_flags: Vec<(String, String)> = Vec::new();
+    let mut idempotency = None;
+    let mut serve_metrics = false;
+    let mut redis_address = None;
+    let mut log_level = None;
+    let mut log_file = None;
+    let mut serve_openapi = false;
+    let mut watch = false;
     let mut filename = None;
 
     let mut i = 1;
@@ -109,6 +654,9 @@ fn main() {
             "--vm" => use_vm = true,
             "--check" => check_only_mode = true,
             "--repl" => repl_mode = true,
+            "--lsp" => lsp_mode = true,
+            "--debug" => debug_mode = true,
+            "--dap" => dap_mode = true,
             "--server" => use_http_server = true,
             "--async" => use_async_server = true,  // Backend milestone 2: Async server mode
             "--host" => {
@@ -138,6 +686,74 @@ fn main() {
                     std::process::exit(2);
                 }
             }
+            "--tls-cert" | "--tls-key" | "--tls-client-ca" => {
+                let Some(path) = args.get(i + 1) else {
+                    eprintln!("✗ {} needs a value: a PEM file", args[i]);
+                    std::process::exit(2);
+                };
+                match args[i].as_str() {
+                    "--tls-cert" => tls_cert = Some(path.clone()),
+                    "--tls-key" => tls_key = Some(path.clone()),
+                    _ => tls_client_ca = Some(path.clone()),
+                }
+                i += 1;
+            }
+            "--header-timeout" | "--body-timeout" | "--handler-timeout" | "--idle-timeout"
+            | "--drain-timeout" | "--max-body" | "--route-max-body" => {
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("✗ {} needs a value", args[i]);
+                    std::process::exit(2);
+                };
+                limit_flags.push((args[i].clone(), value.clone()));
+                i += 1;
+            }
+            "--rate-limit" => {
+                let Some(rule) = args.get(i + 1) else {
+                    eprintln!("✗ --rate-limit needs a value, like '/api/vari=60/min'");
+                    std::process::exit(2);
+                };
+                rate_limit_flags.push(rule.clone());
+                i += 1;
+            }
+            "--cors-origin" | "--cors-methods" | "--cors-headers" | "--cors-max-age" => {
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("✗ {} needs a value", args[i]);
+                    std::process::exit(2);
+                };
+                cors_flags.push((args[i].clone(), value.clone()));
+                i += 1;
+            }
+            "--cors-credentials" => cors_flags.push((args[i].clone(), "true".to_string())),
+            "--idempotency" => {
+                let Some(store) = args.get(i + 1) else {
+                    eprintln!("✗ --idempotency needs a value: memory, redis or sqlite:PATH");
+                    std::process::exit(2);
+                };
+                idempotency = Some(store.clone());
+                i += 1;
+            }
+            "--metrics" => serve_metrics = true,
+            "--openapi" => serve_openapi = true,
+            "--watch" => watch = true,
+            "--redis" => {
+                let Some(address) = args.get(i + 1) else {
+                    eprintln!("✗ --redis needs a value: host:port");
+                    std::process::exit(2);
+                };
+                redis_address = Some(address.clone());
+                i += 1;
+            }
+            "--log-level" | "--log-file" => {
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("✗ {} needs a value", args[i]);
+                    std::process::exit(2);
+                };
+                match args[i].as_str() {
+                    "--log-level" => log_level = Some(value.clone()),
+                    _ => log_file = Some(value.clone()),
+                }
+                i += 1;
+            }
             "--version" | "-V" => {
                 println!("etamil {}", env!("CARGO_PKG_VERSION"));
                 return;
@@ -158,14 +774,110 @@ fn main() {
         i += 1;
     }
     
+    // A certificate only means something to a server. Said now, not after the
+    // program has run as a script and the author wonders where HTTPS went.
+    let tls_flags = tls_cert.is_some() || tls_key.is_some() || tls_client_ca.is_some();
+    if tls_flags && !use_http_server && !use_async_server {
+        eprintln!("✗ --tls-cert, --tls-key and --tls-client-ca need --server or --async");
+        std::process::exit(2);
+    }
+    if !limit_flags.is_empty() && !use_http_server && !use_async_server {
+        eprintln!("✗ {} needs --server or --async", limit_flags[0].0);
+        std::process::exit(2);
+    }
+    if !rate_limit_flags.is_empty() && !use_http_server && !use_async_server {
+        eprintln!("✗ --rate-limit needs --server or --async");
+        std::process::exit(2);
+    }
+    if !cors_flags.is_empty() && !use_http_server && !use_async_server {
+        eprintln!("✗ {} needs --server or --async", cors_flags[0].0);
+        std::process::exit(2);
+    }
+    if idempotency.is_some() && !use_http_server && !use_async_server {
+        eprintln!("✗ --idempotency needs --server or --async");
+        std::process::exit(2);
+    }
+    if (serve_metrics || redis_address.is_some() || serve_openapi) && !use_http_server && !use_async_server {
+        eprintln!("✗ --metrics, --redis and --openapi need --server or --async");
+        std::process::exit(2);
+    }
+    if (log_level.is_some() || log_file.is_some()) && !use_http_server && !use_async_server {
+        eprintln!("✗ --log-level and --log-file need --server or --async");
+        std::process::exit(2);
+    }
+    if watch && (filename.is_none() || (!use_http_server && !use_async_server)) {
+        eprintln!("✗ --watch needs --server or --async, and a FILE to watch");
+        std::process::exit(2);
+    }
+    let mut observe = etamil_compiler::http::observe::Observe::resolve(serve_metrics, redis_address);
+    // A server given a Redis keeps its sessions there, where every server
+    // behind the same load balancer can find them.
+    if let Some(address) = &observe.redis {
+        etamil_compiler::http::session::keep_in_redis(address);
+    }
+
     // Before anything asks for a file: in the shell, the typing is the program.
     if repl_mode {
         etamil_compiler::repl::run();
     }
+    // Likewise: stdin belongs to the editor. Nothing else may write to stdout.
+    if lsp_mode {
+        let stdin = io::stdin();
+        match etamil_compiler::lsp::serve(stdin.lock(), io::stdout()) {
+            Ok(true) => std::process::exit(0),
+            // The protocol says exit without shutdown first is status 1.
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("✗ etamil --lsp: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // The same for a debugging editor, which names the program in `launch`.
+    if dap_mode {
+        let stdin = io::stdin();
+        if let Err(e) = etamil_compiler::dap::serve(stdin.lock(), io::stdout()) {
+            eprintln!("✗ etamil --dap: {}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+    // Built bytecode skips the front end: it was loaded, checked and compiled
+    // by `etamil build`, so only the VM is left to do.
+    if let Some(fname) = filename.as_ref().filter(|name| name.ends_with(".qmzc")) {
+        if check_only_mode || debug_mode || use_http_server || use_async_server || !use_vm {
+            eprintln!("✗ {} is built bytecode, which only runs on the VM", fname);
+            eprintln!("   --check, --debug, --server, --async and --llvm need the .qmz source.");
+            std::process::exit(2);
+        }
+        run_built(Path::new(fname));
+    }
+
+    // Commands come from stdin, so the program cannot.
+    if debug_mode {
+        let Some(fname) = &filename else {
+            eprintln!("✗ --debug needs a FILE: stdin is where the commands come from");
+            std::process::exit(2);
+        };
+        let stdin = io::stdin();
+        match etamil_compiler::debugger::run(Path::new(fname), stdin.lock(), io::stdout()) {
+            Ok(true) => std::process::exit(0),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("✗ etamil --debug: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     // 1-3. Load, lex, parse, and resolve any இறக்கு imports.
+    let mut sources = Vec::new();
     let loaded = match &filename {
-        Some(fname) => module::load_file(Path::new(fname)),
+        Some(fname) => module::load_file_with_sources(Path::new(fname)).map(|(ast, files)| {
+            sources = files;
+            ast
+        }),
         None => {
             let mut buffer = String::new();
             io::stdin()
@@ -200,6 +912,12 @@ fn main() {
         std::process::exit(1);
     }
 
+    // Written once, before a request can ask for it: the routes do not change.
+    if serve_openapi {
+        let source = Path::new(filename.as_deref().unwrap_or("eTamil"));
+        observe.openapi = Some(openapi_document(&ast, source));
+    }
+
     // Backend milestone 2: Check if async server mode is enabled
     if use_async_server {
         // This used to print a warning saying the async runtime was not wired
@@ -210,7 +928,23 @@ fn main() {
         println!("=== eTamil HTTP Server (--async) ===");
         println!("🚀 Starting server on {}:{}\n", server_host, server_port);
 
-        if let Err(e) = run_async_server(&server_host, server_port, ast) {
+        let mut server = AsyncHttpServer::new(&server_host, server_port);
+        if let Some(config) = server_tls(tls_cert, tls_key, tls_client_ca) {
+            server.use_tls(config);
+        }
+        server.use_limits(server_limits(&limit_flags));
+        server.use_rate_limits(server_rate_limits(&rate_limit_flags, observe.redis.as_deref()));
+        server.use_cors(server_cors(&cors_flags));
+        server.use_idempotency(server_idempotency(idempotency, observe.redis.as_deref()));
+        server.use_observe(observe);
+        server.use_logger(server_logger(log_level, log_file));
+        if let Some(fname) = &filename
+            && watch
+        {
+            watch_program(server.route_table(), Path::new(fname), sources);
+        }
+        shutdown::listen();
+        if let Err(e) = run_async_server(server, ast) {
             eprintln!("❌ Async server error: {}", e);
             std::process::exit(1);
         }
@@ -218,18 +952,34 @@ fn main() {
         // === Backend milestone 1: SYNCHRONOUS HTTP SERVER MODE (MVP) ===
         println!("=== eTamil HTTP Server (Minimum Viable Backend) ===\n");
         
-        let mut server = HttpServer::new(&server_host, server_port);
-        register_routes(
+        let logger = server_logger(log_level, log_file);
+        let mut server = HttpServer::with_logger(&server_host, server_port, logger);
+        if let Some(config) = server_tls(tls_cert, tls_key, tls_client_ca) {
+            server.use_tls(config);
+        }
+        server.use_limits(server_limits(&limit_flags));
+        server.use_rate_limits(server_rate_limits(&rate_limit_flags, observe.redis.as_deref()));
+        server.use_cors(server_cors(&cors_flags));
+        server.use_idempotency(server_idempotency(idempotency, observe.redis.as_deref()));
+        server.use_observe(observe);
+        if let Err(why) = register_routes(
             &mut server,
             ast,
             |server, method, path, program| server.register_route(method, path, program),
             |server, seconds, program| server.register_schedule(seconds, program),
-        );
-
-        // Also register health check endpoint
-        server.register_route("GET", "/health", vec![
-            parser::Stmt::Print(parser::Expr::Number(rust_decimal::Decimal::from(200))),
-        ]);
+            |server, hook, prefix, program| server.register_middleware(hook, prefix, program),
+            |server, mount| server.register_mount(mount),
+            |server, prelude| server.register_prelude(prelude),
+        ) {
+            eprintln!("✗ {}", why);
+            std::process::exit(1);
+        }
+        if let Some(fname) = &filename
+            && watch
+        {
+            watch_program(Arc::clone(&server.routes), Path::new(fname), sources);
+        }
+        shutdown::listen();
 
         // Start the server
         if let Err(e) = server.start() {
@@ -308,6 +1058,133 @@ fn main() {
     }
 }
 
+/// Run a `.qmzc` that `etamil build` wrote.
+///
+/// A file from a different compiler version is refused rather than run; see
+/// `vm::bytecode::artifact` for why.
+fn run_built(path: &Path) -> ! {
+    let loaded = std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| vm::bytecode::artifact::decode(&bytes));
+    let bytecode = match loaded {
+        Ok(bytecode) => bytecode,
+        Err(message) => {
+            eprintln!("✗ {}: {}", path.display(), message);
+            std::process::exit(1);
+        }
+    };
+
+    println!("=== eTamil VM Executor ===\n");
+    println!("✓ Bytecode loaded ({} instructions)", bytecode.len());
+    println!("=== Execution Output ===\n");
+
+    let mut vm = vm::VM::new();
+    match vm.execute(bytecode) {
+        Ok(_) => println!("\n✓ Execution completed successfully"),
+        Err(e) => {
+            eprintln!("✗ Runtime error: {}", e);
+            std::process::exit(1);
+        }
+    }
+    std::process::exit(0);
+}
+
+/// The HTTPS configuration from the `--tls-*` flags and `ETAMIL_TLS_SERVER_*`,
+/// or `None` to serve plain HTTP. A certificate that cannot be used stops the
+/// server from starting: falling back to HTTP would send a partner's traffic
+/// in the clear on a port they were told was HTTPS.
+fn server_tls(
+    cert: Option<String>,
+    key: Option<String>,
+    client_ca: Option<String>,
+) -> Option<std::sync::Arc<rustls::ServerConfig>> {
+    use etamil_compiler::http::tls;
+
+    let configured = tls::TlsSettings::resolve(cert, key, client_ca).and_then(|settings| {
+        settings
+            .map(|settings| {
+                let config = tls::server_config(&settings)?;
+                match &settings.client_ca {
+                    Some(ca) => println!("🔒 HTTPS; client certificates from {} required", ca),
+                    None => println!("🔒 HTTPS"),
+                }
+                Ok(config)
+            })
+            .transpose()
+    });
+    match configured {
+        Ok(config) => config,
+        Err(why) => {
+            eprintln!("✗ HTTPS could not be set up: {}", why);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The timeouts and body sizes from `--header-timeout` and its siblings, or
+/// their `ETAMIL_*` variables. One that does not parse stops the server rather
+/// than falling back to a default nobody asked for, as `--port` does.
+fn server_limits(flags: &[(String, String)]) -> etamil_compiler::http::connection::Limits {
+    match etamil_compiler::http::connection::Limits::resolve(flags) {
+        Ok(limits) => limits,
+        Err(why) => {
+            eprintln!("✗ {}", why);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// The `--rate-limit` rules, or `ETAMIL_RATE_LIMIT`'s, counted in the Redis
+/// at `redis` if the server has one. A rule that does not read is a mistake
+/// to stop on, not one to serve without.
+fn server_rate_limits(flags: &[String], redis: Option<&str>) -> etamil_compiler::http::ratelimit::RateLimits {
+    match etamil_compiler::http::ratelimit::RateLimits::resolve(flags, redis) {
+        Ok(rate_limits) => rate_limits,
+        Err(why) => {
+            eprintln!("✗ {}", why);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Where `--idempotency`, or `ETAMIL_IDEMPOTENCY`, keeps answers. A store
+/// that cannot be opened, or is not one, is found out before the first
+/// payment is taken without it.
+fn server_idempotency(flag: Option<String>, redis: Option<&str>) -> etamil_compiler::http::idempotency::Idempotency {
+    match etamil_compiler::http::idempotency::Idempotency::resolve(flag, redis) {
+        Ok(idempotency) => idempotency,
+        Err(why) => {
+            eprintln!("✗ {}", why);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// The CORS policy from `--cors-origin` and its siblings, or their
+/// `ETAMIL_CORS_*` variables. One a browser would refuse stops the server.
+fn server_cors(flags: &[(String, String)]) -> etamil_compiler::http::cors::Cors {
+    match etamil_compiler::http::cors::Cors::resolve(flags) {
+        Ok(cors) => cors,
+        Err(why) => {
+            eprintln!("✗ {}", why);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// The access log's level and output from `--log-level` and `--log-file`, or
+/// their `ETAMIL_*` variables. A level that is not one, or a file that cannot
+/// be opened, stops the server before it takes a request it could not log.
+fn server_logger(level: Option<String>, file: Option<String>) -> etamil_compiler::http::Logger {
+    match etamil_compiler::http::Logger::resolve(level, file) {
+        Ok(logger) => logger,
+        Err(why) => {
+            eprintln!("✗ {}", why);
+            std::process::exit(2);
+        }
+    }
+}
+
 /// Start the concurrent server: `--async`.
 ///
 /// The runtime is built here rather than around `main`, because only this path
@@ -316,17 +1193,21 @@ fn main() {
 /// drivers are the reason the VM is synchronous in the first place. See
 /// docs/ARCHITECTURE.md.
 fn run_async_server(
-    host: &str,
-    port: u16,
+    mut server: AsyncHttpServer,
     ast: Vec<parser::Stmt>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut server = AsyncHttpServer::new(host, port);
-    register_routes(
+    if let Err(why) = register_routes(
         &mut server,
         ast,
         |server, method, path, program| server.register_route(method, path, program),
         |server, seconds, program| server.register_schedule(seconds, program),
-    );
+        |server, hook, prefix, program| server.register_middleware(hook, prefix, program),
+        |server, mount| server.register_mount(mount),
+        |server, prelude| server.register_prelude(prelude),
+    ) {
+        eprintln!("✗ {}", why);
+        std::process::exit(1);
+    }
 
     // Handlers run on the blocking pool, so the worker threads here only ever
     // accept connections and move bytes.
@@ -334,44 +1215,137 @@ fn run_async_server(
         .enable_all()
         .build()?;
 
-    runtime.block_on(server.start())
+    let served = runtime.block_on(server.start());
+    // `start` has already waited --drain-timeout for the requests in hand;
+    // dropping the runtime would wait again, for ever, on a handler that
+    // never returns.
+    runtime.shutdown_background();
+    served
+}
+
+/// Watch the program at `path`, and the files it imports, and serve its
+/// routes again from `table` whenever one of them is saved: `--watch`.
+fn watch_program(table: Arc<reload::Table>, path: &Path, sources: Vec<PathBuf>) {
+    println!("👀 Watching {} file(s); saved changes are served without a restart", sources.len());
+    let path = path.to_path_buf();
+    reload::watch(table, sources, move || reload_program(&path));
+}
+
+/// Load, check and register the program at `path` again, into routes of its
+/// own: everything the server did with it at start, short of serving it.
+fn reload_program(path: &Path) -> Result<(Routes, Vec<PathBuf>), String> {
+    let (ast, sources) = module::load_file_with_sources(path)?;
+    if let Err(errors) = etamil_compiler::check::check(&ast) {
+        let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        return Err(errors.join("\n✗ "));
+    }
+    let mut routes = Routes::default();
+    register_routes(
+        &mut routes,
+        ast,
+        |routes, method, path, program| routes.register_route(method, path, program),
+        // The jobs started with the server keep running as they were.
+        |_, _, _| {},
+        |routes, hook, prefix, program| routes.register_middleware(hook, prefix, program),
+        |routes, mount| routes.register_mount(mount),
+        |routes, prelude| routes.register_prelude(prelude),
+    )?;
+    Ok((routes, sources))
 }
 
 /// Split a program into `வழி` routes and the prelude they share, and register
-/// each one.
+/// each one, with the `இடைவெளி` jobs, the `முன்` / `பின்` blocks around them
+/// and the `கோப்புறை` directories beside them.
 ///
-/// The remainder of the file — imports, functions, setup — is compiled into
-/// every handler, so a route can call what the file defines. Both servers
-/// register the same way, which is why this is written once and handed the
-/// registration function.
+/// The remainder of the file — imports, functions, setup — is the prelude. It
+/// runs once at the start of every request, in the VM the blocks and the route
+/// then share; those carry only the file's functions, so each can call what the
+/// file defines without running its setup again. Each job carries the whole
+/// prelude, having no request to share it with. Both servers register the same way, and so does `--watch`, which is why this is written
+/// once and handed the registration function. A program that cannot be served
+/// is an error: at start it stops the server, and under `--watch` the routes
+/// already being served stay.
 fn register_routes<S>(
     server: &mut S,
     ast: Vec<parser::Stmt>,
     mut register: impl FnMut(&mut S, &str, &str, Vec<parser::Stmt>),
     register_schedule: impl Fn(&mut S, u64, Vec<parser::Stmt>),
-) {
-    // Routes and timed jobs are both lifted out; what is left is the prelude
-    // they share.
+    register_middleware: impl Fn(&mut S, parser::Hook, &str, Vec<parser::Stmt>),
+    register_mount: impl Fn(&mut S, etamil_compiler::http::files::Mount),
+    register_prelude: impl Fn(&mut S, Vec<parser::Stmt>),
+) -> Result<(), String> {
+    // Routes, timed jobs, middleware and mounts are all lifted out; what is
+    // left is the prelude they share.
     let (lifted, prelude): (Vec<parser::Stmt>, Vec<parser::Stmt>) = ast
         .into_iter()
         .partition(|s| {
             matches!(
                 s,
-                parser::Stmt::DefineRoute { .. } | parser::Stmt::Schedule { .. }
+                parser::Stmt::DefineRoute { .. }
+                    | parser::Stmt::Schedule { .. }
+                    | parser::Stmt::Middleware { .. }
+                    | parser::Stmt::Mount { .. }
             )
         });
-    let (routes, schedules): (Vec<parser::Stmt>, Vec<parser::Stmt>) = lifted
+    let (routes, rest): (Vec<parser::Stmt>, Vec<parser::Stmt>) = lifted
         .into_iter()
         .partition(|s| matches!(s, parser::Stmt::DefineRoute { .. }));
+    let (mounts, rest): (Vec<parser::Stmt>, Vec<parser::Stmt>) = rest
+        .into_iter()
+        .partition(|s| matches!(s, parser::Stmt::Mount { .. }));
+    let (middleware, schedules): (Vec<parser::Stmt>, Vec<parser::Stmt>) = rest
+        .into_iter()
+        .partition(|s| matches!(s, parser::Stmt::Middleware { .. }));
+    // A function is called against the table of the code it was compiled
+    // into, so every part of a request carries the file's own.
+    let functions: Vec<parser::Stmt> = prelude
+        .iter()
+        .filter(|s| matches!(s, parser::Stmt::FunctionDef { .. }))
+        .cloned()
+        .collect();
+
+    for mount in &mounts {
+        if let parser::Stmt::Mount { prefix, dir, max_age } = mount {
+            let (parser::Expr::String(prefix), parser::Expr::String(dir)) = (prefix, dir) else {
+                return Err(format!("கோப்புறை needs a literal prefix and directory, got {:?}, {:?}", prefix, dir));
+            };
+            let max_age = match max_age {
+                None => None,
+                Some(parser::Expr::Number(n)) => rust_decimal::prelude::ToPrimitive::to_u64(n),
+                Some(other) => {
+                    return Err(format!("கோப்புறை needs a literal number of seconds, got {:?}", other));
+                }
+            };
+            // Said now rather than as a 404 on every request for it.
+            if !Path::new(dir).is_dir() {
+                return Err(format!("கோப்புறை {}: '{}' is not a directory", prefix, dir));
+            }
+            register_mount(server, etamil_compiler::http::files::Mount::new(prefix, dir, max_age));
+        }
+    }
 
-    if routes.is_empty() {
+    for block in middleware {
+        if let parser::Stmt::Middleware { hook, prefix, body } = block {
+            let prefix = match prefix {
+                parser::Expr::String(literal) => literal,
+                other => {
+                    return Err(format!("முன் and பின் need a literal path prefix, got {:?}", other));
+                }
+            };
+            let mut program = functions.clone();
+            program.extend(body);
+            register_middleware(server, hook, &prefix, program);
+        }
+    }
+
+    if routes.is_empty() && mounts.is_empty() {
         // No வழி statements: the whole program answers every request, which is
         // how server programs behaved before routing existed.
         println!("ℹ️  No வழி routes found; serving the whole program on /");
         for method in ["GET", "POST", "PUT", "DELETE"] {
             register(server, method, "/", prelude.clone());
         }
-        return;
+        return Ok(());
     }
 
     for schedule in schedules {
@@ -381,8 +1355,7 @@ fn register_routes<S>(
                     rust_decimal::prelude::ToPrimitive::to_u64(&n).unwrap_or(0)
                 }
                 other => {
-                    eprintln!("✗ இடைவெளி needs a literal number of seconds, got {:?}", other);
-                    std::process::exit(1);
+                    return Err(format!("இடைவெளி needs a literal number of seconds, got {:?}", other));
                 }
             };
             let mut program = prelude.clone();
@@ -392,18 +1365,19 @@ fn register_routes<S>(
     }
 
     for route in routes {
-        if let parser::Stmt::DefineRoute { method, path, handler } = route {
+        if let parser::Stmt::DefineRoute { method, path, handler, .. } = route {
             let path = match path {
                 parser::Expr::String(literal) => literal,
                 other => {
-                    eprintln!("✗ வழி needs a literal path, got {:?}", other);
-                    std::process::exit(1);
+                    return Err(format!("வழி needs a literal path, got {:?}", other));
                 }
             };
-            let mut program = prelude.clone();
+            let mut program = functions.clone();
             program.extend(handler);
             register(server, &method, &path, program);
         }
     }
+    register_prelude(server, prelude);
+    Ok(())
 }
 
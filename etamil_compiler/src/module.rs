@@ -13,7 +13,8 @@ use crate::lexer;
 use crate::parser::{Parser, Stmt};
 
 /// Parse one source string into statements, with lexical errors reported.
-fn parse_source(source: &str) -> Result<Vec<Stmt>, String> {
+/// Positions are stamped with `file` when the source came from one.
+fn parse_source(source: &str, file: Option<&Path>) -> Result<Vec<Stmt>, String> {
     let tokens = lexer::tokenize(source).map_err(|errors| {
         errors
             .iter()
@@ -21,7 +22,10 @@ fn parse_source(source: &str) -> Result<Vec<Stmt>, String> {
             .collect::<Vec<_>>()
             .join("\n  ")
     })?;
-    let mut parser = Parser::new(tokens.iter());
+    let mut parser = match file {
+        Some(path) => Parser::new(tokens.iter()).in_file(&display_name(path)),
+        None => Parser::new(tokens.iter()),
+    };
     // Parse errors carry a line and column now, so the message a caller sees
     // says where to look rather than only what was wrong.
     parser.parse().map_err(|error| error.to_string())
@@ -33,10 +37,20 @@ pub fn load_file(path: &Path) -> Result<Vec<Stmt>, String> {
     load_inner(path, &mut visited)
 }
 
+/// Load a program from disk, and say which files it was made from: itself
+/// and everything it imports, however deep. `--watch` looks at these.
+pub fn load_file_with_sources(path: &Path) -> Result<(Vec<Stmt>, Vec<PathBuf>), String> {
+    let mut visited = HashSet::new();
+    let statements = load_inner(path, &mut visited)?;
+    let mut sources: Vec<PathBuf> = visited.into_iter().collect();
+    sources.sort();
+    Ok((statements, sources))
+}
+
 /// Load a program held in memory. Imports resolve relative to `base_dir`.
 pub fn load_source(source: &str, base_dir: &Path) -> Result<Vec<Stmt>, String> {
     let mut visited = HashSet::new();
-    let statements = parse_source(source)?;
+    let statements = parse_source(source, None)?;
     resolve(statements, base_dir, &mut visited)
 }
 
@@ -65,7 +79,7 @@ fn load_inner(path: &Path, visited: &mut HashSet<PathBuf>) -> Result<Vec<Stmt>,
         )
     })?;
 
-    let statements = parse_source(&source)?;
+    let statements = parse_source(&source, Some(path))?;
     let base_dir = canonical
         .parent()
         .map(Path::to_path_buf)
@@ -73,11 +87,21 @@ fn load_inner(path: &Path, visited: &mut HashSet<PathBuf>) -> Result<Vec<Stmt>,
     resolve(statements, &base_dir, visited)
 }
 
+/// How a file is named in a runtime error: relative to the working directory
+/// when it is under it, which is how the author would have typed it.
+fn display_name(path: &Path) -> String {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(&cwd).ok().map(Path::to_path_buf));
+    relative.as_deref().unwrap_or(path).display().to_string()
+}
+
 /// Find an imported file: next to the importer first, then along
 /// `ETAMIL_PATH`, then in a `nUlakam` directory beside the executable. That
 /// last one is what lets `இறக்கு "nUlakam/paNam.qmz";` work from anywhere
-/// once the compiler is installed.
-fn locate(relative: &str, base_dir: &Path) -> Option<PathBuf> {
+/// once the compiler is installed. The language server follows imports the
+/// same way, so go-to-definition lands where the program would.
+pub fn locate(relative: &str, base_dir: &Path) -> Option<PathBuf> {
     let beside = base_dir.join(relative);
     if beside.exists() {
         return Some(beside);
@@ -0,0 +1,142 @@
+//! Currencies: what the symbol on a money literal means, and how an amount is
+//! written back out.
+//!
+//! An amount is a decimal and an ISO 4217 code. The code is what is stored and
+//! compared, because it is unambiguous; the symbol is only how a literal is
+//! written and how an amount is shown. `$` means US dollars here, as it does in
+//! most of the data a program written in this language will be reading.
+//!
+//! Portable, like the lexer that depends on it.
+
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// The currencies a literal can be written in, by symbol. Any other ISO code
+/// is still a currency — `பணமாக்கு(100, "CHF")` — it just has no literal.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("INR", "₹"),
+    ("USD", "$"),
+    ("EUR", "€"),
+    ("GBP", "£"),
+    ("JPY", "¥"),
+];
+
+/// The code a literal's symbol stands for.
+pub fn code_for_symbol(symbol: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(_, known)| *known == symbol)
+        .map(|(code, _)| *code)
+}
+
+/// The symbol an amount in `code` is shown with, if it has one.
+pub fn symbol_for_code(code: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, symbol)| *symbol)
+}
+
+/// A currency code as written by an author, checked and upper-cased: three
+/// Latin letters, which is every code ISO 4217 assigns.
+pub fn code(written: &str) -> Result<String, String> {
+    let code = written.trim().to_ascii_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(code)
+    } else {
+        Err(format!(
+            "'{}' ஒரு நாணயக் குறியீடு அல்ல  ('{}' is not a currency code — \
+             use three letters, such as INR or USD)",
+            written, written
+        ))
+    }
+}
+
+/// An amount for people: `₹1500.50`, `-$3.00`, `12.50 CHF`.
+///
+/// At least two decimal places, because that is how money is read, but never
+/// fewer than the amount has — a rate-derived `₹0.125` is not rounded behind
+/// the author's back. Rounding is `வட்டமிடு`'s job.
+pub fn display(amount: Decimal, currency: &str) -> String {
+    let digits = digits(amount.abs());
+    let sign = if amount.is_sign_negative() && !amount.is_zero() {
+        "-"
+    } else {
+        ""
+    };
+    match symbol_for_code(currency) {
+        Some(symbol) => format!("{}{}{}", sign, symbol, digits),
+        None => format!("{}{} {}", sign, digits, currency),
+    }
+}
+
+/// An amount as stored: `1500.50 INR`. The form a database column holds and
+/// `from_text` reads back, so a round trip keeps the currency.
+pub fn to_text(amount: Decimal, currency: &str) -> String {
+    format!("{} {}", digits(amount), currency)
+}
+
+/// Read back what `to_text` wrote.
+pub fn from_text(text: &str) -> Option<(Decimal, String)> {
+    let (number, written) = text.trim().rsplit_once(' ')?;
+    let amount = Decimal::from_str(number).ok()?;
+    let currency = code(written).ok()?;
+    // Only the exact spelling `to_text` writes: "5 inr" in some other
+    // program's free text would otherwise come back as money.
+    (currency == written).then_some((amount, currency))
+}
+
+fn digits(amount: Decimal) -> String {
+    let mut shown = amount.normalize();
+    if shown.scale() < 2 {
+        shown.rescale(2);
+    }
+    shown.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(text: &str) -> Decimal {
+        Decimal::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn an_amount_shows_at_least_two_places() {
+        assert_eq!(display(dec("1500.5"), "INR"), "₹1500.50");
+        assert_eq!(display(dec("3"), "USD"), "$3.00");
+        assert_eq!(display(dec("0.125"), "INR"), "₹0.125");
+    }
+
+    #[test]
+    fn the_sign_goes_before_the_symbol() {
+        assert_eq!(display(dec("-3"), "GBP"), "-£3.00");
+    }
+
+    #[test]
+    fn a_currency_without_a_symbol_is_shown_by_code() {
+        assert_eq!(display(dec("12.5"), "CHF"), "12.50 CHF");
+    }
+
+    #[test]
+    fn stored_text_round_trips() {
+        let text = to_text(dec("-1500.50"), "INR");
+        assert_eq!(text, "-1500.50 INR");
+        assert_eq!(from_text(&text), Some((dec("-1500.50"), "INR".to_string())));
+    }
+
+    #[test]
+    fn only_the_stored_spelling_reads_back_as_money() {
+        assert_eq!(from_text("5 inr"), None);
+        assert_eq!(from_text("5 rupees"), None);
+        assert_eq!(from_text("five INR"), None);
+    }
+
+    #[test]
+    fn a_code_is_three_letters() {
+        assert_eq!(code("usd"), Ok("USD".to_string()));
+        assert!(code("US").is_err());
+        assert!(code("₹").is_err());
+    }
+}
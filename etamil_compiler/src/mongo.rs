@@ -52,6 +52,15 @@ pub fn to_bson(value: &Value) -> Bson {
                 decimal_to_bson(number)
             }
         }
+        // `{amount, currency}`, which is how a document schema would say it
+        // anyway, and which `from_bson` reads back as an amount. The amount is
+        // always a Decimal128 — ₹1500 is a price, not a count.
+        Value::Money { amount, currency } => {
+            let mut document = Document::new();
+            document.insert("amount", decimal_to_bson(amount));
+            document.insert("currency", currency.clone());
+            Bson::Document(document)
+        }
         Value::String(text) => Bson::String(text.clone()),
         Value::Boolean(flag) => Bson::Boolean(*flag),
         Value::Array(items) => Bson::Array(items.iter().map(to_bson).collect()),
@@ -67,6 +76,8 @@ pub fn to_bson(value: &Value) -> Bson {
         Value::Ok(inner) => to_bson(inner),
         Value::Err(inner) => to_bson(inner),
         Value::Null => Bson::Null,
+        // Code is not data: a function stored would come back as its name.
+        Value::Function(_) => Bson::String(value.to_string()),
     }
 }
 
@@ -100,6 +111,9 @@ pub fn from_bson(value: &Bson) -> Value {
         Bson::Boolean(flag) => Value::Boolean(*flag),
         Bson::Array(items) => Value::Array(items.iter().map(from_bson).collect()),
         Bson::Document(document) => {
+            if let Some(money) = money_from(document) {
+                return money;
+            }
             let mut fields = std::collections::HashMap::new();
             for (key, held) in document {
                 fields.insert(key.clone(), from_bson(held));
@@ -117,6 +131,22 @@ pub fn from_bson(value: &Bson) -> Value {
     }
 }
 
+/// A document that is exactly `{amount, currency}` — what `to_bson` writes for
+/// an amount — as that amount. Anything with more fields is a record that
+/// happens to have these two, and stays one.
+fn money_from(document: &Document) -> Option<Value> {
+    if document.len() != 2 {
+        return None;
+    }
+    let amount = match from_bson(document.get("amount")?) {
+        Value::Number(amount) => amount,
+        _ => return None,
+    };
+    let written = document.get_str("currency").ok()?;
+    let currency = crate::money::code(written).ok()?;
+    (currency == written).then_some(Value::Money { amount, currency })
+}
+
 /// The document a record describes, or an explanation.
 pub fn to_document(value: &Value) -> Result<Document, String> {
     match to_bson(value) {
@@ -127,6 +157,7 @@ pub fn to_document(value: &Value) -> Result<Document, String> {
                 Value::Array(_) => "an array",
                 Value::String(_) => "a string",
                 Value::Number(_) => "a number",
+                Value::Money { .. } => "an amount",
                 _ => "something else",
             }
         )),
@@ -324,6 +355,34 @@ mod tests {
         assert_eq!(from_bson(&to_bson(&value)), value);
     }
 
+    #[test]
+    fn an_amount_keeps_its_currency() {
+        let amount = Value::Money {
+            amount: Decimal::from_str("1500.50").unwrap(),
+            currency: "INR".to_string(),
+        };
+        let stored = to_bson(&amount);
+
+        match &stored {
+            Bson::Document(document) => {
+                assert_eq!(document.get_str("currency").unwrap(), "INR")
+            }
+            other => panic!("expected a document, got {:?}", other),
+        }
+        assert_eq!(from_bson(&stored), amount);
+    }
+
+    #[test]
+    fn a_record_with_more_than_an_amount_stays_a_record() {
+        let value = record(&[
+            ("amount", decimal("5")),
+            ("currency", Value::String("INR".into())),
+            ("memo", Value::String("rent".into())),
+        ]);
+
+        assert_eq!(from_bson(&to_bson(&value)), value);
+    }
+
     #[test]
     fn nil_stays_nil() {
         // A field that is absent must not come back as an empty string, for the
@@ -25,9 +25,12 @@
 use std::sync::Arc;
 use std::sync::OnceLock;
 
-use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use rustls::{ClientConfig, RootCertStore};
 
+// Shared with the servers, which read the same PEM shapes for their own
+// certificate.
+use crate::http::tls::{read_certificates, read_private_key};
+
 /// The configuration is built once. Parsing a certificate chain per request
 /// would be wasted work on every call, and a key read repeatedly is a key
 /// exposed repeatedly.
@@ -134,29 +137,6 @@ fn assemble(
     }
 }
 
-fn read_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
-    let pem = std::fs::read(path).map_err(|e| format!("'{}' could not be read: {}", path, e))?;
-    rustls_pemfile::certs(&mut pem.as_slice())
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("'{}' is not a PEM certificate: {}", path, e))
-}
-
-/// A private key in whichever of the three PEM shapes it was written in.
-///
-/// Which one you get depends on what produced it, and an operator handed a key
-/// by their bank should not have to know or convert it.
-fn read_private_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
-    let pem = std::fs::read(path).map_err(|e| format!("'{}' could not be read: {}", path, e))?;
-    rustls_pemfile::private_key(&mut pem.as_slice())
-        .map_err(|e| format!("'{}' is not a PEM private key: {}", path, e))?
-        .ok_or_else(|| {
-            format!(
-                "'{}' holds no private key  (expected PKCS#8, SEC1 or PKCS#1 PEM)",
-                path
-            )
-        })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,7 @@ use crate::lexer::{Spanned, Token};
 use rust_decimal::Decimal;
 use std::iter::Peekable;
 use std::slice::Iter;
+use std::sync::Arc;
 
 /// A parse error, carrying the position of the token that caused it.
 ///
@@ -50,10 +51,31 @@ impl std::fmt::Display for ParseError {
 /// Where something was written, carried on the AST nodes that can be rejected
 /// after parsing, so a type error points at a place rather than only naming a
 /// variable.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The statements that can fail at runtime carry one too: the bytecode
+/// compiler turns them into a source map, which is how a runtime error says
+/// where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Position {
     pub line: usize,
     pub column: usize,
+    /// The file, when the source came from one. Imports are spliced into a
+    /// single program, so a line number alone no longer says which file it is
+    /// a line of.
+    pub file: Option<Arc<str>>,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}, ", file)?;
+        }
+        write!(
+            f,
+            "வரி {}, நெடுவரிசை {}  (line {}, column {})",
+            self.line, self.column, self.line, self.column
+        )
+    }
 }
 
 /// A type written in the source: `எண் வருவாய் = 100000;`
@@ -73,6 +95,9 @@ pub enum DeclaredType {
     Array,
     Record,
     Date,
+    /// An amount with a currency. Distinct from Number so that an account
+    /// balance cannot quietly become a plain count, or a count a balance.
+    Money,
     /// No constraint: used where a type keyword exists that the checker has
     /// nothing to say about yet.
     Any,
@@ -88,14 +113,41 @@ impl DeclaredType {
             DeclaredType::Array => "அணி (aNi, an array)",
             DeclaredType::Record => "பொருள் (poruL, a record)",
             DeclaredType::Date => "தேதி (qEqi, a date)",
+            DeclaredType::Money => "ரொக்கம் (rokkam, an amount of money)",
             DeclaredType::Any => "any type",
         }
     }
+
+    /// The type a keyword names, in either spelling — `எண்` or `eN` — or
+    /// `None` if the word is not a type keyword.
+    pub fn from_keyword(word: &str) -> Option<DeclaredType> {
+        match crate::lexer::tokenize(word).ok()?.as_slice() {
+            [only] if Parser::is_type_token(&only.token) => Some(Parser::type_of(&only.token)),
+            _ => None,
+        }
+    }
+
+    /// Only the keyword, as it is written in a signature: `எண்`.
+    pub fn keyword(&self) -> &'static str {
+        self.name().split(' ').next().unwrap_or_default()
+    }
+}
+
+/// Which side of a route a முன் / பின் block runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    Before,
+    After,
 }
 
 #[derive(Debug, Clone)]
 pub enum Expr {
     Number(Decimal),
+    /// `₹1500.50`: an amount and the ISO code of its currency.
+    Money {
+        amount: Decimal,
+        currency: String,
+    },
     String(String),
     Boolean(bool),
     Null,
@@ -126,6 +178,9 @@ pub enum Expr {
     Call {
         name: String,
         args: Vec<Expr>,
+        /// Where the name was written. A builtin that fails, and the frames
+        /// of a call stack, are reported here.
+        at: Position,
     },
     // அணி — a column: [a, b, c]
     ArrayLiteral(Vec<Expr>),
@@ -143,6 +198,14 @@ pub enum Expr {
     },
     // expr? — unwrap a சரி, or return the தவறு to the caller
     Try(Box<Expr>),
+    // ceyal(params) { body } — an anonymous function, as a value
+    Function {
+        params: Vec<String>,
+        declared: Vec<Option<DeclaredType>>,
+        returns: Option<DeclaredType>,
+        body: Vec<Stmt>,
+        at: Position,
+    },
 }
 
 #[allow(dead_code)]
@@ -157,90 +220,123 @@ pub enum Stmt {
         at: Position,
     },
     // ceyal name(params) { body }
+    // ceyal name(eN a, col b) eN { body } — with a signature
     FunctionDef {
         name: String,
         params: Vec<String>,
+        /// The type written before each parameter, one entry per parameter.
+        declared: Vec<Option<DeclaredType>>,
+        /// The type written after the parameter list, if any.
+        returns: Option<DeclaredType>,
         body: Vec<Stmt>,
+        at: Position,
     },
     // qirumpu value;
-    Return(Option<Expr>),
+    Return {
+        value: Option<Expr>,
+        at: Position,
+    },
     // a[i] = value;  — the base must be a plain variable for now
     SetIndex {
         name: String,
         index: Expr,
         value: Expr,
+        at: Position,
     },
     // r.peyar = value;
     SetField {
         name: String,
         field: String,
         value: Expr,
+        at: Position,
     },
     // A bare expression evaluated for its effect, e.g. a call statement.
-    Expression(Expr),
+    Expression {
+        value: Expr,
+        at: Position,
+    },
     // iRakku "path.qmz"; — resolved before compilation, see module.rs
     Import(String),
-    Print(Expr),
-    Input(Expr),
+    Print {
+        value: Expr,
+        at: Position,
+    },
+    Input {
+        value: Expr,
+        at: Position,
+    },
     // (cond) eZil { then } iZREl { else }
     If {
         condition: Expr,
         then_branch: Vec<Stmt>,
         else_branch: Option<Vec<Stmt>>,
+        at: Position,
     },
     // (cond) cuRRu { body }
     Loop {
         condition: Expr,
         body: Vec<Stmt>,
+        at: Position,
     },
     // ovvoru item il collection { body }
     ForEach {
         var: String,
         collection: Expr,
         body: Vec<Stmt>,
+        at: Position,
     },
     // File I/O Operations
     FileOpen {
         filename: Expr,
         mode: String, // "read", "write", "append"
+        at: Position,
     },
     FileClose {
         filename: Expr,
+        at: Position,
     },
     FileWrite {
         filename: Expr,
         data: Expr,
+        at: Position,
     },
     FileRead {
         filename: Expr,
         variable: String,
+        at: Position,
     },
     ReadCSV {
         filename: Expr,
         variable: String,
+        at: Position,
     },
     WriteCSV {
         filename: Expr,
         data: Expr,
+        at: Position,
     },
     // Database Operations
     DBConnect {
         db_type: String,
         connection_string: Expr,
+        at: Position,
     },
     DBDisconnect {
         db_type: String,
+        at: Position,
     },
     // தளம்_வினா "sql", [params], result;
     DBQuery {
         query: Expr,
         params: Expr,
         result_var: String,
+        at: Position,
     },
     // தளம்_செய் "sql", [params];
     DBExecute {
         command: Expr,
         params: Expr,
+        at: Position,
     },
     DBInsert {
         table: String,
@@ -268,6 +364,9 @@ pub enum Stmt {
     DefineRoute {
         method: String,      // GET, POST, PUT, DELETE, etc.
         path: Expr,          // "/api/users"
+        // வழி பதி, "/ANY", {ஏற்பது: {...}, விடைகள்: {...}} { … } — what the
+        // route takes and answers, for `etamil openapi`. Never run.
+        spec: Option<Expr>,
         handler: Vec<Stmt>,  // Handler code block
     },
     StartServer {
@@ -280,6 +379,21 @@ pub enum Stmt {
         seconds: Expr,
         body: Vec<Stmt>,
     },
+    // முன் "/api" { … } and பின் "/api" { … } — run around every route whose
+    // path is under the prefix. Without a prefix, around every route.
+    Middleware {
+        hook: Hook,
+        prefix: Expr,
+        body: Vec<Stmt>,
+    },
+    // கோப்புறை "/assets", "dist";  or with a max-age in seconds:
+    // கோப்புறை "/assets", "dist", 86400; — serve the directory's files
+    // under the prefix. Lifted out at startup like வழி.
+    Mount {
+        prefix: Expr,
+        dir: Expr,
+        max_age: Option<Expr>,
+    },
     StopServer,
     // பதில் 200, உடல்;  or with headers as an ordinary record:
     // பதில் 200, உடல், {"Content-Type": "text/html"};
@@ -287,6 +401,7 @@ pub enum Stmt {
         status_code: Expr,
         body: Expr,
         headers: Option<Expr>,
+        at: Position,
     },
     SendJSON {
         data: Expr,
@@ -316,6 +431,8 @@ pub struct Parser<'a> {
     /// Where the last consumed token was, so an unexpected end of input can
     /// still be reported somewhere the author recognizes.
     last: (usize, usize),
+    /// The file being parsed, stamped on every position.
+    file: Option<Arc<str>>,
 }
 
 impl<'a> Parser<'a> {
@@ -323,9 +440,16 @@ impl<'a> Parser<'a> {
         Parser {
             tokens: tokens.peekable(),
             last: (1, 1),
+            file: None,
         }
     }
 
+    /// Name the file these tokens came from, so positions can say which one.
+    pub fn in_file(mut self, file: &str) -> Self {
+        self.file = Some(Arc::from(file));
+        self
+    }
+
     /// Entry point: parse the whole token stream into statements.
     pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
         let mut statements = Vec::new();
@@ -375,6 +499,14 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn position(&self, spanned: &Spanned) -> Position {
+        Position {
+            line: spanned.line,
+            column: spanned.column,
+            file: self.file.clone(),
+        }
+    }
+
     fn at_end(&self, expected: &str) -> ParseError {
         ParseError {
             line: self.last.0,
@@ -494,6 +626,30 @@ impl<'a> Parser<'a> {
 
     // --- Statements --------------------------------------------------------
 
+    /// `(எண் அ, ஆ) எண்` after `செயல்` or a function's name: the parameters,
+    /// the type written before each, and the return type.
+    #[allow(clippy::type_complexity)]
+    fn parse_signature(
+        &mut self,
+    ) -> Result<(Vec<String>, Vec<Option<DeclaredType>>, Option<DeclaredType>), ParseError> {
+        self.expect(Token::LParen)?;
+        let mut params = Vec::new();
+        let mut declared = Vec::new();
+        if !self.matches(Token::RParen) {
+            loop {
+                // A parameter is declared the way a variable is: the type
+                // first, then the name — `எண் வருவாய்`.
+                declared.push(self.take_type());
+                params.push(self.take_name("a parameter name")?);
+                if !self.matches(Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(Token::RParen)?;
+        }
+        Ok((params, declared, self.take_type()))
+    }
+
     fn parse_statement(&mut self) -> Result<Stmt, ParseError> {
         let first = self.take("a statement")?;
 
@@ -506,18 +662,16 @@ impl<'a> Parser<'a> {
             (None, first)
         };
 
+        let at = self.position(current);
+
         if Self::is_identifier_like(&current.token) && !Self::is_type_token(&current.token) {
             let name = self.name_of(current);
-            let at = Position {
-                line: current.line,
-                column: current.column,
-            };
 
             // A call used as a statement, e.g. `paqivu_ceyal(x);`
             if self.peek_token() == Some(&Token::LParen) {
-                let call = self.finish_name_or_call(name)?;
+                let call = self.finish_name_or_call(name, at.clone())?;
                 self.expect(Token::Semicolon)?;
-                return Ok(Stmt::Expression(call));
+                return Ok(Stmt::Expression { value: call, at });
             }
 
             // a[i] = value;
@@ -527,7 +681,7 @@ impl<'a> Parser<'a> {
                 self.expect(Token::Assign)?;
                 let value = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                return Ok(Stmt::SetIndex { name, index, value });
+                return Ok(Stmt::SetIndex { name, index, value, at });
             }
 
             // r.field = value;
@@ -536,7 +690,7 @@ impl<'a> Parser<'a> {
                 self.expect(Token::Assign)?;
                 let value = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                return Ok(Stmt::SetField { name, field, value });
+                return Ok(Stmt::SetField { name, field, value, at });
             }
 
             // A declaration with no initializer.
@@ -558,20 +712,10 @@ impl<'a> Parser<'a> {
         match &current.token {
             Token::Function => {
                 let name = self.take_name("a function name")?;
-                self.expect(Token::LParen)?;
-                let mut params = Vec::new();
-                if !self.matches(Token::RParen) {
-                    loop {
-                        params.push(self.take_name("a parameter name")?);
-                        if !self.matches(Token::Comma) {
-                            break;
-                        }
-                    }
-                    self.expect(Token::RParen)?;
-                }
+                let (params, declared, returns) = self.parse_signature()?;
                 self.expect(Token::LBrace)?;
                 let body = self.parse_block()?;
-                Ok(Stmt::FunctionDef { name, params, body })
+                Ok(Stmt::FunctionDef { name, params, declared, returns, body, at })
             }
             Token::Import => {
                 let path = self.parse_expression()?;
@@ -587,26 +731,26 @@ impl<'a> Parser<'a> {
                 let collection = self.parse_expression()?;
                 self.expect(Token::LBrace)?;
                 let body = self.parse_block()?;
-                Ok(Stmt::ForEach { var, collection, body })
+                Ok(Stmt::ForEach { var, collection, body, at })
             }
             Token::Return => {
                 if self.matches(Token::Semicolon) {
-                    Ok(Stmt::Return(None))
+                    Ok(Stmt::Return { value: None, at })
                 } else {
                     let value = self.parse_expression()?;
                     self.expect(Token::Semicolon)?;
-                    Ok(Stmt::Return(Some(value)))
+                    Ok(Stmt::Return { value: Some(value), at })
                 }
             }
             Token::Print => {
                 let value = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::Print(value))
+                Ok(Stmt::Print { value, at })
             }
             Token::Input => {
                 let value = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::Input(value))
+                Ok(Stmt::Input { value, at })
             }
             Token::FileOpen => {
                 let filename = self.parse_expression()?;
@@ -617,40 +761,40 @@ impl<'a> Parser<'a> {
                     "read".to_string()
                 };
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::FileOpen { filename, mode })
+                Ok(Stmt::FileOpen { filename, mode, at })
             }
             Token::FileClose => {
                 let filename = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::FileClose { filename })
+                Ok(Stmt::FileClose { filename, at })
             }
             Token::FileWrite => {
                 let filename = self.parse_expression()?;
                 self.expect(Token::Comma)?;
                 let data = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::FileWrite { filename, data })
+                Ok(Stmt::FileWrite { filename, data, at })
             }
             Token::FileRead => {
                 let filename = self.parse_expression()?;
                 self.expect(Token::Comma)?;
                 let variable = self.take_name("a variable to read into")?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::FileRead { filename, variable })
+                Ok(Stmt::FileRead { filename, variable, at })
             }
             Token::ReadCSV => {
                 let filename = self.parse_expression()?;
                 self.expect(Token::Comma)?;
                 let variable = self.take_name("a variable to read into")?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::ReadCSV { filename, variable })
+                Ok(Stmt::ReadCSV { filename, variable, at })
             }
             Token::WriteCSV => {
                 let filename = self.parse_expression()?;
                 self.expect(Token::Comma)?;
                 let data = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::WriteCSV { filename, data })
+                Ok(Stmt::WriteCSV { filename, data, at })
             }
             Token::DBConnect => {
                 // The database type is the host's name for a backend, not a
@@ -659,12 +803,12 @@ impl<'a> Parser<'a> {
                 self.expect(Token::Comma)?;
                 let connection_string = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::DBConnect { db_type, connection_string })
+                Ok(Stmt::DBConnect { db_type, connection_string, at })
             }
             Token::DBDisconnect => {
                 let db_type = Self::token_name(&self.take("a database type")?.token);
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::DBDisconnect { db_type })
+                Ok(Stmt::DBDisconnect { db_type, at })
             }
             Token::DBQuery => {
                 let query = self.parse_expression()?;
@@ -673,14 +817,14 @@ impl<'a> Parser<'a> {
                 self.expect(Token::Comma)?;
                 let result_var = self.take_name("a variable to hold the rows")?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::DBQuery { query, params, result_var })
+                Ok(Stmt::DBQuery { query, params, result_var, at })
             }
             Token::DBExecute => {
                 let command = self.parse_expression()?;
                 self.expect(Token::Comma)?;
                 let params = self.parse_expression()?;
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::DBExecute { command, params })
+                Ok(Stmt::DBExecute { command, params, at })
             }
             Token::DBInsert => {
                 let table = self.take_name("a table name")?;
@@ -740,9 +884,37 @@ impl<'a> Parser<'a> {
                 let method = Self::token_name(&self.take("an HTTP method")?.token);
                 self.expect(Token::Comma)?;
                 let path = self.parse_expression()?;
+                let spec = if self.matches(Token::Comma) {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
                 self.expect(Token::LBrace)?;
                 let handler = self.parse_block()?;
-                Ok(Stmt::DefineRoute { method, path, handler })
+                Ok(Stmt::DefineRoute { method, path, spec, handler })
+            }
+            Token::Before | Token::After => {
+                let hook = if matches!(current.token, Token::Before) { Hook::Before } else { Hook::After };
+                let prefix = if self.peek_token() == Some(&Token::LBrace) {
+                    Expr::String("/".to_string())
+                } else {
+                    self.parse_expression()?
+                };
+                self.expect(Token::LBrace)?;
+                let body = self.parse_block()?;
+                Ok(Stmt::Middleware { hook, prefix, body })
+            }
+            Token::Mount => {
+                let prefix = self.parse_expression()?;
+                self.expect(Token::Comma)?;
+                let dir = self.parse_expression()?;
+                let max_age = if self.matches(Token::Comma) {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                self.expect(Token::Semicolon)?;
+                Ok(Stmt::Mount { prefix, dir, max_age })
             }
             Token::Every => {
                 let seconds = self.parse_expression()?;
@@ -771,7 +943,7 @@ impl<'a> Parser<'a> {
                     None
                 };
                 self.expect(Token::Semicolon)?;
-                Ok(Stmt::SendResponse { status_code, body, headers })
+                Ok(Stmt::SendResponse { status_code, body, headers, at })
             }
             Token::JSONBody => {
                 let data = self.parse_expression()?;
@@ -789,8 +961,8 @@ impl<'a> Parser<'a> {
 
                 let keyword = self.take("எனில் (eZil) or சுற்று (cuRRu) after a condition")?;
                 match keyword.token {
-                    Token::If => self.parse_if_remainder(condition),
-                    Token::Loop => self.parse_loop_remainder(condition),
+                    Token::If => self.parse_if_remainder(condition, at),
+                    Token::Loop => self.parse_loop_remainder(condition, at),
                     _ => Err(self.mismatch(
                         keyword,
                         "எனில் (eZil) or சுற்று (cuRRu) after a condition",
@@ -815,7 +987,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_if_remainder(&mut self, condition: Expr) -> Result<Stmt, ParseError> {
+    fn parse_if_remainder(&mut self, condition: Expr, at: Position) -> Result<Stmt, ParseError> {
         self.expect(Token::LBrace)?;
         let then_branch = self.parse_block()?;
 
@@ -826,13 +998,13 @@ impl<'a> Parser<'a> {
             None
         };
 
-        Ok(Stmt::If { condition, then_branch, else_branch })
+        Ok(Stmt::If { condition, then_branch, else_branch, at })
     }
 
-    fn parse_loop_remainder(&mut self, condition: Expr) -> Result<Stmt, ParseError> {
+    fn parse_loop_remainder(&mut self, condition: Expr, at: Position) -> Result<Stmt, ParseError> {
         self.expect(Token::LBrace)?;
         let body = self.parse_block()?;
-        Ok(Stmt::Loop { condition, body })
+        Ok(Stmt::Loop { condition, body, at })
     }
 
     // --- Expressions -------------------------------------------------------
@@ -1021,6 +1193,10 @@ impl<'a> Parser<'a> {
             }
             Token::Number(n) => Ok(Expr::Number(*n)),
             Token::Percentage(n) => Ok(Expr::Number(*n)),
+            Token::Money((amount, currency)) => Ok(Expr::Money {
+                amount: *amount,
+                currency: currency.clone(),
+            }),
             Token::String(s) => Ok(Expr::String(s.clone())),
             Token::True => Ok(Expr::Boolean(true)),
             Token::False => Ok(Expr::Boolean(false)),
@@ -1030,17 +1206,26 @@ impl<'a> Parser<'a> {
                 self.expect(Token::RParen)?;
                 Ok(expr)
             }
+            // செயல்(அ) { ... } — a function with no name, as a value.
+            Token::Function => {
+                let at = self.position(spanned);
+                let (params, declared, returns) = self.parse_signature()?;
+                self.expect(Token::LBrace)?;
+                let body = self.parse_block()?;
+                Ok(Expr::Function { params, declared, returns, body, at })
+            }
             // An identifier, or a financial keyword used as a name.
             token if Self::is_identifier_like(token) && !Self::is_type_token(token) => {
                 let name = self.name_of(spanned);
-                self.finish_name_or_call(name)
+                let at = self.position(spanned);
+                self.finish_name_or_call(name, at)
             }
             _ => Err(self.mismatch(spanned, "a value")),
         }
     }
 
     /// A name already consumed: a call if `(` follows, otherwise a variable.
-    fn finish_name_or_call(&mut self, name: String) -> Result<Expr, ParseError> {
+    fn finish_name_or_call(&mut self, name: String, at: Position) -> Result<Expr, ParseError> {
         if !self.matches(Token::LParen) {
             return Ok(Expr::Variable(name));
         }
@@ -1055,14 +1240,27 @@ impl<'a> Parser<'a> {
             }
             self.expect(Token::RParen)?;
         }
-        Ok(Expr::Call { name, args })
+        Ok(Expr::Call { name, args, at })
     }
 
     // --- Token classification ---------------------------------------------
 
-    fn is_identifier_like(token: &Token) -> bool {
+    /// Consume a type keyword if one is next.
+    fn take_type(&mut self) -> Option<DeclaredType> {
+        let token = self.peek_token()?;
+        if !Self::is_type_token(token) {
+            return None;
+        }
+        let declared = Self::type_of(token);
+        self.advance();
+        Some(declared)
+    }
+
+    pub(crate) fn is_identifier_like(token: &Token) -> bool {
         match token {
-            Token::Number(_) | Token::Percentage(_) | Token::String(_) => false,
+            Token::Number(_) | Token::Percentage(_) | Token::Money(_) | Token::String(_) => {
+                false
+            }
             Token::If | Token::Else | Token::Loop | Token::Print | Token::Input => false,
             Token::And | Token::Or | Token::Not => false,
             Token::True | Token::False | Token::Null => false,
@@ -1083,7 +1281,7 @@ impl<'a> Parser<'a> {
             // Database types
             Token::SQL | Token::NoSQL | Token::SQLite | Token::MySQL | Token::PostgreSQL | Token::MongoDB | Token::Redis | Token::JSONdb => false,
             // REST API operations
-            Token::Route | Token::Every | Token::StartServer | Token::StopServer | Token::Response | Token::JSONBody => false,
+            Token::Route | Token::Every | Token::Before | Token::After | Token::Mount | Token::StartServer | Token::StopServer | Token::Response | Token::JSONBody => false,
             Token::Request | Token::Endpoint | Token::API | Token::Header | Token::Body | Token::Param | Token::QueryParam | Token::PathParam => false,
             Token::URL | Token::Host | Token::Port | Token::Method | Token::StatusCode | Token::StatusMessage | Token::Auth | Token::BearerToken | Token::ContentType | Token::Serve => false,
             Token::HttpGet | Token::HttpPost | Token::HttpPut | Token::HttpDelete | Token::HttpPatch | Token::HttpOptions | Token::HttpHead => false,
@@ -1098,7 +1296,14 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn is_type_token(token: &Token) -> bool {
+    /// A token that stands for a name wherever it appears: an identifier, or a
+    /// keyword the parser accepts as one. Its text is the name, so a tool that
+    /// rewrites keywords must leave it alone.
+    pub(crate) fn is_name(token: &Token) -> bool {
+        Self::is_identifier_like(token) && !Self::is_type_token(token)
+    }
+
+    pub(crate) fn is_type_token(token: &Token) -> bool {
         matches!(
             token,
             Token::IntegerType
@@ -1110,6 +1315,7 @@ impl<'a> Parser<'a> {
                 | Token::DataType
                 | Token::ObjectType
                 | Token::DateType
+                | Token::MoneyType
         )
     }
 
@@ -1122,6 +1328,7 @@ impl<'a> Parser<'a> {
             Token::ArrayType => DeclaredType::Array,
             Token::ObjectType | Token::DataType => DeclaredType::Record,
             Token::DateType => DeclaredType::Date,
+            Token::MoneyType => DeclaredType::Money,
             // is_type_token gates every caller, so this is unreachable in
             // practice; treated as unconstrained rather than panicking.
             _ => DeclaredType::Any,
@@ -1133,6 +1340,7 @@ impl<'a> Parser<'a> {
             Expr::String(s) => s,
             Expr::Variable(name) => name,
             Expr::Number(n) => n.to_string(),
+            Expr::Money { amount, currency } => crate::money::display(amount, &currency),
             Expr::Boolean(b) => b.to_string(),
             Expr::Null => "nil".to_string(),
             Expr::BinaryOp { op, .. } => op,
@@ -1146,6 +1354,7 @@ impl<'a> Parser<'a> {
             Expr::Field { name, .. } => name,
             Expr::Try(_) => "try".to_string(),
             Expr::Concat { .. } => "concat".to_string(),
+            Expr::Function { .. } => "function".to_string(),
         }
     }
 }
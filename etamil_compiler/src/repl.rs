@@ -15,7 +15,7 @@
 use std::io::{self, BufRead, Write};
 use std::path::Path;
 
-use crate::parser::Stmt;
+use crate::parser::{Expr, Stmt};
 use crate::vm::{BytecodeCompiler, VM};
 
 const BANNER: &str = "eTamil — வணக்கம். :help for help, :quit to leave.";
@@ -140,8 +140,13 @@ fn evaluate(vm: &mut VM, definitions: &mut Vec<Stmt>, source: &str) {
 
     // A bare expression is a question, so answer it. Anything else is an
     // instruction, and instructions are carried out quietly.
-    let statements: Vec<Stmt> = if let [Stmt::Expression(expr)] = parsed.as_slice() {
-        vec![Stmt::Print(expr.clone())]
+    let statements: Vec<Stmt> = if let [Stmt::Expression { value: call @ Expr::Call { .. }, at }] =
+        parsed.as_slice()
+    {
+        vec![Stmt::Print {
+            value: call.clone(),
+            at: at.clone(),
+        }]
     } else {
         parsed.clone()
     };
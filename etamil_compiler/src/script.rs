@@ -0,0 +1,263 @@
+//! The spellings of every keyword, and the script each one is written in.
+//!
+//! Every keyword has a Tamil spelling and a romanized one, and most have an
+//! `_english` one as well. Which is which is read out of `lexer.rs` itself,
+//! by the same rule `scripts/generate_editor_support.py` uses for the VS Code
+//! extension: the lexer is the only list, so nothing here can fall out of step
+//! with it. The language server completes from this table; the formatter and
+//! `etamil convert` rewrite keywords into one script with it.
+//!
+//! Portable: the source is read at compile time.
+
+use std::sync::OnceLock;
+
+use crate::lexer;
+use crate::parser::Parser;
+
+/// One of the three ways a keyword can be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    /// `செயல்`
+    Tamil,
+    /// `ceyal`, in the transliteration scheme of `scripts/transliterate.py`.
+    Roman,
+    /// `_fn`. Not every keyword has one — the financial vocabulary does
+    /// not — and those are written in roman instead, the other spelling an
+    /// English keyboard can type.
+    English,
+}
+
+impl Script {
+    /// The script a command line names: `tamil`, `roman` or `english`.
+    pub fn from_name(name: &str) -> Result<Script, String> {
+        match name {
+            "tamil" | "தமிழ்" => Ok(Script::Tamil),
+            "roman" => Ok(Script::Roman),
+            "english" => Ok(Script::English),
+            other => Err(format!(
+                "'{}' ஒரு எழுத்துமுறை அல்ல  ('{}' is not a script — use tamil, roman or english)",
+                other, other
+            )),
+        }
+    }
+}
+
+/// A keyword the lexer accepts, with all of its spellings.
+pub struct Keyword {
+    /// The `Token` variant, as named in `lexer.rs`.
+    pub token: String,
+    /// The `// --- Section ---` heading it is declared under.
+    pub section: String,
+    /// Every spelling, in the order the lexer lists them: Tamil, the current
+    /// romanization, the English alias, then any outdated romanization.
+    pub forms: Vec<String>,
+}
+
+impl Keyword {
+    /// How this keyword is written in `script`.
+    pub fn spelling(&self, script: Script) -> &str {
+        let tamil = || self.forms.iter().find(|f| f.chars().any(is_tamil));
+        let roman = || {
+            self.forms
+                .iter()
+                .find(|f| f.is_ascii() && !f.starts_with('_'))
+        };
+        let found = match script {
+            Script::Tamil => tamil(),
+            Script::Roman => roman(),
+            Script::English => self.forms.iter().find(|f| f.starts_with('_')).or_else(roman),
+        };
+        found.unwrap_or(&self.forms[0])
+    }
+
+    /// Whether `form` is a romanization from before the current scheme, which
+    /// the lexer still accepts so that existing programs keep working.
+    pub fn is_outdated(&self, form: &str) -> bool {
+        let romanized = |f: &str| f.is_ascii() && !f.starts_with('_');
+        romanized(form) && self.spelling(Script::Roman) != form
+    }
+}
+
+/// Every keyword the lexer accepts, under the section comment it is declared
+/// in.
+pub fn keywords() -> &'static [Keyword] {
+    static TABLE: OnceLock<Vec<Keyword>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = Vec::new();
+        let mut section = String::new();
+        for line in include_str!("lexer.rs").lines() {
+            let line = line.trim();
+            if let Some(heading) = line.strip_prefix("// ---") {
+                // "Control Flow (Your Updated Syntax) ---" -> "Control Flow"
+                let heading = heading.trim_end_matches('-').trim();
+                section = heading.split(" (").next().unwrap_or(heading).to_string();
+                continue;
+            }
+            let Some(rest) = line.strip_prefix("#[regex(\"") else {
+                continue;
+            };
+            let Some((pattern, after)) = rest.split_once("\")]") else {
+                continue;
+            };
+            // Literals and identifiers are patterns, not words.
+            let forms: Vec<String> = pattern.split('|').map(str::to_string).collect();
+            if forms.iter().any(|form| form.is_empty() || !form.chars().all(is_name_char)) {
+                continue;
+            }
+            let token = after.trim().trim_end_matches(',').to_string();
+            table.push(Keyword { token, section: section.clone(), forms });
+        }
+        table
+    })
+}
+
+/// The keyword `written` is a spelling of, if it is one.
+pub fn keyword(written: &str) -> Option<&'static Keyword> {
+    keywords().iter().find(|k| k.forms.iter().any(|f| f == written))
+}
+
+/// What `convert` did to a program.
+pub struct Conversion {
+    pub text: String,
+    /// Names the author wrote in an outdated spelling, which `--migrate`
+    /// renamed: the old spelling and the new, once each, in the order they
+    /// first appear. A name can be seen from outside the file — as a record's
+    /// field, a database column, a function another file calls — so the
+    /// author is told rather than left to find out.
+    pub renamed: Vec<(String, String)>,
+}
+
+/// Rewrite a program token by token: every keyword into `to`, if one is
+/// given, and with `migrate`, every outdated romanization into the current
+/// one.
+///
+/// Nothing else changes. The text between tokens — spacing, line breaks,
+/// comments — is copied as it was, and so are strings, numbers and names. A
+/// financial keyword used as a name (`வரி = 5;`) is a name: `வரி` and `vari`
+/// are two different variables, so `to` leaves it alone. Only `migrate`
+/// renames one, when it is spelled the old way, because that spelling is the
+/// one going away.
+pub fn convert(source: &str, to: Option<Script>, migrate: bool) -> Result<Conversion, String> {
+    let tokens = lexer::tokenize(source).map_err(|errors| {
+        errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n  ")
+    })?;
+
+    // Positions are counted after a byte-order mark, which the lexer skips;
+    // it is put back as it was.
+    let (mark, body) = match source.strip_prefix('\u{FEFF}') {
+        Some(body) => ("\u{FEFF}", body),
+        None => ("", source),
+    };
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(body.match_indices('\n').map(|(at, _)| at + 1))
+        .collect();
+
+    let mut text = String::with_capacity(source.len());
+    text.push_str(mark);
+    let mut renamed: Vec<(String, String)> = Vec::new();
+    let mut copied = 0;
+
+    for t in &tokens {
+        let Some(keyword) = keyword(&t.text) else {
+            continue;
+        };
+        let outdated = migrate && keyword.is_outdated(&t.text);
+        let replacement = if Parser::is_name(&t.token) {
+            outdated.then(|| keyword.spelling(Script::Roman))
+        } else if let Some(script) = to {
+            Some(keyword.spelling(script))
+        } else {
+            outdated.then(|| keyword.spelling(Script::Roman))
+        };
+        let Some(replacement) = replacement.filter(|r| *r != t.text) else {
+            continue;
+        };
+
+        if Parser::is_name(&t.token) && !renamed.iter().any(|(old, _)| *old == t.text) {
+            renamed.push((t.text.clone(), replacement.to_string()));
+        }
+        let line = &body[line_starts[t.line - 1]..];
+        let start = line_starts[t.line - 1]
+            + line
+                .char_indices()
+                .nth(t.column - 1)
+                .map_or(line.len(), |(at, _)| at);
+        text.push_str(&body[copied..start]);
+        text.push_str(replacement);
+        copied = start + t.text.len();
+    }
+    text.push_str(&body[copied..]);
+
+    Ok(Conversion { text, renamed })
+}
+
+/// The same letters the lexer accepts in a name.
+pub fn is_name_char(ch: char) -> bool {
+    ch == '_' || ch.is_ascii_alphanumeric() || is_tamil(ch)
+}
+
+fn is_tamil(ch: char) -> bool {
+    ('\u{0B80}'..='\u{0BFF}').contains(&ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_keyword_is_spelled_in_each_script() {
+        let print = keyword("accu").unwrap();
+
+        assert_eq!(print.token, "Print");
+        assert_eq!(print.spelling(Script::Tamil), "அச்சு");
+        assert_eq!(print.spelling(Script::Roman), "accu");
+    }
+
+    #[test]
+    fn only_keywords_change_script() {
+        let source = "// அச்சு this\nவரி = 5;   accu \"accu\" & வரி;\n(வரி > 1) eZil { accu 1; }\n";
+        let converted = convert(source, Some(Script::Tamil), false).unwrap();
+
+        assert_eq!(
+            converted.text,
+            "// அச்சு this\nவரி = 5;   அச்சு \"accu\" & வரி;\n(வரி > 1) எனில் { அச்சு 1; }\n"
+        );
+        assert!(converted.renamed.is_empty());
+    }
+
+    #[test]
+    fn a_name_keeps_its_script() {
+        let converted = convert("vari = 5;\naccu vari;\n", Some(Script::Tamil), false).unwrap();
+
+        assert_eq!(converted.text, "vari = 5;\nஅச்சு vari;\n");
+    }
+
+    #[test]
+    fn migration_brings_old_romanizations_up_to_the_scheme() {
+        let source = "vazhi peRu, \"/\" { paDil 200, uqal; }\nsoqqu = 5;\naccu soqqu;\n";
+        let converted = convert(source, None, true).unwrap();
+
+        assert_eq!(
+            converted.text,
+            "vazi peRu, \"/\" { paqil 200, utal; }\ncoqqu = 5;\naccu coqqu;\n"
+        );
+        assert_eq!(converted.renamed, vec![("soqqu".to_string(), "coqqu".to_string())]);
+    }
+
+    #[test]
+    fn without_migration_a_name_in_an_old_spelling_is_left_alone() {
+        let converted = convert("toqai = 5;\n", Some(Script::Roman), false).unwrap();
+
+        assert_eq!(converted.text, "toqai = 5;\n");
+    }
+
+    #[test]
+    fn english_falls_back_to_roman_where_there_is_none() {
+        assert_eq!(keyword("செயல்").unwrap().spelling(Script::English), "_fn");
+        assert_eq!(keyword("வரி").unwrap().spelling(Script::English), "vari");
+    }
+}
@@ -0,0 +1,419 @@
+//! `etamil test`: find the tests, run each in a VM of its own, and say which
+//! failed and where.
+//!
+//! Two kinds of test are found under the paths given:
+//!
+//! * a `செயல்` named `சோதனை_…` (or `cOqaZY_…`) that takes no arguments. Each
+//!   one is a test: the file's top level runs first, as setup, and then that
+//!   function is called. It fails if it stops on a runtime error — which is
+//!   what `எதிர்பார்` and `எதிர்பார்_சமம்` raise — or exits non-zero.
+//! * a file named `…_cOqaZY.qmz` with no such functions, which is how nUlakam's
+//!   suites are written today: the whole file is one test, and fails the same
+//!   way. `cOqaZY.qmz`'s `சோதனை_முடிவு` exits 1 when an assertion failed, so
+//!   those suites work unchanged.
+//!
+//! Every test gets a fresh compile and a fresh VM, so no test sees a variable,
+//! an open connection or a file mode another one left behind. What a test
+//! prints is captured rather than interleaved, and shown with its failure.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::module;
+use crate::parser::{Expr, Position, Stmt};
+use crate::vm::{BytecodeCompiler, VM, host};
+
+/// How results are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// For a person at a terminal.
+    Human,
+    /// Test Anything Protocol, version 13.
+    Tap,
+    /// JUnit XML, which most CI systems read.
+    Junit,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Format, String> {
+        match name {
+            "human" => Ok(Format::Human),
+            "tap" => Ok(Format::Tap),
+            "junit" => Ok(Format::Junit),
+            other => Err(format!(
+                "'{}' ஒரு அறிக்கை வடிவம் அல்ல  ('{}' is not a report format — use human, tap or junit)",
+                other, other
+            )),
+        }
+    }
+}
+
+/// One test, found but not yet run.
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub file: PathBuf,
+    /// The test function, or `None` when the whole file is the test.
+    pub function: Option<String>,
+    /// Where the function is defined; the call that runs it is put here.
+    at: Option<Position>,
+    /// Why the file could not be read, if it could not: reported as this
+    /// test failing rather than the file being skipped without a word.
+    broken: Option<String>,
+}
+
+impl Case {
+    /// `file::function`, or the file alone. This is what `--filter` matches.
+    pub fn name(&self) -> String {
+        match &self.function {
+            Some(function) => format!("{}::{}", self.file.display(), function),
+            None => self.file.display().to_string(),
+        }
+    }
+}
+
+/// One test, run.
+#[derive(Debug)]
+pub struct Outcome {
+    pub case: Case,
+    /// Why it failed, positioned; `None` if it passed.
+    pub failure: Option<String>,
+    /// Everything it printed.
+    pub output: String,
+    pub time: Duration,
+}
+
+/// Whether a name is a test function's.
+fn is_test_function(name: &str) -> bool {
+    name.starts_with("சோதனை_") || name.starts_with("cOqaZY_")
+}
+
+/// Whether a file is a test suite by its name alone.
+fn is_test_file(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.ends_with("_cOqaZY") || stem.ends_with("_சோதனை"))
+}
+
+/// The tests in `files`, in order. A file that defines test functions gives
+/// one case per function; a suite file without any gives one for itself.
+/// Anything else is not a test and is left out.
+pub fn discover(files: &[PathBuf]) -> Vec<Case> {
+    let mut cases = Vec::new();
+    for file in files {
+        let case = |function: Option<String>, at: Option<Position>, broken: Option<String>| Case {
+            file: file.clone(),
+            function,
+            at,
+            broken,
+        };
+        // The file alone, without its imports: a test defined in an imported
+        // file is that file's test, found when that file is.
+        let statements = match std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|source| parse(&source))
+        {
+            Ok(statements) => statements,
+            Err(message) => {
+                cases.push(case(None, None, Some(message)));
+                continue;
+            }
+        };
+
+        let before = cases.len();
+        for statement in statements {
+            if let Stmt::FunctionDef { name, params, at, .. } = statement
+                && params.is_empty()
+                && is_test_function(&name)
+            {
+                cases.push(case(Some(name), Some(at), None));
+            }
+        }
+        if cases.len() == before && is_test_file(file) {
+            cases.push(case(None, None, None));
+        }
+    }
+    cases
+}
+
+fn parse(source: &str) -> Result<Vec<Stmt>, String> {
+    let tokens = crate::lexer::tokenize(source).map_err(|errors| {
+        errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n  ")
+    })?;
+    crate::parser::Parser::new(tokens.iter())
+        .parse()
+        .map_err(|error| error.to_string())
+}
+
+/// Run one test in a VM of its own.
+pub fn run(case: Case) -> Outcome {
+    // The runner's stdout is the report; the test's goes into its outcome.
+    host::capture_output();
+    host::take_output();
+    host::take_exit();
+    let started = Instant::now();
+
+    let failure = match &case.broken {
+        Some(message) => Some(message.clone()),
+        None => execute(&case).err(),
+    };
+    Outcome {
+        output: host::take_output(),
+        failure,
+        time: started.elapsed(),
+        case,
+    }
+}
+
+fn execute(case: &Case) -> Result<(), String> {
+    let mut program = module::load_file(&case.file)?;
+    if let Err(errors) = crate::check::check(&program) {
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        return Err(errors.join("\n"));
+    }
+    if let (Some(function), Some(at)) = (&case.function, &case.at) {
+        program.push(Stmt::Expression {
+            value: Expr::Call {
+                name: function.clone(),
+                args: Vec::new(),
+                at: at.clone(),
+            },
+            at: at.clone(),
+        });
+    }
+
+    let bytecode = BytecodeCompiler::compile_statements(program);
+    match VM::new().execute(bytecode) {
+        Ok(()) => Ok(()),
+        // A suite that ends with வெளியேறு(0) has passed.
+        Err(message) => match host::take_exit() {
+            Some(0) => Ok(()),
+            _ => Err(message),
+        },
+    }
+}
+
+/// Write the results in `format`.
+pub fn report(outcomes: &[Outcome], format: Format, out: &mut impl std::io::Write) -> std::io::Result<()> {
+    match format {
+        Format::Human => human(outcomes, out),
+        Format::Tap => tap(outcomes, out),
+        Format::Junit => junit(outcomes, out),
+    }
+}
+
+fn human(outcomes: &[Outcome], out: &mut impl std::io::Write) -> std::io::Result<()> {
+    let mut file: Option<&Path> = None;
+    for outcome in outcomes {
+        if file != Some(outcome.case.file.as_path()) {
+            file = Some(outcome.case.file.as_path());
+            writeln!(out, "{}", outcome.case.file.display())?;
+        }
+        let name = outcome.case.function.as_deref().unwrap_or("(கோப்பு — the whole file)");
+        match &outcome.failure {
+            None => writeln!(out, "  ✓ {}", name)?,
+            Some(failure) => {
+                writeln!(out, "  ✗ {}", name)?;
+                for line in failure.lines() {
+                    writeln!(out, "      {}", line)?;
+                }
+                if !outcome.output.is_empty() {
+                    writeln!(out, "      ── அச்சிட்டது  (printed) ──")?;
+                    for line in outcome.output.lines() {
+                        writeln!(out, "      {}", line)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let failed = outcomes.iter().filter(|o| o.failure.is_some()).count();
+    let passed = outcomes.len() - failed;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} சோதனைகள், {} வெற்றி, {} தோல்வி  ({} tests, {} passed, {} failed)",
+        outcomes.len(),
+        passed,
+        failed,
+        outcomes.len(),
+        passed,
+        failed
+    )
+}
+
+fn tap(outcomes: &[Outcome], out: &mut impl std::io::Write) -> std::io::Result<()> {
+    writeln!(out, "TAP version 13")?;
+    writeln!(out, "1..{}", outcomes.len())?;
+    for (number, outcome) in outcomes.iter().enumerate() {
+        let name = outcome.case.name();
+        match &outcome.failure {
+            None => writeln!(out, "ok {} - {}", number + 1, name)?,
+            Some(failure) => {
+                writeln!(out, "not ok {} - {}", number + 1, name)?;
+                // A YAML block, indented under the test line.
+                writeln!(out, "  ---")?;
+                writeln!(out, "  message: |")?;
+                for line in failure.lines() {
+                    writeln!(out, "    {}", line)?;
+                }
+                if !outcome.output.is_empty() {
+                    writeln!(out, "  output: |")?;
+                    for line in outcome.output.lines() {
+                        writeln!(out, "    {}", line)?;
+                    }
+                }
+                writeln!(out, "  ...")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One `<testsuite>` per file, one `<testcase>` per test.
+fn junit(outcomes: &[Outcome], out: &mut impl std::io::Write) -> std::io::Result<()> {
+    let failed = outcomes.iter().filter(|o| o.failure.is_some()).count();
+    let total: Duration = outcomes.iter().map(|o| o.time).sum();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites name="etamil" tests="{}" failures="{}" time="{:.3}">"#,
+        outcomes.len(),
+        failed,
+        total.as_secs_f64()
+    )?;
+
+    let mut start = 0;
+    while start < outcomes.len() {
+        let file = &outcomes[start].case.file;
+        let end = start
+            + outcomes[start..]
+                .iter()
+                .take_while(|o| &o.case.file == file)
+                .count();
+        let suite = &outcomes[start..end];
+        let suite_name = xml(&file.display().to_string());
+        writeln!(
+            out,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.3}">"#,
+            suite_name,
+            suite.len(),
+            suite.iter().filter(|o| o.failure.is_some()).count(),
+            suite.iter().map(|o| o.time).sum::<Duration>().as_secs_f64()
+        )?;
+        for outcome in suite {
+            let name = outcome.case.function.clone().unwrap_or_else(|| suite_name.clone());
+            write!(
+                out,
+                r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+                xml(&name),
+                suite_name,
+                outcome.time.as_secs_f64()
+            )?;
+            if outcome.failure.is_none() && outcome.output.is_empty() {
+                writeln!(out, "/>")?;
+                continue;
+            }
+            writeln!(out, ">")?;
+            if let Some(failure) = &outcome.failure {
+                let first = failure.lines().next().unwrap_or_default();
+                writeln!(
+                    out,
+                    r#"      <failure message="{}">{}</failure>"#,
+                    xml(first),
+                    xml(failure)
+                )?;
+            }
+            if !outcome.output.is_empty() {
+                writeln!(out, "      <system-out>{}</system-out>", xml(&outcome.output))?;
+            }
+            writeln!(out, "    </testcase>")?;
+        }
+        writeln!(out, "  </testsuite>")?;
+        start = end;
+    }
+    writeln!(out, "</testsuites>")
+}
+
+fn xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suite(name: &str, source: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("etamil_testing_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("kaNakku.qmz");
+        std::fs::write(&file, source).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_functions_are_found_and_helpers_are_not() {
+        let file = suite(
+            "discover",
+            "செயல் சோதனை_கூட்டல்() { எதிர்பார்(1 + 1 == 2, \"adds\"); }\n\
+             செயல் சோதனை_தொடக்கம்(பெயர்) { திரும்பு பெயர்; }\n\
+             செயல் கூட்டு(a, b) { திரும்பு a + b; }\n",
+        );
+
+        let cases = discover(&[file]);
+        let names: Vec<&str> = cases.iter().filter_map(|c| c.function.as_deref()).collect();
+        assert_eq!(names, vec!["சோதனை_கூட்டல்"]);
+    }
+
+    #[test]
+    fn each_test_runs_in_its_own_vm_and_a_failure_says_where() {
+        let file = suite(
+            "isolated",
+            "எண்ணி = 0;\n\
+             செயல் சோதனை_முதல்() {\n    எண்ணி = எண்ணி + 1;\n    எதிர்பார்_சமம்(எண்ணி, 1, \"first\");\n}\n\
+             செயல் சோதனை_இரண்டாவது() {\n    எதிர்பார்_சமம்(எண்ணி, 1, \"the first test's change is gone\");\n}\n",
+        );
+
+        let outcomes: Vec<Outcome> = discover(&[file]).into_iter().map(run).collect();
+        assert!(outcomes[0].failure.is_none(), "{:?}", outcomes[0].failure);
+        let failure = outcomes[1].failure.as_deref().unwrap();
+        assert!(failure.contains("line 7"), "{}", failure);
+        assert!(failure.contains("(expected): 1") && failure.contains("(got):      0"), "{}", failure);
+    }
+
+    #[test]
+    fn a_suite_file_fails_by_its_exit_status() {
+        let dir = std::env::temp_dir().join("etamil_testing_suite");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let passing = dir.join("nalla_cOqaZY.qmz");
+        let failing = dir.join("keta_cOqaZY.qmz");
+        std::fs::write(&passing, "அச்சு \"✓\";\nவெளியேறு(0);\nஅச்சு \"never\";\n").unwrap();
+        std::fs::write(&failing, "அச்சு \"✗ two is not three\";\nவெளியேறு(1);\n").unwrap();
+
+        let outcomes: Vec<Outcome> = discover(&[passing, failing]).into_iter().map(run).collect();
+        assert!(outcomes[0].failure.is_none());
+        assert_eq!(outcomes[0].output, "✓\n");
+        assert!(outcomes[1].failure.as_deref().unwrap().contains("status 1"));
+        assert_eq!(outcomes[1].output, "✗ two is not three\n");
+    }
+
+    #[test]
+    fn junit_escapes_and_counts() {
+        let file = suite("junit", "செயல் சோதனை_x() { எதிர்பார்(1 > 2, \"1 < 2 & so\"); }\n");
+        let outcomes: Vec<Outcome> = discover(&[file]).into_iter().map(run).collect();
+
+        let mut xml = Vec::new();
+        report(&outcomes, Format::Junit, &mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains(r#"tests="1" failures="1""#), "{}", xml);
+        assert!(xml.contains("1 &lt; 2 &amp; so"), "{}", xml);
+    }
+}
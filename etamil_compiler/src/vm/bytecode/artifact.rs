@@ -0,0 +1,667 @@
+// The .qmzc format: compiled bytecode written to disk, so that a program and
+// everything it imports are lexed, parsed, checked and compiled once, by
+// `etamil build`, rather than on every run.
+//
+// Layout, all integers little-endian:
+//
+//   "QMZC"                  magic
+//   u16                     FORMAT_VERSION
+//   string                  the compiler version that wrote it
+//   u32, string...          source file names, referred to by positions
+//   u32, instruction...     the program
+//   u32, (string, info)...  functions, sorted by name
+//   u32, (u64, position)... the source map
+//   u32, u64...             statement starts, for the debugger
+//
+// A string is a u32 byte length and then UTF-8. A file is refused unless both
+// the format version and the compiler version match this build: the bytecode is
+// a cache of the source, and the instruction set and the meaning of builtins
+// move with the compiler, so running stale bytecode could compute something
+// other than what the source now says. Rebuilding is always the answer.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+
+use super::{Bytecode, FunctionInfo, Instruction};
+use crate::parser::Position;
+use crate::vm::Value;
+
+/// The first four bytes of every .qmzc file.
+pub const MAGIC: &[u8; 4] = b"QMZC";
+
+/// Bumped whenever the encoding below changes shape, or gains an instruction:
+/// 2 added StopServer.
+pub const FORMAT_VERSION: u16 = 2;
+
+/// The compiler that writes and reads this build's bytecode.
+pub const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Serialize a program. Fails only for a value that cannot be written down —
+/// a function value, which the compiler never emits as a constant.
+pub fn encode(bytecode: &Bytecode) -> Result<Vec<u8>, String> {
+    let mut files: Vec<Arc<str>> = Vec::new();
+    for (_, at) in &bytecode.source_map {
+        if let Some(file) = &at.file
+            && !files.contains(file)
+        {
+            files.push(file.clone());
+        }
+    }
+
+    let mut out = Writer(Vec::new());
+    out.0.extend_from_slice(MAGIC);
+    out.u16(FORMAT_VERSION);
+    out.str(COMPILER_VERSION);
+
+    out.len(files.len());
+    for file in &files {
+        out.str(file);
+    }
+
+    out.len(bytecode.instructions.len());
+    for instruction in &bytecode.instructions {
+        out.instruction(instruction)?;
+    }
+
+    let mut functions: Vec<_> = bytecode.functions.iter().collect();
+    functions.sort_by(|a, b| a.0.cmp(b.0));
+    out.len(functions.len());
+    for (name, info) in functions {
+        out.str(name);
+        out.function(info);
+    }
+
+    out.len(bytecode.source_map.len());
+    for (index, at) in &bytecode.source_map {
+        out.u64(*index as u64);
+        out.u64(at.line as u64);
+        out.u64(at.column as u64);
+        // 0 for no file, otherwise one past its place in the table.
+        let file = at
+            .file
+            .as_ref()
+            .and_then(|file| files.iter().position(|known| known == file))
+            .map_or(0, |place| place + 1);
+        out.len(file);
+    }
+
+    out.len(bytecode.statements.len());
+    for index in &bytecode.statements {
+        out.u64(*index as u64);
+    }
+
+    Ok(out.0)
+}
+
+/// Read a program back, refusing anything that is not .qmzc, or was written by
+/// a different compiler.
+pub fn decode(bytes: &[u8]) -> Result<Bytecode, String> {
+    let mut input = Reader { bytes, at: 0 };
+
+    if input.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        return Err("இது .qmzc கோப்பு அல்ல  (this is not a .qmzc file)".to_string());
+    }
+    let format = input.u16()?;
+    let compiler = input.str()?;
+    if format != FORMAT_VERSION || compiler != COMPILER_VERSION {
+        // One compiler version can have written two formats, while it was
+        // being worked on; say which, or the two would read the same.
+        let built = format!("{}, format {}", compiler, format);
+        let this = format!("{}, format {}", COMPILER_VERSION, FORMAT_VERSION);
+        return Err(format!(
+            "இந்த bytecode etamil {} ஆல் உருவாக்கப்பட்டது; இது etamil {} — etamil build மூலம் மீண்டும் உருவாக்கவும்  \
+             (this bytecode was built by etamil {} and this is etamil {} — rebuild it with etamil build)",
+            built, this, built, this
+        ));
+    }
+
+    let mut files: Vec<Arc<str>> = Vec::new();
+    for _ in 0..input.len()? {
+        files.push(Arc::from(input.str()?));
+    }
+
+    let mut bytecode = Bytecode::new();
+    for _ in 0..input.len()? {
+        let instruction = input.instruction()?;
+        bytecode.push(instruction);
+    }
+
+    for _ in 0..input.len()? {
+        let name = input.str()?;
+        let info = input.function()?;
+        bytecode.functions.insert(name, info);
+    }
+
+    for _ in 0..input.len()? {
+        let index = input.usize()?;
+        let line = input.usize()?;
+        let column = input.usize()?;
+        let file = match input.len()? {
+            0 => None,
+            place => Some(files.get(place - 1).cloned().ok_or_else(damaged)?),
+        };
+        bytecode.source_map.push((index, Position { line, column, file }));
+    }
+
+    for _ in 0..input.len()? {
+        let index = input.usize()?;
+        bytecode.statements.push(index);
+    }
+
+    if input.at != bytes.len() {
+        return Err(damaged());
+    }
+    Ok(bytecode)
+}
+
+fn damaged() -> String {
+    ".qmzc கோப்பு சிதைந்துள்ளது  (the .qmzc file is damaged)".to_string()
+}
+
+// Instruction tags. The numbers are the format: a new instruction takes the
+// next free one and bumps FORMAT_VERSION, so that a compiler without it says
+// to rebuild rather than that the file is damaged. Changing an existing one
+// means bumping it too.
+const PUSH: u8 = 0;
+const POP: u8 = 1;
+const STORE_VAR: u8 = 2;
+const LOAD_VAR: u8 = 3;
+const ADD: u8 = 4;
+const SUBTRACT: u8 = 5;
+const MULTIPLY: u8 = 6;
+const DIVIDE: u8 = 7;
+const MODULO: u8 = 8;
+const EQUAL: u8 = 9;
+const NOT_EQUAL: u8 = 10;
+const LESS_THAN: u8 = 11;
+const LESS_OR_EQUAL: u8 = 12;
+const GREATER_THAN: u8 = 13;
+const GREATER_OR_EQUAL: u8 = 14;
+const AND: u8 = 15;
+const OR: u8 = 16;
+const NOT: u8 = 17;
+const JUMP_IF_FALSE: u8 = 18;
+const JUMP: u8 = 19;
+const PRINT: u8 = 20;
+const INPUT: u8 = 21;
+const CONCAT: u8 = 22;
+const MAKE_ARRAY: u8 = 23;
+const MAKE_RECORD: u8 = 24;
+const INDEX: u8 = 25;
+const FIELD: u8 = 26;
+const SET_INDEX: u8 = 27;
+const SET_FIELD: u8 = 28;
+const TRY_UNWRAP: u8 = 29;
+const LENGTH: u8 = 30;
+const NTH_OR_KEY: u8 = 31;
+const FILE_OPEN: u8 = 32;
+const FILE_CLOSE: u8 = 33;
+const FILE_WRITE: u8 = 34;
+const FILE_READ: u8 = 35;
+const READ_CSV: u8 = 36;
+const WRITE_CSV: u8 = 37;
+const DB_CONNECT: u8 = 38;
+const DB_DISCONNECT: u8 = 39;
+const DB_QUERY: u8 = 40;
+const DB_EXECUTE: u8 = 41;
+const DEFINE_ROUTE: u8 = 42;
+const SEND_RESPONSE: u8 = 43;
+const START_SERVER: u8 = 44;
+const CALL: u8 = 45;
+const RETURN: u8 = 46;
+const CLOSURE: u8 = 47;
+const NOP: u8 = 48;
+const UNSUPPORTED: u8 = 49;
+const HALT: u8 = 50;
+const STOP_SERVER: u8 = 51;
+
+// Value tags.
+const NUMBER: u8 = 0;
+const MONEY: u8 = 1;
+const STRING: u8 = 2;
+const BOOLEAN: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const OK: u8 = 6;
+const ERR: u8 = 7;
+const NULL: u8 = 8;
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, value: usize) {
+        self.0.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn function(&mut self, info: &FunctionInfo) {
+        self.u64(info.start as u64);
+        self.len(info.params.len());
+        for param in &info.params {
+            self.str(param);
+        }
+    }
+
+    fn value(&mut self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::Number(n) => {
+                self.u8(NUMBER);
+                self.0.extend_from_slice(&n.serialize());
+            }
+            Value::Money { amount, currency } => {
+                self.u8(MONEY);
+                self.0.extend_from_slice(&amount.serialize());
+                self.str(currency);
+            }
+            Value::String(s) => {
+                self.u8(STRING);
+                self.str(s);
+            }
+            Value::Boolean(b) => {
+                self.u8(BOOLEAN);
+                self.u8(*b as u8);
+            }
+            Value::Array(items) => {
+                self.u8(ARRAY);
+                self.len(items.len());
+                for item in items {
+                    self.value(item)?;
+                }
+            }
+            Value::Map(fields) => {
+                self.u8(MAP);
+                // Sorted, so that building the same program twice writes the
+                // same bytes.
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_by(|a, b| a.0.cmp(b.0));
+                self.len(fields.len());
+                for (key, field) in fields {
+                    self.str(key);
+                    self.value(field)?;
+                }
+            }
+            Value::Ok(inner) => {
+                self.u8(OK);
+                self.value(inner)?;
+            }
+            Value::Err(inner) => {
+                self.u8(ERR);
+                self.value(inner)?;
+            }
+            Value::Null => self.u8(NULL),
+            Value::Function(_) => {
+                return Err(
+                    "செயல் மதிப்பை .qmzc இல் எழுத முடியாது  (a function value cannot be written to .qmzc)"
+                        .to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) -> Result<(), String> {
+        match instruction {
+            Instruction::Push(value) => {
+                self.u8(PUSH);
+                self.value(value)?;
+            }
+            Instruction::Pop => self.u8(POP),
+            Instruction::StoreVar(name) => {
+                self.u8(STORE_VAR);
+                self.str(name);
+            }
+            Instruction::LoadVar(name) => {
+                self.u8(LOAD_VAR);
+                self.str(name);
+            }
+            Instruction::Add => self.u8(ADD),
+            Instruction::Subtract => self.u8(SUBTRACT),
+            Instruction::Multiply => self.u8(MULTIPLY),
+            Instruction::Divide => self.u8(DIVIDE),
+            Instruction::Modulo => self.u8(MODULO),
+            Instruction::Equal => self.u8(EQUAL),
+            Instruction::NotEqual => self.u8(NOT_EQUAL),
+            Instruction::LessThan => self.u8(LESS_THAN),
+            Instruction::LessOrEqual => self.u8(LESS_OR_EQUAL),
+            Instruction::GreaterThan => self.u8(GREATER_THAN),
+            Instruction::GreaterOrEqual => self.u8(GREATER_OR_EQUAL),
+            Instruction::And => self.u8(AND),
+            Instruction::Or => self.u8(OR),
+            Instruction::Not => self.u8(NOT),
+            Instruction::JumpIfFalse(target) => {
+                self.u8(JUMP_IF_FALSE);
+                self.u64(*target as u64);
+            }
+            Instruction::Jump(target) => {
+                self.u8(JUMP);
+                self.u64(*target as u64);
+            }
+            Instruction::Print => self.u8(PRINT),
+            Instruction::Input => self.u8(INPUT),
+            Instruction::Concat => self.u8(CONCAT),
+            Instruction::MakeArray(count) => {
+                self.u8(MAKE_ARRAY);
+                self.u64(*count as u64);
+            }
+            Instruction::MakeRecord(keys) => {
+                self.u8(MAKE_RECORD);
+                self.len(keys.len());
+                for key in keys {
+                    self.str(key);
+                }
+            }
+            Instruction::Index => self.u8(INDEX),
+            Instruction::Field(name) => {
+                self.u8(FIELD);
+                self.str(name);
+            }
+            Instruction::SetIndex(name) => {
+                self.u8(SET_INDEX);
+                self.str(name);
+            }
+            Instruction::SetField(name, field) => {
+                self.u8(SET_FIELD);
+                self.str(name);
+                self.str(field);
+            }
+            Instruction::TryUnwrap => self.u8(TRY_UNWRAP),
+            Instruction::Length => self.u8(LENGTH),
+            Instruction::NthOrKey => self.u8(NTH_OR_KEY),
+            Instruction::FileOpen(mode) => {
+                self.u8(FILE_OPEN);
+                self.str(mode);
+            }
+            Instruction::FileClose => self.u8(FILE_CLOSE),
+            Instruction::FileWrite => self.u8(FILE_WRITE),
+            Instruction::FileRead => self.u8(FILE_READ),
+            Instruction::ReadCSV => self.u8(READ_CSV),
+            Instruction::WriteCSV => self.u8(WRITE_CSV),
+            Instruction::DBConnect(kind) => {
+                self.u8(DB_CONNECT);
+                self.str(kind);
+            }
+            Instruction::DBDisconnect(kind) => {
+                self.u8(DB_DISCONNECT);
+                self.str(kind);
+            }
+            Instruction::DBQuery => self.u8(DB_QUERY),
+            Instruction::DBExecute => self.u8(DB_EXECUTE),
+            Instruction::DefineRoute(method, path) => {
+                self.u8(DEFINE_ROUTE);
+                self.str(method);
+                self.str(path);
+            }
+            Instruction::SendResponse => self.u8(SEND_RESPONSE),
+            Instruction::StartServer(host, port) => {
+                self.u8(START_SERVER);
+                self.str(host);
+                self.u16(*port);
+            }
+            Instruction::StopServer => self.u8(STOP_SERVER),
+            Instruction::Call(name, argc) => {
+                self.u8(CALL);
+                self.str(name);
+                self.u64(*argc as u64);
+            }
+            Instruction::Return => self.u8(RETURN),
+            Instruction::Closure(info) => {
+                self.u8(CLOSURE);
+                self.function(info);
+            }
+            Instruction::Nop => self.u8(NOP),
+            Instruction::Unsupported(message) => {
+                self.u8(UNSUPPORTED);
+                self.str(message);
+            }
+            Instruction::Halt => self.u8(HALT),
+        }
+        Ok(())
+    }
+}
+
+/// Every read is bounds-checked: a truncated or corrupted file is an error,
+/// never a panic.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.at.checked_add(count).ok_or_else(damaged)?;
+        let taken = self.bytes.get(self.at..end).ok_or_else(damaged)?;
+        self.at = end;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn usize(&mut self) -> Result<usize, String> {
+        usize::try_from(self.u64()?).map_err(|_| damaged())
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn str(&mut self) -> Result<String, String> {
+        let len = self.len()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| damaged())
+    }
+
+    fn decimal(&mut self) -> Result<Decimal, String> {
+        Ok(Decimal::deserialize(self.take(16)?.try_into().unwrap()))
+    }
+
+    fn function(&mut self) -> Result<FunctionInfo, String> {
+        let start = self.usize()?;
+        let mut params = Vec::new();
+        for _ in 0..self.len()? {
+            params.push(self.str()?);
+        }
+        Ok(FunctionInfo { start, params })
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        Ok(match self.u8()? {
+            NUMBER => Value::Number(self.decimal()?),
+            MONEY => {
+                let amount = self.decimal()?;
+                Value::Money { amount, currency: self.str()? }
+            }
+            STRING => Value::String(self.str()?),
+            BOOLEAN => Value::Boolean(self.u8()? != 0),
+            ARRAY => {
+                let mut items = Vec::new();
+                for _ in 0..self.len()? {
+                    items.push(self.value()?);
+                }
+                Value::Array(items)
+            }
+            MAP => {
+                let mut fields = HashMap::new();
+                for _ in 0..self.len()? {
+                    let key = self.str()?;
+                    fields.insert(key, self.value()?);
+                }
+                Value::Map(fields)
+            }
+            OK => Value::Ok(Box::new(self.value()?)),
+            ERR => Value::Err(Box::new(self.value()?)),
+            NULL => Value::Null,
+            _ => return Err(damaged()),
+        })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, String> {
+        Ok(match self.u8()? {
+            PUSH => Instruction::Push(self.value()?),
+            POP => Instruction::Pop,
+            STORE_VAR => Instruction::StoreVar(self.str()?),
+            LOAD_VAR => Instruction::LoadVar(self.str()?),
+            ADD => Instruction::Add,
+            SUBTRACT => Instruction::Subtract,
+            MULTIPLY => Instruction::Multiply,
+            DIVIDE => Instruction::Divide,
+            MODULO => Instruction::Modulo,
+            EQUAL => Instruction::Equal,
+            NOT_EQUAL => Instruction::NotEqual,
+            LESS_THAN => Instruction::LessThan,
+            LESS_OR_EQUAL => Instruction::LessOrEqual,
+            GREATER_THAN => Instruction::GreaterThan,
+            GREATER_OR_EQUAL => Instruction::GreaterOrEqual,
+            AND => Instruction::And,
+            OR => Instruction::Or,
+            NOT => Instruction::Not,
+            JUMP_IF_FALSE => Instruction::JumpIfFalse(self.usize()?),
+            JUMP => Instruction::Jump(self.usize()?),
+            PRINT => Instruction::Print,
+            INPUT => Instruction::Input,
+            CONCAT => Instruction::Concat,
+            MAKE_ARRAY => Instruction::MakeArray(self.usize()?),
+            MAKE_RECORD => {
+                let mut keys = Vec::new();
+                for _ in 0..self.len()? {
+                    keys.push(self.str()?);
+                }
+                Instruction::MakeRecord(keys)
+            }
+            INDEX => Instruction::Index,
+            FIELD => Instruction::Field(self.str()?),
+            SET_INDEX => Instruction::SetIndex(self.str()?),
+            SET_FIELD => {
+                let name = self.str()?;
+                Instruction::SetField(name, self.str()?)
+            }
+            TRY_UNWRAP => Instruction::TryUnwrap,
+            LENGTH => Instruction::Length,
+            NTH_OR_KEY => Instruction::NthOrKey,
+            FILE_OPEN => Instruction::FileOpen(self.str()?),
+            FILE_CLOSE => Instruction::FileClose,
+            FILE_WRITE => Instruction::FileWrite,
+            FILE_READ => Instruction::FileRead,
+            READ_CSV => Instruction::ReadCSV,
+            WRITE_CSV => Instruction::WriteCSV,
+            DB_CONNECT => Instruction::DBConnect(self.str()?),
+            DB_DISCONNECT => Instruction::DBDisconnect(self.str()?),
+            DB_QUERY => Instruction::DBQuery,
+            DB_EXECUTE => Instruction::DBExecute,
+            DEFINE_ROUTE => {
+                let method = self.str()?;
+                Instruction::DefineRoute(method, self.str()?)
+            }
+            SEND_RESPONSE => Instruction::SendResponse,
+            START_SERVER => {
+                let host = self.str()?;
+                Instruction::StartServer(host, self.u16()?)
+            }
+            STOP_SERVER => Instruction::StopServer,
+            CALL => {
+                let name = self.str()?;
+                Instruction::Call(name, self.usize()?)
+            }
+            RETURN => Instruction::Return,
+            CLOSURE => Instruction::Closure(self.function()?),
+            NOP => Instruction::Nop,
+            UNSUPPORTED => Instruction::Unsupported(self.str()?),
+            HALT => Instruction::Halt,
+            _ => return Err(damaged()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{BytecodeCompiler, VM};
+
+    fn compile(source: &str) -> Bytecode {
+        let tokens = crate::lexer::tokenize(source).expect("lexes");
+        let ast = crate::parser::Parser::new(tokens.iter()).parse().expect("parses");
+        BytecodeCompiler::compile_statements(ast)
+    }
+
+    #[test]
+    fn a_program_survives_the_round_trip() {
+        let bytecode = compile(
+            "செயல் இரட்டி(அ) { திரும்பு அ * 2; }\n\
+             எண் மொத்தம் = சுருக்கு([1, 2.5, 3], 0, செயல்(அ, ஆ) { திரும்பு அ + இரட்டி(ஆ); });\n\
+             பொருள் ப = {\"பெயர்\": \"கணக்கு\", \"தொகை\": ₹1500.50, \"சரி\": மெய்};\n",
+        );
+        let decoded = decode(&encode(&bytecode).unwrap()).unwrap();
+
+        assert_eq!(format!("{:?}", decoded.instructions), format!("{:?}", bytecode.instructions));
+        assert_eq!(decoded.source_map, bytecode.source_map);
+        assert_eq!(decoded.statements, bytecode.statements);
+        assert_eq!(decoded.functions["இரட்டி"].start, bytecode.functions["இரட்டி"].start);
+
+        let mut vm = VM::new();
+        vm.execute(decoded).unwrap();
+        assert_eq!(vm.variables["மொத்தம்"].to_string(), "13");
+        let Value::Map(record) = &vm.variables["ப"] else { panic!("ப is not a record") };
+        assert_eq!(record["தொகை"].to_string(), "₹1500.50");
+    }
+
+    #[test]
+    fn the_same_program_encodes_to_the_same_bytes() {
+        let source = "பொருள் ப = {\"அ\": 1, \"ஆ\": 2, \"இ\": 3, \"ஈ\": 4};\n";
+        assert_eq!(encode(&compile(source)).unwrap(), encode(&compile(source)).unwrap());
+    }
+
+    #[test]
+    fn bytecode_from_another_compiler_is_refused() {
+        let mut bytes = encode(&compile("அச்சு 1;\n")).unwrap();
+        // The version string follows the magic, the format and its length.
+        let version = MAGIC.len() + 2 + 4;
+        bytes[version] = b'9';
+
+        let error = decode(&bytes).unwrap_err();
+        assert!(error.contains("rebuild it with etamil build"), "{}", error);
+
+        // Nor is bytecode from before StopServer, written under the same
+        // compiler version.
+        let mut bytes = encode(&compile("அச்சு 1;\n")).unwrap();
+        bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&1u16.to_le_bytes());
+        let error = decode(&bytes).unwrap_err();
+        assert!(error.contains("rebuild it with etamil build"), "{}", error);
+        assert!(error.contains("format 1"), "{}", error);
+    }
+
+    #[test]
+    fn a_truncated_file_is_an_error_not_a_panic() {
+        let bytes = encode(&compile("எண் அ = 1;\nஅச்சு அ;\n")).unwrap();
+        for end in 0..bytes.len() {
+            assert!(decode(&bytes[..end]).is_err(), "accepted {} of {} bytes", end, bytes.len());
+        }
+        assert!(decode(b"not bytecode").unwrap_err().contains("not a .qmzc file"));
+    }
+}
@@ -1,5 +1,5 @@
 // Bytecode compiler: Converts AST to bytecode instructions
-use crate::parser::{Expr, Stmt};
+use crate::parser::{Expr, Hook, Stmt};
 use crate::vm::bytecode::{Bytecode, FunctionInfo, Instruction};
 use crate::vm::Value;
 use rust_decimal::Decimal;
@@ -32,11 +32,13 @@ impl BytecodeCompiler {
             // The declared type is the checker's business, not the VM's: by
             // the time bytecode is emitted the program has already been
             // accepted, so there is nothing left to enforce here.
-            Stmt::Assign { name, value, declared: _, at: _ } => {
+            Stmt::Assign { name, value, declared: _, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(value);
                 self.bytecode.push(Instruction::StoreVar(name));
             }
-            Stmt::FunctionDef { name, params, body } => {
+            // The signature, like a declared type, is the checker's business.
+            Stmt::FunctionDef { name, params, body, .. } => {
                 // The body is emitted inline, so execution has to jump over it.
                 let jump_idx = self.bytecode.len();
                 self.bytecode.push(Instruction::Jump(0)); // patched below
@@ -55,38 +57,45 @@ impl BytecodeCompiler {
                     .functions
                     .insert(name, FunctionInfo { start, params });
             }
-            Stmt::Return(value) => {
+            Stmt::Return { value, at } => {
+                self.bytecode.statement(&at);
                 match value {
                     Some(expr) => self.compile_expr(expr),
                     None => self.bytecode.push(Instruction::Push(Value::Null)),
                 }
                 self.bytecode.push(Instruction::Return);
             }
-            Stmt::Expression(expr) => {
+            Stmt::Expression { value, at } => {
+                self.bytecode.statement(&at);
                 // Evaluated for its effect; discard whatever it left behind.
-                self.compile_expr(expr);
+                self.compile_expr(value);
                 self.bytecode.push(Instruction::Pop);
             }
-            Stmt::SetIndex { name, index, value } => {
+            Stmt::SetIndex { name, index, value, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(index);
                 self.compile_expr(value);
                 self.bytecode.push(Instruction::SetIndex(name));
             }
-            Stmt::SetField { name, field, value } => {
+            Stmt::SetField { name, field, value, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(value);
                 self.bytecode.push(Instruction::SetField(name, field));
             }
-            Stmt::Print(expr) => {
-                self.compile_expr(expr);
+            Stmt::Print { value, at } => {
+                self.bytecode.statement(&at);
+                self.compile_expr(value);
                 self.bytecode.push(Instruction::Print);
             }
-            Stmt::Input(expr) => {
+            Stmt::Input { value, at } => {
+                self.bytecode.statement(&at);
                 self.bytecode.push(Instruction::Input);
-                if let Expr::Variable(name) = expr {
+                if let Expr::Variable(name) = value {
                     self.bytecode.push(Instruction::StoreVar(name));
                 }
             }
-            Stmt::If { condition, then_branch, else_branch } => {
+            Stmt::If { condition, then_branch, else_branch, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(condition);
                 
                 let jump_false_idx = self.bytecode.len();
@@ -125,9 +134,10 @@ impl BytecodeCompiler {
                     }
                 }
             }
-            Stmt::Loop { condition, body } => {
+            Stmt::Loop { condition, body, at } => {
                 let loop_start = self.bytecode.len();
-                
+
+                self.bytecode.statement(&at);
                 self.compile_expr(condition);
                 let jump_false_idx = self.bytecode.len();
                 self.bytecode.push(Instruction::JumpIfFalse(0)); // Placeholder
@@ -146,7 +156,8 @@ impl BytecodeCompiler {
             // ovvoru item il collection { ... } is desugared into an index
             // loop over hidden variables. The '#' prefix cannot appear in a
             // user identifier, so these can never collide with a real name.
-            Stmt::ForEach { var, collection, body } => {
+            Stmt::ForEach { var, collection, body, at } => {
+                self.bytecode.statement(&at);
                 let id = self.loop_id;
                 self.loop_id += 1;
                 let items = format!("#each_items_{}", id);
@@ -158,7 +169,9 @@ impl BytecodeCompiler {
                     .push(Instruction::Push(Value::Number(Decimal::ZERO)));
                 self.bytecode.push(Instruction::StoreVar(index.clone()));
 
+                // Each pass stops here again, on the ஒவ்வொரு line.
                 let start = self.bytecode.len();
+                self.bytecode.statement(&at);
                 self.bytecode.push(Instruction::LoadVar(index.clone()));
                 self.bytecode.push(Instruction::LoadVar(items.clone()));
                 self.bytecode.push(Instruction::Length);
@@ -185,35 +198,42 @@ impl BytecodeCompiler {
                 let end = self.bytecode.len();
                 self.bytecode.instructions[jump_false_idx] = Instruction::JumpIfFalse(end);
             }
-            Stmt::FileOpen { filename, mode } => {
+            Stmt::FileOpen { filename, mode, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(filename);
                 self.bytecode.push(Instruction::FileOpen(mode));
             }
-            Stmt::FileClose { filename } => {
+            Stmt::FileClose { filename, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(filename);
                 self.bytecode.push(Instruction::FileClose);
             }
-            Stmt::FileWrite { filename, data } => {
+            Stmt::FileWrite { filename, data, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(filename);
                 self.compile_expr(data);
                 self.bytecode.push(Instruction::FileWrite);
             }
-            Stmt::FileRead { filename, variable } => {
+            Stmt::FileRead { filename, variable, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(filename);
                 self.bytecode.push(Instruction::FileRead);
                 self.bytecode.push(Instruction::StoreVar(variable));
             }
-            Stmt::ReadCSV { filename, variable } => {
+            Stmt::ReadCSV { filename, variable, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(filename);
                 self.bytecode.push(Instruction::ReadCSV);
                 self.bytecode.push(Instruction::StoreVar(variable));
             }
-            Stmt::WriteCSV { filename, data } => {
+            Stmt::WriteCSV { filename, data, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(filename);
                 self.compile_expr(data);
                 self.bytecode.push(Instruction::WriteCSV);
             }
-            Stmt::SendResponse { status_code, body, headers } => {
+            Stmt::SendResponse { status_code, body, headers, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(status_code);
                 self.compile_expr(body);
                 // A response always leaves three values for the instruction,
@@ -225,19 +245,24 @@ impl BytecodeCompiler {
                 }
                 self.bytecode.push(Instruction::SendResponse);
             }
-            Stmt::DBConnect { db_type, connection_string } => {
+            Stmt::StopServer => self.bytecode.push(Instruction::StopServer),
+            Stmt::DBConnect { db_type, connection_string, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(connection_string);
                 self.bytecode.push(Instruction::DBConnect(db_type));
             }
-            Stmt::DBDisconnect { db_type } => {
+            Stmt::DBDisconnect { db_type, at } => {
+                self.bytecode.statement(&at);
                 self.bytecode.push(Instruction::DBDisconnect(db_type));
             }
-            Stmt::DBExecute { command, params } => {
+            Stmt::DBExecute { command, params, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(command);
                 self.compile_expr(params);
                 self.bytecode.push(Instruction::DBExecute);
             }
-            Stmt::DBQuery { query, params, result_var } => {
+            Stmt::DBQuery { query, params, result_var, at } => {
+                self.bytecode.statement(&at);
                 self.compile_expr(query);
                 self.compile_expr(params);
                 self.bytecode.push(Instruction::DBQuery);
@@ -270,8 +295,10 @@ impl BytecodeCompiler {
             Stmt::Select { .. } => "தேர்வெடு (select)",
             Stmt::DefineRoute { .. } => "வழி (route)",
             Stmt::Schedule { .. } => "இடைவெளி (schedule)",
+            Stmt::Middleware { hook: Hook::Before, .. } => "முன் (before routes)",
+            Stmt::Middleware { hook: Hook::After, .. } => "பின் (after routes)",
+            Stmt::Mount { .. } => "கோப்புறை (mount a directory)",
             Stmt::StartServer { .. } => "வழங்கி_தொடங்கு (start server)",
-            Stmt::StopServer => "வழங்கி_நிறுத்து (stop server)",
             Stmt::SendResponse { .. } => "பதில் (response)",
             Stmt::SendJSON { .. } => "ஜேசான்_உரை (json response)",
             Stmt::GetRequestBody { .. } => "உடல் (request body)",
@@ -287,6 +314,9 @@ impl BytecodeCompiler {
             Expr::Number(n) => {
                 self.bytecode.push(Instruction::Push(Value::Number(n)));
             }
+            Expr::Money { amount, currency } => {
+                self.bytecode.push(Instruction::Push(Value::Money { amount, currency }));
+            }
             Expr::String(s) => {
                 self.bytecode.push(Instruction::Push(Value::String(s)));
             }
@@ -383,11 +413,14 @@ impl BytecodeCompiler {
                 self.compile_expr(*inner);
                 self.bytecode.push(Instruction::Not);
             }
-            Expr::Call { name, args } => {
+            Expr::Call { name, args, at } => {
                 let argc = args.len();
                 for arg in args {
                     self.compile_expr(arg);
                 }
+                // After the arguments, which may have marked calls of their
+                // own: the call itself is where its name was written.
+                self.bytecode.mark(&at);
                 self.bytecode.push(Instruction::Call(name, argc));
             }
             Expr::ArrayLiteral(items) => {
@@ -423,6 +456,27 @@ impl BytecodeCompiler {
                 self.compile_expr(*right);
                 self.bytecode.push(Instruction::Concat);
             }
+            // Emitted inline and jumped over, as a named function is; the
+            // value made where it was written carries the entry point.
+            Expr::Function { params, body, at, .. } => {
+                let jump_idx = self.bytecode.len();
+                self.bytecode.push(Instruction::Jump(0)); // patched below
+
+                let start = self.bytecode.len();
+                for stmt in body {
+                    self.compile_stmt(stmt);
+                }
+                self.bytecode.push(Instruction::Push(Value::Null));
+                self.bytecode.push(Instruction::Return);
+
+                let end = self.bytecode.len();
+                self.bytecode.instructions[jump_idx] = Instruction::Jump(end);
+                // The body marked its own lines; what follows is back where
+                // the செயல் was written.
+                self.bytecode.mark(&at);
+                self.bytecode
+                    .push(Instruction::Closure(FunctionInfo { start, params }));
+            }
         }
     }
 }
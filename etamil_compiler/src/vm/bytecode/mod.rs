@@ -1,5 +1,9 @@
 // Bytecode instruction set for the eTamil VM
 pub mod compiler;
+// Bytecode on disk: the .qmzc files `etamil build` writes.
+pub mod artifact;
+
+use crate::parser::Position;
 
 /// Bytecode instruction set
 #[derive(Debug, Clone)]
@@ -90,12 +94,18 @@ pub enum Instruction {
     /// Pop body then status; record them for the server to send back.
     SendResponse,
     StartServer(String, u16),    // host, port
+    /// Ask the server this program is running in to stop, once the requests
+    /// in hand are done. Nothing happens outside one.
+    StopServer,
 
     // Functions
     /// Call a named function with this many arguments already on the stack.
     Call(String, usize),
     /// Pop the return value, restore the caller's frame, push the value back.
     Return,
+    /// Push a function value for the body at `start`, capturing the current
+    /// call's locals: an anonymous `செயல்` written as an expression.
+    Closure(FunctionInfo),
 
     // Misc
     Nop,
@@ -120,6 +130,16 @@ pub struct Bytecode {
     /// to its entry point. Resolution happens at call time, so functions may
     /// be defined in any order and may recurse.
     pub functions: std::collections::HashMap<String, FunctionInfo>,
+    /// Where each run of instructions came from: an entry covers every
+    /// instruction from its index up to the next entry's. Kept as runs rather
+    /// than one position per instruction because a statement compiles to
+    /// several, and only the statements that can fail are marked at all.
+    pub source_map: Vec<(usize, Position)>,
+    /// Where each statement begins, in order. The debugger stops only here,
+    /// so that a stop always has a line to show and a step moves one
+    /// statement rather than one instruction. A loop's condition is listed
+    /// once per loop but reached on every pass.
+    pub statements: Vec<usize>,
 }
 
 impl Bytecode {
@@ -127,6 +147,8 @@ impl Bytecode {
         Bytecode {
             instructions: Vec::new(),
             functions: std::collections::HashMap::new(),
+            source_map: Vec::new(),
+            statements: Vec::new(),
         }
     }
 
@@ -137,4 +159,37 @@ impl Bytecode {
     pub fn len(&self) -> usize {
         self.instructions.len()
     }
+
+    /// Attribute the instructions emitted from here on to `at`.
+    pub fn mark(&mut self, at: &Position) {
+        let index = self.instructions.len();
+        match self.source_map.last_mut() {
+            // Nothing was emitted under the previous mark, so it covers
+            // nothing: replace it rather than leave an empty run behind.
+            Some((start, position)) if *start == index => *position = at.clone(),
+            Some((_, position)) if position == at => {}
+            _ => self.source_map.push((index, at.clone())),
+        }
+    }
+
+    /// Begin a statement written at `at`: mark it, and record it as a place
+    /// the debugger can stop.
+    pub fn statement(&mut self, at: &Position) {
+        self.mark(at);
+        let index = self.instructions.len();
+        if self.statements.last() != Some(&index) {
+            self.statements.push(index);
+        }
+    }
+
+    /// Whether a statement begins at `index`.
+    pub fn is_statement(&self, index: usize) -> bool {
+        self.statements.binary_search(&index).is_ok()
+    }
+
+    /// The source position of the instruction at `index`, if it was marked.
+    pub fn position_of(&self, index: usize) -> Option<&Position> {
+        let after = self.source_map.partition_point(|(start, _)| *start <= index);
+        after.checked_sub(1).map(|run| &self.source_map[run].1)
+    }
 }
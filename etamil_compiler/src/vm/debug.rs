@@ -0,0 +1,315 @@
+//! Breakpoints and stepping, over the bytecode VM.
+//!
+//! The VM executes one instruction per `VM::step`; this drives it, and
+//! between instructions decides whether to stop. It only ever stops where a
+//! statement begins — `Bytecode::statements` — so every stop has a line to
+//! show, and a step moves a statement at a time rather than an instruction.
+//!
+//! The console behind `etamil --debug` and the Debug Adapter Protocol behind
+//! `etamil --dap` are both built on this, so the two cannot disagree about
+//! what "step over" means.
+
+use std::collections::BTreeSet;
+
+use crate::parser::Position;
+use crate::vm::{Bytecode, Value, VM};
+
+/// How far to run before stopping again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// Until a breakpoint, or the end.
+    Continue,
+    /// To the next statement, even one inside a function this one calls.
+    StepInto,
+    /// To the next statement in this function or a caller of it: a call on
+    /// this line runs to completion.
+    StepOver,
+    /// To the next statement once this function has returned.
+    StepOut,
+}
+
+/// Why the program stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint,
+    Step,
+    /// It ran to the end. Nothing further can be resumed.
+    Finished,
+}
+
+/// One call in progress, as the debugger shows it.
+#[derive(Debug, Clone)]
+pub struct Call {
+    /// The function's name, or `நிரல்` for the top level.
+    pub function: String,
+    /// Where that call is now: the current statement for the innermost, the
+    /// call it is waiting on for every other.
+    pub at: Option<Position>,
+}
+
+/// A program paused under the debugger.
+pub struct Debugger {
+    pub vm: VM,
+    bytecode: Bytecode,
+    /// Instruction indexes, each the start of a statement.
+    breakpoints: BTreeSet<usize>,
+    started: bool,
+    finished: bool,
+}
+
+impl Debugger {
+    /// Ready to run `bytecode` from its first instruction. Nothing runs until
+    /// the first `resume`.
+    pub fn new(bytecode: Bytecode) -> Self {
+        Debugger {
+            vm: VM::new(),
+            bytecode,
+            breakpoints: BTreeSet::new(),
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Where the program is stopped.
+    pub fn position(&self) -> Option<&Position> {
+        self.bytecode.position_of(self.vm.instruction_pointer)
+    }
+
+    /// Break at `line` of whichever file `in_file` accepts — given each
+    /// position's file, `None` for a program read from stdin.
+    ///
+    /// A line with no statement on it — blank, a comment, a closing brace —
+    /// moves the breakpoint down to the next line that has one, as editors
+    /// expect. The line it landed on is returned, or `None` if there is no
+    /// statement at or after `line` in that file.
+    pub fn set_breakpoint(
+        &mut self,
+        line: usize,
+        in_file: impl Fn(Option<&str>) -> bool,
+    ) -> Option<usize> {
+        let candidates: Vec<(usize, usize)> = self
+            .bytecode
+            .statements
+            .iter()
+            .filter_map(|&index| {
+                let at = self.bytecode.position_of(index)?;
+                (at.line >= line && in_file(at.file.as_deref())).then_some((index, at.line))
+            })
+            .collect();
+        let landed = candidates.iter().map(|&(_, line)| line).min()?;
+        // Every statement on that line: a loop's condition is one statement
+        // compiled twice, and a line may hold several.
+        for &(index, line) in &candidates {
+            if line == landed {
+                self.breakpoints.insert(index);
+            }
+        }
+        Some(landed)
+    }
+
+    /// Remove every breakpoint in the files `in_file` accepts.
+    pub fn clear_breakpoints(&mut self, in_file: impl Fn(Option<&str>) -> bool) {
+        let bytecode = &self.bytecode;
+        self.breakpoints.retain(|&index| {
+            !in_file(bytecode.position_of(index).and_then(|at| at.file.as_deref()))
+        });
+    }
+
+    /// Run until the next stop. A runtime error ends the program, and is
+    /// returned with its position and call stack, as `execute` would report
+    /// it.
+    pub fn resume(&mut self, how: Resume) -> Result<Stop, String> {
+        if self.finished {
+            return Ok(Stop::Finished);
+        }
+        let depth = self.vm.frames.len();
+        // The statement it is stopped on is where it starts from, not a
+        // reason to stop again — except before the very first one.
+        let mut moved = !self.started;
+        self.started = true;
+
+        loop {
+            let here = self.vm.instruction_pointer;
+            if moved && self.bytecode.is_statement(here) {
+                if self.breakpoints.contains(&here) {
+                    return Ok(Stop::Breakpoint);
+                }
+                let frames = self.vm.frames.len();
+                let arrived = match how {
+                    Resume::Continue => false,
+                    Resume::StepInto => true,
+                    Resume::StepOver => frames <= depth,
+                    Resume::StepOut => frames < depth,
+                };
+                if arrived {
+                    return Ok(Stop::Step);
+                }
+            }
+            moved = true;
+
+            match self.vm.step(&self.bytecode) {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.finished = true;
+                    return Ok(Stop::Finished);
+                }
+                Err(message) => {
+                    self.finished = true;
+                    return Err(self.vm.locate(&self.bytecode, message));
+                }
+            }
+        }
+    }
+
+    /// The calls in progress, innermost first, ending with the top level.
+    pub fn calls(&self) -> Vec<Call> {
+        let mut calls = Vec::new();
+        // A frame's return address is the instruction after its call.
+        let mut at = self.position().cloned();
+        for frame in self.vm.frames.iter().rev() {
+            calls.push(Call {
+                function: frame.function.clone(),
+                at,
+            });
+            at = self
+                .bytecode
+                .position_of(frame.return_ip.saturating_sub(1))
+                .cloned();
+        }
+        calls.push(Call {
+            function: "நிரல்".to_string(),
+            at,
+        });
+        calls
+    }
+
+    /// The names visible to one call, counted as `calls` counts them: 0 is
+    /// the innermost, and the last is the top level's globals. Sorted by name.
+    /// The hidden counters `ஒவ்வொரு` keeps are left out.
+    pub fn variables(&self, call: usize) -> Vec<(String, Value)> {
+        let frames = &self.vm.frames;
+        let names = match frames.len().checked_sub(call + 1) {
+            Some(frame) => &frames[frame].locals,
+            None => &self.vm.variables,
+        };
+        let mut variables: Vec<(String, Value)> = names
+            .iter()
+            .filter(|(name, _)| !name.starts_with('#'))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        variables
+    }
+
+    /// Look a name up as the innermost call sees it.
+    pub fn lookup(&self, name: &str) -> Option<&Value> {
+        self.vm
+            .frames
+            .last()
+            .and_then(|frame| frame.locals.get(name))
+            .or_else(|| self.vm.variables.get(name))
+    }
+}
+
+/// A value as the debugger shows it: as `அச்சு` would print it, except that
+/// text is quoted, so `"5"` and `5` look different.
+pub fn shown(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("{:?}", text),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debugger(source: &str) -> Debugger {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let ast = crate::parser::Parser::new(tokens.iter()).parse().unwrap();
+        Debugger::new(crate::vm::BytecodeCompiler::compile_statements(ast))
+    }
+
+    fn line(debugger: &Debugger) -> usize {
+        debugger.position().unwrap().line
+    }
+
+    const PROGRAM: &str = "\
+செயல் இரட்டி(n) {
+    vidY = n * 2;
+    திரும்பு vidY;
+}
+அ = 1;
+ஆ = இரட்டி(அ);
+
+இ = ஆ + 1;
+";
+
+    #[test]
+    fn a_breakpoint_on_a_blank_line_moves_to_the_next_statement() {
+        let mut debugger = debugger(PROGRAM);
+
+        assert_eq!(debugger.set_breakpoint(7, |_| true), Some(8));
+        assert_eq!(debugger.resume(Resume::Continue), Ok(Stop::Breakpoint));
+        assert_eq!(line(&debugger), 8);
+        assert_eq!(shown(debugger.lookup("ஆ").unwrap()), "2");
+        assert_eq!(debugger.resume(Resume::Continue), Ok(Stop::Finished));
+    }
+
+    #[test]
+    fn step_over_runs_a_call_and_step_into_enters_it() {
+        let mut over = debugger(PROGRAM);
+        let lines: Vec<usize> = std::iter::from_fn(|| {
+            (over.resume(Resume::StepOver) == Ok(Stop::Step)).then(|| line(&over))
+        })
+        .collect();
+        assert_eq!(lines, vec![5, 6, 8]);
+
+        let mut into = debugger(PROGRAM);
+        let lines: Vec<usize> = std::iter::from_fn(|| {
+            (into.resume(Resume::StepInto) == Ok(Stop::Step)).then(|| line(&into))
+        })
+        .collect();
+        assert_eq!(lines, vec![5, 6, 2, 3, 8]);
+    }
+
+    #[test]
+    fn inside_a_call_the_stack_and_locals_are_the_callers_and_its_own() {
+        let mut debugger = debugger(PROGRAM);
+        debugger.set_breakpoint(3, |_| true);
+        debugger.resume(Resume::Continue).unwrap();
+
+        let calls = debugger.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function, "இரட்டி");
+        assert_eq!(calls[1].at.as_ref().unwrap().line, 6);
+
+        let locals: Vec<String> = debugger.variables(0).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(locals, vec!["n", "vidY"]);
+        assert_eq!(debugger.variables(1).len(), 1); // அ; ஆ is not assigned yet
+
+        assert_eq!(debugger.resume(Resume::StepOut), Ok(Stop::Step));
+        assert_eq!(line(&debugger), 8);
+    }
+
+    #[test]
+    fn a_loop_stops_on_every_pass() {
+        let mut debugger = debugger("அ = 0;\n(அ < 3) சுற்று {\n    அ = அ + 1;\n}\n");
+        debugger.set_breakpoint(3, |_| true);
+
+        let mut passes = 0;
+        while debugger.resume(Resume::Continue) == Ok(Stop::Breakpoint) {
+            passes += 1;
+        }
+        assert_eq!(passes, 3);
+    }
+
+    #[test]
+    fn a_runtime_error_ends_the_session_with_its_position() {
+        let mut debugger = debugger("அ = 1;\nஆ = அ / 0;\n");
+
+        let error = debugger.resume(Resume::Continue).unwrap_err();
+        assert!(error.contains("line 2"), "{}", error);
+        assert_eq!(debugger.resume(Resume::StepInto), Ok(Stop::Finished));
+    }
+}
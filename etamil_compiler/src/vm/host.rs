@@ -16,21 +16,48 @@
 //! On wasm, output accumulates in a buffer the embedder drains, and files live
 //! in memory. Both are `thread_local`: wasm32 is single-threaded, so there is
 //! nothing to lock, and a native build gets one host per thread, which is what
-//! a thread-per-request server wants.
+//! a thread-per-request server wants. A native thread can capture its output
+//! the same way, for the debug adapter, whose stdout belongs to the editor.
 
 // --- Native -----------------------------------------------------------------
 
 #[cfg(not(target_family = "wasm"))]
 mod imp {
+    use std::cell::RefCell;
     use std::fs;
     use std::fs::OpenOptions;
     use std::io::Write as _;
 
+    thread_local! {
+        /// Output held back from stdout, when stdout is not the program's:
+        /// under `etamil --dap` it carries the protocol. `None` otherwise.
+        static CAPTURED: RefCell<Option<String>> = const { RefCell::new(None) };
+        /// The status `வெளியேறு` asked for while output was captured: the
+        /// process is not the program's to end then either.
+        static EXITED: std::cell::Cell<Option<i32>> = const { std::cell::Cell::new(None) };
+    }
+
     pub fn print_line(text: &str) {
-        println!("{}", text);
+        let held = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+            Some(out) => {
+                out.push_str(text);
+                out.push('\n');
+                true
+            }
+            None => false,
+        });
+        if !held {
+            println!("{}", text);
+        }
     }
 
+    /// While output is captured stdin is not the program's either.
     pub fn read_line() -> Result<String, String> {
+        if CAPTURED.with(|captured| captured.borrow().is_some()) {
+            return Err("உள்ளிடு இங்கு கிடைக்காது  \
+                        (input is not available while an editor is debugging the program)"
+                .to_string());
+        }
         let mut input = String::new();
         std::io::stdin()
             .read_line(&mut input)
@@ -59,11 +86,51 @@ mod imp {
     ///
     /// Exiting does not unwind, so anything still buffered would be lost --
     /// including the summary line that explains the status.
+    ///
+    /// While output is captured the process belongs to whoever captured it,
+    /// so the program is stopped with an error instead, and the status kept
+    /// for `take_exit`.
     pub fn exit(status: i32) -> Result<(), String> {
+        if CAPTURED.with(|captured| captured.borrow().is_some()) {
+            EXITED.with(|exited| exited.set(Some(status)));
+            return Err(format!(
+                "நிரல் {} நிலையுடன் நின்றது  (the program exited with status {})",
+                status, status
+            ));
+        }
         let _ = std::io::stdout().flush();
         let _ = std::io::stderr().flush();
         std::process::exit(status);
     }
+
+    /// Hold everything this thread prints from now on, for `take_output`.
+    pub fn capture_output() {
+        CAPTURED.with(|captured| *captured.borrow_mut() = Some(String::new()));
+    }
+
+    /// The status the program exited with, if it called `வெளியேறு` while
+    /// output was captured. Cleared by asking.
+    pub fn take_exit() -> Option<i32> {
+        EXITED.with(|exited| exited.take())
+    }
+
+    /// Wait, holding this thread: a handler pausing between two pushes to an
+    /// events stream is the only one on it.
+    pub fn sleep(duration: std::time::Duration) -> Result<(), String> {
+        std::thread::sleep(duration);
+        Ok(())
+    }
+
+    /// Take everything captured so far, leaving the buffer empty.
+    pub fn take_output() -> String {
+        CAPTURED.with(|captured| {
+            captured
+                .borrow_mut()
+                .as_mut()
+                .map(std::mem::take)
+                .unwrap_or_default()
+        })
+    }
 }
 
 // --- Browser ----------------------------------------------------------------
@@ -147,6 +214,12 @@ mod imp {
         }
     }
 
+    /// A browser tab has one thread and it is the page's; blocking it would
+    /// freeze the editor the program was typed into.
+    pub fn sleep(_duration: std::time::Duration) -> Result<(), String> {
+        Err("உலாவியில் காத்திருக்க முடியாது  (cannot wait in a browser)".to_string())
+    }
+
     /// Take everything printed so far, leaving the buffer empty.
     pub fn take_output() -> String {
         OUTPUT.with(|out| std::mem::take(&mut *out.borrow_mut()))
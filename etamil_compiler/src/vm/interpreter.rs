@@ -16,6 +16,8 @@ use std::str::FromStr;
 use unicode_segmentation::UnicodeSegmentation;
 use crate::vm::host;
 use crate::vm::{Value, Instruction, Bytecode};
+use crate::vm::value::Closure;
+use std::sync::Arc;
 
 /// Split text the way a reader would: by written letter. A Tamil letter is
 /// frequently several code points (consonant + vowel sign, or + pulli), so
@@ -65,9 +67,54 @@ pub struct Upload {
     pub data: Vec<u8>,
 }
 
+/// Where a streamed response goes: the connection a handler is answering.
+///
+/// Set by the server for the length of one request. `பதில்_தொடங்கு` sends the
+/// head and `பதில்_பகுதி` each part as it is produced, so a statement export
+/// never has to exist in memory all at once. The server calls `finish` once
+/// the handler returns. Without one — a script, a test — the parts collect
+/// into `response_body` instead, and the handler behaves the same.
+///
+/// A connection the client asked to make a WebSocket can also be upgraded:
+/// after `upgrade`, each part is one message, `receive` waits for the
+/// client's next one, and `finish` closes the socket.
+pub trait ResponseStream: Send {
+    fn start(&mut self, status: u16, headers: Vec<(String, String)>) -> Result<(), String>;
+    fn part(&mut self, data: &[u8]) -> Result<(), String>;
+    fn finish(&mut self) -> Result<(), String>;
+
+    fn upgrade(&mut self) -> Result<(), String> {
+        Err("this request did not ask for a WebSocket".to_string())
+    }
+
+    /// The client's next message, or `None` if none came within `wait`.
+    fn receive(&mut self, _wait: Option<std::time::Duration>) -> Result<Option<String>, String> {
+        Err("this request did not ask for a WebSocket".to_string())
+    }
+}
+
+/// What a handler has opened for pushing messages, once it has: an events
+/// stream, one way, or a WebSocket, both ways. `Closed` after
+/// `இணைப்பு_மூடு` or once the client has gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Events,
+    Socket,
+    Closed,
+}
+
+impl std::fmt::Debug for dyn ResponseStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ResponseStream")
+    }
+}
+
 /// One active function call: where to resume, and that call's local names.
 #[derive(Debug)]
 pub struct Frame {
+    /// The function this frame is running, for the call stack an error
+    /// reports.
+    pub function: String,
     pub return_ip: usize,
     pub locals: HashMap<String, Value>,
     /// Stack depth when the call began. Returning truncates back to this, so
@@ -152,6 +199,26 @@ pub struct VM {
     /// a transaction the way two requests sharing a SQL connection do. The fix
     /// is an exclusive lease, which the SQL side has and this does not yet.
     pub cache: Option<crate::redis::Connection>,
+    /// The connection a streamed response is written to; see ResponseStream.
+    pub response_stream: Option<Box<dyn ResponseStream>>,
+    /// Whether `பதில்_தொடங்கு` has run, so the head goes out exactly once.
+    pub response_started: bool,
+    /// Set by `நிகழ்வுகள்_தொடங்கு` or `வலைசாக்கெட்_தொடங்கு`.
+    pub channel: Option<Channel>,
+    /// The id of the request's session, when its cookie carried a good
+    /// signature or அமர்வு_அமை has started one.
+    pub session: Option<String>,
+    /// Whether the request came in over TLS; see `http::session::cookie_for`.
+    pub secure: bool,
+    /// Instructions executed so far, and the most `execute_limited` allows.
+    /// Counted per instruction rather than per loop in `run_steps`, so a
+    /// function run from inside a builtin — `மாற்றியமை`'s — counts too.
+    steps: u64,
+    step_limit: Option<u64>,
+    /// When `execute_until` gives up, and whether it did. A server reads
+    /// `timed_out` to answer 504 rather than the 500 of any other failure.
+    deadline: Option<std::time::Instant>,
+    pub timed_out: bool,
 }
 
 impl VM {
@@ -167,6 +234,15 @@ impl VM {
             cache: None,
             frames: Vec::new(),
             connections: Connections::default(),
+            response_stream: None,
+            response_started: false,
+            channel: None,
+            session: None,
+            secure: false,
+            steps: 0,
+            step_limit: None,
+            deadline: None,
+            timed_out: false,
         }
     }
 
@@ -226,10 +302,21 @@ impl VM {
         self.stack.pop().ok_or_else(|| "Stack underflow".to_string())
     }
 
+    /// Pop a call's arguments, in the order they were written.
+    fn pop_args(&mut self, argc: usize) -> Result<Vec<Value>, String> {
+        let mut args = Vec::with_capacity(argc);
+        for _ in 0..argc {
+            args.push(self.pop()?);
+        }
+        args.reverse();
+        Ok(args)
+    }
+
     /// Human-readable type name, for error messages.
     fn type_name(value: &Value) -> &'static str {
         match value {
             Value::Number(_) => "a number",
+            Value::Money { .. } => "an amount",
             Value::String(_) => "a string",
             Value::Boolean(_) => "a boolean",
             Value::Array(_) => "an array",
@@ -237,6 +324,137 @@ impl VM {
             Value::Ok(_) => "a result",
             Value::Err(_) => "a result",
             Value::Null => "nil",
+            Value::Function(_) => "a function",
+        }
+    }
+
+    /// Arithmetic where an operand is an amount of money, or `None` when
+    /// neither is and the plain number path applies.
+    ///
+    /// An amount keeps its currency through everything that makes sense for
+    /// money — adding, subtracting, scaling, splitting — and refuses the rest
+    /// rather than guessing a rate. Zero is the one plain number that adds to
+    /// an amount, since it is zero in every currency: that is what an
+    /// accumulator starts at, and what unary minus is compiled against.
+    fn money_arithmetic(op: char, left: &Value, right: &Value) -> Option<Result<Value, String>> {
+        let money = |amount: Decimal, currency: &str| Value::Money {
+            amount,
+            currency: currency.to_string(),
+        };
+        if !matches!(left, Value::Money { .. }) && !matches!(right, Value::Money { .. }) {
+            return None;
+        }
+        Some(match (op, left, right) {
+            (
+                '+' | '-' | '%',
+                Value::Money { amount: a, currency: x },
+                Value::Money { amount: b, currency: y },
+            ) => {
+                if x != y {
+                    return Some(Err(Self::currency_mismatch(op, x, y)));
+                }
+                Ok(money(
+                    match op {
+                        '+' => a + b,
+                        '-' => a - b,
+                        _ => a % b,
+                    },
+                    x,
+                ))
+            }
+            ('+' | '-', Value::Money { amount, currency }, plain)
+            | ('+' | '-', plain, Value::Money { amount, currency }) => {
+                let number = plain.to_number();
+                if !number.is_zero() {
+                    return Some(Err(format!(
+                        "ஒரு தொகையுடன் வெறும் எண் {} சேர்க்க முடியாது  \
+                         (cannot {} a plain number and an amount — write {} \
+                         if {} is in {} too)",
+                        plain.to_string(),
+                        if op == '+' { "add" } else { "subtract" },
+                        crate::money::display(number, currency),
+                        plain.to_string(),
+                        currency
+                    )));
+                }
+                // 0 - ₹5 is -₹5; ₹5 - 0 and 0 + ₹5 are ₹5.
+                let negate = op == '-' && matches!(right, Value::Money { .. });
+                Ok(money(if negate { -amount } else { *amount }, currency))
+            }
+            ('*', Value::Money { .. }, Value::Money { .. }) => Err(
+                "இரண்டு தொகைகளைப் பெருக்க முடியாது  \
+                 (cannot multiply an amount by an amount — one side should be \
+                 a plain number, such as a rate or a count)"
+                    .to_string(),
+            ),
+            ('*', Value::Money { amount, currency }, plain)
+            | ('*', plain, Value::Money { amount, currency }) => {
+                Ok(money(amount * plain.to_number(), currency))
+            }
+            // ₹300 / ₹1200 is a ratio, not an amount.
+            (
+                '/',
+                Value::Money { amount: a, currency: x },
+                Value::Money { amount: b, currency: y },
+            ) => {
+                if x != y {
+                    return Some(Err(Self::currency_mismatch(op, x, y)));
+                }
+                Ok(Value::Number(a / b))
+            }
+            ('/' | '%', Value::Money { amount, currency }, plain) => {
+                let divisor = plain.to_number();
+                Ok(money(
+                    if op == '/' { amount / divisor } else { amount % divisor },
+                    currency,
+                ))
+            }
+            (_, _, Value::Money { .. }) => Err(
+                "ஒரு எண்ணை ஒரு தொகையால் வகுக்க முடியாது  \
+                 (cannot divide a plain number by an amount)"
+                    .to_string(),
+            ),
+            _ => unreachable!("one operand is an amount"),
+        })
+    }
+
+    fn currency_mismatch(op: char, left: &str, right: &str) -> String {
+        let (verb, joined) = match op {
+            '+' => ("add", format!("{} to {}", right, left)),
+            '-' => ("subtract", format!("{} from {}", right, left)),
+            _ => ("divide", format!("{} by {}", left, right)),
+        };
+        format!(
+            "{} தொகையும் {} தொகையும் வெவ்வேறு நாணயங்கள்  \
+             (cannot {} {} — convert one of them first)",
+            left, right, verb, joined
+        )
+    }
+
+    /// Refuse to order amounts in different currencies. `partial_cmp` has no
+    /// answer for them, and the comparisons would otherwise read that as
+    /// "no" — so `(₹100 < $2)` and `(₹100 > $2)` would both be false.
+    fn comparable(left: &Value, right: &Value) -> Result<(), String> {
+        match (left, right) {
+            (Value::Money { currency: x, .. }, Value::Money { currency: y, .. }) if x != y => {
+                Err(format!(
+                    "{} தொகையை {} தொகையுடன் ஒப்பிட முடியாது  \
+                     (cannot compare {} with {} — convert one of them first)",
+                    x, y, x, y
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// A rounded amount is still an amount; a rounded number is a number.
+    fn same_kind(original: &Value, rounded: Decimal) -> Value {
+        match original {
+            Value::Money { currency, .. } => Value::Money {
+                amount: rounded,
+                currency: currency.clone(),
+            },
+            _ => Value::Number(rounded),
         }
     }
 
@@ -284,14 +502,84 @@ impl VM {
         }
     }
 
+    /// The builtins that take a function and call it, which need the bytecode
+    /// to run it in. `None` when `name` is not one of them, with the stack
+    /// untouched.
+    fn call_higher_order(
+        &mut self,
+        bytecode: &Bytecode,
+        name: &str,
+        argc: usize,
+    ) -> Result<Option<Value>, String> {
+        match name {
+            // மாற்றியமை(அணி, செயல்) — a new array: the function applied to each element
+            "மாற்றியமை" | "mARRiyamY" | "_map" => {
+                let args = self.pop_args(argc)?;
+                Self::expect_args(name, &args, 2)?;
+                let (items, function) = Self::array_and_function(name, &args[0], &args[1])?;
+                let mut mapped = Vec::with_capacity(items.len());
+                for item in items {
+                    mapped.push(self.call_value(bytecode, &function, vec![item])?);
+                }
+                Ok(Some(Value::Array(mapped)))
+            }
+            // வடிகட்டு(அணி, செயல்) — the elements the function answers true for, in order
+            "வடிகட்டு" | "vatikattu" | "_filter" => {
+                let args = self.pop_args(argc)?;
+                Self::expect_args(name, &args, 2)?;
+                let (items, function) = Self::array_and_function(name, &args[0], &args[1])?;
+                let mut kept = Vec::new();
+                for item in items {
+                    if self.call_value(bytecode, &function, vec![item.clone()])?.is_truthy() {
+                        kept.push(item);
+                    }
+                }
+                Ok(Some(Value::Array(kept)))
+            }
+            // சுருக்கு(அணி, தொடக்கம், செயல்) — fold: the function called with the running value and each element in turn
+            "சுருக்கு" | "curukku" | "_reduce" => {
+                let args = self.pop_args(argc)?;
+                Self::expect_args(name, &args, 3)?;
+                let (items, function) = Self::array_and_function(name, &args[0], &args[2])?;
+                let mut running = args[1].clone();
+                for item in items {
+                    running = self.call_value(bytecode, &function, vec![running, item])?;
+                }
+                Ok(Some(running))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The array and the function a higher-order builtin was given.
+    fn array_and_function(
+        name: &str,
+        items: &Value,
+        function: &Value,
+    ) -> Result<(Vec<Value>, Arc<Closure>), String> {
+        let Value::Array(items) = items else {
+            return Err(format!(
+                "{} ஒரு அணி தேவை  ({} needs an array, got {})",
+                name,
+                name,
+                Self::type_name(items)
+            ));
+        };
+        let Value::Function(function) = function else {
+            return Err(format!(
+                "{} ஒரு செயல் தேவை  ({} needs a function, got {})",
+                name,
+                name,
+                Self::type_name(function)
+            ));
+        };
+        Ok((items.clone(), function.clone()))
+    }
+
     /// Builtins, callable under Tamil, romanized or English names. This is
     /// the extension point the tax and accounting builtins will plug into.
     fn call_builtin(&mut self, name: &str, argc: usize) -> Result<Value, String> {
-        let mut args = Vec::with_capacity(argc);
-        for _ in 0..argc {
-            args.push(self.pop()?);
-        }
-        args.reverse();
+        let args = self.pop_args(argc)?;
 
         match name {
             // நீளம் — length of an array, record or string
@@ -445,20 +733,67 @@ impl VM {
                 Self::expect_args(name, &args, 2)?;
                 let places = rust_decimal::prelude::ToPrimitive::to_u32(&args[1].to_number())
                     .ok_or("வட்டமிடு: இடங்கள் ஒரு முழு எண்  (round: places must be a whole number)")?;
-                Ok(Value::Number(args[0].to_number().round_dp_with_strategy(
-                    places,
-                    rust_decimal::RoundingStrategy::MidpointAwayFromZero,
-                )))
+                Ok(Self::same_kind(
+                    &args[0],
+                    args[0].to_number().round_dp_with_strategy(
+                        places,
+                        rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+                    ),
+                ))
             }
             // தரை(n) — floor
             "தரை" | "qarY" | "_floor" => {
                 Self::expect_args(name, &args, 1)?;
-                Ok(Value::Number(args[0].to_number().floor()))
+                Ok(Self::same_kind(&args[0], args[0].to_number().floor()))
             }
             // மேல்(n) — ceiling
             "மேல்" | "mEl" | "_ceil" => {
                 Self::expect_args(name, &args, 1)?;
-                Ok(Value::Number(args[0].to_number().ceil()))
+                Ok(Self::same_kind(&args[0], args[0].to_number().ceil()))
+            }
+            // --- Money ---
+            // பணமாக்கு(தொகை, "USD") — an amount in a currency that has no
+            // literal, or one computed from a plain number.
+            "பணமாக்கு" | "paNamAkku" | "_money" => {
+                Self::expect_args(name, &args, 2)?;
+                if let Value::Money { currency, .. } = &args[0] {
+                    return Err(format!(
+                        "இது ஏற்கனவே {} தொகை  (this is already an amount in {}; \
+                         converting between currencies needs a rate)",
+                        currency, currency
+                    ));
+                }
+                Ok(Value::Money {
+                    amount: args[0].to_number(),
+                    currency: crate::money::code(&args[1].to_string())?,
+                })
+            }
+            // நாணயம்(v) — the currency's code, "INR"; இன்மை for a plain number
+            "நாணயம்" | "nANayam" | "_currency" => {
+                Self::expect_args(name, &args, 1)?;
+                Ok(match &args[0] {
+                    Value::Money { currency, .. } => Value::String(currency.clone()),
+                    _ => Value::Null,
+                })
+            }
+            // நாணய_குறி(v) — the symbol it is shown with, "₹"; the code when
+            // the currency has no symbol, and இன்மை for a plain number
+            "நாணய_குறி" | "nANaya_kuRi" | "_currencySymbol" => {
+                Self::expect_args(name, &args, 1)?;
+                Ok(match &args[0] {
+                    Value::Money { currency, .. } => Value::String(
+                        crate::money::symbol_for_code(currency)
+                            .map(str::to_string)
+                            .unwrap_or_else(|| currency.clone()),
+                    ),
+                    _ => Value::Null,
+                })
+            }
+            // தொகை(v) — the amount as a plain number, for arithmetic that
+            // deliberately leaves the currency behind
+            "தொகை" | "qokY" | "_amount" => {
+                Self::expect_args(name, &args, 1)?;
+                Ok(Value::Number(args[0].to_number()))
             }
             // சொல்லாக்கு(v) — render any value as text
             "சொல்லாக்கு" | "collAkku" | "_toString" => {
@@ -692,6 +1027,351 @@ impl VM {
                 Ok(Value::Ok(Box::new(Value::Null)))
             }
 
+            // --- Streaming a response ---------------------------------------
+            // பதில்_தொடங்கு(நிலை, தலைப்புகள்) — send the status and headers now
+            //
+            // For a body too large to build first: a statement export, a
+            // report over every row. The head goes out at once and each
+            // பதில்_பகுதி after it is sent as it is made, chunked, so the
+            // server holds one part at a time rather than the whole body. A
+            // பதில் after this changes nothing; the head has already gone.
+            "பதில்_தொடங்கு" | "paqil_qotawku" | "_respondStart" => {
+                Self::expect_args(name, &args, 2)?;
+                if self.response_started {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "பதில் ஏற்கனவே தொடங்கப்பட்டது  (the response has already started)"
+                            .to_string(),
+                    ))));
+                }
+                let status = rust_decimal::prelude::ToPrimitive::to_u16(&args[0].to_number())
+                    .unwrap_or(200);
+                let headers = match &args[1] {
+                    Value::Map(fields) => fields
+                        .iter()
+                        .map(|(name, value)| (name.clone(), value.to_string()))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                Ok(self.start_response(status, headers, args[1].clone()))
+            }
+
+            // பதில்_பகுதி(உரை) — send the next part of a streamed response
+            //
+            // Starts the response with 200 if பதில்_தொடங்கு has not. Answers
+            // தவறு once the client has gone, which is the handler's cue to
+            // stop producing rows nobody will read.
+            "பதில்_பகுதி" | "paqil_pakuqi" | "_respondPart" => {
+                Self::expect_args(name, &args, 1)?;
+                if !self.response_started {
+                    let started = self.start_response(200, Vec::new(), Value::Map(HashMap::new()));
+                    if matches!(started, Value::Err(_)) {
+                        return Ok(started);
+                    }
+                }
+                Ok(self.send_part(&args[0].to_string()))
+            }
+
+            // --- Pushing messages ----------------------------------------------
+            // நிகழ்வுகள்_தொடங்கு() — answer with an events stream
+            //
+            // Server-Sent Events: the browser's EventSource keeps the request
+            // open and each செய்தி_அனுப்பு arrives as one message, so a
+            // payment-status screen is told when the state changes instead of
+            // asking every two seconds. One way only; a handler that must
+            // hear from the client opens a WebSocket instead.
+            "நிகழ்வுகள்_தொடங்கு" | "nikazvukaL_qotawku" | "_eventsStart" => {
+                Self::expect_args(name, &args, 0)?;
+                if self.response_started {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "பதில் ஏற்கனவே தொடங்கப்பட்டது  (the response has already started)"
+                            .to_string(),
+                    ))));
+                }
+                // No-cache, and no buffering by a proxy such as nginx, which
+                // would otherwise hold the messages until it had a bufferful.
+                let headers = vec![
+                    ("Content-Type".to_string(), "text/event-stream".to_string()),
+                    ("Cache-Control".to_string(), "no-cache".to_string()),
+                    ("X-Accel-Buffering".to_string(), "no".to_string()),
+                ];
+                let recorded = Value::Map(
+                    headers
+                        .iter()
+                        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                        .collect(),
+                );
+                let started = self.start_response(200, headers, recorded);
+                if matches!(started, Value::Ok(_)) {
+                    self.channel = Some(Channel::Events);
+                }
+                Ok(started)
+            }
+
+            // வலைசாக்கெட்_தொடங்கு() — accept the client's WebSocket
+            //
+            // Answers 101 and turns the request into a socket both sides can
+            // send on. தவறு if the request did not ask to be one.
+            "வலைசாக்கெட்_தொடங்கு" | "valYcAkket_qotawku" | "_socketStart" => {
+                Self::expect_args(name, &args, 0)?;
+                if self.response_started {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "பதில் ஏற்கனவே தொடங்கப்பட்டது  (the response has already started)"
+                            .to_string(),
+                    ))));
+                }
+                let upgraded = match self.response_stream.as_mut() {
+                    Some(stream) => stream.upgrade(),
+                    None => Err("இது வலைசாக்கெட் கோரிக்கை அல்ல  (this request did not ask for a WebSocket)"
+                        .to_string()),
+                };
+                match upgraded {
+                    Ok(()) => {
+                        self.response_started = true;
+                        self.channel = Some(Channel::Socket);
+                        self.variables.insert(
+                            "response_status".to_string(),
+                            Value::Number(Decimal::from(101)),
+                        );
+                        Ok(Value::Ok(Box::new(Value::Null)))
+                    }
+                    Err(why) => Ok(Value::Err(Box::new(Value::String(why)))),
+                }
+            }
+
+            // செய்தி_அனுப்பு(உரை) — push one message to the client
+            //
+            // On an events stream, one event; on a WebSocket, one text
+            // message. தவறு once the client has gone, which is the handler's
+            // cue to stop.
+            "செய்தி_அனுப்பு" | "ceyqi_aZuppu" | "_messageSend" => {
+                Self::expect_args(name, &args, 1)?;
+                let text = args[0].to_string();
+                match self.channel {
+                    Some(Channel::Events) => {
+                        // A line break inside the text would end the field, so
+                        // each line goes as a data line of its own; the
+                        // client joins them back with line breaks.
+                        let mut event: String = text
+                            .split('\n')
+                            .map(|line| format!("data: {}\n", line))
+                            .collect();
+                        event.push('\n');
+                        let sent = self.send_part(&event);
+                        if matches!(sent, Value::Err(_)) {
+                            self.channel = Some(Channel::Closed);
+                        }
+                        Ok(sent)
+                    }
+                    Some(Channel::Socket) => {
+                        let sent = match self.response_stream.as_mut() {
+                            Some(stream) => stream.part(text.as_bytes()),
+                            None => Err("the connection has gone".to_string()),
+                        };
+                        match sent {
+                            Ok(()) => Ok(Value::Ok(Box::new(Value::Null))),
+                            Err(why) => {
+                                self.channel = Some(Channel::Closed);
+                                Ok(Value::Err(Box::new(Value::String(why))))
+                            }
+                        }
+                    }
+                    Some(Channel::Closed) => Ok(Value::Err(Box::new(Value::String(
+                        "இணைப்பு மூடப்பட்டது  (the connection is closed)".to_string(),
+                    )))),
+                    None => Ok(Value::Err(Box::new(Value::String(
+                        "நிகழ்வுகளோ வலைசாக்கெட்டோ திறக்கப்படவில்லை  \
+                         (no events stream or WebSocket is open)"
+                            .to_string(),
+                    )))),
+                }
+            }
+
+            // செய்தி_பெறு(நொடிகள்) — the client's next WebSocket message
+            //
+            // Waits at most that many seconds, or for as long as it takes
+            // with 0. சரி(உரை) for a message, சரி(இன்மை) if none came in
+            // time, தவறு once the client has closed the socket.
+            "செய்தி_பெறு" | "ceyqi_peRu" | "_messageReceive" => {
+                Self::expect_args(name, &args, 1)?;
+                if self.channel != Some(Channel::Socket) {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "வலைசாக்கெட் திறக்கப்படவில்லை  (no WebSocket is open)".to_string(),
+                    ))));
+                }
+                let seconds = rust_decimal::prelude::ToPrimitive::to_f64(&args[0].to_number())
+                    .unwrap_or(0.0)
+                    .max(0.0);
+                // Longer than a Duration holds is no limit, as 0 is.
+                let wait = (seconds > 0.0)
+                    .then(|| std::time::Duration::try_from_secs_f64(seconds).ok())
+                    .flatten();
+                let received = match self.response_stream.as_mut() {
+                    Some(stream) => stream.receive(wait),
+                    None => Err("the connection has gone".to_string()),
+                };
+                match received {
+                    Ok(Some(message)) => Ok(Value::Ok(Box::new(Value::String(message)))),
+                    Ok(None) => Ok(Value::Ok(Box::new(Value::Null))),
+                    Err(why) => {
+                        self.channel = Some(Channel::Closed);
+                        Ok(Value::Err(Box::new(Value::String(why))))
+                    }
+                }
+            }
+
+            // இணைப்பு_மூடு() — end the events stream or close the WebSocket
+            //
+            // The handler returning does the same; this is for closing first
+            // and doing something else after, such as recording the outcome.
+            "இணைப்பு_மூடு" | "iNYppu_mUtu" | "_connectionClose" => {
+                Self::expect_args(name, &args, 0)?;
+                if matches!(self.channel, Some(Channel::Events) | Some(Channel::Socket)) {
+                    self.channel = Some(Channel::Closed);
+                    if let Some(stream) = self.response_stream.as_mut()
+                        && let Err(why) = stream.finish()
+                    {
+                        return Ok(Value::Err(Box::new(Value::String(why))));
+                    }
+                }
+                Ok(Value::Ok(Box::new(Value::Null)))
+            }
+
+            // காத்திரு(நொடிகள்) — wait, then carry on
+            //
+            // For a handler pushing updates: check, send, wait, check again.
+            // Never past the handler's time limit, which is still enforced.
+            "காத்திரு" | "kAqqiru" | "_sleep" => {
+                Self::expect_args(name, &args, 1)?;
+                let seconds = rust_decimal::prelude::ToPrimitive::to_f64(&args[0].to_number())
+                    .unwrap_or(0.0)
+                    .max(0.0);
+                // Longer than a Duration holds is as long as one can be.
+                let mut wait = std::time::Duration::try_from_secs_f64(seconds)
+                    .unwrap_or(std::time::Duration::MAX);
+                if let Some(deadline) = self.deadline {
+                    wait = wait.min(deadline.saturating_duration_since(std::time::Instant::now()));
+                }
+                host::sleep(wait)?;
+                Ok(Value::Null)
+            }
+
+            // பதில்_தலைப்பு(பெயர், மதிப்பு) — add one header to the response
+            //
+            // Alongside whatever the response already has, where பதில் with
+            // a headers record replaces them all. For a பின் block adding a
+            // header to every route's answer without knowing what each route
+            // set. Too late once a streamed response has started.
+            "பதில்_தலைப்பு" | "paqil_qalYppu" | "_responseHeader" => {
+                Self::expect_args(name, &args, 2)?;
+                if self.response_started {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "பதில் ஏற்கனவே தொடங்கப்பட்டது  (the response has already started)"
+                            .to_string(),
+                    ))));
+                }
+                Self::check_header(&args[0].to_string(), &args[1].to_string())?;
+                let mut fields = match self.variables.get("response_headers") {
+                    Some(Value::Map(fields)) => fields.clone(),
+                    _ => HashMap::new(),
+                };
+                fields.insert(args[0].to_string(), Value::String(args[1].to_string()));
+                self.variables
+                    .insert("response_headers".to_string(), Value::Map(fields));
+                Ok(Value::Ok(Box::new(Value::Null)))
+            }
+
+            // --- Cookies and sessions ---------------------------------------
+            // The request's cookies arrive parsed, as `cookies`; these set the
+            // response's, and keep a session on the server. See
+            // src/http/session.rs.
+
+            // குக்கீ_அமை(பெயர், மதிப்பு, விருப்பங்கள்) — set a cookie
+            //
+            // விருப்பங்கள் is a record of the attributes as RFC 6265 names
+            // them — HttpOnly, Secure, SameSite, "Max-Age" in seconds,
+            // Expires as a date, Path, Domain — or இன்மை for none. Path is /
+            // unless given. Setting one name twice sends the second.
+            "குக்கீ_அமை" | "kukkI_amY" | "_setCookie" => {
+                Self::expect_args(name, &args, 3)?;
+                if self.response_started {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "பதில் ஏற்கனவே தொடங்கப்பட்டது  (the response has already started)"
+                            .to_string(),
+                    ))));
+                }
+                let cookie = Self::cookie_line(&args[0].to_string(), &args[1].to_string(), &args[2])?;
+                self.add_cookie(cookie);
+                Ok(Value::Ok(Box::new(Value::Null)))
+            }
+            // குக்கீ_நீக்கு(பெயர்) — tell the browser to forget one set at /
+            "குக்கீ_நீக்கு" | "kukkI_nIkku" | "_deleteCookie" => {
+                Self::expect_args(name, &args, 1)?;
+                if self.response_started {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "பதில் ஏற்கனவே தொடங்கப்பட்டது  (the response has already started)"
+                            .to_string(),
+                    ))));
+                }
+                let mut options = HashMap::new();
+                options.insert("Max-Age".to_string(), Value::Number(Decimal::ZERO));
+                let cookie = Self::cookie_line(&args[0].to_string(), "", &Value::Map(options))?;
+                self.add_cookie(cookie);
+                Ok(Value::Ok(Box::new(Value::Null)))
+            }
+
+            // அமர்வு_பெறு(பெயர்) — a value from the request's session
+            //
+            // இன்மை when there is no session, or nothing by that name in it.
+            // A session keeps text, as Redis does: a number written to one
+            // comes back as its digits, for எண்() to make a number again.
+            "அமர்வு_பெறு" | "amarvu_peRu" | "_sessionGet" => {
+                Self::expect_args(name, &args, 1)?;
+                let Some(id) = &self.session else {
+                    return Ok(Value::Null);
+                };
+                match crate::http::session::get(id, &args[0].to_string()) {
+                    Ok(value) => Ok(value.map_or(Value::Null, Value::String)),
+                    Err(why) => Err(format!("அமர்வைப் படிக்க முடியவில்லை  (cannot read the session): {}", why)),
+                }
+            }
+            // அமர்வு_அமை(பெயர், மதிப்பு) — write one, starting a session if
+            // the request has none
+            //
+            // Each write sends the session cookie again, so a session in use
+            // does not expire under the person using it.
+            "அமர்வு_அமை" | "amarvu_amY" | "_sessionSet" => {
+                Self::expect_args(name, &args, 2)?;
+                if self.response_started && self.session.is_none() {
+                    return Ok(Value::Err(Box::new(Value::String(
+                        "பதில் ஏற்கனவே தொடங்கப்பட்டது  (the response has already started)"
+                            .to_string(),
+                    ))));
+                }
+                let id = self
+                    .session
+                    .clone()
+                    .unwrap_or_else(crate::http::session::start);
+                crate::http::session::set(&id, &args[0].to_string(), &args[1].to_string())
+                    .map_err(|why| format!("அமர்வில் எழுத முடியவில்லை  (cannot write to the session): {}", why))?;
+                if !self.response_started {
+                    self.add_cookie(crate::http::session::cookie_for(&id, self.secure));
+                }
+                self.session = Some(id);
+                Ok(Value::Ok(Box::new(Value::Null)))
+            }
+            // அமர்வு_முடி() — end the session and everything in it: signing out
+            "அமர்வு_முடி" | "amarvu_muti" | "_sessionEnd" => {
+                Self::expect_args(name, &args, 0)?;
+                if let Some(id) = self.session.take() {
+                    crate::http::session::end(&id)
+                        .map_err(|why| format!("அமர்வை முடிக்க முடியவில்லை  (cannot end the session): {}", why))?;
+                }
+                if !self.response_started {
+                    self.add_cookie(crate::http::session::ended_cookie(self.secure));
+                }
+                Ok(Value::Ok(Box::new(Value::Null)))
+            }
+
             // --- Uploads ----------------------------------------------------
             // பதிவேற்றம்_சேமி(குறியீடு, கோப்பு) — write an uploaded file out
             //
@@ -785,6 +1465,45 @@ impl VM {
                 return Ok(Value::Ok(Box::new(Value::Number(Decimal::from(status)))));
             }
 
+            // --- Expectations -----------------------------------------------
+            // எதிர்பார்(நிபந்தனை, விவரம்) — stop here unless the condition holds
+            //
+            // For `etamil test`. A failed expectation is a runtime error, so it
+            // ends the test and is reported with the line it was written on and
+            // the calls that led there — which is what an அச்சு of "✗" cannot
+            // say.
+            "எதிர்பார்" | "eqirpAr" | "_expect" => {
+                Self::expect_args(name, &args, 2)?;
+                if args[0].is_truthy() {
+                    return Ok(Value::Boolean(true));
+                }
+                Err(format!(
+                    "எதிர்பார்ப்பு தவறியது: {}  (expectation failed: {})",
+                    args[1].to_string(),
+                    args[1].to_string()
+                ))
+            }
+            // எதிர்பார்_சமம்(கிடைத்தது, எதிர்பார்த்தது, விவரம்) — the two must be equal
+            //
+            // Separate for the same reason nUlakam's சமம் is: a failure that
+            // shows both values does not send the reader back to work the
+            // calculation out by hand.
+            "எதிர்பார்_சமம்" | "eqirpAr_camam" | "_expectEqual" => {
+                Self::expect_args(name, &args, 3)?;
+                if args[0] == args[1] {
+                    return Ok(Value::Boolean(true));
+                }
+                Err(format!(
+                    "எதிர்பார்ப்பு தவறியது: {}  (expectation failed: {})\n      \
+                     எதிர்பார்த்தது (expected): {}\n      \
+                     கிடைத்தது   (got):      {}",
+                    args[2].to_string(),
+                    args[2].to_string(),
+                    crate::vm::debug::shown(&args[1]),
+                    crate::vm::debug::shown(&args[0])
+                ))
+            }
+
             // --- Signing with a key only one side holds ---------------------
             // கையொப்பம் is HMAC: it proves a message came from someone holding
             // the same secret you do. Both sides can forge each other's
@@ -1272,12 +1991,12 @@ impl VM {
             // வலை_பெறு(உரலி, தலைப்புகள்)
             "வலை_பெறு" | "valY_peRu" | "_httpGet" => {
                 Self::expect_args(name, &args, 2)?;
-                Ok(Self::http_call("GET", &args[0], None, &args[1]))
+                Ok(Self::http_call("GET", &args[0], None, &args[1], None))
             }
             // வலை_பதி(உரலி, உடலுரை, தலைப்புகள்)
             "வலை_பதி" | "valY_paqi" | "_httpPost" => {
                 Self::expect_args(name, &args, 3)?;
-                Ok(Self::http_call("POST", &args[0], Some(&args[1]), &args[2]))
+                Ok(Self::http_call("POST", &args[0], Some(&args[1]), &args[2], None))
             }
             // வலை_அனுப்பு(முறை, உரலி, உடலுரை, தலைப்புகள்) — any method
             "வலை_அனுப்பு" | "valY_aZuppu" | "_httpRequest" => {
@@ -1288,7 +2007,28 @@ impl VM {
                     Value::Null => None,
                     other => Some(other),
                 };
-                Ok(Self::http_call(&method, &args[1], body, &args[3]))
+                Ok(Self::http_call(&method, &args[1], body, &args[3], None))
+            }
+            // வலை_காப்பு_அனுப்பு(முறை, உரலி, உடலுரை, தலைப்புகள், கொள்கை) — retried, through a breaker
+            //
+            // As வலை_அனுப்பு, but கொள்கை says what to do when the other end
+            // is down: மீள்முயற்சிகள் (retries, 3), காத்திருப்பு (seconds
+            // before the first, 0.1, doubling each time), அதிக_காத்திருப்பு
+            // (the longest wait, 10), மீள்_நிலைகள் (statuses retried, 429
+            // 502 503 504), and சுற்று, the name of a circuit breaker every
+            // call naming it shares, with தோல்விகள் (failures in a row that
+            // open it, 5) and திறப்பு (seconds it stays open, 60). The English
+            // names — retries, backoff, max_backoff, retry_on, breaker,
+            // failures, open_for — work too. See src/http/resilience.rs.
+            "வலை_காப்பு_அனுப்பு" | "valY_kAppu_aZuppu" | "_httpRequestGuarded" => {
+                Self::expect_args(name, &args, 5)?;
+                let policy = Self::call_policy(&args[4])?;
+                let method = args[0].to_string().to_uppercase();
+                let body = match &args[2] {
+                    Value::Null => None,
+                    other => Some(other),
+                };
+                Ok(Self::http_call(&method, &args[1], body, &args[3], Some(&policy)))
             }
             unknown => Err(format!(
                 "அறியப்படாத செயல் '{}'  (unknown function '{}')",
@@ -1356,6 +2096,175 @@ impl VM {
         format!("{:04}-{:02}-{:02}", year, month, day)
     }
 
+    /// Refuses a header பதில்_தலைப்பு could not send as it was given: a name
+    /// that is not an HTTP token, or a value with a line break or another
+    /// control character in it, which would end the header early and start
+    /// one the program never wrote.
+    fn check_header(name: &str, value: &str) -> Result<(), String> {
+        let separator = |c: char| "()<>@,;:\\\"/[]?={}".contains(c);
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic() && !separator(c)) {
+            return Err(format!(
+                "'{}' தலைப்புப் பெயர் அல்ல  ('{}' cannot name a header)",
+                name.escape_debug(),
+                name.escape_debug()
+            ));
+        }
+        if value.chars().any(|c| c.is_control() && c != '\t') {
+            return Err(format!(
+                "தலைப்பு '{}' மதிப்பில் கட்டுப்பாட்டு எழுத்து  \
+                 (header '{}' has a line break or another control character in its value)",
+                name, name
+            ));
+        }
+        Ok(())
+    }
+
+    /// A `Set-Cookie` value from what குக்கீ_அமை was given.
+    ///
+    /// Refuses what a browser would drop without a word — a name or value
+    /// with a space or a semicolon in it, `SameSite=None` without `Secure` —
+    /// since a cookie that silently never arrives is the hardest kind of bug
+    /// to find.
+    fn cookie_line(name: &str, value: &str, options: &Value) -> Result<String, String> {
+        let separator = |c: char| "()<>@,;:\\\"/[]?={}".contains(c);
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic() && !separator(c)) {
+            return Err(format!(
+                "'{}' குக்கீப் பெயர் அல்ல  ('{}' cannot name a cookie)",
+                name, name
+            ));
+        }
+        // RFC 6265's cookie-octet: printable ASCII but for the quote, comma,
+        // semicolon and backslash.
+        if !value.chars().all(|c| c.is_ascii_graphic() && !"\",;\\".contains(c)) {
+            return Err(format!(
+                "குக்கீ '{}' மதிப்பில் இடம், ; , \" அல்லது ASCII அல்லாத எழுத்து  \
+                 (cookie '{}' has a space, ; , \" or a non-ASCII character in its value; encode it first)",
+                name, name
+            ));
+        }
+
+        let mut line = format!("{}={}", name, value);
+        let mut path = "/".to_string();
+        let mut secure = false;
+        let mut same_site = None;
+        let fields = match options {
+            Value::Map(fields) => fields.clone(),
+            Value::Null => HashMap::new(),
+            other => {
+                return Err(format!(
+                    "குக்கீ விருப்பங்கள் ஒரு பொருள் தேவை  (the cookie's options must be a record, got {})",
+                    Self::type_name(other)
+                ));
+            }
+        };
+        // Sorted, so one set of options always makes one header.
+        let mut fields: Vec<_> = fields.into_iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, option) in fields {
+            match key.to_ascii_lowercase().as_str() {
+                "httponly" => {
+                    if option.to_boolean() {
+                        line.push_str("; HttpOnly");
+                    }
+                }
+                "secure" => secure = option.to_boolean(),
+                "samesite" => {
+                    let given = option.to_string();
+                    same_site = Some(match given.to_ascii_lowercase().as_str() {
+                        "strict" => "Strict",
+                        "lax" => "Lax",
+                        "none" => "None",
+                        _ => {
+                            return Err(format!(
+                                "SameSite '{}' அல்ல  (SameSite is Strict, Lax or None, not '{}')",
+                                given, given
+                            ));
+                        }
+                    });
+                }
+                "max-age" => {
+                    let seconds = match &option {
+                        Value::Number(n) => rust_decimal::prelude::ToPrimitive::to_i64(n),
+                        _ => None,
+                    }
+                    .filter(|seconds| *seconds >= 0)
+                    .ok_or_else(|| {
+                        format!(
+                            "Max-Age நொடிகளில் தேவை  (Max-Age must be a number of seconds, got '{}')",
+                            option.to_string()
+                        )
+                    })?;
+                    line.push_str(&format!("; Max-Age={}", seconds));
+                }
+                "expires" => {
+                    let days = Self::parse_date(&option)?;
+                    let (year, month, day) = Self::civil_from_days(days);
+                    const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+                    const MONTHS: [&str; 12] = [
+                        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+                    ];
+                    line.push_str(&format!(
+                        "; Expires={}, {:02} {} {:04} 00:00:00 GMT",
+                        WEEKDAYS[(days + 4).rem_euclid(7) as usize],
+                        day,
+                        MONTHS[(month - 1) as usize],
+                        year
+                    ));
+                }
+                "path" | "domain" => {
+                    let given = option.to_string();
+                    if given.chars().any(|c| c == ';' || c.is_control()) {
+                        return Err(format!(
+                            "குக்கீ {} '{}' செல்லாது  (the cookie's {} cannot be '{}')",
+                            key, given, key, given
+                        ));
+                    }
+                    if key.eq_ignore_ascii_case("path") {
+                        path = given;
+                    } else {
+                        line.push_str(&format!("; Domain={}", given));
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "'{}' குக்கீ விருப்பம் அல்ல  ('{}' is not a cookie option: use HttpOnly, Secure, \
+                         SameSite, Max-Age, Expires, Path or Domain)",
+                        key, key
+                    ));
+                }
+            }
+        }
+        if same_site == Some("None") && !secure {
+            return Err(
+                "SameSite=None உடன் Secure தேவை  (SameSite=None needs Secure, or browsers drop the cookie)"
+                    .to_string(),
+            );
+        }
+        line.push_str(&format!("; Path={}", path));
+        if secure {
+            line.push_str("; Secure");
+        }
+        if let Some(same_site) = same_site {
+            line.push_str(&format!("; SameSite={}", same_site));
+        }
+        Ok(line)
+    }
+
+    /// Add a cookie to the response, in place of any earlier one by the same
+    /// name.
+    fn add_cookie(&mut self, line: String) {
+        let name_of = |line: &str| line.split('=').next().unwrap_or_default().to_string();
+        let name = name_of(&line);
+        let mut cookies = match self.variables.remove("response_cookies") {
+            Some(Value::Array(cookies)) => cookies,
+            _ => Vec::new(),
+        };
+        cookies.retain(|cookie| name_of(&cookie.to_string()) != name);
+        cookies.push(Value::String(line));
+        self.variables
+            .insert("response_cookies".to_string(), Value::Array(cookies));
+    }
+
     /// Make one HTTP request, handing the language back a result.
     ///
     /// A non-2xx status is a *successful* call: a gateway declining a charge
@@ -1367,6 +2276,7 @@ impl VM {
         url: &Value,
         body: Option<&Value>,
         headers: &Value,
+        policy: Option<&crate::http::resilience::Policy>,
     ) -> Value {
         let headers: Vec<(String, String)> = match headers {
             Value::Map(fields) => fields
@@ -1380,7 +2290,13 @@ impl VM {
 
         let body = body.map(|value| value.to_string());
 
-        match crate::net::request(method, &url.to_string(), body.as_deref(), &headers) {
+        let sent = match policy {
+            Some(policy) => {
+                crate::http::resilience::send(policy, method, &url.to_string(), body.as_deref(), &headers)
+            }
+            None => crate::net::request(method, &url.to_string(), body.as_deref(), &headers),
+        };
+        match sent {
             Ok(response) => {
                 let mut record = HashMap::with_capacity(3);
                 record.insert(
@@ -1404,6 +2320,145 @@ impl VM {
         }
     }
 
+    /// Read the record `வலை_காப்பு_அனுப்பு` is given. A name it does not know
+    /// is an error rather than ignored: a misspelt சுற்று would otherwise
+    /// leave a payment call with no breaker and nobody the wiser.
+    fn call_policy(given: &Value) -> Result<crate::http::resilience::Policy, String> {
+        use crate::http::resilience::{CircuitBreakerConfig, Policy};
+        use rust_decimal::prelude::ToPrimitive;
+
+        let fields = match given {
+            Value::Map(fields) => fields,
+            other => {
+                return Err(format!(
+                    "கொள்கை ஒரு பொருள் தேவை  (the policy must be a record, got {})",
+                    Self::type_name(other)
+                ));
+            }
+        };
+        // Longer than a Duration holds is refused like any other bad wait.
+        let seconds = |key: &str, value: &Value| match value {
+            Value::Number(n) if !n.is_sign_negative() => {
+                std::time::Duration::try_from_secs_f64(n.to_f64().unwrap_or_default()).map_err(|_| ())
+            }
+            _ => Err(()),
+        }
+        .map_err(|_| {
+            format!(
+                "கொள்கையின் '{}' நொடிகளில் தேவை  (the policy's '{}' must be a number of seconds, got '{}')",
+                key, key, value.to_string()
+            )
+        });
+        let count = |key: &str, value: &Value| match value {
+            Value::Number(n) if n.fract().is_zero() => n.to_u32().ok_or(()),
+            _ => Err(()),
+        }
+        .map_err(|_| {
+            format!(
+                "கொள்கையின் '{}' ஒரு முழு எண் தேவை  (the policy's '{}' must be a whole number, got '{}')",
+                key, key, value.to_string()
+            )
+        });
+
+        let mut policy = Policy::default();
+        let mut breaker = None;
+        let mut breaker_config = CircuitBreakerConfig::default();
+        for (key, value) in fields {
+            match key.as_str() {
+                "மீள்முயற்சிகள்" | "retries" => policy.retry.max_retries = count(key, value)?,
+                "காத்திருப்பு" | "backoff" => policy.retry.initial_delay = seconds(key, value)?,
+                "அதிக_காத்திருப்பு" | "max_backoff" => policy.retry.max_delay = seconds(key, value)?,
+                "மீள்_நிலைகள்" | "retry_on" => policy.retry_on = Self::policy_statuses(key, value)?,
+                "சுற்று" | "breaker" => breaker = Some(value.to_string()),
+                "தோல்விகள்" | "failures" => breaker_config.failure_threshold = count(key, value)?.max(1),
+                "திறப்பு" | "open_for" => breaker_config.timeout = seconds(key, value)?,
+                unknown => {
+                    return Err(format!(
+                        "கொள்கையில் '{}' என்ற பெயர் இல்லை  (the policy has no '{}'; it takes \
+                         மீள்முயற்சிகள், காத்திருப்பு, அதிக_காத்திருப்பு, மீள்_நிலைகள், சுற்று, தோல்விகள் and திறப்பு)",
+                        unknown, unknown
+                    ));
+                }
+            }
+        }
+        policy.breaker = breaker.map(|name| (name, breaker_config));
+        Ok(policy)
+    }
+
+    /// The statuses a policy retries: an array of them, each a real one.
+    fn policy_statuses(key: &str, value: &Value) -> Result<Vec<u16>, String> {
+        let Value::Array(statuses) = value else {
+            return Err(format!(
+                "கொள்கையின் '{}' நிலைக் குறிகளின் அணி தேவை  \
+                 (the policy's '{}' must be an array of statuses, got {})",
+                key,
+                key,
+                Self::type_name(value)
+            ));
+        };
+        statuses
+            .iter()
+            .map(|status| match status {
+                Value::Number(n) => rust_decimal::prelude::ToPrimitive::to_u16(n)
+                    .filter(|code| (100..600).contains(code)),
+                _ => None,
+            })
+            .collect::<Option<Vec<u16>>>()
+            .ok_or_else(|| {
+                format!(
+                    "கொள்கையின் '{}' நிலைக் குறிகள் 100 முதல் 599 வரை  \
+                     (the policy's '{}' must hold statuses from 100 to 599)",
+                    key, key
+                )
+            })
+    }
+
+    /// Send one part of a streamed response, or, with no connection to send
+    /// it to, add it to `response_body`.
+    fn send_part(&mut self, part: &str) -> Value {
+        match self.response_stream.as_mut() {
+            Some(stream) => match stream.part(part.as_bytes()) {
+                Ok(()) => Value::Ok(Box::new(Value::Null)),
+                Err(why) => Value::Err(Box::new(Value::String(why))),
+            },
+            None => {
+                let mut body = self
+                    .variables
+                    .get("response_body")
+                    .map(|value| value.to_string())
+                    .unwrap_or_default();
+                body.push_str(part);
+                self.variables
+                    .insert("response_body".to_string(), Value::String(body));
+                Value::Ok(Box::new(Value::Null))
+            }
+        }
+    }
+
+    /// Send the head of a streamed response, or, with no connection to send
+    /// it to, record it the way பதில் would.
+    fn start_response(&mut self, status: u16, mut headers: Vec<(String, String)>, recorded: Value) -> Value {
+        self.response_started = true;
+        // The cookies set so far go out with the head; none can follow it.
+        if let Some(Value::Array(cookies)) = self.variables.get("response_cookies") {
+            headers.extend(cookies.iter().map(|cookie| ("Set-Cookie".to_string(), cookie.to_string())));
+        }
+        self.variables.insert(
+            "response_status".to_string(),
+            Value::Number(rust_decimal::Decimal::from(status)),
+        );
+        self.variables.insert("response_headers".to_string(), recorded);
+        self.variables
+            .insert("response_body".to_string(), Value::String(String::new()));
+        match self.response_stream.as_mut() {
+            Some(stream) => match stream.start(status, headers) {
+                Ok(()) => Value::Ok(Box::new(Value::Null)),
+                Err(why) => Value::Err(Box::new(Value::String(why))),
+            },
+            None => Value::Ok(Box::new(Value::Null)),
+        }
+    }
+
     fn expect_args(name: &str, args: &[Value], want: usize) -> Result<(), String> {
         if args.len() != want {
             return Err(format!(
@@ -1697,7 +2752,7 @@ impl VM {
     }
 
     pub fn execute(&mut self, bytecode: Bytecode) -> Result<(), String> {
-        self.run(bytecode, None)
+        self.run(bytecode, None, None)
     }
 
     /// Execute, but give up after `max_steps` instructions.
@@ -1708,522 +2763,730 @@ impl VM {
     /// callers use `execute`, which has no ceiling -- a long-running report is
     /// a legitimate thing for a server to do.
     pub fn execute_limited(&mut self, bytecode: Bytecode, max_steps: u64) -> Result<(), String> {
-        self.run(bytecode, Some(max_steps))
+        self.run(bytecode, Some(max_steps), None)
+    }
+
+    /// Execute, but give up at `deadline`.
+    ///
+    /// The server's version of `execute_limited`: a handler stuck in a loop
+    /// would otherwise hold its worker for good, and a few of them take the
+    /// server down. The clock is checked between instructions, so one that
+    /// blocks — a slow query — finishes before the handler is stopped.
+    pub fn execute_until(
+        &mut self,
+        bytecode: Bytecode,
+        deadline: std::time::Instant,
+    ) -> Result<(), String> {
+        self.run(bytecode, None, Some(deadline))
+    }
+
+    /// Run, and say where a failure happened.
+    ///
+    /// The instruction pointer is left on the instruction that failed, and the
+    /// frames of any calls in progress are still in place, so both can be
+    /// looked up in the bytecode's source map after the fact.
+    fn run(
+        &mut self,
+        bytecode: Bytecode,
+        max_steps: Option<u64>,
+        deadline: Option<std::time::Instant>,
+    ) -> Result<(), String> {
+        self.steps = 0;
+        self.step_limit = max_steps;
+        self.deadline = deadline;
+        self.timed_out = false;
+        self.run_steps(&bytecode)
+            .map_err(|message| self.locate(&bytecode, message))
+    }
+
+    /// A runtime error with its position in front and, when it happened inside
+    /// a function, the call stack that led there underneath:
+    ///
+    /// ```text
+    /// kAcu.qmz, வரி 4, நெடுவரிசை 5  (line 4, column 5): பூஜ்ஜியத்தால் வகுத்தல்  (division by zero)
+    ///   அழைப்பு அடுக்கு  (call stack):
+    ///     பங்கிடு — kAcu.qmz, வரி 4, நெடுவரிசை 5  (line 4, column 5)
+    ///     நிரல் (top level) — kAcu.qmz, வரி 9, நெடுவரிசை 1  (line 9, column 1)
+    /// ```
+    ///
+    /// Bytecode compiled without positions leaves the message as it was.
+    pub fn locate(&self, bytecode: &Bytecode, message: String) -> String {
+        let Some(at) = bytecode.position_of(self.instruction_pointer) else {
+            return message;
+        };
+        let mut located = format!("{}: {}", at, message);
+        if self.frames.is_empty() {
+            return located;
+        }
+
+        located.push_str("\n  அழைப்பு அடுக்கு  (call stack):");
+        // Innermost first. Each frame's return address is the instruction
+        // after the call, so the call itself is the one before it.
+        let mut here = Some(at);
+        for frame in self.frames.iter().rev() {
+            located.push_str(&format!("\n    {} — {}", frame.function, Self::place(here)));
+            here = bytecode.position_of(frame.return_ip.saturating_sub(1));
+        }
+        located.push_str(&format!("\n    நிரல் (top level) — {}", Self::place(here)));
+        located
+    }
+
+    fn place(at: Option<&crate::parser::Position>) -> String {
+        match at {
+            Some(at) => at.to_string(),
+            None => "இடம் தெரியவில்லை  (position unknown)".to_string(),
+        }
     }
 
-    fn run(&mut self, bytecode: Bytecode, max_steps: Option<u64>) -> Result<(), String> {
-        let mut steps: u64 = 0;
+    fn run_steps(&mut self, bytecode: &Bytecode) -> Result<(), String> {
         while self.instruction_pointer < bytecode.instructions.len() {
-            if let Some(limit) = max_steps {
-                steps += 1;
-                if steps > limit {
-                    return Err(format!(
-                        "நிரல் {} செயல்முறைகளுக்குப் பிறகும் முடியவில்லை — முடிவில்லாத சுற்று?  \
-                         (the program was still running after {} instructions — an endless loop?)",
-                        limit, limit
-                    ));
-                }
+            if !self.step(bytecode)? {
+                break;
             }
-            let instruction = bytecode.instructions[self.instruction_pointer].clone();
-            
-            match instruction {
-                Instruction::Push(value) => {
-                    self.stack.push(value);
-                }
-                Instruction::Pop => {
-                    self.stack.pop();
-                }
-                Instruction::StoreVar(name) => {
-                    if let Some(value) = self.stack.pop() {
-                        self.set_var(name, value);
-                    }
-                }
-                Instruction::LoadVar(name) => {
-                    // An unknown name used to silently load Null, which
-                    // to_number() then turned into 0.0 — a typo became a
-                    // wrong answer with no diagnostic.
-                    let value = self.get_var(&name).ok_or_else(|| {
-                        format!(
-                            "அறிவிக்கப்படாத மாறி '{}'  (undefined variable '{}')",
-                            name, name
-                        )
-                    })?;
-                    self.stack.push(value);
-                }
-                Instruction::Add => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    self.stack.push(Value::Number(left.to_number() + right.to_number()));
-                }
-                Instruction::Subtract => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    self.stack.push(Value::Number(left.to_number() - right.to_number()));
-                }
-                Instruction::Multiply => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    self.stack.push(Value::Number(left.to_number() * right.to_number()));
-                }
-                Instruction::Divide => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    let divisor = right.to_number();
-                    if divisor == Decimal::ZERO {
-                        return Err("பூஜ்ஜியத்தால் வகுத்தல்  (division by zero)".to_string());
-                    }
-                    // Division stays exact to the decimal type's full
-                    // precision. Rounding is deliberately not applied here:
-                    // Indian tax computation rounds once at the end, and
-                    // rounding every intermediate would compound error in a
-                    // chained calculation.
-                    self.stack.push(Value::Number(left.to_number() / divisor));
-                }
-                Instruction::Modulo => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    self.stack.push(Value::Number(left.to_number() % right.to_number()));
-                }
-                Instruction::Equal => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    self.stack.push(Value::Boolean(left == right));
-                }
-                Instruction::NotEqual => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    self.stack.push(Value::Boolean(left != right));
-                }
-                Instruction::LessThan => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    let result = left.partial_cmp(&right)
-                        .map(|ord| ord == std::cmp::Ordering::Less)
-                        .unwrap_or(false);
-                    self.stack.push(Value::Boolean(result));
-                }
-                Instruction::LessOrEqual => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    let result = left.partial_cmp(&right)
-                        .map(|ord| ord != std::cmp::Ordering::Greater)
-                        .unwrap_or(false);
-                    self.stack.push(Value::Boolean(result));
-                }
-                Instruction::GreaterThan => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    let result = left.partial_cmp(&right)
-                        .map(|ord| ord == std::cmp::Ordering::Greater)
-                        .unwrap_or(false);
-                    self.stack.push(Value::Boolean(result));
-                }
-                Instruction::GreaterOrEqual => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    let result = left.partial_cmp(&right)
-                        .map(|ord| ord != std::cmp::Ordering::Less)
-                        .unwrap_or(false);
-                    self.stack.push(Value::Boolean(result));
-                }
-                Instruction::Concat => {
-                    let right = self.stack.pop().ok_or("Stack underflow")?;
-                    let left = self.stack.pop().ok_or("Stack underflow")?;
-                    let result = format!("{}{}", left.to_string(), right.to_string());
-                    self.stack.push(Value::String(result));
-                }
-                Instruction::Print => {
-                    if let Some(value) = self.stack.pop() {
-                        host::print_line(&value.to_string());
-                    }
-                }
-                Instruction::Input => {
-                    let input = host::read_line()?;
-                    self.stack.push(Value::String(input.trim().to_string()));
-                }
-                Instruction::JumpIfFalse(target) => {
-                    if let Some(value) = self.stack.pop() {
-                        if !value.is_truthy() {
-                            self.instruction_pointer = target;
-                            continue;
+        }
+
+        Ok(())
+    }
+
+    /// Execute the one instruction at the instruction pointer, and move past
+    /// it. `false` once the program has halted.
+    ///
+    /// This is the whole of the interpreter; `execute` calls it in a loop, and
+    /// the debugger calls it between deciding whether to stop.
+    pub fn step(&mut self, bytecode: &Bytecode) -> Result<bool, String> {
+        if self.instruction_pointer >= bytecode.instructions.len() {
+            return Ok(false);
+        }
+        if self.step_limit.is_some() || self.deadline.is_some() {
+            self.steps += 1;
+        }
+        if let Some(limit) = self.step_limit
+            && self.steps > limit
+        {
+            return Err(format!(
+                "நிரல் {} செயல்முறைகளுக்குப் பிறகும் முடியவில்லை — முடிவில்லாத சுற்று?  \
+                 (the program was still running after {} instructions — an endless loop?)",
+                limit, limit
+            ));
+        }
+        // Reading the clock on every instruction would cost more than most
+        // instructions do, so it is read every thousand or so.
+        if let Some(deadline) = self.deadline
+            && self.steps.is_multiple_of(1024)
+            && std::time::Instant::now() >= deadline
+        {
+            self.timed_out = true;
+            return Err("நேர வரம்பு கடந்தது  (the program ran past its time limit)".to_string());
+        }
+        let instruction = bytecode.instructions[self.instruction_pointer].clone();
+        
+        match instruction {
+            Instruction::Push(value) => {
+                self.stack.push(value);
+            }
+            Instruction::Pop => {
+                self.stack.pop();
+            }
+            Instruction::StoreVar(name) => {
+                if let Some(value) = self.stack.pop() {
+                    self.set_var(name, value);
+                }
+            }
+            Instruction::LoadVar(name) => {
+                // An unknown name used to silently load Null, which
+                // to_number() then turned into 0.0 — a typo became a
+                // wrong answer with no diagnostic.
+                let value = match self.get_var(&name) {
+                    Some(value) => value,
+                    // A function's name, written where a value goes:
+                    // `மாற்றியமை(தொகைகள், வரி_சேர்)`.
+                    None => match bytecode.functions.get(&name) {
+                        Some(info) => Value::Function(Arc::new(Closure {
+                            start: info.start,
+                            params: info.params.clone(),
+                            captured: HashMap::new(),
+                            name: Some(name),
+                        })),
+                        None => {
+                            return Err(format!(
+                                "அறிவிக்கப்படாத மாறி '{}'  (undefined variable '{}')",
+                                name, name
+                            ));
                         }
+                    },
+                };
+                self.stack.push(value);
+            }
+            Instruction::Add => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                let result = match Self::money_arithmetic('+', &left, &right) {
+                    Some(money) => money?,
+                    None => Value::Number(left.to_number() + right.to_number()),
+                };
+                self.stack.push(result);
+            }
+            Instruction::Subtract => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                let result = match Self::money_arithmetic('-', &left, &right) {
+                    Some(money) => money?,
+                    None => Value::Number(left.to_number() - right.to_number()),
+                };
+                self.stack.push(result);
+            }
+            Instruction::Multiply => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                let result = match Self::money_arithmetic('*', &left, &right) {
+                    Some(money) => money?,
+                    None => Value::Number(left.to_number() * right.to_number()),
+                };
+                self.stack.push(result);
+            }
+            Instruction::Divide => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                let divisor = right.to_number();
+                if divisor == Decimal::ZERO {
+                    return Err("பூஜ்ஜியத்தால் வகுத்தல்  (division by zero)".to_string());
+                }
+                // Division stays exact to the decimal type's full
+                // precision. Rounding is deliberately not applied here:
+                // Indian tax computation rounds once at the end, and
+                // rounding every intermediate would compound error in a
+                // chained calculation.
+                let result = match Self::money_arithmetic('/', &left, &right) {
+                    Some(money) => money?,
+                    None => Value::Number(left.to_number() / divisor),
+                };
+                self.stack.push(result);
+            }
+            Instruction::Modulo => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                let result = match Self::money_arithmetic('%', &left, &right) {
+                    Some(money) => money?,
+                    None => Value::Number(left.to_number() % right.to_number()),
+                };
+                self.stack.push(result);
+            }
+            Instruction::Equal => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                self.stack.push(Value::Boolean(left == right));
+            }
+            Instruction::NotEqual => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                self.stack.push(Value::Boolean(left != right));
+            }
+            Instruction::LessThan => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                Self::comparable(&left, &right)?;
+                let result = left.partial_cmp(&right)
+                    .map(|ord| ord == std::cmp::Ordering::Less)
+                    .unwrap_or(false);
+                self.stack.push(Value::Boolean(result));
+            }
+            Instruction::LessOrEqual => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                Self::comparable(&left, &right)?;
+                let result = left.partial_cmp(&right)
+                    .map(|ord| ord != std::cmp::Ordering::Greater)
+                    .unwrap_or(false);
+                self.stack.push(Value::Boolean(result));
+            }
+            Instruction::GreaterThan => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                Self::comparable(&left, &right)?;
+                let result = left.partial_cmp(&right)
+                    .map(|ord| ord == std::cmp::Ordering::Greater)
+                    .unwrap_or(false);
+                self.stack.push(Value::Boolean(result));
+            }
+            Instruction::GreaterOrEqual => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                Self::comparable(&left, &right)?;
+                let result = left.partial_cmp(&right)
+                    .map(|ord| ord != std::cmp::Ordering::Less)
+                    .unwrap_or(false);
+                self.stack.push(Value::Boolean(result));
+            }
+            Instruction::Concat => {
+                let right = self.stack.pop().ok_or("Stack underflow")?;
+                let left = self.stack.pop().ok_or("Stack underflow")?;
+                let result = format!("{}{}", left.to_string(), right.to_string());
+                self.stack.push(Value::String(result));
+            }
+            Instruction::Print => {
+                if let Some(value) = self.stack.pop() {
+                    host::print_line(&value.to_string());
+                }
+            }
+            Instruction::Input => {
+                let input = host::read_line()?;
+                self.stack.push(Value::String(input.trim().to_string()));
+            }
+            Instruction::JumpIfFalse(target) => {
+                if let Some(value) = self.stack.pop() {
+                    if !value.is_truthy() {
+                        self.instruction_pointer = target;
+                        return Ok(true);
                     }
                 }
-                Instruction::Jump(target) => {
-                    self.instruction_pointer = target;
-                    continue;
-                }
-                Instruction::And => {
-                    let right = self.pop()?;
-                    let left = self.pop()?;
-                    self.stack.push(Value::Boolean(left.is_truthy() && right.is_truthy()));
-                }
-                Instruction::Or => {
-                    let right = self.pop()?;
-                    let left = self.pop()?;
-                    self.stack.push(Value::Boolean(left.is_truthy() || right.is_truthy()));
+            }
+            Instruction::Jump(target) => {
+                self.instruction_pointer = target;
+                return Ok(true);
+            }
+            Instruction::And => {
+                let right = self.pop()?;
+                let left = self.pop()?;
+                self.stack.push(Value::Boolean(left.is_truthy() && right.is_truthy()));
+            }
+            Instruction::Or => {
+                let right = self.pop()?;
+                let left = self.pop()?;
+                self.stack.push(Value::Boolean(left.is_truthy() || right.is_truthy()));
+            }
+            Instruction::Not => {
+                let value = self.pop()?;
+                self.stack.push(Value::Boolean(!value.is_truthy()));
+            }
+            Instruction::FileOpen(mode) => {
+                let filename = self.pop()?.to_string();
+                // Opening for writing truncates once; later writes append,
+                // so a sequence of writes reads back in order.
+                if mode == "write" {
+                    host::write(&filename, b"")
+                        .map_err(|e| format!("கோப்பு '{}' திறக்க முடியவில்லை  (cannot open '{}' for writing): {}", filename, filename, e))?;
+                }
+                self.file_modes.insert(filename, mode);
+            }
+            Instruction::FileClose => {
+                let filename = self.pop()?.to_string();
+                self.file_modes.remove(&filename);
+            }
+            Instruction::FileWrite => {
+                let data = self.pop()?.to_string();
+                let filename = self.pop()?.to_string();
+                Self::append_line(&filename, &data)?;
+            }
+            Instruction::FileRead => {
+                let filename = self.pop()?.to_string();
+                let contents = host::read_to_string(&filename)
+                    .map_err(|e| format!("கோப்பு '{}' படிக்க முடியவில்லை  (cannot read '{}'): {}", filename, filename, e))?;
+                self.stack.push(Value::String(contents.trim_end_matches('\n').to_string()));
+            }
+            Instruction::ReadCSV => {
+                let filename = self.pop()?.to_string();
+                let contents = host::read_to_string(&filename)
+                    .map_err(|e| format!("கோப்பு '{}' படிக்க முடியவில்லை  (cannot read '{}'): {}", filename, filename, e))?;
+                // Count data rows, excluding the header line.
+                let rows = contents.lines().filter(|l| !l.trim().is_empty()).count();
+                let data_rows = if rows > 0 { rows - 1 } else { 0 };
+                self.stack.push(Value::Number(Decimal::from(data_rows)));
+            }
+            Instruction::WriteCSV => {
+                let row = self.pop()?.to_string();
+                let filename = self.pop()?.to_string();
+                Self::append_line(&filename, &row)?;
+            }
+            Instruction::Nop => {}
+            Instruction::Unsupported(what) => {
+                return Err(format!(
+                    "{} — இந்த VM இல் இன்னும் செயல்படுத்தப்படவில்லை  (not implemented in the VM yet)",
+                    what
+                ));
+            }
+            Instruction::MakeArray(count) => {
+                let mut items = Vec::with_capacity(count);
+                for _ in 0..count {
+                    items.push(self.pop()?);
                 }
-                Instruction::Not => {
+                items.reverse(); // pushed left to right
+                self.stack.push(Value::Array(items));
+            }
+            Instruction::MakeRecord(keys) => {
+                let mut fields = HashMap::with_capacity(keys.len());
+                for key in keys.into_iter().rev() {
                     let value = self.pop()?;
-                    self.stack.push(Value::Boolean(!value.is_truthy()));
-                }
-                Instruction::FileOpen(mode) => {
-                    let filename = self.pop()?.to_string();
-                    // Opening for writing truncates once; later writes append,
-                    // so a sequence of writes reads back in order.
-                    if mode == "write" {
-                        host::write(&filename, b"")
-                            .map_err(|e| format!("கோப்பு '{}' திறக்க முடியவில்லை  (cannot open '{}' for writing): {}", filename, filename, e))?;
+                    fields.insert(key, value);
+                }
+                self.stack.push(Value::Map(fields));
+            }
+            Instruction::Index => {
+                let index = self.pop()?;
+                let base = self.pop()?;
+                self.stack.push(Self::index_of(&base, &index)?);
+            }
+            Instruction::Field(name) => {
+                let base = self.pop()?;
+                match base {
+                    Value::Map(fields) => {
+                        let value = fields.get(&name).cloned().ok_or_else(|| {
+                            format!("புலம் '{}' இல்லை  (no field '{}' on this record)", name, name)
+                        })?;
+                        self.stack.push(value);
                     }
-                    self.file_modes.insert(filename, mode);
-                }
-                Instruction::FileClose => {
-                    let filename = self.pop()?.to_string();
-                    self.file_modes.remove(&filename);
-                }
-                Instruction::FileWrite => {
-                    let data = self.pop()?.to_string();
-                    let filename = self.pop()?.to_string();
-                    Self::append_line(&filename, &data)?;
-                }
-                Instruction::FileRead => {
-                    let filename = self.pop()?.to_string();
-                    let contents = host::read_to_string(&filename)
-                        .map_err(|e| format!("கோப்பு '{}' படிக்க முடியவில்லை  (cannot read '{}'): {}", filename, filename, e))?;
-                    self.stack.push(Value::String(contents.trim_end_matches('\n').to_string()));
-                }
-                Instruction::ReadCSV => {
-                    let filename = self.pop()?.to_string();
-                    let contents = host::read_to_string(&filename)
-                        .map_err(|e| format!("கோப்பு '{}' படிக்க முடியவில்லை  (cannot read '{}'): {}", filename, filename, e))?;
-                    // Count data rows, excluding the header line.
-                    let rows = contents.lines().filter(|l| !l.trim().is_empty()).count();
-                    let data_rows = if rows > 0 { rows - 1 } else { 0 };
-                    self.stack.push(Value::Number(Decimal::from(data_rows)));
-                }
-                Instruction::WriteCSV => {
-                    let row = self.pop()?.to_string();
-                    let filename = self.pop()?.to_string();
-                    Self::append_line(&filename, &row)?;
-                }
-                Instruction::Nop => {}
-                Instruction::Unsupported(what) => {
-                    return Err(format!(
-                        "{} — இந்த VM இல் இன்னும் செயல்படுத்தப்படவில்லை  (not implemented in the VM yet)",
-                        what
-                    ));
-                }
-                Instruction::MakeArray(count) => {
-                    let mut items = Vec::with_capacity(count);
-                    for _ in 0..count {
-                        items.push(self.pop()?);
-                    }
-                    items.reverse(); // pushed left to right
-                    self.stack.push(Value::Array(items));
-                }
-                Instruction::MakeRecord(keys) => {
-                    let mut fields = HashMap::with_capacity(keys.len());
-                    for key in keys.into_iter().rev() {
-                        let value = self.pop()?;
-                        fields.insert(key, value);
-                    }
-                    self.stack.push(Value::Map(fields));
-                }
-                Instruction::Index => {
-                    let index = self.pop()?;
-                    let base = self.pop()?;
-                    self.stack.push(Self::index_of(&base, &index)?);
-                }
-                Instruction::Field(name) => {
-                    let base = self.pop()?;
-                    match base {
-                        Value::Map(fields) => {
-                            let value = fields.get(&name).cloned().ok_or_else(|| {
-                                format!("புலம் '{}' இல்லை  (no field '{}' on this record)", name, name)
-                            })?;
-                            self.stack.push(value);
-                        }
-                        other => {
-                            return Err(format!(
-                                "'{}' ஒரு பொருள் அல்ல  ('.{}' needs a record, got {})",
-                                name,
-                                name,
-                                Self::type_name(&other)
-                            ));
-                        }
+                    other => {
+                        return Err(format!(
+                            "'{}' ஒரு பொருள் அல்ல  ('.{}' needs a record, got {})",
+                            name,
+                            name,
+                            Self::type_name(&other)
+                        ));
                     }
                 }
-                Instruction::SetIndex(name) => {
-                    let value = self.pop()?;
-                    let index = self.pop()?;
-                    let mut base = self.get_var(&name).ok_or_else(|| {
-                        format!("அறிவிக்கப்படாத மாறி '{}'  (undefined variable '{}')", name, name)
-                    })?;
-                    match &mut base {
-                        Value::Array(items) => {
-                            let i = Self::array_index(items.len(), &index)?;
-                            items[i] = value;
-                        }
-                        Value::Map(fields) => {
-                            fields.insert(index.to_string(), value);
-                        }
-                        other => {
-                            return Err(format!(
-                                "'{}' ஐ அட்டவணைப்படுத்த முடியாது  (cannot index into {})",
-                                name,
-                                Self::type_name(other)
-                            ));
-                        }
+            }
+            Instruction::SetIndex(name) => {
+                let value = self.pop()?;
+                let index = self.pop()?;
+                let mut base = self.get_var(&name).ok_or_else(|| {
+                    format!("அறிவிக்கப்படாத மாறி '{}'  (undefined variable '{}')", name, name)
+                })?;
+                match &mut base {
+                    Value::Array(items) => {
+                        let i = Self::array_index(items.len(), &index)?;
+                        items[i] = value;
                     }
-                    self.set_var(name, base);
-                }
-                Instruction::SetField(name, field) => {
-                    let value = self.pop()?;
-                    let mut base = self.get_var(&name).ok_or_else(|| {
-                        format!("அறிவிக்கப்படாத மாறி '{}'  (undefined variable '{}')", name, name)
-                    })?;
-                    match &mut base {
-                        Value::Map(fields) => {
-                            fields.insert(field, value);
-                        }
-                        other => {
-                            return Err(format!(
-                                "'{}' ஒரு பொருள் அல்ல  ('{}.{}' needs a record, got {})",
-                                name,
-                                name,
-                                field,
-                                Self::type_name(other)
-                            ));
-                        }
+                    Value::Map(fields) => {
+                        fields.insert(index.to_string(), value);
+                    }
+                    other => {
+                        return Err(format!(
+                            "'{}' ஐ அட்டவணைப்படுத்த முடியாது  (cannot index into {})",
+                            name,
+                            Self::type_name(other)
+                        ));
                     }
-                    self.set_var(name, base);
                 }
-                Instruction::Length => {
-                    let value = self.pop()?;
-                    let n = match &value {
-                        Value::Array(items) => items.len(),
-                        Value::Map(fields) => fields.len(),
-                        Value::String(s) => letters(s).len(),
-                        other => {
-                            return Err(format!(
-                                "இதை சுற்ற முடியாது  (cannot iterate over {})",
-                                Self::type_name(other)
-                            ));
-                        }
-                    };
-                    self.stack.push(Value::Number(Decimal::from(n)));
-                }
-                Instruction::NthOrKey => {
-                    let index = self.pop()?;
-                    let base = self.pop()?;
-                    let value = match &base {
-                        Value::Array(items) => {
-                            let i = Self::array_index(items.len(), &index)?;
-                            items[i].clone()
-                        }
-                        Value::Map(fields) => {
-                            // Sorted so iteration order is stable run to run.
-                            let mut keys: Vec<&String> = fields.keys().collect();
-                            keys.sort();
-                            let i = Self::array_index(keys.len(), &index)?;
-                            Value::String(keys[i].clone())
-                        }
-                        Value::String(s) => {
-                            let parts = letters(s);
-                            let i = Self::array_index(parts.len(), &index)?;
-                            Value::String(parts[i].to_string())
-                        }
-                        other => {
-                            return Err(format!(
-                                "இதை சுற்ற முடியாது  (cannot iterate over {})",
-                                Self::type_name(other)
-                            ));
-                        }
-                    };
-                    self.stack.push(value);
-                }
-                Instruction::Call(name, argc) => {
-                    // User-defined functions shadow builtins.
-                    if !bytecode.functions.contains_key(&name) {
-                        let result = self.call_builtin(&name, argc)?;
-                        self.stack.push(result);
-                        self.instruction_pointer += 1;
-                        continue;
+                self.set_var(name, base);
+            }
+            Instruction::SetField(name, field) => {
+                let value = self.pop()?;
+                let mut base = self.get_var(&name).ok_or_else(|| {
+                    format!("அறிவிக்கப்படாத மாறி '{}'  (undefined variable '{}')", name, name)
+                })?;
+                match &mut base {
+                    Value::Map(fields) => {
+                        fields.insert(field, value);
                     }
-                    let info = bytecode.functions.get(&name).cloned().ok_or_else(|| {
-                        format!(
-                            "அறியப்படாத செயல் '{}'  (unknown function '{}')",
-                            name, name
-                        )
-                    })?;
-                    if info.params.len() != argc {
+                    other => {
                         return Err(format!(
-                            "செயல் '{}' {} அளவுருக்களை எதிர்பார்க்கிறது, {} வழங்கப்பட்டது  \
-                             (function '{}' expects {} argument(s), got {})",
+                            "'{}' ஒரு பொருள் அல்ல  ('{}.{}' needs a record, got {})",
                             name,
-                            info.params.len(),
-                            argc,
                             name,
-                            info.params.len(),
-                            argc
+                            field,
+                            Self::type_name(other)
                         ));
                     }
-                    if self.frames.len() >= MAX_CALL_DEPTH {
+                }
+                self.set_var(name, base);
+            }
+            Instruction::Length => {
+                let value = self.pop()?;
+                let n = match &value {
+                    Value::Array(items) => items.len(),
+                    Value::Map(fields) => fields.len(),
+                    Value::String(s) => letters(s).len(),
+                    other => {
                         return Err(format!(
-                            "செயல் அழைப்பு ஆழம் மிகுதி ({})  (call depth exceeded — infinite recursion?)",
-                            MAX_CALL_DEPTH
+                            "இதை சுற்ற முடியாது  (cannot iterate over {})",
+                            Self::type_name(other)
                         ));
                     }
-
-                    // Arguments were pushed left to right, so bind in reverse.
-                    let mut locals = HashMap::new();
-                    for param in info.params.iter().rev() {
-                        let value = self.pop()?;
-                        locals.insert(param.clone(), value);
+                };
+                self.stack.push(Value::Number(Decimal::from(n)));
+            }
+            Instruction::NthOrKey => {
+                let index = self.pop()?;
+                let base = self.pop()?;
+                let value = match &base {
+                    Value::Array(items) => {
+                        let i = Self::array_index(items.len(), &index)?;
+                        items[i].clone()
                     }
-
-                    self.frames.push(Frame {
-                        return_ip: self.instruction_pointer + 1,
-                        locals,
-                        base_len: self.stack.len(),
-                    });
-                    self.instruction_pointer = info.start;
-                    continue;
-                }
-                Instruction::Return => {
-                    let value = self.pop()?;
-                    let frame = self.frames.pop().ok_or(
-                        "செயலுக்கு வெளியே திரும்பு  (return outside of a function)",
-                    )?;
-                    self.stack.truncate(frame.base_len);
-                    self.instruction_pointer = frame.return_ip;
-                    self.stack.push(value);
-                    continue;
-                }
-                Instruction::TryUnwrap => {
-                    let value = self.pop()?;
-                    match value {
-                        Value::Ok(inner) => self.stack.push(*inner),
-                        Value::Err(error) => {
-                            // Rust's `?`: hand the failure to the caller.
-                            match self.frames.pop() {
-                                Some(frame) => {
-                                    self.stack.truncate(frame.base_len);
-                                    self.instruction_pointer = frame.return_ip;
-                                    self.stack.push(Value::Err(error));
-                                    continue;
-                                }
-                                None => {
-                                    return Err(format!(
-                                        "கையாளப்படாத தவறு: {}  (unhandled error at top level: {})",
-                                        error.to_string(),
-                                        error.to_string()
-                                    ));
-                                }
+                    Value::Map(fields) => {
+                        // Sorted so iteration order is stable run to run.
+                        let mut keys: Vec<&String> = fields.keys().collect();
+                        keys.sort();
+                        let i = Self::array_index(keys.len(), &index)?;
+                        Value::String(keys[i].clone())
+                    }
+                    Value::String(s) => {
+                        let parts = letters(s);
+                        let i = Self::array_index(parts.len(), &index)?;
+                        Value::String(parts[i].to_string())
+                    }
+                    other => {
+                        return Err(format!(
+                            "இதை சுற்ற முடியாது  (cannot iterate over {})",
+                            Self::type_name(other)
+                        ));
+                    }
+                };
+                self.stack.push(value);
+            }
+            Instruction::Call(name, argc) => {
+                // A variable holding a function is called through, and
+                // shadows a function or builtin of the same name, as any
+                // local does.
+                if let Some(Value::Function(function)) = self.get_var(&name) {
+                    let captured = function.captured.clone();
+                    self.enter(function.label(), function.start, &function.params, captured, argc)?;
+                    return Ok(true);
+                }
+                // User-defined functions shadow builtins.
+                if let Some(info) = bytecode.functions.get(&name) {
+                    let params = info.params.clone();
+                    self.enter(name, info.start, &params, HashMap::new(), argc)?;
+                    return Ok(true);
+                }
+                let result = match self.call_higher_order(bytecode, &name, argc)? {
+                    Some(result) => result,
+                    None => self.call_builtin(&name, argc)?,
+                };
+                self.stack.push(result);
+            }
+            Instruction::Closure(info) => {
+                // The call it is made in, as it is now. Globals are left out:
+                // a closure reads those when it runs, as any function does.
+                let captured = match self.frames.last() {
+                    Some(frame) => frame
+                        .locals
+                        .iter()
+                        .filter(|(name, _)| !name.starts_with('#'))
+                        .map(|(name, value)| (name.clone(), value.clone()))
+                        .collect(),
+                    None => HashMap::new(),
+                };
+                self.stack.push(Value::Function(Arc::new(Closure {
+                    name: None,
+                    start: info.start,
+                    params: info.params,
+                    captured,
+                })));
+            }
+            Instruction::Return => {
+                let value = self.pop()?;
+                let frame = self.frames.pop().ok_or(
+                    "செயலுக்கு வெளியே திரும்பு  (return outside of a function)",
+                )?;
+                self.stack.truncate(frame.base_len);
+                self.instruction_pointer = frame.return_ip;
+                self.stack.push(value);
+                return Ok(true);
+            }
+            Instruction::TryUnwrap => {
+                let value = self.pop()?;
+                match value {
+                    Value::Ok(inner) => self.stack.push(*inner),
+                    Value::Err(error) => {
+                        // Rust's `?`: hand the failure to the caller.
+                        match self.frames.pop() {
+                            Some(frame) => {
+                                self.stack.truncate(frame.base_len);
+                                self.instruction_pointer = frame.return_ip;
+                                self.stack.push(Value::Err(error));
+                                return Ok(true);
+                            }
+                            None => {
+                                return Err(format!(
+                                    "கையாளப்படாத தவறு: {}  (unhandled error at top level: {})",
+                                    error.to_string(),
+                                    error.to_string()
+                                ));
                             }
                         }
-                        other => {
-                            return Err(format!(
-                                "'?' க்கு ஒரு முடிவு தேவை  ('?' needs a result, got {})",
-                                Self::type_name(&other)
-                            ));
-                        }
+                    }
+                    other => {
+                        return Err(format!(
+                            "'?' க்கு ஒரு முடிவு தேவை  ('?' needs a result, got {})",
+                            Self::type_name(&other)
+                        ));
                     }
                 }
-                Instruction::DBConnect(db_type) => {
-                    let connection = self.pop()?.to_string();
-
-                    // Connecting again through the same driver used to replace
-                    // the open connection without saying so. The map is keyed
-                    // by driver, so the second insert overwrote the first, the
-                    // count stayed at one, and connection_mut — which does
-                    // refuse when several are open — never saw a reason to.
-                    // Every query after that went to the second database while
-                    // the program still believed it was talking to the first.
-                    //
-                    // Two different databases through one driver is the case
-                    // the language cannot express: தளம்_வினா names no handle,
-                    // so there would be no way to say which one a query meant.
-                    // Refusing is the honest answer until it can.
-                    if let Some(already) = self.connections.connection_of(&db_type) {
-                        if already != connection {
-                            return Err(format!(
-                                "'{}' ஏற்கனவே '{}' உடன் இணைக்கப்பட்டுள்ளது  \
-                                 ('{}' is already connected to '{}'): \
-                                 தளம்_பிரி first — a query cannot say which of two it means",
-                                db_type, already, db_type, already
-                            ));
-                        }
-                        // The same database again: already connected, so there
-                        // is nothing to do and no lease to take.
-                        self.instruction_pointer += 1;
-                        continue;
+            }
+            Instruction::DBConnect(db_type) => {
+                let connection = self.pop()?.to_string();
+
+                // Connecting again through the same driver used to replace
+                // the open connection without saying so. The map is keyed
+                // by driver, so the second insert overwrote the first, the
+                // count stayed at one, and connection_mut — which does
+                // refuse when several are open — never saw a reason to.
+                // Every query after that went to the second database while
+                // the program still believed it was talking to the first.
+                //
+                // Two different databases through one driver is the case
+                // the language cannot express: தளம்_வினா names no handle,
+                // so there would be no way to say which one a query meant.
+                // Refusing is the honest answer until it can.
+                if let Some(already) = self.connections.connection_of(&db_type) {
+                    if already != connection {
+                        return Err(format!(
+                            "'{}' ஏற்கனவே '{}' உடன் இணைக்கப்பட்டுள்ளது  \
+                             ('{}' is already connected to '{}'): \
+                             தளம்_பிரி first — a query cannot say which of two it means",
+                            db_type, already, db_type, already
+                        ));
                     }
-
-                    // Borrowed rather than opened: under --server every request
-                    // runs on a fresh VM, so this statement is reached once per
-                    // request and used to mean a new connection each time.
-                    let lease = crate::db::pool::checkout(&db_type, &connection)?;
-                    self.connections.insert(db_type, connection, lease);
-                }
-                Instruction::DBDisconnect(db_type) => {
-                    // Returns the connection to the cache rather than closing
-                    // it. தளம்_பிரி means "I am done with this", which is what
-                    // a program actually wants to say; keeping the socket open
-                    // for the next request is the host's business.
-                    match self.connections.remove(&db_type) {
-                        Some(lease) => drop(lease),
-                        None => {
-                            return Err(format!(
-                                "'{}' இணைக்கப்படவில்லை  (not connected to {})",
-                                db_type, db_type
-                            ));
-                        }
+                    // The same database again: already connected, so there
+                    // is nothing to do and no lease to take.
+                    self.instruction_pointer += 1;
+                    return Ok(true);
+                }
+
+                // Borrowed rather than opened: under --server every request
+                // runs on a fresh VM, so this statement is reached once per
+                // request and used to mean a new connection each time.
+                let lease = crate::db::pool::checkout(&db_type, &connection)?;
+                self.connections.insert(db_type, connection, lease);
+            }
+            Instruction::DBDisconnect(db_type) => {
+                // Returns the connection to the cache rather than closing
+                // it. தளம்_பிரி means "I am done with this", which is what
+                // a program actually wants to say; keeping the socket open
+                // for the next request is the host's business.
+                match self.connections.remove(&db_type) {
+                    Some(lease) => drop(lease),
+                    None => {
+                        return Err(format!(
+                            "'{}' இணைக்கப்படவில்லை  (not connected to {})",
+                            db_type, db_type
+                        ));
                     }
                 }
-                Instruction::DBExecute => {
-                    let params = crate::db::params_from(&self.pop()?)?;
-                    let sql = self.pop()?.to_string();
-                    let handle = self.connection_mut()?;
-                    handle.execute(&sql, &params)?;
-                }
-                Instruction::DBQuery => {
-                    let params = crate::db::params_from(&self.pop()?)?;
-                    let sql = self.pop()?.to_string();
-                    let handle = self.connection_mut()?;
-                    // One record per row, so a result set is an array of
-                    // records — a table in the language's own terms.
-                    let rows = handle.query(&sql, &params)?;
-                    self.stack.push(Value::Array(rows));
-                }
-                Instruction::SendResponse => {
-                    let headers = self.pop()?;
-                    let body = self.pop()?;
-                    let status = self.pop()?;
-                    // Written to globals, not the current frame: the server
-                    // reads them from the VM once the handler has returned,
-                    // and பதில் is often called from inside a function.
-                    self.variables
-                        .insert("response_status".to_string(), status);
-                    self.variables
-                        .insert("response_body".to_string(), Value::String(body.to_string()));
-                    self.variables
-                        .insert("response_headers".to_string(), headers);
-                }
-                Instruction::DefineRoute(_, _) | Instruction::StartServer(_, _) => {
-                    return Err(
-                        "வழங்கி செயல்பாடுகள் VM இல் இன்னும் இல்லை  (server operations are not implemented in the VM yet)"
-                            .to_string(),
-                    );
-                }
-                Instruction::Halt => {
-                    break;
-                }
             }
-            
-            self.instruction_pointer += 1;
+            Instruction::DBExecute => {
+                let params = crate::db::params_from(&self.pop()?)?;
+                let sql = self.pop()?.to_string();
+                let handle = self.connection_mut()?;
+                handle.execute(&sql, &params)?;
+            }
+            Instruction::DBQuery => {
+                let params = crate::db::params_from(&self.pop()?)?;
+                let sql = self.pop()?.to_string();
+                let handle = self.connection_mut()?;
+                // One record per row, so a result set is an array of
+                // records — a table in the language's own terms.
+                let rows = handle.query(&sql, &params)?;
+                self.stack.push(Value::Array(rows));
+            }
+            Instruction::SendResponse => {
+                let headers = self.pop()?;
+                let body = self.pop()?;
+                let status = self.pop()?;
+                // Written to globals, not the current frame: the server
+                // reads them from the VM once the handler has returned,
+                // and பதில் is often called from inside a function.
+                self.variables
+                    .insert("response_status".to_string(), status);
+                self.variables
+                    .insert("response_body".to_string(), Value::String(body.to_string()));
+                self.variables
+                    .insert("response_headers".to_string(), headers);
+            }
+            Instruction::DefineRoute(_, _) | Instruction::StartServer(_, _) => {
+                return Err(
+                    "வழங்கி செயல்பாடுகள் VM இல் இன்னும் இல்லை  (server operations are not implemented in the VM yet)"
+                        .to_string(),
+                );
+            }
+            Instruction::StopServer => {
+                crate::http::shutdown::stop();
+            }
+            Instruction::Halt => {
+                return Ok(false);
+            }
         }
-        
+
+        self.instruction_pointer += 1;
+        Ok(true)
+    }
+
+    /// Begin a call: bind the `argc` arguments on the stack to `params`, on
+    /// top of `captured`, and jump to `start`.
+    fn enter(
+        &mut self,
+        function: String,
+        start: usize,
+        params: &[String],
+        captured: HashMap<String, Value>,
+        argc: usize,
+    ) -> Result<(), String> {
+        if params.len() != argc {
+            return Err(format!(
+                "செயல் '{}' {} அளவுருக்களை எதிர்பார்க்கிறது, {} வழங்கப்பட்டது  \
+                 (function '{}' expects {} argument(s), got {})",
+                function,
+                params.len(),
+                argc,
+                function,
+                params.len(),
+                argc
+            ));
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(format!(
+                "செயல் அழைப்பு ஆழம் மிகுதி ({})  (call depth exceeded — infinite recursion?)",
+                MAX_CALL_DEPTH
+            ));
+        }
+
+        // Arguments were pushed left to right, so bind in reverse. An
+        // argument shadows a captured variable of the same name.
+        let mut locals = captured;
+        for param in params.iter().rev() {
+            let value = self.pop()?;
+            locals.insert(param.clone(), value);
+        }
+
+        self.frames.push(Frame {
+            function,
+            return_ip: self.instruction_pointer + 1,
+            locals,
+            base_len: self.stack.len(),
+        });
+        self.instruction_pointer = start;
         Ok(())
     }
+
+    /// Run a function value to completion from inside a builtin, and return
+    /// what it returned.
+    ///
+    /// Its frame goes on the same stack as any call, returning to the builtin's
+    /// own `Call`, so an error inside it is reported with the builtin in the
+    /// call stack beneath it.
+    fn call_value(
+        &mut self,
+        bytecode: &Bytecode,
+        function: &Closure,
+        args: Vec<Value>,
+    ) -> Result<Value, String> {
+        let depth = self.frames.len();
+        let resume = self.instruction_pointer;
+        let argc = args.len();
+        self.stack.extend(args);
+        self.enter(
+            function.label(),
+            function.start,
+            &function.params,
+            function.captured.clone(),
+            argc,
+        )?;
+        while self.frames.len() > depth {
+            if !self.step(bytecode)? {
+                break;
+            }
+        }
+        self.instruction_pointer = resume;
+        self.pop()
+    }
 }
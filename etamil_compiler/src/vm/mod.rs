@@ -6,11 +6,15 @@
 pub mod host;
 pub mod bytecode;
 pub mod interpreter;
+// Breakpoints and stepping; the console and DAP front ends are in src/.
+pub mod debug;
 pub mod value;
 
 pub use bytecode::{Bytecode, Instruction};
 pub use interpreter::VM;
 pub use interpreter::Upload;
+pub use interpreter::ResponseStream;
+pub use interpreter::Channel;
 pub use value::Value;
 
 /// Initialize the eTamil VM with bytecode
@@ -2,6 +2,7 @@
 use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// Runtime values in the eTamil VM.
 ///
@@ -12,6 +13,13 @@ use std::str::FromStr;
 #[derive(Debug, Clone)]
 pub enum Value {
     Number(Decimal),
+    /// An amount in a currency, identified by ISO code: `₹1500.50` is
+    /// `Money { amount: 1500.50, currency: "INR" }`. Kept apart from Number so
+    /// that arithmetic can refuse to add rupees to dollars.
+    Money {
+        amount: Decimal,
+        currency: String,
+    },
     String(String),
     Boolean(bool),
     Array(Vec<Value>),
@@ -22,12 +30,48 @@ pub enum Value {
     /// must be handled, not an exception that unwinds silently.
     Err(Box<Value>),
     Null,
+    /// A `செயல்` held as a value: a named function passed by its name, or an
+    /// anonymous `செயல்(அ) { ... }` written where a value goes.
+    Function(Arc<Closure>),
+}
+
+/// A function value: where its body starts, the names its arguments bind to,
+/// and the variables it captured.
+///
+/// Capture is by value, when the `செயல்` expression is evaluated: a closure
+/// made inside a function keeps that call's locals as they were then, after
+/// the call has returned. That is the rule assignment already follows —
+/// `ஆ = அ;` copies an array rather than sharing it — and it is why a closure
+/// cannot change a variable of the function that made it, just as a function
+/// cannot change a global. Globals are not captured; a closure reads them when
+/// it runs, as a named function does.
+///
+/// `start` is an index into the bytecode the value was made from, so a
+/// function value is meaningful only within the program that made it.
+#[derive(Debug)]
+pub struct Closure {
+    /// The function's name; `None` for an anonymous one.
+    pub name: Option<String>,
+    pub start: usize,
+    pub params: Vec<String>,
+    pub captured: HashMap<String, Value>,
+}
+
+impl Closure {
+    /// What a call stack shows for it.
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => "செயல் (anonymous)".to_string(),
+        }
+    }
 }
 
 impl Value {
     pub fn to_number(&self) -> Decimal {
         match self {
             Value::Number(n) => *n,
+            Value::Money { amount, .. } => *amount,
             Value::Boolean(true) => Decimal::ONE,
             Value::Boolean(false) => Decimal::ZERO,
             // Input arrives as text, so strings coerce when used as numbers.
@@ -47,6 +91,7 @@ impl Value {
                     n.normalize().to_string()
                 }
             }
+            Value::Money { amount, currency } => crate::money::display(*amount, currency),
             Value::String(s) => s.clone(),
             Value::Boolean(b) => b.to_string(),
             Value::Null => "nil".to_string(),
@@ -66,6 +111,10 @@ impl Value {
             }
             Value::Ok(inner) => format!("சரி({})", inner.to_string()),
             Value::Err(inner) => format!("தவறு({})", inner.to_string()),
+            Value::Function(function) => match &function.name {
+                Some(name) => format!("செயல் {}({})", name, function.params.join(", ")),
+                None => format!("செயல்({})", function.params.join(", ")),
+            },
         }
     }
 
@@ -73,6 +122,7 @@ impl Value {
         match self {
             Value::Null => false,
             Value::Number(n) => *n != Decimal::ZERO,
+            Value::Money { amount, .. } => !amount.is_zero(),
             Value::String(s) => !s.is_empty(),
             Value::Boolean(b) => *b,
             Value::Array(a) => !a.is_empty(),
@@ -81,6 +131,7 @@ impl Value {
             // way you would expect without unwrapping first.
             Value::Ok(_) => true,
             Value::Err(_) => false,
+            Value::Function(_) => true,
         }
     }
 
@@ -96,11 +147,25 @@ impl PartialEq for Value {
             // here, which meant two amounts a hundredth of a paisa apart
             // compared equal.
             (Value::Number(a), Value::Number(b)) => a == b,
+            // ₹5 and $5 are different amounts.
+            (
+                Value::Money { amount: a, currency: x },
+                Value::Money { amount: b, currency: y },
+            ) => x == y && a == b,
+            // Zero is zero in every currency, so `(இருப்பு == 0)` answers
+            // the question it asks. Any other plain number is not an amount.
+            (Value::Money { amount, .. }, Value::Number(n))
+            | (Value::Number(n), Value::Money { amount, .. }) => n.is_zero() && amount.is_zero(),
             (Value::String(a), Value::String(b)) => a == b,
             (Value::Boolean(a), Value::Boolean(b)) => a == b,
             (Value::Ok(a), Value::Ok(b)) => a == b,
             (Value::Err(a), Value::Err(b)) => a == b,
             (Value::Null, Value::Null) => true,
+            // The same body over the same captured values. Two bodies that
+            // happen to do the same thing are different functions.
+            (Value::Function(a), Value::Function(b)) => {
+                a.start == b.start && a.captured == b.captured
+            }
 
             // Arrays and records had no arm here at all, so they fell to the
             // catch-all and `[1, 2] == [1, 2]` was false. So was `[] == []`.
@@ -126,6 +191,15 @@ impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match (self, other) {
             (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            // Amounts in different currencies have no order without a rate.
+            (
+                Value::Money { amount: a, currency: x },
+                Value::Money { amount: b, currency: y },
+            ) if x == y => a.partial_cmp(b),
+            // A threshold — `(இருப்பு > 1000)` — is written as a plain number.
+            (Value::Money { .. }, Value::Number(_)) | (Value::Number(_), Value::Money { .. }) => {
+                self.to_number().partial_cmp(&other.to_number())
+            }
             (Value::String(a), Value::String(b)) => a.partial_cmp(b),
             (Value::String(_), Value::Number(_)) | (Value::Number(_), Value::String(_)) => {
                 self.to_number().partial_cmp(&other.to_number())
@@ -8,7 +8,7 @@
 //! file.
 //!
 //! So instead of removing the modules, this re-creates just the surface the
-//! interpreter actually touches -- sixteen items -- as types that compile and
+//! interpreter actually touches -- twenty-seven items -- as types that compile and
 //! functions that fail. The interpreter needs no change at all, and a program
 //! that asks for a database in the browser gets a sentence explaining why it
 //! cannot have one rather than a compile error nobody sees.
@@ -158,4 +158,78 @@ pub mod http {
             unavailable!("குறிதாங்கி", "token verification")
         }
     }
+
+    /// Sessions are kept by a server, and there is none here. The cookie
+    /// helpers answer anyway: there is no response in a browser for what
+    /// they make to be sent on.
+    pub mod session {
+        pub fn start() -> String {
+            String::new()
+        }
+
+        pub fn cookie_for(_id: &str, _secure: bool) -> String {
+            String::new()
+        }
+
+        pub fn ended_cookie(_secure: bool) -> String {
+            String::new()
+        }
+
+        pub fn get(_id: &str, _name: &str) -> Result<Option<String>, String> {
+            unavailable!("அமர்வு", "a session")
+        }
+
+        pub fn set(_id: &str, _name: &str, _value: &str) -> Result<(), String> {
+            unavailable!("அமர்வு", "a session")
+        }
+
+        pub fn end(_id: &str) -> Result<(), String> {
+            unavailable!("அமர்வு", "a session")
+        }
+    }
+
+    /// A browser cannot make the calls these guard. The policy types are
+    /// kept so the interpreter can still read a program's policy record, and
+    /// report a mistake in it as it would natively.
+    pub mod resilience {
+        use std::time::Duration;
+
+        #[derive(Clone, Default)]
+        pub struct RetryConfig {
+            pub max_retries: u32,
+            pub initial_delay: Duration,
+            pub max_delay: Duration,
+            pub backoff_factor: f64,
+        }
+
+        #[derive(Clone, Default)]
+        pub struct CircuitBreakerConfig {
+            pub failure_threshold: u32,
+            pub success_threshold: u32,
+            pub timeout: Duration,
+        }
+
+        #[derive(Clone, Default)]
+        pub struct Policy {
+            pub retry: RetryConfig,
+            pub retry_on: Vec<u16>,
+            pub breaker: Option<(String, CircuitBreakerConfig)>,
+        }
+
+        pub fn send(
+            _policy: &Policy,
+            _method: &str,
+            _url: &str,
+            _body: Option<&str>,
+            _headers: &[(String, String)],
+        ) -> Result<crate::net::Response, String> {
+            unavailable!("வலைக் கோரிக்கை", "an outbound request")
+        }
+    }
+
+    /// There is no server here to stop, which is what `வழங்கி_நிறுத்து` does
+    /// outside one on a native build too.
+    pub mod shutdown {
+        pub fn stop() {}
+    }
 }
@@ -0,0 +1,106 @@
+//! Tests for `etamil build` and running the `.qmzc` it writes.
+//!
+//! Run through the real binary: the point of a `.qmzc` is that a later,
+//! separate run needs nothing but it, so each test builds in one process and
+//! runs in another.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn etamil(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .args(args)
+        .output()
+        .expect("could not start the compiler");
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+/// A program that imports a function from a second file.
+fn project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("etamil_build_mode_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("vari.qmz"),
+        "செயல் வரி(தொகை) {\n    திரும்பு தொகை * 18 / 100;\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("main.qmz"),
+        "இறக்கு \"vari.qmz\";\nஅச்சு வரி(1000);\nஅச்சு 1 / 0;\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn a_built_program_runs_without_its_source_or_imports() {
+    let dir = project("run");
+    let (code, stdout, stderr) = etamil(&["build", dir.join("main.qmz").to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", stderr);
+    assert!(stdout.contains("main.qmzc"), "{}", stdout);
+
+    std::fs::remove_file(dir.join("vari.qmz")).unwrap();
+    std::fs::remove_file(dir.join("main.qmz")).unwrap();
+
+    let (code, stdout, stderr) = etamil(&["run", dir.join("main.qmzc").to_str().unwrap()]);
+    assert!(stdout.contains("180"), "{}", stdout);
+    // The source is gone, but the error still says where in it.
+    assert_eq!(code, 1);
+    assert!(stderr.contains("main.qmz") && stderr.contains("line 3"), "{}", stderr);
+}
+
+#[test]
+fn build_writes_where_it_is_told_and_refuses_a_program_that_does_not_check() {
+    let dir = project("output");
+    let out = dir.join("dist.qmzc");
+    let (code, _, stderr) = etamil(&[
+        "build",
+        "-o",
+        out.to_str().unwrap(),
+        dir.join("main.qmz").to_str().unwrap(),
+    ]);
+    assert_eq!(code, 0, "{}", stderr);
+    assert!(out.exists());
+
+    std::fs::write(dir.join("wrong.qmz"), "எண் அ = \"இது எண் அல்ல\";\n").unwrap();
+    let (code, _, _) = etamil(&["build", dir.join("wrong.qmz").to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert!(!dir.join("wrong.qmzc").exists());
+}
+
+#[test]
+fn bytecode_from_another_version_is_refused() {
+    let dir = project("version");
+    let (code, _, stderr) = etamil(&["build", dir.join("main.qmz").to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", stderr);
+
+    // The compiler version string starts after the magic, the format version
+    // and the string's length.
+    let built = dir.join("main.qmzc");
+    let mut bytes = std::fs::read(&built).unwrap();
+    bytes[10] = b'9';
+    std::fs::write(&built, bytes).unwrap();
+
+    let (code, stdout, stderr) = etamil(&[built.to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("rebuild it with etamil build"), "{}", stderr);
+    assert!(!stdout.contains("180"), "{}", stdout);
+}
+
+#[test]
+fn modes_that_need_the_source_refuse_bytecode() {
+    let dir = project("modes");
+    etamil(&["build", dir.join("main.qmz").to_str().unwrap()]);
+    let built = dir.join("main.qmzc");
+
+    for mode in ["--check", "--server", "--debug"] {
+        let (code, _, stderr) = etamil(&[mode, built.to_str().unwrap()]);
+        assert_eq!(code, 2, "{}: {}", mode, stderr);
+        assert!(stderr.contains("need the .qmz source"), "{}: {}", mode, stderr);
+    }
+}
@@ -155,3 +155,29 @@ fn a_port_that_is_not_a_number_is_refused() {
         String::from_utf8_lossy(&output.stderr)
     );
 }
+
+/// Regression: calls were walked only in expressions and assignments, so one
+/// inside பதில், a database statement or a file statement was never looked at.
+#[test]
+fn a_bad_call_inside_a_response_is_reported() {
+    let source = "செயல் இரட்டை(எண் எ) எண் { திரும்பு எ * 2; }\n\
+                  வழி பெறு, \"/x\" {\n    பதில் 200, இரட்டை(\"இரண்டு\");\n}\n";
+
+    let (code, _, stderr) = check(source);
+
+    assert_eq!(code, 1, "{stderr}");
+    assert!(stderr.contains("line 3, column 16"), "{stderr}");
+    assert!(stderr.contains("இரட்டை(எ)"), "{stderr}");
+}
+
+#[test]
+fn a_call_with_the_wrong_number_of_arguments_is_reported() {
+    let source = "செயல் இரட்டை(எ) { திரும்பு எ * 2; }\n\
+                  கோப்பு_எழுது \"x.txt\", இரட்டை(1, 2);\n";
+
+    let (code, _, stderr) = check(source);
+
+    assert_eq!(code, 1, "{stderr}");
+    assert!(stderr.contains("line 2, column 23"), "{stderr}");
+    assert!(stderr.contains("expects 1 argument(s), got 2"), "{stderr}");
+}
@@ -0,0 +1,120 @@
+//! Starting `etamil --server` / `--async` for the `*_mode.rs` tests.
+//!
+//! The server is asked for `--port 0` and the port it got is read from
+//! its startup banner, so no test ever races another for a free port.
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// A running server, killed when the test is done with it if it has not stopped.
+pub struct Server {
+    child: Child,
+    pub port: u16,
+}
+
+impl Server {
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// How it exited, waiting no longer than `timeout`.
+    pub fn exited(&mut self, timeout: Duration) -> Option<ExitStatus> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return Some(status);
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        None
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Writes `source` as `main.qmz` in a fresh directory of its own.
+pub fn program(name: &str, source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("etamil_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.qmz"), source).unwrap();
+    dir.join("main.qmz")
+}
+
+/// `etamil <mode> --port 0 <flags> <file>`, run from the file's directory
+/// and with none of the `ETAMIL_*` settings of whoever runs the tests.
+pub fn command(mode: &str, file: &Path, flags: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_etamil"));
+    command.args([mode, "--port", "0"]).args(flags).arg(file);
+    if let Some(dir) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        command.current_dir(dir);
+    }
+    for (name, _) in std::env::vars_os() {
+        if name.to_string_lossy().starts_with("ETAMIL_") {
+            command.env_remove(name);
+        }
+    }
+    command
+}
+
+pub fn serve(mode: &str, file: &Path, flags: &[&str]) -> Server {
+    start(command(mode, file, flags))
+}
+
+/// As `serve`, with some environment set for the server.
+pub fn serve_with(mode: &str, file: &Path, flags: &[&str], env: &[(&str, &str)]) -> Server {
+    let mut command = command(mode, file, flags);
+    command.envs(env.iter().copied());
+    start(command)
+}
+
+/// Starts `command` and waits for the port on its banner.
+pub fn start(mut command: Command) -> Server {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("could not start the compiler");
+    let stdout = child.stdout.take().unwrap();
+    let (found, port) = mpsc::channel();
+    // Read to the end, not only to the banner: a full pipe would stall
+    // a server that logs its requests.
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let port = line.split("Listening on: ").nth(1).and_then(|address| address.rsplit(':').next());
+            if let Some(port) = port.and_then(|port| port.parse::<u16>().ok()) {
+                let _ = found.send(port);
+            }
+        }
+    });
+    let mut server = Server { child, port: 0 };
+    server.port = port
+        .recv_timeout(Duration::from_secs(20))
+        .expect("the server never said where it is listening");
+    server
+}
+
+/// Sends `request` as it is and reads the answer until the server closes.
+pub fn send(port: u16, request: impl AsRef<[u8]>) -> String {
+    let mut socket = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(20))).unwrap();
+    socket.write_all(request.as_ref()).unwrap();
+    let mut response = Vec::new();
+    let _ = socket.read_to_end(&mut response);
+    String::from_utf8_lossy(&response).into_owned()
+}
+
+pub fn get(port: u16, path: &str) -> String {
+    send(port, format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path))
+}
@@ -0,0 +1,77 @@
+//! Tests for `etamil convert`.
+//!
+//! Run through the real binary: what matters is that a converted program
+//! still runs the same, and that a renamed name is reported where the author
+//! will see it.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn etamil(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("could not start the compiler");
+
+    // A usage error exits before reading stdin, so the write may find the
+    // pipe closed; what the compiler said is the answer either way.
+    let _ = child.stdin.as_mut().expect("stdin").write_all(stdin.as_bytes());
+
+    let output = child.wait_with_output().expect("the compiler did not finish");
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+const PROGRAM: &str = "// ஒரு சோதனை\nஎண்ணி = 0;\n(எண்ணி < 3) சுற்று {\n    எண்ணி = எண்ணி + 1;\n}\nஅச்சு \"எனில்: \" & எண்ணி;\n";
+
+#[test]
+fn a_converted_program_runs_the_same_in_every_script() {
+    let (_, expected, _) = etamil(&[], PROGRAM);
+
+    for script in ["roman", "english", "tamil"] {
+        let (code, converted, stderr) = etamil(&["convert", "--to", script], PROGRAM);
+        assert_eq!(code, 0, "{}: {}", script, stderr);
+        assert!(converted.starts_with("// ஒரு சோதனை\n"), "{}", converted);
+        assert!(converted.contains("\"எனில்: \""), "{}", converted);
+
+        let (_, output, _) = etamil(&[], &converted);
+        assert_eq!(output, expected, "{}:\n{}", script, converted);
+    }
+}
+
+#[test]
+fn migration_reports_a_renamed_name() {
+    let (code, stdout, stderr) = etamil(&["convert", "--migrate"], "soqqu = 5;\naccu soqqu;\n");
+
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "coqqu = 5;\naccu coqqu;\n");
+    assert!(stderr.contains("'soqqu'") && stderr.contains("'coqqu'"), "{}", stderr);
+}
+
+#[test]
+fn a_file_is_rewritten_in_place() {
+    let dir = std::env::temp_dir().join("etamil_convert_in_place");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("nirali.qmz");
+    std::fs::write(&file, "அச்சு   1;  // வணக்கம்\n").unwrap();
+
+    let (code, _, stderr) = etamil(&["convert", "--to", "english", dir.to_str().unwrap()], "");
+
+    assert_eq!(code, 0, "{}", stderr);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "accu   1;  // வணக்கம்\n");
+}
+
+#[test]
+fn nothing_to_do_is_a_usage_error() {
+    let (code, _, stderr) = etamil(&["convert"], "அச்சு 1;");
+
+    assert_eq!(code, 2);
+    assert!(stderr.contains("--to"), "{}", stderr);
+}
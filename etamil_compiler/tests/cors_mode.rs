@@ -0,0 +1,91 @@
+//! `--cors-origin` and preflights, in both servers.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::Server;
+
+const PROGRAM: &str = r#"வழி பெறு, "/api/kaNakku/:eN" {
+    பதில் 200, "1000";
+}
+
+வழி இடு, "/api/kaNakku/:eN" {
+    பதில் 200, "saved";
+}
+"#;
+
+fn program(name: &str) -> PathBuf {
+    common::program(&format!("cors_{}", name), PROGRAM)
+}
+
+fn serve(mode: &str, name: &str) -> Server {
+    let policy = [
+        "--cors-origin",
+        "https://dash.example.com",
+        "--cors-credentials",
+        "--cors-headers",
+        "Content-Type, Authorization",
+        "--cors-max-age",
+        "600",
+    ];
+    common::serve(mode, &program(name), &policy)
+}
+
+fn send(port: u16, request: &str) -> String {
+    common::send(port, format!("{}\r\nConnection: close\r\n\r\n", request))
+}
+
+#[test]
+fn preflights_are_answered_and_responses_carry_the_policy() {
+    for mode in ["--server", "--async"] {
+        let server = serve(mode, &mode[2..]);
+
+        let preflight = send(
+            server.port,
+            "OPTIONS /api/kaNakku/7 HTTP/1.1\r\nOrigin: https://dash.example.com\r\n\
+             Access-Control-Request-Method: PUT\r\nAccess-Control-Request-Headers: authorization",
+        );
+        assert!(preflight.starts_with("HTTP/1.1 204"), "{}: {}", mode, preflight);
+        assert!(preflight.contains("Access-Control-Allow-Origin: https://dash.example.com\r\n"), "{}: {}", mode, preflight);
+        assert!(preflight.contains("Access-Control-Allow-Methods: GET, PUT\r\n"), "{}: {}", mode, preflight);
+        assert!(preflight.contains("Access-Control-Allow-Headers: Content-Type, Authorization\r\n"), "{}: {}", mode, preflight);
+        assert!(preflight.contains("Access-Control-Allow-Credentials: true\r\n"), "{}: {}", mode, preflight);
+        assert!(preflight.contains("Access-Control-Max-Age: 600\r\n"), "{}: {}", mode, preflight);
+
+        let allowed = send(server.port, "GET /api/kaNakku/7 HTTP/1.1\r\nOrigin: https://dash.example.com");
+        assert!(allowed.ends_with("1000"), "{}: {}", mode, allowed);
+        assert!(allowed.contains("Access-Control-Allow-Origin: https://dash.example.com\r\n"), "{}: {}", mode, allowed);
+        assert!(allowed.contains("Vary: Origin\r\n"), "{}: {}", mode, allowed);
+
+        // Another origin is still served, but its page may not read it.
+        let other = send(server.port, "GET /api/kaNakku/7 HTTP/1.1\r\nOrigin: https://evil.example");
+        assert!(other.ends_with("1000"), "{}: {}", mode, other);
+        assert!(!other.contains("Access-Control-Allow-Origin"), "{}: {}", mode, other);
+
+        let refused = send(
+            server.port,
+            "OPTIONS /api/kaNakku/7 HTTP/1.1\r\nOrigin: https://evil.example\r\n\
+             Access-Control-Request-Method: PUT",
+        );
+        assert!(refused.starts_with("HTTP/1.1 403"), "{}: {}", mode, refused);
+
+        // No route, no preflight.
+        let nowhere = send(
+            server.port,
+            "OPTIONS /vERu HTTP/1.1\r\nOrigin: https://dash.example.com\r\n\
+             Access-Control-Request-Method: GET",
+        );
+        assert!(nowhere.starts_with("HTTP/1.1 404"), "{}: {}", mode, nowhere);
+    }
+}
+
+#[test]
+fn credentials_for_any_origin_stop_the_server() {
+    let output = common::command("--server", &program("credentials"), &["--cors-credentials"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("needs the origins named"), "{}", stderr);
+}
@@ -0,0 +1,91 @@
+//! Tests for `etamil --debug`.
+//!
+//! Run through the real binary, with the commands on stdin as someone at a
+//! terminal would type them. Where to stop is tested in `vm::debug`; this is
+//! the console around it.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+fn debug(file: &PathBuf, commands: &str) -> (i32, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .arg("--debug")
+        .arg(file)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("could not start the compiler");
+
+    child
+        .stdin
+        .as_mut()
+        .expect("stdin")
+        .write_all(commands.as_bytes())
+        .expect("could not write the commands");
+
+    let output = child.wait_with_output().expect("the compiler did not finish");
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("etamil_debug_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("kaNakku.qmz");
+    std::fs::write(&file, source).unwrap();
+    file
+}
+
+const LEDGER: &str = "\
+செயல் கூட்டு(a, b) {
+    c = a + b;
+    திரும்பு c;
+}
+மொத்தம் = கூட்டு(40, 2);
+அச்சு மொத்தம்;
+";
+
+#[test]
+fn a_breakpoint_shows_the_line_the_stack_and_the_locals() {
+    let file = program("breakpoint", LEDGER);
+
+    let (code, stdout) = debug(&file, "break 3\ncontinue\nstack\nvars\nprint c\ncontinue\n");
+
+    assert_eq!(code, 0, "{}", stdout);
+    assert!(stdout.contains("● ") && stdout.contains("திரும்பு c;"), "{}", stdout);
+    assert!(stdout.contains("#0 கூட்டு") && stdout.contains("#1 நிரல்"), "{}", stdout);
+    assert!(stdout.contains("a = 40\n") && stdout.contains("b = 2\n"), "{}", stdout);
+    assert!(stdout.contains("c = 42\n"), "{}", stdout);
+    // The program's own output, then the end.
+    assert!(stdout.contains("42\n✓"), "{}", stdout);
+}
+
+#[test]
+fn next_steps_over_a_call_and_a_blank_line_repeats_it() {
+    let file = program("next", LEDGER);
+
+    let (_, stdout) = debug(&file, "next\n\n\n");
+
+    let stops: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.split("→ ").nth(1))
+        .collect();
+    assert_eq!(stops.len(), 2, "{}", stdout);
+    assert!(stops[0].contains("line 5"), "{}", stdout);
+    assert!(stops[1].contains("line 6"), "{}", stdout);
+}
+
+#[test]
+fn a_runtime_error_is_reported_and_fails() {
+    let file = program("error", "அ = 0;\nஆ = 1 / அ;\n");
+
+    let (code, stdout) = debug(&file, "continue\n");
+
+    assert_eq!(code, 1);
+    assert!(stdout.contains("line 2"), "{}", stdout);
+}
@@ -0,0 +1,99 @@
+//! `கோப்புறை`: directories served by both servers.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::Server;
+
+// The directories are relative, as a database file is: to where the server
+// was started.
+const PROGRAM: &str = r#"கோப்புறை "/", "public";
+கோப்புறை "/aRikkY", "reports", 3600;
+
+வழி பெறு, "/api/ping" {
+    பதில் 200, "pong";
+}
+
+வழி பெறு, "/about.html" {
+    பதில் 200, "from the route";
+}
+"#;
+
+/// The program, beside the two directories it serves.
+fn site(name: &str) -> PathBuf {
+    let main = common::program(&format!("files_mode_{}", name), PROGRAM);
+    let dir = main.parent().unwrap();
+    std::fs::create_dir_all(dir.join("public")).unwrap();
+    std::fs::create_dir_all(dir.join("reports")).unwrap();
+    std::fs::write(dir.join("public/index.html"), "<h1>கடை</h1>").unwrap();
+    std::fs::write(dir.join("public/about.html"), "from the file").unwrap();
+    std::fs::write(dir.join("reports/statement.pdf"), b"%PDF-0123456789").unwrap();
+    main
+}
+
+fn serve(mode: &str, name: &str) -> Server {
+    common::serve(mode, &site(name), &[])
+}
+
+fn send(port: u16, request: &str) -> String {
+    common::send(port, format!("{}\r\nConnection: close\r\n\r\n", request))
+}
+
+/// A header's value, by name, from a response's head.
+fn header<'r>(response: &'r str, name: &str) -> Option<&'r str> {
+    let head = response.split("\r\n\r\n").next()?;
+    head.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+fn both_servers(test: impl Fn(&str)) {
+    for mode in ["--server", "--async"] {
+        test(mode);
+    }
+}
+
+#[test]
+fn files_are_served_beside_routes_which_win_where_both_match() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("beside{}", mode));
+
+        let index = send(server.port, "GET / HTTP/1.1");
+        assert!(index.starts_with("HTTP/1.1 200"), "{}: {}", mode, index);
+        assert_eq!(header(&index, "Content-Type"), Some("text/html; charset=utf-8"), "{}", mode);
+        assert!(index.ends_with("<h1>கடை</h1>"), "{}: {}", mode, index);
+
+        let routed = send(server.port, "GET /about.html HTTP/1.1");
+        assert!(routed.ends_with("from the route"), "{}: {}", mode, routed);
+        assert!(send(server.port, "GET /api/ping HTTP/1.1").ends_with("pong"), "{}", mode);
+
+        let escaped = send(server.port, "GET /%2e%2e/main.qmz HTTP/1.1");
+        assert!(escaped.starts_with("HTTP/1.1 404"), "{}: {}", mode, escaped);
+    });
+}
+
+#[test]
+fn a_report_is_cached_revalidated_and_sent_in_ranges() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("report{}", mode));
+
+        let whole = send(server.port, "GET /aRikkY/statement.pdf HTTP/1.1");
+        assert_eq!(header(&whole, "Cache-Control"), Some("public, max-age=3600"), "{}", mode);
+        let etag = header(&whole, "ETag").expect("an ETag").to_string();
+
+        let again = send(server.port, &format!("GET /aRikkY/statement.pdf HTTP/1.1\r\nIf-None-Match: {}", etag));
+        assert!(again.starts_with("HTTP/1.1 304"), "{}: {}", mode, again);
+        assert!(again.ends_with("\r\n\r\n"), "{}: {}", mode, again);
+
+        let part = send(server.port, "GET /aRikkY/statement.pdf HTTP/1.1\r\nRange: bytes=5-9");
+        assert!(part.starts_with("HTTP/1.1 206"), "{}: {}", mode, part);
+        assert_eq!(header(&part, "Content-Range"), Some("bytes 5-9/15"), "{}", mode);
+        assert!(part.ends_with("\r\n\r\n01234"), "{}: {}", mode, part);
+
+        let head = send(server.port, "HEAD /aRikkY/statement.pdf HTTP/1.1");
+        assert_eq!(header(&head, "Content-Length"), Some("15"), "{}", mode);
+        assert!(head.ends_with("\r\n\r\n"), "{}: {}", mode, head);
+    });
+}
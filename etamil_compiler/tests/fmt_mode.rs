@@ -0,0 +1,120 @@
+//! Tests for `etamil fmt`.
+//!
+//! Run through the real binary, because what CI depends on is the process:
+//! that `--check` exits non-zero and names the file, and that a file that
+//! does not parse is left exactly as it was.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+fn etamil(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("could not start the compiler");
+
+    child
+        .stdin
+        .as_mut()
+        .expect("stdin")
+        .write_all(stdin.as_bytes())
+        .expect("could not write the program");
+
+    let output = child.wait_with_output().expect("the compiler did not finish");
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+/// A fresh directory for one test's files.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("etamil_fmt_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn path(file: &Path) -> &str {
+    file.to_str().unwrap()
+}
+
+#[test]
+fn stdin_is_formatted_to_stdout() {
+    let (code, stdout, _) = etamil(&["fmt"], "அ=1;அச்சு அ;");
+
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "அ = 1;\nஅச்சு அ;\n");
+}
+
+#[test]
+fn check_names_the_file_and_changes_nothing() {
+    let dir = scratch("check");
+    let messy = dir.join("kuzappam.qmz");
+    let tidy = dir.join("neRi.qmz");
+    std::fs::write(&messy, "அ=1;\n").unwrap();
+    std::fs::write(&tidy, "அ = 1;\n").unwrap();
+
+    let (code, stdout, _) = etamil(&["fmt", "--check", path(&dir)], "");
+
+    assert_eq!(code, 1);
+    assert!(stdout.contains("kuzappam.qmz"), "{stdout}");
+    assert!(!stdout.contains("neRi.qmz"), "{stdout}");
+    assert_eq!(std::fs::read_to_string(&messy).unwrap(), "அ=1;\n");
+}
+
+#[test]
+fn a_file_is_rewritten_in_place_and_a_broken_one_is_left_alone() {
+    let dir = scratch("rewrite");
+    let good = dir.join("nalla.qmz");
+    let broken = dir.join("udaindha.qmz");
+    std::fs::write(&good, "(அ>1) eZil {accu அ;}\n").unwrap();
+    std::fs::write(&broken, "அச்சு 1\n").unwrap();
+
+    let (code, _, stderr) = etamil(
+        &["fmt", "--script", "tamil", path(&good), path(&broken)],
+        "",
+    );
+
+    assert_eq!(code, 1, "a file that could not be formatted is a failure");
+    assert!(stderr.contains("udaindha.qmz"), "{stderr}");
+    assert_eq!(std::fs::read_to_string(&good).unwrap(), "(அ > 1) எனில் { அச்சு அ; }\n");
+    assert_eq!(std::fs::read_to_string(&broken).unwrap(), "அச்சு 1\n");
+}
+
+/// The standard library and the examples are the largest body of eTamil there
+/// is. Formatting any of it must succeed, and formatting the result again must
+/// change nothing.
+#[test]
+fn the_library_and_examples_format_and_stay_formatted() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut files = Vec::new();
+    for dir in ["nUlakam", "examples"] {
+        collect(&root.join(dir), &mut files);
+    }
+    assert!(!files.is_empty());
+
+    for file in files {
+        let source = std::fs::read_to_string(&file).unwrap();
+        let once = etamil_compiler::formatter::format(&source, None)
+            .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+        let twice = etamil_compiler::formatter::format(&once, None).unwrap();
+        assert_eq!(once, twice, "{} is not stable under formatting", file.display());
+    }
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "qmz") {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! HTTPS in both servers, and mutual TLS.
+//!
+//! The real binary, a real port and a real handshake: a certificate authority,
+//! a server certificate and a client certificate are made for each test, so
+//! nothing checked in can expire.
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rcgen::{BasicConstraints, CertificateParams, DistinguishedName, DnType, IsCa, KeyPair};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+
+use common::Server;
+
+const PROGRAM: &str = "வழி பெறு, \"/whoami\" {\n    பதில் 200, \"subject=\" & client_subject;\n}\n";
+
+/// A CA, a server certificate for 127.0.0.1 and a client certificate, all
+/// written as PEM into a fresh directory.
+struct Pki {
+    dir: PathBuf,
+    ca: CertificateDer<'static>,
+    client_chain: Vec<CertificateDer<'static>>,
+    client_key: Vec<u8>,
+}
+
+impl Pki {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("etamil_https_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.distinguished_name.push(DnType::CommonName, "Example Bank Partner CA");
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let server_key = KeyPair::generate().unwrap();
+        let server = CertificateParams::new(vec!["127.0.0.1".to_string()])
+            .unwrap()
+            .signed_by(&server_key, &ca, &ca_key)
+            .unwrap();
+
+        let client_key = KeyPair::generate().unwrap();
+        let mut client_params = CertificateParams::new(Vec::new()).unwrap();
+        // Starts with a placeholder common name, which would keep its place.
+        client_params.distinguished_name = DistinguishedName::new();
+        client_params.distinguished_name.push(DnType::CountryName, "IN");
+        client_params.distinguished_name.push(DnType::OrganizationName, "Example Bank");
+        client_params.distinguished_name.push(DnType::CommonName, "upi-partner");
+        let client = client_params.signed_by(&client_key, &ca, &ca_key).unwrap();
+
+        std::fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+        std::fs::write(dir.join("server.pem"), server.pem()).unwrap();
+        std::fs::write(dir.join("server.key"), server_key.serialize_pem()).unwrap();
+        std::fs::write(dir.join("main.qmz"), PROGRAM).unwrap();
+
+        Pki {
+            dir,
+            ca: ca.der().clone(),
+            client_chain: vec![client.der().clone()],
+            client_key: client_key.serialize_der(),
+        }
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.dir.join(file).to_string_lossy().into_owned()
+    }
+}
+
+fn serve(mode: &str, pki: &Pki, client_ca: bool) -> Server {
+    let cert = pki.path("server.pem");
+    let key = pki.path("server.key");
+    let ca = pki.path("ca.pem");
+    let mut flags = vec!["--tls-cert", &cert, "--tls-key", &key];
+    if client_ca {
+        flags.extend(["--tls-client-ca", ca.as_str()]);
+    }
+    common::serve(mode, &pki.dir.join("main.qmz"), &flags)
+}
+
+/// GET /whoami over TLS, presenting the client certificate if asked to.
+fn whoami(pki: &Pki, port: u16, present_certificate: bool) -> Result<String, String> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(pki.ca.clone()).unwrap();
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots);
+    let config = if present_certificate {
+        let key = PrivateKeyDer::try_from(pki.client_key.clone()).unwrap();
+        builder.with_client_auth_cert(pki.client_chain.clone(), key).unwrap()
+    } else {
+        builder.with_no_client_auth()
+    };
+
+    let name = ServerName::try_from("127.0.0.1").unwrap();
+    let connection = rustls::ClientConnection::new(Arc::new(config), name).unwrap();
+    let socket = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(20))).unwrap();
+    let mut stream = rustls::StreamOwned::new(connection, socket);
+
+    stream
+        .write_all(b"GET /whoami HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n")
+        .map_err(|e| e.to_string())?;
+    let mut response = Vec::new();
+    match stream.read_to_end(&mut response) {
+        Ok(_) => Ok(String::from_utf8_lossy(&response).into_owned()),
+        // Whatever arrived before an abrupt close is still the answer.
+        Err(_) if !response.is_empty() => Ok(String::from_utf8_lossy(&response).into_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn both_servers(test: impl Fn(&str)) {
+    for mode in ["--server", "--async"] {
+        test(mode);
+    }
+}
+
+#[test]
+fn both_servers_speak_https() {
+    both_servers(|mode| {
+        let pki = Pki::new(&format!("plain{}", mode));
+        let server = serve(mode, &pki, false);
+
+        let response = whoami(&pki, server.port, false).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}: {}", mode, response);
+        // No client CA, so no certificate was asked for.
+        assert!(response.ends_with("subject="), "{}: {}", mode, response);
+    });
+}
+
+#[test]
+fn a_verified_client_certificate_reaches_the_handler() {
+    both_servers(|mode| {
+        let pki = Pki::new(&format!("mutual{}", mode));
+        let server = serve(mode, &pki, true);
+
+        let response = whoami(&pki, server.port, true).unwrap();
+        assert!(
+            response.ends_with("subject=CN=upi-partner,O=Example Bank,C=IN"),
+            "{}: {}",
+            mode,
+            response
+        );
+    });
+}
+
+#[test]
+fn a_client_without_a_certificate_is_refused() {
+    both_servers(|mode| {
+        let pki = Pki::new(&format!("refused{}", mode));
+        let server = serve(mode, &pki, true);
+
+        let outcome = whoami(&pki, server.port, false);
+        assert!(
+            outcome.as_ref().is_err() || !outcome.as_ref().unwrap().contains("HTTP/1.1"),
+            "{}: {:?}",
+            mode,
+            outcome
+        );
+    });
+}
+
+#[test]
+fn a_certificate_without_its_key_stops_the_server_starting() {
+    let pki = Pki::new("half");
+    let cert = pki.path("server.pem");
+    let output = common::command("--server", &pki.dir.join("main.qmz"), &["--tls-cert", &cert])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("without its key"), "{}", stderr);
+}
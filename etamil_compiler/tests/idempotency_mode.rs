@@ -0,0 +1,99 @@
+//! `--idempotency`, in both servers.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use common::Server;
+
+/// Each order placed is a row, so the count says how often the handler ran.
+const PROGRAM: &str = r#"வழி பதி, "/ANY" {
+    தளம்_இணை சீகுலைட், "kadai.db";
+    தளம்_செய் "CREATE TABLE IF NOT EXISTS ANYkaL (vivaram TEXT)", [];
+    தளம்_செய் "INSERT INTO ANYkaL (vivaram) VALUES (?)", [request_body];
+    தளம்_வினா "SELECT COUNT(*) AS ennikkY FROM ANYkaL", [], கிடைப்பு;
+    பதில் 201, "ANY " & கிடைப்பு[0].ennikkY;
+}
+"#;
+
+fn program(name: &str) -> PathBuf {
+    common::program(&format!("idempotency_{}", name), PROGRAM)
+}
+
+fn serve(mode: &str, file: &Path, store: &str) -> Server {
+    common::serve(mode, file, &["--idempotency", store])
+}
+
+fn post(port: u16, key: Option<&str>, body: &str) -> String {
+    let key = key.map_or(String::new(), |key| format!("Idempotency-Key: {}\r\n", key));
+    common::send(
+        port,
+        format!("POST /ANY HTTP/1.1\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}", key, body.len(), body),
+    )
+}
+
+#[test]
+fn an_order_sent_twice_with_its_key_is_placed_once() {
+    for mode in ["--server", "--async"] {
+        let server = serve(mode, &program(&mode[2..]), "memory");
+
+        let first = post(server.port, Some("ord-7f3a"), r#"{"kuRi": "P1"}"#);
+        assert!(first.starts_with("HTTP/1.1 201"), "{}: {}", mode, first);
+        assert!(first.ends_with("ANY 1"), "{}: {}", mode, first);
+
+        let again = post(server.port, Some("ord-7f3a"), r#"{"kuRi": "P1"}"#);
+        assert!(again.starts_with("HTTP/1.1 201"), "{}: {}", mode, again);
+        assert!(again.ends_with("ANY 1"), "{}: {}", mode, again);
+        assert!(again.contains("Idempotent-Replayed: true"), "{}: {}", mode, again);
+
+        // Without a key nothing is kept, and a new key is a new order.
+        assert!(post(server.port, None, r#"{"kuRi": "P1"}"#).ends_with("ANY 2"), "{}", mode);
+        assert!(post(server.port, Some("ord-9c21"), r#"{"kuRi": "P1"}"#).ends_with("ANY 3"), "{}", mode);
+    }
+}
+
+#[test]
+fn a_key_sent_again_with_another_body_is_refused() {
+    for mode in ["--server", "--async"] {
+        let server = serve(mode, &program(&format!("reused_{}", &mode[2..])), "memory");
+
+        assert!(post(server.port, Some("ord-1"), r#"{"kuRi": "P1"}"#).ends_with("ANY 1"), "{}", mode);
+        let refused = post(server.port, Some("ord-1"), r#"{"kuRi": "P2"}"#);
+        assert!(refused.starts_with("HTTP/1.1 422"), "{}: {}", mode, refused);
+        assert!(refused.contains("different body"), "{}: {}", mode, refused);
+    }
+}
+
+#[test]
+fn answers_kept_in_sqlite_outlive_the_server() {
+    let file = program("sqlite");
+    let store = format!("sqlite:{}", file.with_file_name("keys.db").display());
+    {
+        let server = serve("--server", &file, &store);
+        assert!(post(server.port, Some("ord-42"), "{}").ends_with("ANY 1"));
+    }
+    let server = serve("--async", &file, &store);
+    let again = post(server.port, Some("ord-42"), "{}");
+    assert!(again.ends_with("ANY 1"), "{}", again);
+    assert!(again.contains("Idempotent-Replayed: true"), "{}", again);
+}
+
+#[test]
+fn a_store_that_is_not_one_stops_the_server() {
+    for (args, said) in [
+        (vec!["--server", "--idempotency", "disk"], "memory, redis or sqlite:PATH"),
+        (vec!["--server", "--idempotency", "redis"], "needs --redis"),
+        (vec!["--idempotency", "memory"], "--idempotency needs --server or --async"),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_etamil"))
+            .args(&args)
+            .arg(program("refused"))
+            .env_remove("ETAMIL_REDIS")
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(said), "{:?}: {}", args, stderr);
+    }
+}
@@ -0,0 +1,207 @@
+//! Keep-alive, chunked requests and streamed responses, in both servers.
+//!
+//! Over a raw socket rather than an HTTP client, because what is under test
+//! is the framing itself: where one request ends and the next begins, and
+//! what a chunked body looks like on the wire.
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use common::Server;
+
+const PROGRAM: &str = r#"வழி பதி, "/echo" {
+    பதில் 200, request_body;
+}
+
+வழி பெறு, "/ping" {
+    பதில் 200, "pong";
+}
+
+வழி பெறு, "/report" {
+    பதில்_தொடங்கு(200, {"Content-Type": "text/csv"});
+    ஒவ்வொரு எண்ணிக்கை இல் [1, 2, 3] {
+        பதில்_பகுதி("row " & எண்ணிக்கை & "\n");
+    }
+}
+"#;
+
+fn serve(mode: &str, name: &str) -> Server {
+    common::serve(mode, &common::program(&format!("keepalive_{}", name), PROGRAM), &[])
+}
+
+fn connect(port: u16) -> TcpStream {
+    let socket = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(20))).unwrap();
+    socket
+}
+
+/// Read one response off a connection that stays open: the head, then a body
+/// framed by Content-Length or by chunks. Chunked bodies come back decoded,
+/// with the raw chunks alongside so a test can see they were streamed.
+struct Response {
+    head: String,
+    body: String,
+    chunks: usize,
+}
+
+fn read_response(socket: &mut TcpStream, pending: &mut Vec<u8>) -> Response {
+    let head_end = loop {
+        if let Some(at) = pending.windows(4).position(|w| w == b"\r\n\r\n") {
+            break at + 4;
+        }
+        fill(socket, pending);
+    };
+    let head = String::from_utf8_lossy(&pending[..head_end]).into_owned();
+    pending.drain(..head_end);
+
+    if head.contains("Transfer-Encoding: chunked") {
+        let mut body = Vec::new();
+        let mut chunks = 0;
+        loop {
+            let line_end = loop {
+                if let Some(at) = pending.windows(2).position(|w| w == b"\r\n") {
+                    break at;
+                }
+                fill(socket, pending);
+            };
+            let size =
+                usize::from_str_radix(std::str::from_utf8(&pending[..line_end]).unwrap(), 16)
+                    .unwrap();
+            while pending.len() < line_end + 2 + size + 2 {
+                fill(socket, pending);
+            }
+            body.extend_from_slice(&pending[line_end + 2..line_end + 2 + size]);
+            pending.drain(..line_end + 2 + size + 2);
+            if size == 0 {
+                let body = String::from_utf8(body).unwrap();
+                return Response { head, body, chunks };
+            }
+            chunks += 1;
+        }
+    }
+
+    let length: usize = head
+        .lines()
+        .find_map(|line| line.strip_prefix("Content-Length: "))
+        .map(|value| value.trim().parse().unwrap())
+        .unwrap_or(0);
+    while pending.len() < length {
+        fill(socket, pending);
+    }
+    let body = String::from_utf8_lossy(&pending[..length]).into_owned();
+    pending.drain(..length);
+    Response { head, body, chunks: 0 }
+}
+
+fn fill(socket: &mut TcpStream, pending: &mut Vec<u8>) {
+    let mut chunk = [0u8; 4096];
+    let n = socket.read(&mut chunk).expect("the server stopped answering");
+    assert!(n > 0, "the server closed the connection early");
+    pending.extend_from_slice(&chunk[..n]);
+}
+
+fn both_servers(test: impl Fn(&str)) {
+    for mode in ["--server", "--async"] {
+        test(mode);
+    }
+}
+
+#[test]
+fn one_connection_carries_several_requests() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("several{}", mode));
+        let mut socket = connect(server.port);
+        let mut pending = Vec::new();
+
+        for _ in 0..3 {
+            socket.write_all(b"GET /ping HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+            let response = read_response(&mut socket, &mut pending);
+            assert!(response.head.starts_with("HTTP/1.1 200"), "{}: {}", mode, response.head);
+            assert!(response.head.contains("Connection: keep-alive"), "{}: {}", mode, response.head);
+            assert_eq!(response.body, "pong", "{}", mode);
+        }
+    });
+}
+
+// Sent back to back without waiting, the answers come back in the order the
+// requests were sent.
+#[test]
+fn pipelined_requests_are_answered_in_order() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("pipelined{}", mode));
+        let mut socket = connect(server.port);
+        socket
+            .write_all(
+                b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nfirst\
+                  POST /echo HTTP/1.1\r\nContent-Length: 6\r\n\r\nsecond\
+                  GET /ping HTTP/1.1\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+
+        let mut pending = Vec::new();
+        let bodies: Vec<String> = (0..3)
+            .map(|_| read_response(&mut socket, &mut pending).body)
+            .collect();
+        assert_eq!(bodies, ["first", "second", "pong"], "{}", mode);
+
+        // It asked for the connection to close, and it was.
+        let mut rest = Vec::new();
+        let _ = socket.read_to_end(&mut rest);
+        assert!(rest.is_empty(), "{}: {:?}", mode, rest);
+    });
+}
+
+// How several payment gateways send webhooks.
+#[test]
+fn a_chunked_request_body_reaches_the_handler_whole() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("chunked{}", mode));
+        let mut socket = connect(server.port);
+        socket
+            .write_all(
+                b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                  b\r\n{\"qokY\":150\r\n2\r\n0}\r\n0\r\n\r\n",
+            )
+            .unwrap();
+
+        let response = read_response(&mut socket, &mut Vec::new());
+        assert_eq!(response.body, "{\"qokY\":1500}", "{}", mode);
+    });
+}
+
+#[test]
+fn a_streamed_response_arrives_in_chunks_and_the_connection_survives_it() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("streamed{}", mode));
+        let mut socket = connect(server.port);
+        let mut pending = Vec::new();
+
+        socket.write_all(b"GET /report HTTP/1.1\r\n\r\n").unwrap();
+        let report = read_response(&mut socket, &mut pending);
+        assert!(report.head.contains("Content-Type: text/csv"), "{}: {}", mode, report.head);
+        assert!(!report.head.contains("Content-Length"), "{}: {}", mode, report.head);
+        assert_eq!(report.body, "row 1\nrow 2\nrow 3\n", "{}", mode);
+        assert_eq!(report.chunks, 3, "{}", mode);
+
+        socket.write_all(b"GET /ping HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(read_response(&mut socket, &mut pending).body, "pong", "{}", mode);
+    });
+}
+
+#[test]
+fn an_http_1_0_request_is_answered_and_closed() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("old{}", mode));
+        let mut socket = connect(server.port);
+        socket.write_all(b"GET /ping HTTP/1.0\r\n\r\n").unwrap();
+
+        let mut all = Vec::new();
+        socket.read_to_end(&mut all).unwrap();
+        let all = String::from_utf8_lossy(&all);
+        assert!(all.contains("Connection: close"), "{}: {}", mode, all);
+        assert!(all.ends_with("pong"), "{}: {}", mode, all);
+    });
+}
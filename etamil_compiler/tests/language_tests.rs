@@ -445,6 +445,76 @@ fn tamil_function_definition_and_call() {
     assert_eq!(num(&vm, "விடை"), dec(100));
 }
 
+// --- Function values and closures ------------------------------------------
+
+#[test]
+fn map_filter_and_reduce_take_a_function_by_name_or_written_in_place() {
+    let vm = run("செயல் இரட்டி(n) { திரும்பு n * 2; } \
+                  எண்கள் = [1, 2, 3, 4]; \
+                  இரட்டைகள் = மாற்றியமை(எண்கள், இரட்டி); \
+                  பெரியவை = வடிகட்டு(எண்கள், செயல்(n) { திரும்பு n > 2; }); \
+                  மொத்தம் = சுருக்கு(எண்கள், 0, செயல்(கூட்டல், n) { திரும்பு கூட்டல் + n; });")
+        .unwrap();
+    assert_eq!(text(&vm, "இரட்டைகள்"), "[2, 4, 6, 8]");
+    assert_eq!(text(&vm, "பெரியவை"), "[3, 4]");
+    assert_eq!(num(&vm, "மொத்தம்"), dec(10));
+    assert!(vm.stack.is_empty(), "stack leaked: {:?}", vm.stack);
+}
+
+#[test]
+fn a_closure_keeps_the_locals_of_the_call_that_made_it() {
+    let vm = run("ceyal kUttu_vaN(aLavu) { qirumpu ceyal(n) { qirumpu n + aLavu; }; } \
+                  aIntu = kUttu_vaN(5); pattu = kUttu_vaN(10); \
+                  a = aIntu(1); b = pattu(1); \
+                  c = _map([1, 2], kUttu_vaN(100));")
+        .unwrap();
+    // Each call made its own: the second did not overwrite the first's aLavu.
+    assert_eq!(num(&vm, "a"), dec(6));
+    assert_eq!(num(&vm, "b"), dec(11));
+    assert_eq!(text(&vm, "c"), "[101, 102]");
+}
+
+#[test]
+fn a_closure_captures_a_copy_and_reads_globals_when_it_runs() {
+    let vm = run("vIqam = 2; \
+                  ceyal cey() { eNNi = 1; f = ceyal() { qirumpu eNNi * vIqam; }; eNNi = 50; qirumpu f; } \
+                  g = cey(); vIqam = 3; vitY = g();")
+        .unwrap();
+    // eNNi was 1 when the closure was made; vIqam is read at the call.
+    assert_eq!(num(&vm, "vitY"), dec(3));
+}
+
+#[test]
+fn a_function_value_in_a_variable_is_called_by_that_name() {
+    let vm = run("ceyal kUttu(a, b) { qirumpu a + b; } \
+                  f = kUttu; x = f(2, 3); \
+                  ceyal payaNpatuqqu(g, v) { qirumpu g(v, v); } \
+                  y = payaNpatuqqu(kUttu, 4); \
+                  same = f == kUttu; vakai = vakY(f);")
+        .unwrap();
+    assert_eq!(num(&vm, "x"), dec(5));
+    assert_eq!(num(&vm, "y"), dec(8));
+    assert_eq!(vm.variables["same"], Value::Boolean(true));
+    assert_eq!(text(&vm, "vakai"), "a function");
+}
+
+#[test]
+fn a_failure_inside_a_mapped_function_shows_the_builtin_beneath_it() {
+    let err = run("x = 1;\nஅ = மாற்றியமை([1, 0], செயல்(n) {\n    திரும்பு 10 / n;\n});\n")
+        .expect_err("division by zero inside the function");
+    assert!(err.contains("line 3"), "{}", err);
+    assert!(err.contains("செயல் (anonymous)"), "{}", err);
+    assert!(err.contains("நிரல் (top level)"), "{}", err);
+}
+
+#[test]
+fn map_needs_a_function() {
+    let err = run("a = _map([1, 2], 5);").expect_err("5 is not a function");
+    assert!(err.contains("needs a function, got a number"), "{}", err);
+    let err = run("a = _filter(5, ceyal(n) { qirumpu n; });").expect_err("5 is not an array");
+    assert!(err.contains("needs an array"), "{}", err);
+}
+
 // --- Arrays (அணி — a column) ----------------------------------------------
 
 #[test]
@@ -1423,6 +1493,106 @@ fn a_request_needs_the_right_number_of_arguments() {
     assert!(run(r#"கை = கையொப்பம்("only-a-key");"#).is_err());
 }
 
+/// A server on a loopback port that answers each connection with the next of
+/// `statuses`, the last one for ever after, and counts how many it answered.
+/// Loopback is not the network: nothing here leaves the machine.
+fn canned_server(statuses: &[u16]) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let answered = std::sync::Arc::new(AtomicUsize::new(0));
+    let counted = std::sync::Arc::clone(&answered);
+    let statuses = statuses.to_vec();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut head = [0u8; 1024];
+            let _ = stream.read(&mut head);
+            let turn = counted.fetch_add(1, Ordering::SeqCst);
+            let status = statuses[turn.min(statuses.len() - 1)];
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {} X\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                status
+            );
+        }
+    });
+    (url, answered)
+}
+
+#[test]
+fn a_guarded_request_retries_until_it_is_answered() {
+    let (url, answered) = canned_server(&[503, 502, 200]);
+    let vm = run(&format!(
+        r#"விளைவு = வலை_காப்பு_அனுப்பு("GET", "{}", இன்மை, {{}},
+               {{"மீள்முயற்சிகள்": 3, "காத்திருப்பு": 0.01}});
+           நிலை = மதிப்பு(விளைவு)["நிலைக்_குறி"];"#,
+        url
+    ))
+    .unwrap();
+
+    assert_eq!(vm.variables.get("நிலை"), Some(&Value::Number(dec(200))));
+    assert_eq!(answered.load(std::sync::atomic::Ordering::SeqCst), 3);
+}
+
+// A decline is an answer, not an outage: 402 is handed back on the first try.
+#[test]
+fn a_guarded_request_does_not_retry_an_answer() {
+    let (url, answered) = canned_server(&[402]);
+    let vm = run(&format!(
+        r#"விளைவு = வலை_காப்பு_அனுப்பு("POST", "{}", "{{}}", {{}}, {{"retries": 3, "backoff": 0.01}});
+           நிலை = மதிப்பு(விளைவு)["நிலைக்_குறி"];"#,
+        url
+    ))
+    .unwrap();
+
+    assert_eq!(vm.variables.get("நிலை"), Some(&Value::Number(dec(402))));
+    assert_eq!(answered.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+// Once the breaker is open a call fails at once, without reaching the
+// gateway, so a page can say "try later" instead of waiting on every retry.
+#[test]
+fn an_open_breaker_fails_calls_without_making_them() {
+    let (url, answered) = canned_server(&[503]);
+    let vm = run(&format!(
+        r#"கொள்கை = {{"மீள்முயற்சிகள்": 0, "சுற்று": "language-test-gateway", "தோல்விகள்": 2}};
+           ஒன்று = வலை_காப்பு_அனுப்பு("GET", "{url}", இன்மை, {{}}, கொள்கை);
+           இரண்டு = வலை_காப்பு_அனுப்பு("GET", "{url}", இன்மை, {{}}, கொள்கை);
+           மூன்று = வலை_காப்பு_அனுப்பு("GET", "{url}", இன்மை, {{}}, கொள்கை);
+           முதலில்_பதிலா = சரியா(ஒன்று);
+           பின்_தவறா = தவறா(மூன்று);"#
+    ))
+    .unwrap();
+
+    assert_eq!(vm.variables.get("முதலில்_பதிலா"), Some(&Value::Boolean(true)));
+    assert_eq!(vm.variables.get("பின்_தவறா"), Some(&Value::Boolean(true)));
+    assert_eq!(answered.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[test]
+fn a_misspelt_policy_is_an_error() {
+    let error = run(r#"விளைவு = வலை_காப்பு_அனுப்பு("GET", "http://127.0.0.1:9/", இன்மை, {}, {"சுற்ற": "x"});"#)
+        .err()
+        .unwrap();
+    assert!(error.contains("the policy has no 'சுற்ற'"), "{}", error);
+}
+
+#[test]
+fn a_wait_too_long_to_keep_is_a_policy_error() {
+    for key in ["backoff", "max_backoff", "திறப்பு"] {
+        let error = run(&format!(
+            r#"விளைவு = வலை_காப்பு_அனுப்பு("GET", "http://127.0.0.1:9/", இன்மை, {{}}, {{"{}": 100000000000000000000}});"#,
+            key
+        ))
+        .err()
+        .unwrap();
+        assert!(error.contains(&format!("the policy's '{}' must be a number of seconds", key)), "{}", error);
+    }
+}
+
 // --- Authentication -------------------------------------------------------
 // bcrypt and HMAC live in the host because eTamil cannot express them; the
 // policy built on top stays in the language. A token's payload crosses the
@@ -2374,6 +2544,270 @@ fn a_loop_variable_drops_an_earlier_declaration() {
     assert_eq!(num(&vm, "மொத்தம்"), dec(6));
 }
 
+// --- Function signatures ----------------------------------------------------
+// A செயல் may declare its parameters the way a variable is declared, type
+// first, and its return type after the parameter list. What it declares is
+// held to at every call and every திரும்பு; what it leaves out is not.
+
+#[test]
+fn a_function_with_a_signature_runs() {
+    let vm = run(
+        r#"செயல் வரி(எண் வருமானம், சொல் பெயர்) எண் {
+               திரும்பு வருமானம் * 10%;
+           }
+           எண் விளைவு = வரி(50000, "ரவி");"#,
+    )
+    .unwrap();
+
+    assert_eq!(num(&vm, "விளைவு"), dec(5000));
+}
+
+#[test]
+fn an_argument_is_held_to_its_parameter_type() {
+    let message = run(
+        r#"செயல் இரட்டை(எண் மதிப்பு) { திரும்பு மதிப்பு * 2; }
+           விளைவு = இரட்டை("இருபது");"#,
+    )
+    .unwrap_err();
+
+    assert!(message.contains("இரட்டை(மதிப்பு)"), "should name the parameter: {}", message);
+    assert!(message.contains("a string"), "should name what it got: {}", message);
+    assert!(message.contains("line 2"), "should point at the call: {}", message);
+}
+
+// Calls are checked wherever they appear, not only on the right of `=`.
+#[test]
+fn a_call_inside_an_expression_is_checked() {
+    let message = run(
+        r#"செயல் இல்லையா(ஈர்ம கொடி) { திரும்பு இல்லை கொடி; }
+           அச்சு [1, இல்லையா(5)];"#,
+    )
+    .unwrap_err();
+
+    assert!(message.contains("இல்லையா(கொடி)"), "{}", message);
+}
+
+// Functions may be called before they are defined, so a signature must be
+// known before the call is reached.
+#[test]
+fn a_call_before_the_definition_is_checked() {
+    let failure = run(
+        r#"விளைவு = நீட்டு("அ");
+           செயல் நீட்டு(அணி பட்டியல்) { திரும்பு பட்டியல்; }"#,
+    );
+
+    assert!(failure.is_err(), "a string is not an array");
+}
+
+#[test]
+fn a_return_is_held_to_the_declared_return_type() {
+    let message = run(
+        r#"செயல் பெயர்() சொல் {
+               திரும்பு "ரவி";
+           }
+           செயல் சரிபார்() ஈர்ம {
+               திரும்பு [1];
+           }"#,
+    )
+    .unwrap_err();
+
+    assert!(message.contains("சரிபார்"), "should name the function: {}", message);
+    assert!(message.contains("line 5"), "should point at the திரும்பு: {}", message);
+    assert!(!message.contains("'பெயர்'"), "a correct return was rejected: {}", message);
+}
+
+#[test]
+fn a_call_infers_as_its_declared_return_type() {
+    let failure = run(
+        r#"செயல் மொத்தம்(அணி எண்கள்) எண் { திரும்பு நீளம்(எண்கள்); }
+           அணி விளைவு = மொத்தம்([1, 2]);"#,
+    );
+    assert!(failure.is_err(), "a number is not an array");
+
+    // And the result can be used where the declared type is wanted.
+    let vm = run(
+        r#"செயல் மொத்தம்(அணி எண்கள்) எண் { திரும்பு நீளம்(எண்கள்); }
+           எண் விளைவு = மொத்தம்([1, 2]) + 1;"#,
+    )
+    .unwrap();
+    assert_eq!(num(&vm, "விளைவு"), dec(3));
+}
+
+// A typed parameter is a declaration inside the body like any other.
+#[test]
+fn a_typed_parameter_is_a_declaration_in_the_body() {
+    let failure = run(
+        r#"செயல் மாற்று(எண் மதிப்பு) {
+               மதிப்பு = "பத்து";
+               திரும்பு மதிப்பு;
+           }"#,
+    );
+
+    assert!(failure.is_err(), "a string is not a number");
+}
+
+// Leaving the types out is still a complete definition.
+#[test]
+fn an_undeclared_parameter_accepts_anything() {
+    let vm = run(
+        r#"செயல் முதல்(எண் அ, ஆ) { திரும்பு ஆ; }
+           விளைவு = முதல்(1, "எதுவும்");"#,
+    )
+    .unwrap();
+
+    assert_eq!(text(&vm, "விளைவு"), "எதுவும்");
+}
+
+// --- Money (ரொக்கம்) ------------------------------------------------------
+// `₹1500.50` is an amount in a currency, not a number with a symbol on it:
+// arithmetic keeps the currency, and refuses to mix two of them rather than
+// adding rupees to dollars as if a rate of one had been agreed.
+
+fn money(amount: &str, currency: &str) -> Value {
+    Value::Money {
+        amount: amount.parse().unwrap(),
+        currency: currency.to_string(),
+    }
+}
+
+#[test]
+fn a_money_literal_keeps_its_currency() {
+    let vm = run("விலை = ₹1500.50 + ₹20; டாலர் = $12;").unwrap();
+
+    assert_eq!(vm.variables["விலை"], money("1520.50", "INR"));
+    assert_eq!(text(&vm, "விலை"), "₹1520.50");
+    assert_eq!(text(&vm, "டாலர்"), "$12.00");
+}
+
+#[test]
+fn amounts_in_different_currencies_do_not_add() {
+    let message = run("மொத்தம் = ₹100 + $2;").unwrap_err();
+
+    assert!(message.contains("INR"), "should name one currency: {}", message);
+    assert!(message.contains("USD"), "and the other: {}", message);
+    assert!(message.contains("line 1"), "with a position: {}", message);
+}
+
+// A plain number has not said which currency it is in. Zero has nothing to
+// say, which is what lets an accumulator start at 0 and unary minus work.
+#[test]
+fn only_zero_adds_to_an_amount() {
+    assert!(run("அ = ₹100 + 5;").is_err(), "5 of what?");
+
+    let vm = run(
+        r#"மொத்தம் = 0;
+           ஒவ்வொரு அ இல் [₹100, ₹250.50] { மொத்தம் = மொத்தம் + அ; }
+           கடன் = -₹40;"#,
+    )
+    .unwrap();
+    assert_eq!(vm.variables["மொத்தம்"], money("350.50", "INR"));
+    assert_eq!(vm.variables["கடன்"], money("-40", "INR"));
+}
+
+#[test]
+fn an_amount_scales_and_splits_but_a_ratio_is_a_number() {
+    let vm = run(
+        r#"வரி = ₹1000 * 18%;
+           பங்கு = ₹1000 / 4;
+           விகிதம் = ₹250 / ₹1000;"#,
+    )
+    .unwrap();
+
+    assert_eq!(vm.variables["வரி"], money("180", "INR"));
+    assert_eq!(vm.variables["பங்கு"], money("250", "INR"));
+    assert_eq!(vm.variables["விகிதம்"], Value::Number("0.25".parse().unwrap()));
+    assert!(run("அ = ₹10 * ₹10;").is_err(), "rupees squared is not money");
+}
+
+// `partial_cmp` has no answer for ₹100 against $2, and read as "no" that made
+// `<` and `>` both false. It is an error instead.
+#[test]
+fn amounts_in_different_currencies_do_not_compare() {
+    assert!(run("அ = (₹100 < $2);").is_err());
+
+    let vm = run("அ = (₹1500 > 1000); ஆ = (₹5 == $5); இ = (₹0 == 0);").unwrap();
+    assert_eq!(vm.variables["அ"], Value::Boolean(true), "a plain threshold compares");
+    assert_eq!(vm.variables["ஆ"], Value::Boolean(false));
+    assert_eq!(vm.variables["இ"], Value::Boolean(true), "zero is zero in every currency");
+}
+
+#[test]
+fn money_builtins_answer_to_all_three_spellings() {
+    let vm = run(
+        r#"அ = பணமாக்கு(12.5, "chf");
+           நா = நாணயம்(₹5);
+           கு = nANaya_kuRi($5);
+           தொ = _amount(₹5.25);
+           வெறும் = நாணயம்(5);
+           வ = vattamitu(₹10.125, 2);"#,
+    )
+    .unwrap();
+
+    assert_eq!(vm.variables["அ"], money("12.5", "CHF"));
+    assert_eq!(text(&vm, "அ"), "12.50 CHF");
+    assert_eq!(text(&vm, "நா"), "INR");
+    assert_eq!(text(&vm, "கு"), "$");
+    assert_eq!(vm.variables["தொ"], Value::Number("5.25".parse().unwrap()));
+    assert_eq!(vm.variables["வெறும்"], Value::Null);
+    assert_eq!(vm.variables["வ"], money("10.13", "INR"), "rounding keeps the currency");
+}
+
+#[test]
+fn a_money_declaration_is_enforced() {
+    let message = run("ரொக்கம் இருப்பு = 1500;").unwrap_err();
+    assert!(message.contains("ரொக்கம்"), "should name the declared type: {}", message);
+
+    assert!(run("எண் எண்ணிக்கை = ₹5;").is_err(), "an amount is not a count");
+
+    // A declaration with no value starts at zero, which is an amount.
+    let vm = run("ரொக்கம் மொத்தம்; மொத்தம் = மொத்தம் + ₹5; rokkam பாக்கி = ₹5 * 2;").unwrap();
+    assert_eq!(vm.variables["மொத்தம்"], money("5", "INR"));
+}
+
+#[test]
+fn rupee_formatting_uses_the_amounts_own_symbol() {
+    let vm = run_with_stdlib(
+        r#"இறக்கு "paNam.qmz";
+           அ = ரூபாய்(₹1234567.5);
+           ஆ = ரூபாய்($1500);
+           இ = ரூபாய்(1500);"#,
+    )
+    .unwrap();
+
+    assert_eq!(text(&vm, "அ"), "₹12,34,567.50");
+    assert_eq!(text(&vm, "ஆ"), "$1,500.00");
+    assert_eq!(text(&vm, "இ"), "₹1,500.00", "a plain number is still rupees");
+}
+
+#[test]
+fn an_amount_is_bound_with_its_currency() {
+    let src = r#"qaLam_cey "INSERT INTO kaNakku (vari) VALUES (?)", [₹4200.50];"#;
+    let (vm, log) = run_with_db(src, vec![]);
+    vm.unwrap();
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.params[0][0], Value::String("4200.50 INR".into()));
+}
+
+#[test]
+fn an_amount_round_trips_through_sqlite() {
+    let path = std::env::temp_dir().join("etamil_money.db");
+    let _ = std::fs::remove_file(&path);
+    let shown = path.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/");
+
+    let vm = run(&format!(
+        r#"தளம்_இணை சீகுலைட், "{}";
+           தளம்_செய் "CREATE TABLE vilY (x TEXT)", [];
+           தளம்_செய் "INSERT INTO vilY VALUES (?)", [$19.99];
+           தளம்_வினா "SELECT x FROM vilY", [], வரிசைகள்;
+           திரும்ப = வரிசைகள்[0].x;"#,
+        shown
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(vm.unwrap().variables["திரும்ப"], money("19.99", "USD"));
+}
+
 // --- Parse errors carry a position ----------------------------------------
 // Regression: the parser panicked with `Expected Semicolon` and nothing else.
 // tokenize() returned a bare Vec<Token>, so there was no position to report
@@ -2440,6 +2874,67 @@ fn a_column_counts_written_letters_not_bytes() {
     assert!(error.contains("column 13"), "wrong column: {}", error);
 }
 
+// --- Runtime errors carry a position --------------------------------------
+// Regression: a runtime error said what went wrong and nothing about where,
+// so a division by zero three imports deep read the same as one on line 1.
+
+#[test]
+fn a_runtime_error_is_reported_with_its_line_and_column() {
+    let err = run("x = 1;\ny = x / 0;").expect_err("division by zero");
+
+    assert!(err.contains("வரி 2, நெடுவரிசை 1"), "no Tamil position in: {}", err);
+    assert!(err.contains("line 2, column 1"), "no English position in: {}", err);
+    assert!(err.contains("division by zero"), "lost the message: {}", err);
+}
+
+#[test]
+fn a_failing_builtin_is_reported_where_it_was_called() {
+    let err = run("x = 1;\nஅச்சு 2;\ny = 3 + maqippu(qavaRu(\"pizai\"));")
+        .expect_err("unwrap on Err");
+
+    // `maqippu` is the ninth written letter of line 3.
+    assert!(err.contains("line 3, column 9"), "wrong position: {}", err);
+}
+
+#[test]
+fn an_error_in_a_call_statement_is_reported_at_that_statement() {
+    // Its argument fails before the call is made, where nothing had marked a
+    // line since the body of the function above.
+    let err = run("ceyal kAttu(x) {\n    qirumpu x;\n}\nkAttu(1 / 0);").expect_err("division by zero");
+
+    assert!(err.contains("line 4, column 1"), "wrong position: {}", err);
+}
+
+#[test]
+fn an_error_inside_a_function_shows_the_call_stack() {
+    let src = "ceyal pangkitu(a, b) {\n    qirumpu a / b;\n}\n\
+               ceyal veLiyE(x) {\n    qirumpu pangkitu(x, 0);\n}\n\
+               y = veLiyE(5);";
+    let err = run(src).expect_err("division by zero");
+
+    assert!(err.contains("call stack"), "no call stack in: {}", err);
+    let lines: Vec<&str> = err.lines().collect();
+    // Innermost first, then each caller at the place it made the call.
+    assert!(lines[0].contains("line 2, column 5"), "wrong failure position: {}", err);
+    assert!(lines[2].contains("pangkitu") && lines[2].contains("line 2"), "{}", err);
+    assert!(lines[3].contains("veLiyE") && lines[3].contains("line 5, column 13"), "{}", err);
+    assert!(lines[4].contains("top level") && lines[4].contains("line 7, column 5"), "{}", err);
+}
+
+#[test]
+fn a_runtime_error_in_an_imported_file_names_that_file() {
+    let lib = write_module("runtime_lib.qmz", "ceyal pAti(x) {\n    qirumpu x / 0;\n}");
+    let name = lib.file_name().unwrap().to_string_lossy().to_string();
+    let main = format!(r#"iRakku "{}"; y = pAti(4);"#, name);
+    let ast = etamil_compiler::module::load_source(&main, &std::env::temp_dir()).unwrap();
+    let err = run_program(ast).expect_err("division by zero");
+
+    let first = err.lines().next().unwrap();
+    assert!(first.contains(&name), "the imported file is not named: {}", err);
+    assert!(first.contains("line 2, column 5"), "wrong position: {}", err);
+    let _ = std::fs::remove_file(lib);
+}
+
 // --- String escapes -------------------------------------------------------
 // Regression: literals were kept exactly as written, so "a\nb" was four
 // characters and a double quote could not be put in a string at all — which
@@ -2538,6 +3033,27 @@ fn response_headers_are_not_silently_discarded() {
     assert_ne!(vm.variables.get("response_headers"), Some(&Value::Null));
 }
 
+// A line break in a value would end the header there and start one the
+// program never wrote; a space or a colon in a name makes a header no client
+// reads as meant.
+#[test]
+fn a_header_that_cannot_be_sent_as_given_is_refused() {
+    let vm = run(r#"பதில்_தலைப்பு("X-Rows", "2");"#).unwrap();
+    match vm.variables.get("response_headers") {
+        Some(Value::Map(fields)) => {
+            assert_eq!(fields.get("X-Rows").map(|v| v.to_string()), Some("2".to_string()));
+        }
+        other => panic!("expected a record of headers, got {:?}", other),
+    }
+
+    let split = run(r#"பதில்_தலைப்பு("X-Rows", "2\r\nSet-Cookie: admin=1");"#).unwrap_err();
+    assert!(split.contains("control character"), "got: {}", split);
+    for name in ["X Rows", "X-Rows:", ""] {
+        let error = run(&format!(r#"பதில்_தலைப்பு("{}", "2");"#, name)).unwrap_err();
+        assert!(error.contains("cannot name a header"), "{:?} got: {}", name, error);
+    }
+}
+
 // பதில் is normally reached from inside a route's helper function, so it must
 // not land in that function's locals.
 #[test]
@@ -2555,6 +3071,47 @@ fn a_response_sent_from_a_function_is_still_visible() {
     assert_eq!(text(&vm, "response_body"), "from a function");
 }
 
+// With no connection to stream to — a test, or `etamil test` — the parts of
+// a streamed response collect into the body, so a handler that streams can
+// still be checked without a server.
+#[test]
+fn a_streamed_response_without_a_connection_collects_into_the_body() {
+    let vm = run(
+        r#"தொடக்கம் = பதில்_தொடங்கு(200, {"Content-Type": "text/csv"});
+           ஒவ்வொரு துண்டு இல் ["id,qokY\n", "1,1500\n"] { பதில்_பகுதி(துண்டு); }
+           மீண்டும் = தவறா(பதில்_தொடங்கு(500, {}));"#,
+    )
+    .unwrap();
+
+    assert_eq!(num(&vm, "response_status"), dec(200));
+    assert_eq!(text(&vm, "response_body"), "id,qokY\n1,1500\n");
+    // The head goes once; a second start is refused rather than ignored.
+    assert_eq!(vm.variables.get("மீண்டும்"), Some(&Value::Boolean(true)));
+}
+
+// Likewise an events stream: each message is framed as the browser will read
+// it, a line break inside one becoming a second data line.
+#[test]
+fn events_without_a_connection_collect_into_the_body_as_they_would_be_sent() {
+    let vm = run(
+        r#"நிகழ்வுகள்_தொடங்கு();
+           செய்தி_அனுப்பு("PENDING");
+           செய்தி_அனுப்பு("SUCCESS\nUTR 412345678901");
+           இணைப்பு_மூடு();
+           பிறகு = தவறா(செய்தி_அனுப்பு("late"));
+           சாக்கெட் = தவறா(வலைசாக்கெட்_தொடங்கு());"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        text(&vm, "response_body"),
+        "data: PENDING\n\ndata: SUCCESS\ndata: UTR 412345678901\n\n"
+    );
+    assert_eq!(vm.variables.get("பிறகு"), Some(&Value::Boolean(true)));
+    // The response has started, and it is not a WebSocket request besides.
+    assert_eq!(vm.variables.get("சாக்கெட்"), Some(&Value::Boolean(true)));
+}
+
 // --- Text over a whole string ---------------------------------------------
 // மாற்று, பிரி and ஒன்றிணை moved from nUlakam/col.qmz into the host because
 // the eTamil versions re-segmented the string on every letter read. These
@@ -0,0 +1,135 @@
+//! Timeouts and body sizes, in both servers.
+//!
+//! Each limit is set low on the command line so a test takes a second or two
+//! rather than the minute the defaults allow.
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use common::{Server, send};
+
+const PROGRAM: &str = r#"வழி பதி, "/echo" {
+    பதில் 200, "got " & நீளம்(request_body);
+}
+
+வழி பதி, "/journal/:batch" {
+    பதில் 200, "got " & நீளம்(request_body);
+}
+
+வழி பெறு, "/spin" {
+    எண்ணி = 0;
+    (எண்ணி == 0) சுற்று {
+        எண்ணி = 0;
+    }
+    பதில் 200, "never";
+}
+
+வழி பெறு, "/ping" {
+    பதில் 200, "pong";
+}
+"#;
+
+fn program(name: &str) -> std::path::PathBuf {
+    common::program(&format!("limits_{}", name), PROGRAM)
+}
+
+fn serve(mode: &str, name: &str, limits: &[&str]) -> Server {
+    common::serve(mode, &program(name), limits)
+}
+
+fn both_servers(test: impl Fn(&str)) {
+    for mode in ["--server", "--async"] {
+        test(mode);
+    }
+}
+
+#[test]
+fn a_handler_that_runs_too_long_is_stopped_with_a_504() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("spin{}", mode), &["--handler-timeout", "1"]);
+
+        let started = Instant::now();
+        let response = send(server.port, b"GET /spin HTTP/1.1\r\nConnection: close\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 504"), "{}: {}", mode, response);
+        assert!(started.elapsed() < Duration::from_secs(10), "{}", mode);
+
+        // The worker it held is free again.
+        let response = send(server.port, b"GET /ping HTTP/1.1\r\nConnection: close\r\n\r\n");
+        assert!(response.ends_with("pong"), "{}: {}", mode, response);
+    });
+}
+
+// Refused as soon as the head says how large the body is, without reading it.
+#[test]
+fn a_body_over_the_limit_is_refused_with_a_413() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("large{}", mode), &[]);
+        let response = send(server.port, b"POST /echo HTTP/1.1\r\nContent-Length: 2000000\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 413"), "{}: {}", mode, response);
+    });
+}
+
+// The bulk journal upload takes 50 MB; everything else keeps the default.
+#[test]
+fn one_route_can_take_a_larger_body() {
+    both_servers(|mode| {
+        let server = serve(
+            mode,
+            &format!("route{}", mode),
+            &["--route-max-body", "POST /journal/:batch=50MB"],
+        );
+        let body = vec![b'x'; 3 * 1024 * 1024];
+        let mut request =
+            format!("POST /journal/2026-10 HTTP/1.1\r\nConnection: close\r\nContent-Length: {}\r\n\r\n", body.len())
+                .into_bytes();
+        request.extend_from_slice(&body);
+
+        let response = send(server.port, &request);
+        assert!(response.ends_with(&format!("got {}", body.len())), "{}: {}", mode, &response[..response.len().min(200)]);
+    });
+}
+
+#[test]
+fn a_client_that_never_finishes_its_headers_gets_a_408() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("slow{}", mode), &["--header-timeout", "1"]);
+
+        let started = Instant::now();
+        let response = send(server.port, b"GET /ping HTTP/1.1\r\nHost: x\r\n");
+        assert!(response.starts_with("HTTP/1.1 408"), "{}: {}", mode, response);
+        assert!(started.elapsed() < Duration::from_secs(10), "{}", mode);
+    });
+}
+
+#[test]
+fn a_quiet_connection_is_closed_after_the_idle_timeout() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("idle{}", mode), &["--idle-timeout", "1"]);
+        let mut socket = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(20))).unwrap();
+        socket.write_all(b"GET /ping HTTP/1.1\r\n\r\n").unwrap();
+
+        // The response, then nothing more: the server closes a connection it
+        // was keeping open once the client goes quiet, without answering.
+        let started = Instant::now();
+        let mut all = Vec::new();
+        socket.read_to_end(&mut all).unwrap();
+        let all = String::from_utf8_lossy(&all);
+        assert!(all.starts_with("HTTP/1.1 200") && all.ends_with("pong"), "{}: {}", mode, all);
+        assert!(started.elapsed() < Duration::from_secs(10), "{}", mode);
+    });
+}
+
+#[test]
+fn a_limit_that_does_not_parse_stops_the_server_starting() {
+    let output = common::command("--server", &program("unparsed"), &["--max-body", "lots"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--max-body"), "{}", stderr);
+}
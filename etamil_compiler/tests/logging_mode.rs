@@ -0,0 +1,112 @@
+//! The access log and `X-Request-Id`, in both servers.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use common::serve;
+
+const PROGRAM: &str = r#"வழி பெறு, "/kaNakku" {
+    பதில் 200, "கோரிக்கை " & request_id;
+}
+"#;
+
+fn program(name: &str) -> PathBuf {
+    common::program(&format!("logging_{}", name), PROGRAM)
+}
+
+fn get(port: u16, path: &str, headers: &str) -> String {
+    common::send(port, format!("GET {} HTTP/1.1\r\n{}Connection: close\r\n\r\n", path, headers))
+}
+
+/// The log's request lines, once the server has written as many as expected.
+fn access_lines(log: &Path, expected: usize) -> Vec<serde_json::Value> {
+    let deadline = Instant::now() + Duration::from_secs(20);
+    loop {
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(log)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("a log line that is not JSON"))
+            .filter(|entry: &serde_json::Value| entry["message"] == "request")
+            .collect();
+        if lines.len() >= expected || Instant::now() > deadline {
+            return lines;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn both_servers(test: impl Fn(&str)) {
+    for mode in ["--server", "--async"] {
+        test(mode);
+    }
+}
+
+// A proxy's ID is the one the handler sees, the response carries and the log
+// records, so the three can be joined on it.
+#[test]
+fn a_request_id_from_the_proxy_follows_the_request_through() {
+    both_servers(|mode| {
+        let main = program(&format!("proxy{}", mode));
+        let log = main.with_file_name("access.log");
+        let server = serve(mode, &main, &["--log-file", log.to_str().unwrap()]);
+
+        let response = get(server.port, "/kaNakku", "X-Request-Id: lb-7f3a9c\r\n");
+        assert!(response.contains("X-Request-Id: lb-7f3a9c"), "{}: {}", mode, response);
+        assert!(response.ends_with("கோரிக்கை lb-7f3a9c"), "{}: {}", mode, response);
+
+        let lines = access_lines(&log, 1);
+        assert_eq!(lines.len(), 1, "{}: {:?}", mode, lines);
+        assert_eq!(lines[0]["request"]["request_id"], "lb-7f3a9c", "{}", mode);
+        assert_eq!(lines[0]["request"]["status_code"], 200, "{}", mode);
+        assert!(lines[0]["request"]["duration_ms"].is_u64(), "{}: {:?}", mode, lines[0]);
+    });
+}
+
+#[test]
+fn each_request_without_an_id_is_given_its_own() {
+    both_servers(|mode| {
+        let main = program(&format!("own{}", mode));
+        let log = main.with_file_name("access.log");
+        let server = serve(mode, &main, &["--log-file", log.to_str().unwrap()]);
+
+        let first = get(server.port, "/kaNakku", "");
+        let second = get(server.port, "/illai", "");
+        assert!(second.starts_with("HTTP/1.1 404"), "{}: {}", mode, second);
+
+        let lines = access_lines(&log, 2);
+        assert_eq!(lines.len(), 2, "{}: {:?}", mode, lines);
+        let first_id = lines[0]["request"]["request_id"].as_str().unwrap().to_string();
+        let second_id = lines[1]["request"]["request_id"].as_str().unwrap().to_string();
+        assert_ne!(first_id, second_id, "{}", mode);
+        assert!(first.ends_with(&format!("கோரிக்கை {}", first_id)), "{}: {}", mode, first);
+        assert!(second.contains(&format!("X-Request-Id: {}", second_id)), "{}: {}", mode, second);
+        assert_eq!(lines[1]["request"]["status_code"], 404, "{}", mode);
+    });
+}
+
+#[test]
+fn above_the_level_nothing_is_logged() {
+    both_servers(|mode| {
+        let main = program(&format!("level{}", mode));
+        let log = main.with_file_name("access.log");
+        let server = serve(mode, &main, &["--log-level", "error", "--log-file", log.to_str().unwrap()]);
+
+        let response = get(server.port, "/kaNakku", "");
+        assert!(response.starts_with("HTTP/1.1 200"), "{}: {}", mode, response);
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(std::fs::read_to_string(&log).unwrap_or_default(), "", "{}", mode);
+    });
+}
+
+#[test]
+fn a_level_that_is_not_one_stops_the_server_starting() {
+    let output = common::command("--async", &program("unparsed"), &["--log-level", "loud"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--log-level"), "{}", stderr);
+}
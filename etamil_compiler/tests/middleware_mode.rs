@@ -0,0 +1,39 @@
+//! `முன்` / `பின்` blocks around the routes, and the file's setup they share,
+//! in both servers.
+
+mod common;
+
+const PROGRAM: &str = r#"கோப்பு_எழுது "setup.log", "ran";
+பயனர் = "guest";
+
+முன் "/api" {
+    பயனர் = "ravi";
+}
+
+வழி பெறு, "/api/kaNakku" {
+    பதில் 200, "கணக்கு " & பயனர்;
+}
+"#;
+
+// Compiled into every block as well as the route, the setup ran once for
+// each of them, and the route's run put back the "guest" the முன் block had
+// replaced.
+fn the_setup_runs_once_per_request_in(mode: &str) {
+    let file = common::program(&format!("middleware_setup{}", mode), PROGRAM);
+    let server = common::serve(mode, &file, &[]);
+
+    for _ in 0..2 {
+        let response = common::get(server.port, "/api/kaNakku");
+        assert!(response.starts_with("HTTP/1.1 200"), "got: {}", response);
+        assert!(response.ends_with("கணக்கு ravi"), "got: {}", response);
+    }
+
+    let log = std::fs::read_to_string(file.with_file_name("setup.log")).unwrap();
+    assert_eq!(log.lines().count(), 2, "got: {:?}", log);
+}
+
+#[test]
+fn the_setup_runs_once_per_request() {
+    the_setup_runs_once_per_request_in("--server");
+    the_setup_runs_once_per_request_in("--async");
+}
@@ -0,0 +1,103 @@
+//! The built-in `/health` and `/metrics`, in both servers.
+
+mod common;
+
+use common::{Server, get};
+
+const PROGRAM: &str = r#"வழி பெறு, "/kaNakku/:id" {
+    தளம்_இணை சீகுலைட், "observe.db";
+    தளம்_வினா "SELECT 1 AS onRu", [], விடை;
+    பதில் 200, "கணக்கு " & param_id;
+}
+"#;
+
+fn serve(mode: &str, name: &str, program: &str, flags: &[&str]) -> Server {
+    common::serve(mode, &common::program(&format!("observe_{}", name), program), flags)
+}
+
+fn both_servers(test: impl Fn(&str)) {
+    for mode in ["--server", "--async"] {
+        test(mode);
+    }
+}
+
+#[test]
+fn health_names_each_database_the_program_has_opened() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("health{}", mode), PROGRAM, &[]);
+
+        let before = get(server.port, "/health");
+        assert!(before.starts_with("HTTP/1.1 200"), "{}: {}", mode, before);
+        assert!(before.contains(r#""status":"HEALTHY""#), "{}: {}", mode, before);
+        assert!(!before.contains("observe.db"), "{}: {}", mode, before);
+
+        get(server.port, "/kaNakku/1000");
+        let after = get(server.port, "/health");
+        assert!(after.contains("SQLite observe.db"), "{}: {}", mode, after);
+    });
+}
+
+#[test]
+fn a_redis_that_is_not_there_takes_the_server_out_of_rotation() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("redis{}", mode), PROGRAM, &["--redis", "127.0.0.1:1"]);
+        let response = get(server.port, "/health");
+        assert!(response.starts_with("HTTP/1.1 503"), "{}: {}", mode, response);
+        assert!(response.contains("Redis 127.0.0.1:1"), "{}: {}", mode, response);
+    });
+}
+
+// Counted under the route's pattern, not the path: one series for every
+// account rather than one per account.
+#[test]
+fn metrics_count_each_route_by_its_pattern() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("metrics{}", mode), PROGRAM, &["--metrics"]);
+        get(server.port, "/kaNakku/1000");
+        get(server.port, "/kaNakku/2000");
+        get(server.port, "/illai");
+
+        let metrics = get(server.port, "/metrics");
+        assert!(metrics.contains("Content-Type: text/plain; version=0.0.4"), "{}: {}", mode, metrics);
+        assert!(
+            metrics.contains(r#"etamil_requests_total{method="GET",route="/kaNakku/:id",status="200"} 2"#),
+            "{}: {}",
+            mode,
+            metrics
+        );
+        assert!(
+            metrics.contains(r#"etamil_request_duration_seconds_count{method="GET",route="/kaNakku/:id"} 2"#),
+            "{}: {}",
+            mode,
+            metrics
+        );
+        assert!(
+            metrics.contains(r#"etamil_requests_total{method="GET",route="unmatched",status="404"} 1"#),
+            "{}: {}",
+            mode,
+            metrics
+        );
+    });
+}
+
+#[test]
+fn metrics_are_not_served_unless_asked_for() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("nometrics{}", mode), PROGRAM, &[]);
+        let response = get(server.port, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 404"), "{}: {}", mode, response);
+    });
+}
+
+#[test]
+fn a_program_that_defines_health_answers_it_itself() {
+    let program = r#"வழி பெறு, "/health" {
+    பதில் 200, "எங்கள் சொந்த";
+}
+"#;
+    both_servers(|mode| {
+        let server = serve(mode, &format!("own{}", mode), program, &[]);
+        let response = get(server.port, "/health");
+        assert!(response.ends_with("எங்கள் சொந்த"), "{}: {}", mode, response);
+    });
+}
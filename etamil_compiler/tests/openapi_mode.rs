@@ -0,0 +1,79 @@
+//! `etamil openapi`, and `/openapi.json` from both servers.
+
+mod common;
+
+use std::path::PathBuf;
+use std::process::Command;
+
+const PROGRAM: &str = r#"வழி பெறு, "/ANY/:kuRi", {
+    சுருக்கம்: "One order",
+    விடைகள்: {"200": {kuRi: "எண்", nilY: "சொல்"}, "404": {பிழை: "சொல்"}}
+} {
+    பதில் 200, "{}";
+}
+
+வழி பதி, "/ANY", {
+    ஏற்பது: {vAtikkYyALar: "சொல்", kuRippu: "சொல்?"},
+    விடைகள்: {"201": {kuRi: "எண்"}}
+} {
+    பதில் 201, "{}";
+}
+"#;
+
+/// Named kadai.qmz rather than main.qmz: the document takes its title
+/// from the file name.
+fn program(name: &str, source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("etamil_openapi_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("kadai.qmz"), source).unwrap();
+    dir.join("kadai.qmz")
+}
+
+#[test]
+fn the_document_describes_every_route_and_what_it_says_it_takes() {
+    let output = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .arg("openapi")
+        .arg(program("cli", PROGRAM))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["openapi"], "3.0.3");
+    assert_eq!(document["info"]["title"], "kadai");
+
+    let get = &document["paths"]["/ANY/{kuRi}"]["get"];
+    assert_eq!(get["summary"], "One order");
+    assert_eq!(get["parameters"][0]["name"], "kuRi");
+    assert_eq!(get["responses"]["404"]["description"], "Not Found");
+
+    let body = &document["paths"]["/ANY"]["post"]["requestBody"]["content"]["application/json"]["schema"];
+    assert_eq!(body["required"], serde_json::json!(["vAtikkYyALar"]));
+}
+
+#[test]
+fn a_route_the_document_cannot_describe_is_named() {
+    let output = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .arg("openapi")
+        .arg(program("unknown", "வழி பெறு, \"/x\", {விடை: \"சொல்\"} { பதில் 200, \"\"; }\n"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("GET /x: விடை is not something a route can say"), "{}", stderr);
+}
+
+#[test]
+fn both_servers_serve_the_document_when_asked() {
+    for mode in ["--server", "--async"] {
+        let server = common::serve(mode, &program(&format!("serve{}", mode), PROGRAM), &["--openapi"]);
+        let response = common::get(server.port, "/openapi.json");
+
+        assert!(response.starts_with("HTTP/1.1 200"), "{}: {}", mode, response);
+        assert!(response.contains("Content-Type: application/json"), "{}: {}", mode, response);
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or_default();
+        let document: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert!(document["paths"]["/ANY"]["post"].is_object(), "{}: {}", mode, body);
+    }
+}
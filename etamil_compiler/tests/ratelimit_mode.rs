@@ -0,0 +1,51 @@
+//! `--rate-limit`, in both servers.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{Server, get};
+
+const PROGRAM: &str = r#"வழி பெறு, "/api/vari/:varampu" {
+    பதில் 200, "18";
+}
+
+வழி பெறு, "/api/nilY" {
+    பதில் 200, "OK";
+}
+"#;
+
+fn program(name: &str) -> PathBuf {
+    common::program(&format!("ratelimit_{}", name), PROGRAM)
+}
+
+fn serve(mode: &str, name: &str) -> Server {
+    let rules = ["--rate-limit", "/api/vari=2/min", "--rate-limit", "/api=100/min by header:X-Api-Key"];
+    common::serve(mode, &program(name), &rules)
+}
+
+#[test]
+fn a_client_past_its_limit_is_refused_with_a_retry_after() {
+    for mode in ["--server", "--async"] {
+        let server = serve(mode, &mode[2..]);
+
+        assert!(get(server.port, "/api/vari/5").ends_with("18"), "{}", mode);
+        assert!(get(server.port, "/api/vari/12").ends_with("18"), "{}", mode);
+        let refused = get(server.port, "/api/vari/18");
+        assert!(refused.starts_with("HTTP/1.1 429 Too Many Requests"), "{}: {}", mode, refused);
+        assert!(refused.contains("Retry-After: "), "{}: {}", mode, refused);
+
+        // /api/nilY is under the second rule, with a bucket of its own.
+        assert!(get(server.port, "/api/nilY").ends_with("OK"), "{}", mode);
+    }
+}
+
+#[test]
+fn a_rule_that_does_not_read_stops_the_server() {
+    let output = common::command("--server", &program("malformed"), &["--rate-limit", "/api=60/fortnight"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("'fortnight' is not a period"), "{}", stderr);
+}
@@ -0,0 +1,184 @@
+//! Events streams and WebSockets, in both servers.
+//!
+//! Over a raw socket, as keepalive_mode.rs is, because the framing is what is
+//! under test: an event's `data:` lines, and a WebSocket's masked frames.
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use common::Server;
+
+const PROGRAM: &str = r#"வழி பெறு, "/nilY/:id" {
+    நிகழ்வுகள்_தொடங்கு();
+    ஒவ்வொரு நிலை இல் ["PENDING", "PENDING", "SUCCESS"] {
+        செய்தி_அனுப்பு(param_id & " " & நிலை);
+        காத்திரு(0.6);
+    }
+}
+
+வழி பெறு, "/echo" {
+    வலைசாக்கெட்_தொடங்கு();
+    தொடர் = மெய்;
+    (தொடர்) சுற்று {
+        வந்தது = செய்தி_பெறு(0);
+        (தவறா(வந்தது)) எனில் {
+            தொடர் = பொய்;
+        } இன்றேல் {
+            வாசகம் = மதிப்பு(வந்தது);
+            (வாசகம் == "bye") எனில் {
+                இணைப்பு_மூடு();
+                தொடர் = பொய்;
+            } இன்றேல் {
+                செய்தி_அனுப்பு("echo " & வாசகம்);
+            }
+        }
+    }
+}
+
+வழி பெறு, "/neetu" {
+    காத்திரு(100000000000000000000);
+    பதில் 200, "woke";
+}
+
+வழி பெறு, "/ping" {
+    பதில் 200, "pong";
+}
+"#;
+
+fn serve(mode: &str, name: &str, flags: &[&str], workers: &str) -> Server {
+    let main = common::program(&format!("realtime_{}", name), PROGRAM);
+    common::serve_with(mode, &main, flags, &[("ETAMIL_WORKERS", workers)])
+}
+
+fn connect(port: u16) -> TcpStream {
+    let socket = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(20))).unwrap();
+    socket
+}
+
+/// Read up to the blank line that ends a response's head.
+fn read_head(socket: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        assert_eq!(socket.read(&mut byte).unwrap(), 1, "closed before the head ended");
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).unwrap()
+}
+
+/// Open a WebSocket on `/echo`, returning the server's handshake.
+fn open_socket(port: u16) -> (TcpStream, String) {
+    let mut socket = connect(port);
+    socket
+        .write_all(
+            b"GET /echo HTTP/1.1\r\nHost: kadai\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+              Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        )
+        .unwrap();
+    let head = read_head(&mut socket);
+    (socket, head)
+}
+
+/// Send one frame as a client must: masked.
+fn send_frame(socket: &mut TcpStream, opcode: u8, payload: &[u8]) {
+    let mask = [0x5a, 0x13, 0xc4, 0x7e];
+    let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    socket.write_all(&frame).unwrap();
+}
+
+/// One frame from the server: its opcode and payload.
+fn read_frame(socket: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut head = [0u8; 2];
+    socket.read_exact(&mut head).unwrap();
+    let mut payload = vec![0u8; (head[1] & 0x7F) as usize];
+    socket.read_exact(&mut payload).unwrap();
+    (head[0] & 0x0F, payload)
+}
+
+fn both_servers(test: impl Fn(&str)) {
+    for mode in ["--server", "--async"] {
+        test(mode);
+    }
+}
+
+// The three events take longer than the handler time limit allows: an open
+// stream is meant to stay open, and is not cut off as a stuck handler would be.
+#[test]
+fn events_arrive_as_they_are_sent_and_outlive_the_handler_timeout() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("events{}", mode), &["--handler-timeout", "1"], "4");
+        let mut socket = connect(server.port);
+        socket
+            .write_all(b"GET /nilY/9000 HTTP/1.1\r\nAccept: text/event-stream\r\n\r\n")
+            .unwrap();
+
+        let mut all = Vec::new();
+        let _ = socket.read_to_end(&mut all);
+        let all = String::from_utf8_lossy(&all);
+        assert!(all.starts_with("HTTP/1.1 200"), "{}: {}", mode, all);
+        assert!(all.contains("Content-Type: text/event-stream"), "{}: {}", mode, all);
+        assert!(all.contains("data: 9000 PENDING\n\n"), "{}: {}", mode, all);
+        assert!(all.contains("data: 9000 SUCCESS\n\n"), "{}: {}", mode, all);
+        assert!(all.ends_with("0\r\n\r\n"), "{}: {}", mode, all);
+    });
+}
+
+// Longer than a Duration can hold, and still cut short by the time limit.
+#[test]
+fn a_wait_of_any_length_ends_at_the_handler_timeout() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("wait{}", mode), &["--handler-timeout", "1"], "4");
+        let started = Instant::now();
+        let response = common::get(server.port, "/neetu");
+        assert!(response.ends_with("woke"), "{}: {}", mode, response);
+        assert!(started.elapsed() < Duration::from_secs(10), "{}", mode);
+        assert!(common::get(server.port, "/ping").ends_with("pong"), "{}", mode);
+    });
+}
+
+#[test]
+fn a_websocket_answers_messages_and_pings_until_it_is_closed() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("socket{}", mode), &[], "4");
+        let (mut socket, head) = open_socket(server.port);
+        // The accept key the RFC gives for this client key.
+        assert!(head.starts_with("HTTP/1.1 101"), "{}: {}", mode, head);
+        assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="), "{}: {}", mode, head);
+
+        send_frame(&mut socket, 0x1, "வணக்கம்".as_bytes());
+        assert_eq!(read_frame(&mut socket), (0x1, "echo வணக்கம்".as_bytes().to_vec()), "{}", mode);
+
+        send_frame(&mut socket, 0x9, b"hb");
+        assert_eq!(read_frame(&mut socket), (0xA, b"hb".to_vec()), "{}", mode);
+
+        send_frame(&mut socket, 0x1, b"bye");
+        assert_eq!(read_frame(&mut socket), (0x8, 1000u16.to_be_bytes().to_vec()), "{}", mode);
+        let mut rest = Vec::new();
+        let _ = socket.read_to_end(&mut rest);
+        assert!(rest.is_empty(), "{}: {:?}", mode, rest);
+    });
+}
+
+// One worker, held by nobody: the socket waiting on its client has a thread
+// of its own.
+#[test]
+fn an_open_socket_does_not_hold_a_worker() {
+    both_servers(|mode| {
+        let server = serve(mode, &format!("worker{}", mode), &[], "1");
+        let (_idle, head) = open_socket(server.port);
+        assert!(head.starts_with("HTTP/1.1 101"), "{}: {}", mode, head);
+
+        let mut socket = connect(server.port);
+        socket.write_all(b"GET /ping HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+        let mut response = Vec::new();
+        let _ = socket.read_to_end(&mut response);
+        let response = String::from_utf8_lossy(&response);
+        assert!(response.ends_with("pong"), "{}: {}", mode, response);
+    });
+}
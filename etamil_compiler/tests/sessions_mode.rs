@@ -0,0 +1,80 @@
+//! Cookies and sessions, in both servers.
+
+mod common;
+
+use common::Server;
+
+const PROGRAM: &str = r#"வழி பதி, "/uLnuzY" {
+    அமர்வு_அமை("payar", request_body);
+    குக்கீ_அமை("mozi", "ta", {HttpOnly: மெய், "Max-Age": 3600, SameSite: "strict"});
+    பதில் 200, "vaNakkam";
+}
+
+வழி பெறு, "/nAn" {
+    payar = அமர்வு_பெறு("payar");
+    (payar == இன்மை) எனில் {
+        பதில் 401, "yAr?";
+    } இன்றேல் {
+        பதில் 200, payar & " " & cookies["mozi"];
+    }
+}
+
+வழி பதி, "/veLiyERu" {
+    அமர்வு_முடி();
+    பதில் 204, "";
+}
+"#;
+
+fn serve(mode: &str, name: &str) -> Server {
+    let main = common::program(&format!("sessions_{}", name), PROGRAM);
+    common::serve_with(mode, &main, &[], &[("ETAMIL_SESSION_SECRET", "a secret for the tests")])
+}
+
+fn send(port: u16, request: &str, body: &str) -> String {
+    common::send(
+        port,
+        format!("{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", request, body.len(), body),
+    )
+}
+
+/// Every `Set-Cookie` in a response's head.
+fn cookies_set(response: &str) -> Vec<&str> {
+    let head = response.split("\r\n\r\n").next().unwrap_or_default();
+    head.lines()
+        .filter_map(|line| line.strip_prefix("Set-Cookie: "))
+        .collect()
+}
+
+#[test]
+fn a_session_is_kept_between_requests_until_it_ends() {
+    for mode in ["--server", "--async"] {
+        let server = serve(mode, &format!("keep{}", mode));
+
+        let signed_in = send(server.port, "POST /uLnuzY HTTP/1.1", "kumar");
+        assert!(signed_in.starts_with("HTTP/1.1 200"), "{}: {}", mode, signed_in);
+        let set = cookies_set(&signed_in);
+        assert_eq!(set.len(), 2, "{}: {}", mode, signed_in);
+        assert!(set.contains(&"mozi=ta; HttpOnly; Max-Age=3600; Path=/; SameSite=Strict"), "{}: {:?}", mode, set);
+        let session = set
+            .iter()
+            .find(|cookie| cookie.starts_with("etamil_session="))
+            .expect("a session cookie");
+        // Plain HTTP, so not Secure: the browser would not send it back.
+        assert!(session.ends_with("; HttpOnly; SameSite=Lax"), "{}: {}", mode, session);
+        let session = session.split(';').next().unwrap();
+
+        let cookie = format!("Cookie: {}; mozi=ta", session);
+        let me = send(server.port, &format!("GET /nAn HTTP/1.1\r\n{}", cookie), "");
+        assert!(me.ends_with("\r\n\r\nkumar ta"), "{}: {}", mode, me);
+
+        // The same id without its signature is nobody.
+        let id = session.trim_start_matches("etamil_session=").split('.').next().unwrap();
+        let forged = send(server.port, &format!("GET /nAn HTTP/1.1\r\nCookie: etamil_session={}", id), "");
+        assert!(forged.starts_with("HTTP/1.1 401"), "{}: {}", mode, forged);
+
+        let out = send(server.port, &format!("POST /veLiyERu HTTP/1.1\r\n{}", cookie), "");
+        assert!(cookies_set(&out).iter().any(|c| c.starts_with("etamil_session=; Path=/; Max-Age=0")), "{}: {}", mode, out);
+        let after = send(server.port, &format!("GET /nAn HTTP/1.1\r\n{}", cookie), "");
+        assert!(after.starts_with("HTTP/1.1 401"), "{}: {}", mode, after);
+    }
+}
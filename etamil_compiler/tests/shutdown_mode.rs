@@ -0,0 +1,94 @@
+//! Stopping a server cleanly, and `--watch`, in both servers.
+
+mod common;
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use common::{get, serve};
+
+const PROGRAM: &str = r#"வழி பெறு, "/mella" {
+    காத்திரு(2);
+    பதில் 200, "mudinthathu";
+}
+
+வழி பெறு, "/niRuththu" {
+    வழங்கி_நிறுத்து;
+    பதில் 200, "sari";
+}
+"#;
+
+fn program(name: &str) -> PathBuf {
+    common::program(&format!("shutdown_{}", name), PROGRAM)
+}
+
+#[cfg(unix)]
+#[test]
+fn sigterm_finishes_the_request_in_hand_then_exits() {
+    for mode in ["--server", "--async"] {
+        let mut server = serve(mode, &program(&mode[2..]), &[]);
+
+        let port = server.port;
+        let slow = std::thread::spawn(move || get(port, "/mella"));
+        std::thread::sleep(Duration::from_millis(500));
+        let sent = Command::new("kill")
+            .args(["-TERM", &server.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(sent.success());
+
+        let answer = slow.join().unwrap();
+        assert!(answer.starts_with("HTTP/1.1 200"), "{}: {}", mode, answer);
+        assert!(answer.ends_with("mudinthathu"), "{}: {}", mode, answer);
+        let status = server.exited(Duration::from_secs(20));
+        assert_eq!(status.and_then(|status| status.code()), Some(0), "{}", mode);
+    }
+}
+
+#[test]
+fn a_handler_can_stop_its_server() {
+    for mode in ["--server", "--async"] {
+        let mut server = serve(mode, &program(&format!("handler_{}", &mode[2..])), &[]);
+
+        let answer = get(server.port, "/niRuththu");
+        assert!(answer.ends_with("sari"), "{}: {}", mode, answer);
+        let status = server.exited(Duration::from_secs(20));
+        assert_eq!(status.and_then(|status| status.code()), Some(0), "{}", mode);
+    }
+}
+
+#[test]
+fn watch_serves_a_saved_program_and_keeps_serving_through_a_broken_one() {
+    for mode in ["--server", "--async"] {
+        let file = program(&format!("watch_{}", &mode[2..]));
+        let server = serve(mode, &file, &["--watch"]);
+        let port = server.port;
+        assert!(get(port, "/puthu").starts_with("HTTP/1.1 404"), "{}", mode);
+
+        std::fs::write(&file, PROGRAM.replace("/mella", "/puthu").replace("காத்திரு(2);", "")).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while !get(port, "/puthu").ends_with("mudinthathu") {
+            assert!(Instant::now() < deadline, "{}: the new route was never served", mode);
+            std::thread::sleep(Duration::from_millis(100));
+        }
+
+        // Saved halfway through an edit: the routes already served stay.
+        std::fs::write(&file, "வழி பெறு, \"/puthu\" {").unwrap();
+        std::thread::sleep(Duration::from_secs(2));
+        let answer = get(port, "/puthu");
+        assert!(answer.ends_with("mudinthathu"), "{}: {}", mode, answer);
+    }
+}
+
+#[test]
+fn watch_needs_a_server_and_a_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .arg("--watch")
+        .arg(program("watch_alone"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--watch needs --server or --async"), "{}", stderr);
+}
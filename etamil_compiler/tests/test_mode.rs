@@ -0,0 +1,88 @@
+//! Tests for `etamil test`.
+//!
+//! Run through the real binary, against a directory of tests written for the
+//! occasion: what matters is what a CI job would see — the exit status, and a
+//! report it can read.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn etamil(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_etamil"))
+        .args(args)
+        .output()
+        .expect("could not start the compiler");
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+/// A directory with one passing and one failing test function, and a helper
+/// file that has no tests at all.
+fn project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("etamil_test_mode_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("kUttal.qmz"),
+        "செயல் கூட்டு(a, b) {\n    திரும்பு a + b;\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("kUttal_cOqaZY.qmz"),
+        "இறக்கு \"kUttal.qmz\";\n\
+         செயல் சோதனை_சரி() {\n    எதிர்பார்_சமம்(கூட்டு(2, 2), 4, \"2 + 2\");\n}\n\
+         செயல் சோதனை_தவறு() {\n    அச்சு \"computing\";\n    எதிர்பார்_சமம்(கூட்டு(2, 2), 5, \"2 + 2 is not 5\");\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn a_failing_test_fails_the_run_and_says_where() {
+    let dir = project("human");
+    let (code, stdout, stderr) = etamil(&["test", dir.to_str().unwrap()]);
+
+    assert_eq!(code, 1, "{}", stderr);
+    assert!(stdout.contains("✓ சோதனை_சரி"), "{}", stdout);
+    assert!(stdout.contains("✗ சோதனை_தவறு"), "{}", stdout);
+    assert!(stdout.contains("line 7"), "{}", stdout);
+    assert!(stdout.contains("computing"), "{}", stdout);
+    assert!(stdout.contains("(2 tests, 1 passed, 1 failed)"), "{}", stdout);
+}
+
+#[test]
+fn a_filter_runs_only_the_tests_it_names() {
+    let dir = project("filter");
+    let (code, stdout, _) = etamil(&["test", "--filter", "சரி", dir.to_str().unwrap()]);
+
+    assert_eq!(code, 0, "{}", stdout);
+    assert!(!stdout.contains("சோதனை_தவறு"), "{}", stdout);
+    assert!(stdout.contains("(1 tests, 1 passed, 0 failed)"), "{}", stdout);
+}
+
+#[test]
+fn tap_and_junit_reports() {
+    let dir = project("formats");
+
+    let (_, tap, _) = etamil(&["test", "--format", "tap", dir.to_str().unwrap()]);
+    assert!(tap.starts_with("TAP version 13\n1..2\n"), "{}", tap);
+    assert!(tap.contains("\nok 1 - "), "{}", tap);
+    assert!(tap.contains("\nnot ok 2 - "), "{}", tap);
+    assert!(tap.contains("  message: |"), "{}", tap);
+
+    let (_, junit, _) = etamil(&["test", "--format", "junit", dir.to_str().unwrap()]);
+    assert!(junit.starts_with("<?xml"), "{}", junit);
+    assert!(junit.contains(r#"tests="2" failures="1""#), "{}", junit);
+    assert!(junit.contains("<failure message="), "{}", junit);
+    assert!(junit.contains("<system-out>computing\n</system-out>"), "{}", junit);
+}
+
+#[test]
+fn an_unknown_format_is_a_usage_error() {
+    let (code, _, stderr) = etamil(&["test", "--format", "xml"]);
+    assert_eq!(code, 2);
+    assert!(stderr.contains("human, tap or junit"), "{}", stderr);
+}
@@ -115,6 +115,9 @@ TOKEN_SCOPES = {
     "HttpHead": "support.constant.http-method.etamil",
     "Route": "keyword.control.route.etamil",
     "Every": "keyword.control.every.etamil",
+    "Before": "keyword.control.before.etamil",
+    "After": "keyword.control.after.etamil",
+    "Mount": "keyword.control.mount.etamil",
     "Response": "keyword.other.response.etamil",
 }
 
@@ -207,6 +210,15 @@ SNIPPETS = {
         '{kw} ${1:3600} {\n\t${2:அச்சு "ஒவ்வொரு மணிநேரமும்";}\n}',
         '{kw} ${1:3600} {\n\t${2:accu "every hour";}\n}',
     ),
+    "Before": (
+        '{kw} "${1:/api}" {\n\t${2:அச்சு request_method & " " & request_path;}\n}',
+        '{kw} "${1:/api}" {\n\t${2:accu request_method & " " & request_path;}\n}',
+    ),
+    "After": (
+        '{kw} "${1:/api}" {\n\t${2:பதில்_தலைப்பு("X-Served-By", "etamil");}\n}',
+        '{kw} "${1:/api}" {\n\t${2:paqil_qalYppu("X-Served-By", "etamil");}\n}',
+    ),
+    "Mount": ('{kw} "${1:/assets}", "${2:dist}";', '{kw} "${1:/assets}", "${2:dist}";'),
     "Response": ('{kw} ${1:200}, ${2:"ok"};', '{kw} ${1:200}, ${2:"ok"};'),
     "StartServer": ('{kw} "${1:127.0.0.1}", ${2:8080};', '{kw} "${1:127.0.0.1}", ${2:8080};'),
     "Select": (
@@ -558,6 +570,9 @@ def build_grammar(tokens: list[dict], builtins: list[dict], stdlib: list[dict])
         "keyword.control.import.etamil",
         "keyword.control.route.etamil",
         "keyword.control.every.etamil",
+        "keyword.control.before.etamil",
+        "keyword.control.after.etamil",
+        "keyword.control.mount.etamil",
         "keyword.control.etamil",
         "keyword.declaration.function.etamil",
         "keyword.operator.logical.etamil",
@@ -27,7 +27,9 @@ Every keyword in the language, grouped as the lexer groups them.
 
 Each row lists the forms that produce the same token: Tamil script, the
 romanized (ezuqqu) spelling, and where one exists an English alias. All
-forms are interchangeable in source code.
+forms are interchangeable in source code. A romanization marked *was* is the
+spelling from before the current scheme: it still works, and
+`etamil convert --migrate` rewrites it.
 
 The three Tamil nasals stay distinct in romanized form: **ண = `N`, ந = `n`,
 ன = `Z`**.
@@ -68,8 +70,14 @@ def main() -> None:
         if keyword:
             alts = keyword.group(1).split("|")
             tamil = alts[0]
-            roman = f"`{alts[1]}`" if len(alts) > 1 else "—"
-            english = f"`{alts[2]}`" if len(alts) > 2 else "—"
+            # Tamil, the scheme romanization, the `_english` alias if any,
+            # then older romanizations the lexer still accepts.
+            romans = [a for a in alts[1:] if not a.startswith("_")]
+            englishes = [a for a in alts[1:] if a.startswith("_")]
+            roman = f"`{romans[0]}`" if romans else "—"
+            if len(romans) > 1:
+                roman += " (was " + ", ".join(f"`{r}`" for r in romans[1:]) + ")"
+            english = f"`{englishes[0]}`" if englishes else "—"
             token = keyword.group(2)
             out.append(f"| {tamil} | {roman} | {english} | `{token}` |")
             count += 1